@@ -0,0 +1,36 @@
+//! Example demonstrating the CachedLLM wrapper with an on-disk response cache.
+//!
+//! Run with:
+//! `cargo run --example cache_example --features openai`
+
+use llm::builder::{LLMBackend, LLMBuilder};
+use llm::chat::ChatMessage;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    llm::init_logging();
+
+    let api_key = std::env::var("OPENAI_API_KEY").unwrap_or_default();
+
+    let llm = LLMBuilder::new()
+        .backend(LLMBackend::OpenAI)
+        .api_key(api_key)
+        .model("gpt-4o-mini")
+        .temperature(0.0)
+        .cache(true)
+        .cache_capacity(500)
+        .cache_ttl_seconds(3600)
+        .cache_dir(std::env::temp_dir().join("llm-cache-example"))
+        .build()?;
+
+    let messages = vec![ChatMessage::user()
+        .content("Reply with a single short greeting.")
+        .build()];
+
+    // The second call is served from the cache without reaching the API.
+    for _ in 0..2 {
+        let response = llm.chat(&messages).await?;
+        println!("{response}");
+    }
+    Ok(())
+}
@@ -0,0 +1,47 @@
+// Import required modules from the LLM library
+use std::sync::Arc;
+
+use llm::{
+    builder::{LLMBackend, LLMBuilder},
+    chat::ChatMessage,
+    mcp::McpClient,
+    tool_runtime::ToolRuntime,
+};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Start the reference filesystem MCP server on the current directory
+    let client = McpClient::stdio(
+        "npx",
+        ["-y", "@modelcontextprotocol/server-filesystem", "."],
+    )
+    .await?;
+    println!(
+        "Connected to {} {}",
+        client.server_info().name,
+        client.server_info().version
+    );
+
+    // Register every server tool; calls made by the model are proxied to the server
+    let client = Arc::new(client);
+    let runtime = ToolRuntime::new().register_mcp(client.clone()).await?;
+    for tool in runtime.tools() {
+        println!("- {}: {}", tool.function.name, tool.function.description);
+    }
+
+    let llm = LLMBuilder::new()
+        .backend(LLMBackend::OpenAI)
+        .api_key(std::env::var("OPENAI_API_KEY").unwrap_or("sk-TESTKEY".into()))
+        .model("gpt-4o-mini")
+        .build()?;
+
+    let messages = vec![ChatMessage::user()
+        .content("List the files of the current directory and summarize what this project is.")
+        .build()];
+
+    let outcome = runtime.run(llm.as_ref(), messages).await?;
+    println!("{}", outcome.response);
+
+    client.close().await?;
+    Ok(())
+}
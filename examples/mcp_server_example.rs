@@ -0,0 +1,54 @@
+// Import required modules from the LLM library
+use llm::{
+    builder::{LLMBackend, LLMBuilder},
+    chain::{LLMRegistryBuilder, MultiChainStepBuilder, MultiChainStepMode},
+    mcp::McpServer,
+};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Providers exposed as `ask_openai` and `ask_anthropic` tools
+    let openai = LLMBuilder::new()
+        .backend(LLMBackend::OpenAI)
+        .api_key(std::env::var("OPENAI_API_KEY").unwrap_or("sk-OPENAI".into()))
+        .model("gpt-4o-mini")
+        .build()?;
+    let anthropic = LLMBuilder::new()
+        .backend(LLMBackend::Anthropic)
+        .api_key(std::env::var("ANTHROPIC_API_KEY").unwrap_or("anthro-key".into()))
+        .model("claude-3-5-sonnet-20240620")
+        .build()?;
+    let registry = LLMRegistryBuilder::new()
+        .register("openai", openai)
+        .register("anthropic", anthropic)
+        .build();
+
+    // Chain exposed as the `run_review` tool; stdout is reserved for the protocol
+    eprintln!("serving MCP over stdio");
+    McpServer::new(registry)
+        .chain(
+            "review",
+            "Draft an answer with OpenAI and have Claude review it",
+            &["question"],
+            || {
+                Ok(vec![
+                    MultiChainStepBuilder::new(MultiChainStepMode::Chat)
+                        .provider_id("openai")
+                        .id("draft")
+                        .template("Answer concisely: {{question}}")
+                        .build()?,
+                    MultiChainStepBuilder::new(MultiChainStepMode::Chat)
+                        .provider_id("anthropic")
+                        .id("review")
+                        .template(
+                            "Review this answer to '{{question}}' and fix mistakes: {{draft}}",
+                        )
+                        .build()?,
+                ])
+            },
+        )
+        .serve_stdio()
+        .await?;
+
+    Ok(())
+}
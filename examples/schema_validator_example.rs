@@ -0,0 +1,54 @@
+// Import required modules from the LLM library
+use llm::{
+    builder::{LLMBackend, LLMBuilder},
+    chat::{ChatMessage, StructuredOutputFormat},
+    validated_llm::Validator,
+};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Retrieve Anthropic API key from environment variable or use fallback
+    let api_key = std::env::var("ANTHROPIC_API_KEY").unwrap_or("anthro-key".into());
+
+    // Schema the answer must follow
+    let format: StructuredOutputFormat = serde_json::from_str(
+        r#"{
+            "name": "cat",
+            "schema": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "color": { "type": "string", "enum": ["orange", "black", "white"] }
+                },
+                "required": ["name", "color"],
+                "additionalProperties": false
+            }
+        }"#,
+    )?;
+
+    // JSON matching the schema, and short enough to display
+    let validator = Validator::json_schema(format).and(Validator::max_length(200));
+
+    let llm = LLMBuilder::new()
+        .backend(LLMBackend::Anthropic)
+        .model("claude-3-5-sonnet-20240620")
+        .api_key(api_key)
+        .max_tokens(512)
+        .validation(validator)
+        .validator_attempts(3)
+        .validator_on_failure(|failure| {
+            eprintln!("attempt {} rejected: {}", failure.attempt, failure.reason)
+        })
+        .build()?;
+
+    let messages = vec![ChatMessage::user()
+        .content("Describe Garfield the cat as JSON with a name and a color. Return only the JSON.")
+        .build()];
+
+    match llm.chat(&messages).await {
+        Ok(text) => println!("{text}"),
+        Err(e) => eprintln!("Chat error: {e}"),
+    }
+
+    Ok(())
+}
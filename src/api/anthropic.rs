@@ -0,0 +1,24 @@
+//! Anthropic Messages API compatibility.
+//!
+//! Serves `/v1/messages` on top of any registered backend, translating content
+//! blocks, tool use and streaming events to and from the unified chat types.
+//! Thinking blocks are returned in complete responses only, as provider streams
+//! carry no thinking deltas.
+
+#[path = "anthropic/convert.rs"]
+mod convert;
+
+#[path = "anthropic/handler.rs"]
+mod handler;
+
+#[path = "anthropic/stream.rs"]
+mod stream;
+
+#[path = "anthropic/types.rs"]
+mod types;
+
+pub use handler::handle_messages;
+
+#[cfg(test)]
+#[path = "anthropic/tests.rs"]
+mod tests;
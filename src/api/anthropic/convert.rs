@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::types::{
+    ContentBlock, InputContent, InputMessage, InputTool, MessageUsage, MessagesResponse, Source,
+};
+use crate::api::handlers::helpers::{bad_request, ApiResult};
+use crate::api::handlers::messages::decode_image;
+use crate::chat::{ChatMessage, ChatMessageBuilder, ChatResponse, ChatRole, Tool};
+use crate::{FunctionCall, ToolCall};
+
+/// Converts Anthropic turns into chat messages.
+///
+/// In each turn, tool results come first, then the text, tool uses and images,
+/// each image or document as its own message. Thinking blocks are dropped.
+pub fn build_messages(messages: Vec<InputMessage>) -> ApiResult<Vec<ChatMessage>> {
+    let mut converted = Vec::new();
+    let mut tool_names = HashMap::new();
+
+    for message in messages {
+        let role = match message.role.as_str() {
+            "assistant" => ChatRole::Assistant,
+            _ => ChatRole::User,
+        };
+        let blocks = match message.content {
+            InputContent::Text(text) => vec![ContentBlock::Text { text }],
+            InputContent::Blocks(blocks) => blocks,
+        };
+
+        let mut texts = Vec::new();
+        let mut calls = Vec::new();
+        let mut results = Vec::new();
+        let mut attachments = Vec::new();
+        for block in blocks {
+            match block {
+                ContentBlock::Text { text } => texts.push(text),
+                ContentBlock::ToolUse { id, name, input } => {
+                    tool_names.insert(id.clone(), name.clone());
+                    calls.push(ToolCall {
+                        id,
+                        call_type: "function".to_string(),
+                        function: FunctionCall {
+                            name,
+                            arguments: input.to_string(),
+                        },
+                    });
+                }
+                ContentBlock::ToolResult {
+                    tool_use_id,
+                    content,
+                    is_error,
+                } => {
+                    let output = content.as_ref().map(InputContent::text).unwrap_or_default();
+                    results.push(ToolCall {
+                        call_type: "function".to_string(),
+                        function: FunctionCall {
+                            name: tool_names.get(&tool_use_id).cloned().unwrap_or_default(),
+                            arguments: if is_error {
+                                format!("Error: {output}")
+                            } else {
+                                output
+                            },
+                        },
+                        id: tool_use_id,
+                    });
+                }
+                ContentBlock::Image { source } => {
+                    attachments.push(image_message(role.clone(), source)?)
+                }
+                ContentBlock::Document { source } => {
+                    attachments.push(document_message(role.clone(), source)?)
+                }
+                ContentBlock::Thinking { .. } | ContentBlock::RedactedThinking { .. } => {}
+            }
+        }
+
+        let has_results = !results.is_empty();
+        if has_results {
+            converted.push(ChatMessage::user().tool_result(results).build());
+        }
+        let text = texts.join("\n");
+        if !calls.is_empty() {
+            converted.push(
+                ChatMessage::assistant()
+                    .tool_use(calls)
+                    .content(text)
+                    .build(),
+            );
+        } else if !text.is_empty() || (attachments.is_empty() && !has_results) {
+            converted.push(ChatMessageBuilder::new(role).content(text).build());
+        }
+        converted.extend(attachments);
+    }
+    Ok(converted)
+}
+
+fn image_message(role: ChatRole, source: Source) -> ApiResult<ChatMessage> {
+    let builder = ChatMessageBuilder::new(role);
+    Ok(match source {
+        Source::Base64 { media_type, data } => {
+            let (mime, bytes) = decode_image(&media_type, &data)?;
+            builder.image(mime, bytes).build()
+        }
+        Source::Url { url } => builder.image_url(url).build(),
+    })
+}
+
+fn document_message(role: ChatRole, source: Source) -> ApiResult<ChatMessage> {
+    match source {
+        Source::Base64 { media_type, data } if media_type == "application/pdf" => {
+            let bytes = BASE64
+                .decode(data)
+                .map_err(|e| bad_request(format!("Invalid document data: {e}")))?;
+            Ok(ChatMessageBuilder::new(role).pdf(bytes).build())
+        }
+        Source::Base64 { media_type, .. } => Err(bad_request(format!(
+            "Unsupported document type: {media_type}"
+        ))),
+        Source::Url { .. } => Err(bad_request("Documents must be base64 encoded")),
+    }
+}
+
+pub fn build_tools(tools: Vec<InputTool>) -> Vec<Tool> {
+    tools
+        .into_iter()
+        .map(|tool| Tool {
+            tool_type: "function".to_string(),
+            function: crate::chat::FunctionTool {
+                name: tool.name,
+                description: tool.description,
+                parameters: tool.input_schema,
+            },
+            cache_control: None,
+        })
+        .collect()
+}
+
+pub fn message_id() -> String {
+    format!("msg_{}", Uuid::new_v4().simple())
+}
+
+/// Tool arguments as a JSON object; arguments that are not one are wrapped.
+pub fn tool_input(arguments: &str) -> Value {
+    if arguments.trim().is_empty() {
+        return json!({});
+    }
+    match serde_json::from_str(arguments) {
+        Ok(Value::Object(input)) => Value::Object(input),
+        _ => json!({ "arguments": arguments }),
+    }
+}
+
+/// Builds the response: thinking, text, then tool use blocks.
+pub fn build_response(model: String, response: &dyn ChatResponse) -> MessagesResponse {
+    let mut content = Vec::new();
+    if let Some(thinking) = response.thinking().filter(|t| !t.is_empty()) {
+        content.push(ContentBlock::Thinking {
+            thinking,
+            signature: String::new(),
+        });
+    }
+    let calls = response.tool_calls().unwrap_or_default();
+    let text = response.text().unwrap_or_default();
+    if !text.is_empty() || calls.is_empty() {
+        content.push(ContentBlock::Text { text });
+    }
+    let stop_reason = if calls.is_empty() {
+        "end_turn"
+    } else {
+        "tool_use"
+    };
+    content.extend(calls.into_iter().map(|call| ContentBlock::ToolUse {
+        input: tool_input(&call.function.arguments),
+        id: call.id,
+        name: call.function.name,
+    }));
+
+    let usage = response
+        .usage()
+        .map_or_else(MessageUsage::default, |usage| MessageUsage {
+            input_tokens: usage.prompt_tokens,
+            output_tokens: usage.completion_tokens,
+        });
+    MessagesResponse {
+        id: message_id(),
+        kind: "message".to_string(),
+        role: "assistant".to_string(),
+        model,
+        content,
+        stop_reason: Some(stop_reason.to_string()),
+        stop_sequence: None,
+        usage,
+    }
+}
@@ -0,0 +1,104 @@
+use axum::{
+    extract::{rejection::JsonRejection, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+
+use super::convert::{build_messages, build_response, build_tools};
+use super::stream::{error_event, sse_response};
+use super::types::{MessagesRequest, ToolChoice};
+use crate::api::handlers::helpers::{
+    authenticate, internal_error, parse_model, resolve_provider_with, ApiResult,
+};
+use crate::api::handlers::stream::{metered, open_stream};
+use crate::api::metrics::RequestMetrics;
+use crate::api::providers::RequestSettings;
+use crate::api::ServerState;
+use crate::chat::{ChatMessage, Tool};
+
+/// Anthropic-compatible `/v1/messages`; errors use the Anthropic error format.
+pub async fn handle_messages(
+    State(state): State<ServerState>,
+    Extension(metrics): Extension<RequestMetrics>,
+    headers: HeaderMap,
+    req: Result<Json<MessagesRequest>, JsonRejection>,
+) -> Response {
+    let result = match req {
+        Ok(Json(req)) => messages(state, metrics, headers, req).await,
+        Err(rejection) => Err((StatusCode::BAD_REQUEST, rejection.body_text())),
+    };
+    result.unwrap_or_else(|(status, message)| error_response(status, message))
+}
+
+async fn messages(
+    state: ServerState,
+    metrics: RequestMetrics,
+    headers: HeaderMap,
+    req: MessagesRequest,
+) -> ApiResult<Response> {
+    let access = authenticate(&state, &headers, &metrics)?;
+
+    let (provider_id, model_name) = match &state.default_provider {
+        Some(default) if !req.model.contains(':') => (default.clone(), req.model.clone()),
+        _ => parse_model(&req.model)?,
+    };
+    access.check(&provider_id, Some(&model_name))?;
+    access.admit()?;
+    let system = req.system.map(|s| s.text()).filter(|text| !text.is_empty());
+    let settings = RequestSettings {
+        max_tokens: req.max_tokens,
+        temperature: req.temperature,
+        ..Default::default()
+    };
+    let (provider, _) = resolve_provider_with(&state, &provider_id, &model_name, settings)?;
+    access.label(&provider_id, &model_name);
+    // Providers cannot take a system prompt per request, so it leads the
+    // conversation instead.
+    let messages: Vec<ChatMessage> = system
+        .map(|system| ChatMessage::user().content(system).build())
+        .into_iter()
+        .chain(build_messages(req.messages)?)
+        .collect();
+    let request_tools: Option<Vec<Tool>> =
+        req.tools.map(build_tools).filter(|tools| !tools.is_empty());
+    let available = request_tools.as_deref().or(provider.tools());
+    let named: Option<Vec<Tool>>;
+    let tools = match &req.tool_choice {
+        Some(ToolChoice::None) => None,
+        Some(ToolChoice::Tool { name }) => {
+            named = available.map(|tools| {
+                let matching = tools.iter().filter(|tool| &tool.function.name == name);
+                matching.cloned().collect()
+            });
+            named.as_deref()
+        }
+        _ => available,
+    };
+
+    if req.stream {
+        let pieces = open_stream(&*provider, &messages, tools)
+            .await
+            .map_err(|e| internal_error(e.to_string()))?;
+        return Ok(sse_response(metered(pieces, access), req.model));
+    }
+
+    let response = provider
+        .chat_with_tools(&messages, tools)
+        .await
+        .map_err(|e| internal_error(e.to_string()))?;
+    access.record(response.usage().as_ref());
+    Ok(Json(build_response(req.model, response.as_ref())).into_response())
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    let kind = match status {
+        StatusCode::BAD_REQUEST => "invalid_request_error",
+        StatusCode::UNAUTHORIZED => "authentication_error",
+        StatusCode::FORBIDDEN => "permission_error",
+        StatusCode::NOT_FOUND => "not_found_error",
+        StatusCode::TOO_MANY_REQUESTS => "rate_limit_error",
+        _ => "api_error",
+    };
+    (status, Json(error_event(kind, message))).into_response()
+}
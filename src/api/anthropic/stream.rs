@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+
+use axum::response::{
+    sse::{Event, KeepAlive, Sse},
+    IntoResponse, Response,
+};
+use futures::stream::{self, StreamExt};
+use serde_json::json;
+
+use super::convert::{message_id, tool_input};
+use super::types::{
+    BlockDelta, ContentBlock, ErrorBody, MessageDelta, MessageUsage, MessagesResponse, StreamEvent,
+};
+use crate::api::handlers::stream::{Piece, PieceStream};
+use crate::chat::StreamChunk;
+use crate::ToolCall;
+
+/// Encodes a provider stream as Anthropic message events, from `message_start` to
+/// `message_stop`.
+pub fn sse_response(pieces: PieceStream, model: String) -> Response {
+    let mut encoder = EventEncoder::default();
+    let start = encoder.start(model);
+    let events = stream::unfold(Some((pieces, encoder)), |state| async move {
+        let (mut pieces, mut encoder) = state?;
+        match pieces.next().await {
+            Some(Ok(piece)) => {
+                let events = encoder.piece(piece);
+                Some((events, Some((pieces, encoder))))
+            }
+            Some(Err(err)) => Some((vec![error_event("api_error", err.to_string())], None)),
+            None => Some((encoder.finish(), None)),
+        }
+    });
+    let events = stream::iter([vec![start]])
+        .chain(events)
+        .flat_map(|events| stream::iter(events.into_iter().map(sse_event)));
+
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+pub fn error_event(kind: &str, message: String) -> StreamEvent {
+    StreamEvent::Error {
+        error: ErrorBody {
+            kind: kind.to_string(),
+            message,
+        },
+    }
+}
+
+fn sse_event(event: StreamEvent) -> Result<Event, Infallible> {
+    let data = serde_json::to_string(&event).unwrap_or_else(|_| json!({}).to_string());
+    Ok(Event::default().event(event.name()).data(data))
+}
+
+/// Kind of the content block currently open.
+#[derive(PartialEq)]
+enum OpenBlock {
+    Text,
+    /// Tool use block, with the provider block index it was started from.
+    ToolUse(Option<usize>),
+}
+
+/// Turns provider stream items into content block events.
+#[derive(Default)]
+struct EventEncoder {
+    open: Option<OpenBlock>,
+    next_index: usize,
+    /// Provider block indices of the tool calls already announced.
+    started_tools: HashMap<usize, usize>,
+    has_tool_use: bool,
+    stop_reason: Option<String>,
+    output_tokens: u32,
+}
+
+impl EventEncoder {
+    fn start(&mut self, model: String) -> StreamEvent {
+        StreamEvent::MessageStart {
+            message: MessagesResponse {
+                id: message_id(),
+                kind: "message".to_string(),
+                role: "assistant".to_string(),
+                model,
+                content: Vec::new(),
+                stop_reason: None,
+                stop_sequence: None,
+                usage: MessageUsage::default(),
+            },
+        }
+    }
+
+    fn piece(&mut self, piece: Piece) -> Vec<StreamEvent> {
+        match piece {
+            Piece::Usage(usage) | Piece::Chunk(StreamChunk::Usage(usage)) => {
+                self.output_tokens = usage.completion_tokens;
+                Vec::new()
+            }
+            Piece::Chunk(StreamChunk::Done { stop_reason }) => {
+                self.stop_reason = Some(stop_reason);
+                Vec::new()
+            }
+            Piece::Chunk(StreamChunk::Text(text)) => {
+                let mut events = Vec::new();
+                if self.open != Some(OpenBlock::Text) {
+                    events.extend(self.open_block(
+                        OpenBlock::Text,
+                        ContentBlock::Text {
+                            text: String::new(),
+                        },
+                    ));
+                }
+                events.push(self.delta(BlockDelta::TextDelta { text }));
+                events
+            }
+            Piece::Chunk(StreamChunk::ToolUseStart { index, id, name }) => {
+                let block = ContentBlock::ToolUse {
+                    id,
+                    name,
+                    input: json!({}),
+                };
+                let events = self.open_block(OpenBlock::ToolUse(Some(index)), block);
+                self.started_tools.insert(index, self.next_index - 1);
+                events
+            }
+            Piece::Chunk(StreamChunk::ToolUseInputDelta {
+                index,
+                partial_json,
+            }) => match self.started_tools.get(&index) {
+                Some(&block) => vec![StreamEvent::ContentBlockDelta {
+                    index: block,
+                    delta: BlockDelta::InputJsonDelta { partial_json },
+                }],
+                None => Vec::new(),
+            },
+            Piece::Chunk(StreamChunk::ToolUseComplete { index, tool_call }) => {
+                if self.started_tools.contains_key(&index) {
+                    if self.open == Some(OpenBlock::ToolUse(Some(index))) {
+                        return self.close_block();
+                    }
+                    return Vec::new();
+                }
+                self.complete_tool(tool_call)
+            }
+            Piece::ToolCall(tool_call) => self.complete_tool(tool_call),
+        }
+    }
+
+    /// Sends a tool call received in one piece as a complete block.
+    fn complete_tool(&mut self, call: ToolCall) -> Vec<StreamEvent> {
+        let block = ContentBlock::ToolUse {
+            id: call.id,
+            name: call.function.name,
+            input: json!({}),
+        };
+        let mut events = self.open_block(OpenBlock::ToolUse(None), block);
+        let input = tool_input(&call.function.arguments);
+        events.push(self.delta(BlockDelta::InputJsonDelta {
+            partial_json: input.to_string(),
+        }));
+        events.extend(self.close_block());
+        events
+    }
+
+    fn open_block(&mut self, kind: OpenBlock, block: ContentBlock) -> Vec<StreamEvent> {
+        let mut events = self.close_block();
+        if matches!(kind, OpenBlock::ToolUse(_)) {
+            self.has_tool_use = true;
+        }
+        events.push(StreamEvent::ContentBlockStart {
+            index: self.next_index,
+            content_block: block,
+        });
+        self.open = Some(kind);
+        self.next_index += 1;
+        events
+    }
+
+    fn close_block(&mut self) -> Vec<StreamEvent> {
+        match self.open.take() {
+            Some(_) => vec![StreamEvent::ContentBlockStop {
+                index: self.next_index - 1,
+            }],
+            None => Vec::new(),
+        }
+    }
+
+    fn delta(&self, delta: BlockDelta) -> StreamEvent {
+        StreamEvent::ContentBlockDelta {
+            index: self.next_index - 1,
+            delta,
+        }
+    }
+
+    fn finish(&mut self) -> Vec<StreamEvent> {
+        let mut events = self.close_block();
+        events.push(StreamEvent::MessageDelta {
+            delta: MessageDelta {
+                stop_reason: stop_reason(self.stop_reason.as_deref(), self.has_tool_use),
+                stop_sequence: None,
+            },
+            usage: MessageUsage {
+                input_tokens: 0,
+                output_tokens: self.output_tokens,
+            },
+        });
+        events.push(StreamEvent::MessageStop);
+        events
+    }
+}
+
+/// Maps provider stop reasons to Anthropic stop reasons.
+fn stop_reason(stop_reason: Option<&str>, has_tool_use: bool) -> String {
+    match stop_reason {
+        _ if has_tool_use => "tool_use",
+        Some("max_tokens" | "length") => "max_tokens",
+        _ => "end_turn",
+    }
+    .to_string()
+}
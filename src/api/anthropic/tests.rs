@@ -0,0 +1,381 @@
+use serde_json::{json, Value};
+
+use crate::{
+    api::tests::{serve, spawn_app},
+    chat::{ChatRole, MessageType, StreamChunk, Usage},
+    testing::{MockLLM, MockResponse},
+    FunctionCall, ToolCall,
+};
+
+async fn post(base: &str, body: Value) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("{base}/v1/messages"))
+        .header("anthropic-version", "2023-06-01")
+        .json(&body)
+        .send()
+        .await
+        .unwrap()
+}
+
+/// `(event name, data)` pairs of a server-sent event body.
+async fn events(response: reqwest::Response) -> Vec<(String, Value)> {
+    let body = response.text().await.unwrap();
+    let mut events = Vec::new();
+    let mut name = String::new();
+    for line in body.lines() {
+        if let Some(event) = line.strip_prefix("event: ") {
+            name = event.to_string();
+        } else if let Some(data) = line.strip_prefix("data: ") {
+            events.push((name.clone(), serde_json::from_str(data).unwrap()));
+        }
+    }
+    events
+}
+
+fn weather_call() -> ToolCall {
+    ToolCall {
+        id: "toolu_1".to_string(),
+        call_type: "function".to_string(),
+        function: FunctionCall {
+            name: "weather".to_string(),
+            arguments: r#"{"city":"Paris"}"#.to_string(),
+        },
+    }
+}
+
+#[tokio::test]
+async fn answers_with_thinking_text_and_usage() {
+    let usage = Usage {
+        prompt_tokens: 10,
+        completion_tokens: 3,
+        total_tokens: 13,
+        completion_tokens_details: None,
+        prompt_tokens_details: None,
+    };
+    let mock = MockLLM::new().with_fallback(
+        MockResponse::text("Hello!")
+            .with_thinking("The user greets me.")
+            .with_usage(usage),
+    );
+    let base = serve(mock.clone()).await;
+    let body: Value = post(
+        &base,
+        json!({
+            "model": "mock:test",
+            "max_tokens": 100,
+            "system": [{ "type": "text", "text": "Be kind." }],
+            "messages": [{ "role": "user", "content": "hi" }]
+        }),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+
+    assert_eq!(body["type"], "message");
+    assert_eq!(body["role"], "assistant");
+    assert_eq!(body["model"], "mock:test");
+    assert_eq!(body["content"][0]["type"], "thinking");
+    assert_eq!(body["content"][0]["thinking"], "The user greets me.");
+    assert_eq!(
+        body["content"][1],
+        json!({ "type": "text", "text": "Hello!" })
+    );
+    assert_eq!(body["stop_reason"], "end_turn");
+    assert_eq!(
+        body["usage"],
+        json!({ "input_tokens": 10, "output_tokens": 3 })
+    );
+
+    let messages = mock.last_messages().unwrap();
+    assert_eq!(messages[0].content, "Be kind.");
+    assert_eq!(messages[1].content, "hi");
+}
+
+#[tokio::test]
+async fn round_trips_tool_use_and_tool_results() {
+    let mock = MockLLM::new();
+    mock.push_tool_calls(vec![weather_call()]);
+    mock.push_text("It is sunny in Paris.");
+    let base = serve(mock.clone()).await;
+    let tools = json!([{
+        "name": "weather",
+        "description": "Current weather",
+        "input_schema": { "type": "object", "properties": { "city": { "type": "string" } } }
+    }]);
+
+    let first: Value = post(
+        &base,
+        json!({
+            "model": "mock:test",
+            "max_tokens": 100,
+            "tools": tools,
+            "messages": [{ "role": "user", "content": "Weather in Paris?" }]
+        }),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(first["stop_reason"], "tool_use");
+    let tool_use = first["content"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|block| block["type"] == "tool_use")
+        .unwrap()
+        .clone();
+    assert_eq!(tool_use["input"], json!({ "city": "Paris" }));
+
+    let second: Value = post(
+        &base,
+        json!({
+            "model": "mock:test",
+            "max_tokens": 100,
+            "tools": tools,
+            "messages": [
+                { "role": "user", "content": "Weather in Paris?" },
+                { "role": "assistant", "content": [tool_use] },
+                { "role": "user", "content": [
+                    { "type": "tool_result", "tool_use_id": "toolu_1", "content": [{ "type": "text", "text": "sunny" }] }
+                ] }
+            ]
+        }),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(second["content"][0]["text"], "It is sunny in Paris.");
+
+    let messages = mock.last_messages().unwrap();
+    assert_eq!(messages.len(), 3);
+    assert_eq!(messages[1].role, ChatRole::Assistant);
+    let MessageType::ToolUse(calls) = &messages[1].message_type else {
+        panic!("expected a tool use");
+    };
+    assert_eq!(calls[0].function.name, "weather");
+    let MessageType::ToolResult(results) = &messages[2].message_type else {
+        panic!("expected tool results");
+    };
+    assert_eq!(results[0].id, "toolu_1");
+    assert_eq!(results[0].function.name, "weather");
+    assert_eq!(results[0].function.arguments, "sunny");
+}
+
+#[tokio::test]
+async fn streams_message_events() {
+    let mock = MockLLM::new();
+    mock.push_stream(vec![
+        StreamChunk::Text("Hel".to_string()),
+        StreamChunk::Text("lo".to_string()),
+        StreamChunk::Done {
+            stop_reason: "end_turn".to_string(),
+        },
+    ]);
+    let base = serve(mock).await;
+    let response = post(
+        &base,
+        json!({
+            "model": "mock:test",
+            "max_tokens": 100,
+            "stream": true,
+            "messages": [{ "role": "user", "content": "hi" }]
+        }),
+    )
+    .await;
+    let events = events(response).await;
+    let names: Vec<&str> = events.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "message_start",
+            "content_block_start",
+            "content_block_delta",
+            "content_block_delta",
+            "content_block_stop",
+            "message_delta",
+            "message_stop"
+        ]
+    );
+    assert!(events.iter().all(|(name, data)| data["type"] == *name));
+    assert_eq!(events[0].1["message"]["content"], json!([]));
+    assert_eq!(events[1].1["content_block"]["type"], "text");
+    assert_eq!(
+        events[3].1["delta"],
+        json!({ "type": "text_delta", "text": "lo" })
+    );
+    assert_eq!(events[5].1["delta"]["stop_reason"], "end_turn");
+}
+
+#[tokio::test]
+async fn streams_tool_use_blocks() {
+    let mock = MockLLM::new();
+    mock.push_stream(vec![
+        StreamChunk::Text("Checking".to_string()),
+        StreamChunk::ToolUseStart {
+            index: 1,
+            id: "toolu_1".to_string(),
+            name: "weather".to_string(),
+        },
+        StreamChunk::ToolUseInputDelta {
+            index: 1,
+            partial_json: r#"{"city":"#.to_string(),
+        },
+        StreamChunk::ToolUseInputDelta {
+            index: 1,
+            partial_json: r#""Paris"}"#.to_string(),
+        },
+        StreamChunk::ToolUseComplete {
+            index: 1,
+            tool_call: weather_call(),
+        },
+        StreamChunk::Done {
+            stop_reason: "tool_use".to_string(),
+        },
+    ]);
+    let base = serve(mock).await;
+    let response = post(
+        &base,
+        json!({
+            "model": "mock:test",
+            "max_tokens": 100,
+            "stream": true,
+            "tools": [{ "name": "weather", "input_schema": { "type": "object" } }],
+            "messages": [{ "role": "user", "content": "weather?" }]
+        }),
+    )
+    .await;
+    let events = events(response).await;
+
+    let starts: Vec<&Value> = events
+        .iter()
+        .filter(|(name, _)| name == "content_block_start")
+        .map(|(_, data)| data)
+        .collect();
+    assert_eq!(starts.len(), 2);
+    assert_eq!(starts[1]["index"], 1);
+    assert_eq!(starts[1]["content_block"]["type"], "tool_use");
+    assert_eq!(starts[1]["content_block"]["id"], "toolu_1");
+    let input: String = events
+        .iter()
+        .filter(|(_, data)| data["delta"]["type"] == "input_json_delta")
+        .map(|(_, data)| data["delta"]["partial_json"].as_str().unwrap())
+        .collect();
+    assert_eq!(input, r#"{"city":"Paris"}"#);
+    let stops = events
+        .iter()
+        .filter(|(name, _)| name == "content_block_stop")
+        .count();
+    assert_eq!(stops, 2);
+    let (_, delta) = events
+        .iter()
+        .find(|(name, _)| name == "message_delta")
+        .unwrap();
+    assert_eq!(delta["delta"]["stop_reason"], "tool_use");
+}
+
+#[tokio::test]
+async fn errors_use_the_anthropic_format() {
+    let base = serve(MockLLM::new()).await;
+    let response = post(
+        &base,
+        json!({ "model": "unknown:model", "max_tokens": 1, "messages": [{ "role": "user", "content": "hi" }] }),
+    )
+    .await;
+    assert_eq!(response.status(), 400);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["type"], "error");
+    assert_eq!(body["error"]["type"], "invalid_request_error");
+
+    let response = post(&base, json!({ "model": "mock:test" })).await;
+    assert_eq!(response.status(), 400);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["type"], "invalid_request_error");
+}
+
+#[tokio::test]
+async fn accepts_the_key_in_x_api_key() {
+    let registry = crate::chain::LLMRegistryBuilder::new()
+        .register(
+            "mock",
+            Box::new(MockLLM::new().with_fallback(MockResponse::text("ok"))),
+        )
+        .build();
+    let app = crate::api::Server::new(registry)
+        .with_auth_key("secret")
+        .router();
+    let addr = spawn_app(app).await;
+
+    let body = json!({ "model": "mock:test", "max_tokens": 1, "messages": [{ "role": "user", "content": "hi" }] });
+    let client = reqwest::Client::new();
+    let url = format!("http://{addr}/v1/messages");
+    let rejected = client.post(&url).json(&body).send().await.unwrap();
+    assert_eq!(rejected.status(), 401);
+    let rejected: Value = rejected.json().await.unwrap();
+    assert_eq!(rejected["error"]["type"], "authentication_error");
+
+    let accepted = client
+        .post(&url)
+        .header("x-api-key", "secret")
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(accepted.status(), 200);
+}
+
+#[cfg(feature = "groq")]
+#[tokio::test]
+async fn plain_models_use_the_default_provider_with_request_settings() {
+    use crate::builder::{LLMBackend, LLMBuilder};
+    use axum::{routing::post as route_post, Json, Router};
+
+    let upstream = Router::new().route(
+        "/chat/completions",
+        route_post(|Json(body): Json<Value>| async move {
+            Json(json!({
+                "choices": [{ "message": { "role": "assistant", "content": body.to_string() } }]
+            }))
+        }),
+    );
+    let upstream_addr = spawn_app(upstream).await;
+
+    let template = LLMBuilder::new()
+        .backend(LLMBackend::Groq)
+        .api_key("upstream-key")
+        .base_url(format!("http://{upstream_addr}/"));
+    let app = crate::api::Server::new(crate::chain::LLMRegistryBuilder::new().build())
+        .with_model_template("groq", template)
+        .with_default_provider("groq")
+        .router();
+    let addr = spawn_app(app).await;
+
+    let body: Value = post(
+        &format!("http://{addr}"),
+        json!({
+            "model": "claude-sonnet-4-5",
+            "max_tokens": 42,
+            "temperature": 0.5,
+            "system": "Be brief.",
+            "messages": [{ "role": "user", "content": "hi" }]
+        }),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(body["model"], "claude-sonnet-4-5");
+    let sent: Value = serde_json::from_str(body["content"][0]["text"].as_str().unwrap()).unwrap();
+    assert_eq!(sent["model"], "claude-sonnet-4-5");
+    assert_eq!(sent["max_tokens"], 42);
+    assert_eq!(sent["temperature"], 0.5);
+    assert_eq!(
+        sent["messages"][0],
+        json!({ "role": "user", "content": "Be brief." })
+    );
+    assert_eq!(
+        sent["messages"][1],
+        json!({ "role": "user", "content": "hi" })
+    );
+}
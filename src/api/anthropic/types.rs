@@ -0,0 +1,244 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Request payload of the Anthropic messages endpoint
+#[derive(Deserialize)]
+pub struct MessagesRequest {
+    /// Model identifier in format "provider:model_name", or a model name of the
+    /// default provider
+    pub model: String,
+    /// Maximum tokens to generate
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// Sampling temperature
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// Conversation turns
+    pub messages: Vec<InputMessage>,
+    /// System prompt
+    #[serde(default)]
+    pub system: Option<SystemPrompt>,
+    /// Tools the model may call, replacing the tools configured on the provider
+    #[serde(default)]
+    pub tools: Option<Vec<InputTool>>,
+    /// Tool choice; `none` sends no tools and `tool` only the named one
+    #[serde(default)]
+    pub tool_choice: Option<ToolChoice>,
+    /// Stream the response as server-sent events
+    #[serde(default)]
+    pub stream: bool,
+}
+
+/// System prompt, either plain text or text blocks
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum SystemPrompt {
+    Text(String),
+    Blocks(Vec<TextBlock>),
+}
+
+impl SystemPrompt {
+    pub fn text(&self) -> String {
+        match self {
+            SystemPrompt::Text(text) => text.clone(),
+            SystemPrompt::Blocks(blocks) => blocks
+                .iter()
+                .map(|block| block.text.as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+/// Text block of a system prompt
+#[derive(Deserialize)]
+pub struct TextBlock {
+    pub text: String,
+}
+
+/// Single conversation turn
+#[derive(Deserialize)]
+pub struct InputMessage {
+    /// "user" or "assistant"
+    pub role: String,
+    /// Plain text or content blocks
+    pub content: InputContent,
+}
+
+/// Content of a turn or of a tool result
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum InputContent {
+    Text(String),
+    Blocks(Vec<ContentBlock>),
+}
+
+impl InputContent {
+    /// Text of the content, with the text blocks joined by newlines.
+    pub fn text(&self) -> String {
+        match self {
+            InputContent::Text(text) => text.clone(),
+            InputContent::Blocks(blocks) => blocks
+                .iter()
+                .filter_map(|block| match block {
+                    ContentBlock::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+/// Content block of a request or a response
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentBlock {
+    Text {
+        text: String,
+    },
+    Image {
+        source: Source,
+    },
+    Document {
+        source: Source,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: Value,
+    },
+    ToolResult {
+        tool_use_id: String,
+        #[serde(default, skip_serializing)]
+        content: Option<InputContent>,
+        #[serde(default)]
+        is_error: bool,
+    },
+    Thinking {
+        thinking: String,
+        #[serde(default)]
+        signature: String,
+    },
+    RedactedThinking {
+        data: String,
+    },
+}
+
+/// Source of an image or document block
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Source {
+    Base64 { media_type: String, data: String },
+    Url { url: String },
+}
+
+/// Tool definition
+#[derive(Deserialize)]
+pub struct InputTool {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub input_schema: Value,
+}
+
+/// How the model should use the tools
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolChoice {
+    Auto,
+    Any,
+    Tool { name: String },
+    None,
+}
+
+/// Response payload of the Anthropic messages endpoint
+#[derive(Serialize)]
+pub struct MessagesResponse {
+    pub id: String,
+    /// Object type identifier ("message")
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Always "assistant"
+    pub role: String,
+    pub model: String,
+    pub content: Vec<ContentBlock>,
+    /// "end_turn", "tool_use" or "max_tokens"; unset in `message_start`
+    pub stop_reason: Option<String>,
+    pub stop_sequence: Option<String>,
+    pub usage: MessageUsage,
+}
+
+/// Token usage of a response
+#[derive(Serialize, Default)]
+pub struct MessageUsage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+}
+
+/// Server-sent event of a streamed response, also the shape of error responses
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    MessageStart {
+        message: MessagesResponse,
+    },
+    ContentBlockStart {
+        index: usize,
+        content_block: ContentBlock,
+    },
+    ContentBlockDelta {
+        index: usize,
+        delta: BlockDelta,
+    },
+    ContentBlockStop {
+        index: usize,
+    },
+    MessageDelta {
+        delta: MessageDelta,
+        usage: MessageUsage,
+    },
+    MessageStop,
+    Error {
+        error: ErrorBody,
+    },
+}
+
+impl StreamEvent {
+    /// SSE event name, the same as the `type` field.
+    pub fn name(&self) -> &'static str {
+        match self {
+            StreamEvent::MessageStart { .. } => "message_start",
+            StreamEvent::ContentBlockStart { .. } => "content_block_start",
+            StreamEvent::ContentBlockDelta { .. } => "content_block_delta",
+            StreamEvent::ContentBlockStop { .. } => "content_block_stop",
+            StreamEvent::MessageDelta { .. } => "message_delta",
+            StreamEvent::MessageStop => "message_stop",
+            StreamEvent::Error { .. } => "error",
+        }
+    }
+}
+
+/// Incremental content of a block
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BlockDelta {
+    TextDelta { text: String },
+    InputJsonDelta { partial_json: String },
+}
+
+/// Final message fields sent in `message_delta`
+#[derive(Serialize)]
+pub struct MessageDelta {
+    pub stop_reason: String,
+    pub stop_sequence: Option<String>,
+}
+
+/// Error details
+#[derive(Serialize)]
+pub struct ErrorBody {
+    /// Error type, e.g. "invalid_request_error"
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub message: String,
+}
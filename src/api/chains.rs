@@ -0,0 +1,152 @@
+//! Named chains defined on the server, run by id on `/v1/chains/{id}`.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::api::types::ChainStepRequest;
+use crate::chain::{MultiChainStep, MultiChainStepBuilder, MultiChainStepMode, PromptTemplate};
+use crate::error::LLMError;
+
+pub(crate) const DEFAULT_TEMPERATURE: f32 = 0.7;
+pub(crate) const DEFAULT_MAX_TOKENS: u32 = 1000;
+
+/// A chain clients run by id, with the request input available to its templates as
+/// `{{input}}` and request variables by name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainDefinition {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub steps: Vec<ChainStepRequest>,
+    /// Step whose output is returned; the last step by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+}
+
+impl ChainDefinition {
+    /// Id of the step whose output is returned.
+    pub fn output_step(&self) -> &str {
+        self.output
+            .as_deref()
+            .or_else(|| self.steps.last().map(|step| step.id.as_str()))
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Deserialize)]
+struct ChainFile {
+    chains: Vec<ChainDefinition>,
+}
+
+/// Chains served by id.
+///
+/// ```yaml
+/// chains:
+///   - id: summarize
+///     description: Summarizes the input in French
+///     steps:
+///       - id: summary
+///         provider_id: openai
+///         template: "Summarize: {{input}}"
+///         response_transform: [strip_think, trim]
+///       - id: french
+///         provider_id: anthropic
+///         template: "Translate to French: {{summary}}"
+/// ```
+#[derive(Debug, Default)]
+pub struct ChainStore {
+    chains: HashMap<String, ChainDefinition>,
+}
+
+impl ChainStore {
+    /// Validates the chains: ids must be unique, templates must parse and the
+    /// output step must exist.
+    pub fn new(chains: Vec<ChainDefinition>) -> Result<Self, LLMError> {
+        let mut store = HashMap::new();
+        for chain in chains {
+            validate(&chain)?;
+            let id = chain.id.clone();
+            if store.insert(id.clone(), chain).is_some() {
+                return Err(invalid(format!("duplicate chain id '{id}'")));
+            }
+        }
+        Ok(Self { chains: store })
+    }
+
+    /// Loads `{ "chains": [...] }` from a JSON or YAML (`.yaml`, `.yml`) file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, LLMError> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| invalid(format!("cannot read chain file {}: {e}", path.display())))?;
+        let parsed: Result<ChainFile, String> = match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml" | "yml") => serde_yaml::from_str(&content).map_err(|e| e.to_string()),
+            _ => serde_json::from_str(&content).map_err(|e| e.to_string()),
+        };
+        let file =
+            parsed.map_err(|e| invalid(format!("invalid chain file {}: {e}", path.display())))?;
+        Self::new(file.chains)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ChainDefinition> {
+        self.chains.get(id)
+    }
+
+    /// Chains sorted by id.
+    pub fn chains(&self) -> Vec<&ChainDefinition> {
+        let mut chains: Vec<_> = self.chains.values().collect();
+        chains.sort_by(|a, b| a.id.cmp(&b.id));
+        chains
+    }
+}
+
+fn validate(chain: &ChainDefinition) -> Result<(), LLMError> {
+    if chain.id.trim().is_empty() {
+        return Err(invalid("chain id is empty".to_string()));
+    }
+    if chain.steps.is_empty() {
+        return Err(invalid(format!("chain '{}' has no steps", chain.id)));
+    }
+    let mut ids = HashSet::new();
+    for step in &chain.steps {
+        if !ids.insert(step.id.as_str()) {
+            return Err(invalid(format!(
+                "chain '{}' has two steps named '{}'",
+                chain.id, step.id
+            )));
+        }
+        build_step(step).map_err(|e| invalid(format!("chain '{}': {e}", chain.id)))?;
+        // Named chains run strict, so a template that only parses leniently would
+        // fail every request.
+        PromptTemplate::parse(&step.template)
+            .map_err(|e| invalid(format!("chain '{}': step '{}': {e}", chain.id, step.id)))?;
+    }
+    if !ids.contains(chain.output_step()) {
+        return Err(invalid(format!(
+            "chain '{}' outputs unknown step '{}'",
+            chain.id,
+            chain.output_step()
+        )));
+    }
+    Ok(())
+}
+
+/// Chat step running `step`, with its transform pipeline failing the step when it
+/// finds nothing to extract.
+pub(crate) fn build_step(step: &ChainStepRequest) -> Result<MultiChainStep, LLMError> {
+    let mut builder = MultiChainStepBuilder::new(MultiChainStepMode::Chat)
+        .provider_id(&step.provider_id)
+        .id(&step.id)
+        .template(&step.template)
+        .temperature(step.temperature.unwrap_or(DEFAULT_TEMPERATURE))
+        .max_tokens(step.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS));
+    if let Some(pipeline) = step.response_transform.clone() {
+        builder = builder.try_response_transform(move |response| pipeline.apply(&response));
+    }
+    builder.build()
+}
+
+fn invalid(msg: String) -> LLMError {
+    LLMError::InvalidRequest(msg)
+}
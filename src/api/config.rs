@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use axum::extract::DefaultBodyLimit;
+use axum::http::HeaderValue;
+use axum::Router;
+use tower::limit::GlobalConcurrencyLimitLayer;
+use tower_http::cors::{Any, CorsLayer};
+use tower_http::timeout::TimeoutLayer;
+
+#[cfg(feature = "rustls-tls")]
+use super::tls::TlsConfig;
+
+/// Transport settings and request limits for [`Server`](super::Server)
+///
+/// The default keeps the previous behaviour: any origin may call the API, bodies
+/// are limited to axum's 2 MB default, and requests have no timeout or
+/// concurrency limit.
+#[derive(Debug, Clone, Default)]
+pub struct ServerConfig {
+    /// Origins allowed to call the API from a browser; any origin when `None`
+    pub cors_origins: Option<Vec<String>>,
+    /// Largest accepted request body in bytes
+    pub max_body_size: Option<usize>,
+    /// Time a handler has to produce the response head; streamed bodies are not cut
+    pub request_timeout: Option<Duration>,
+    /// Requests handled at once; further requests wait for a free slot
+    pub concurrency_limit: Option<usize>,
+    /// Whether `/ready` lists the models of every provider before reporting ready
+    pub probe_providers: bool,
+    /// Certificate and private key to serve HTTPS with
+    #[cfg(feature = "rustls-tls")]
+    pub tls: Option<TlsConfig>,
+}
+
+impl ServerConfig {
+    /// Creates a configuration with the defaults
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows cross-origin requests from `origin`, e.g. "https://app.example.com"
+    ///
+    /// Once an origin is set, only the listed origins are allowed.
+    pub fn cors_origin(mut self, origin: impl Into<String>) -> Self {
+        self.cors_origins
+            .get_or_insert_with(Vec::new)
+            .push(origin.into());
+        self
+    }
+
+    /// Rejects request bodies larger than `bytes` with 413 Payload Too Large
+    pub fn max_body_size(mut self, bytes: usize) -> Self {
+        self.max_body_size = Some(bytes);
+        self
+    }
+
+    /// Answers 408 Request Timeout when a handler takes longer than `timeout`
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Handles at most `limit` requests at once across all routes
+    pub fn concurrency_limit(mut self, limit: usize) -> Self {
+        self.concurrency_limit = Some(limit);
+        self
+    }
+
+    /// Makes `/ready` call `list_models` on every provider and answer 503 when one
+    /// fails or takes longer than 5 seconds
+    pub fn probe_providers(mut self, probe: bool) -> Self {
+        self.probe_providers = probe;
+        self
+    }
+
+    /// Serves HTTPS with the PEM certificate chain and private key at the given paths
+    #[cfg(feature = "rustls-tls")]
+    pub fn tls(
+        mut self,
+        cert_path: impl Into<std::path::PathBuf>,
+        key_path: impl Into<std::path::PathBuf>,
+    ) -> Self {
+        self.tls = Some(TlsConfig::new(cert_path, key_path));
+        self
+    }
+
+    /// Wraps every route of `router` with the configured layers
+    pub(super) fn apply<S>(&self, mut router: Router<S>) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        if let Some(bytes) = self.max_body_size {
+            router = router.layer(DefaultBodyLimit::max(bytes));
+        }
+        if let Some(limit) = self.concurrency_limit {
+            router = router.layer(GlobalConcurrencyLimitLayer::new(limit));
+        }
+        if let Some(timeout) = self.request_timeout {
+            router = router.layer(TimeoutLayer::new(timeout));
+        }
+        router.layer(self.cors())
+    }
+
+    fn cors(&self) -> CorsLayer {
+        let Some(origins) = &self.cors_origins else {
+            return CorsLayer::permissive();
+        };
+        let origins: Vec<HeaderValue> = origins
+            .iter()
+            .filter_map(|origin| match HeaderValue::from_str(origin) {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    log::warn!("Ignoring invalid CORS origin '{origin}'");
+                    None
+                }
+            })
+            .collect();
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(Any)
+            .allow_headers(Any)
+    }
+}
@@ -4,7 +4,34 @@ mod chat;
 #[path = "handlers/chain.rs"]
 mod chain;
 
+#[path = "handlers/embeddings.rs"]
+mod embeddings;
+
+#[path = "handlers/health.rs"]
+mod health;
+
 #[path = "handlers/helpers.rs"]
-mod helpers;
+pub(super) mod helpers;
+
+#[path = "handlers/jobs.rs"]
+mod jobs;
+
+#[path = "handlers/messages.rs"]
+pub(super) mod messages;
+
+#[path = "handlers/models.rs"]
+mod models;
+
+#[path = "handlers/sessions.rs"]
+mod sessions;
+
+#[path = "handlers/stream.rs"]
+pub(super) mod stream;
 
+pub use chain::{handle_chains, handle_run_chain};
 pub use chat::handle_chat;
+pub use embeddings::handle_embeddings;
+pub use health::{handle_health, handle_metrics, handle_ready};
+pub use jobs::{handle_cancel_job, handle_create_job, handle_get_job};
+pub use models::handle_models;
+pub use sessions::{handle_new_session, handle_resume_session};
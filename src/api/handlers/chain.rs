@@ -1,18 +1,41 @@
-use axum::{http::StatusCode, Json};
-use uuid::Uuid;
-
-use super::helpers::{bad_request, internal_error, parse_model, transform_response, ApiResult};
-use crate::api::types::{ChainStepRequest, ChatRequest, ChatResponse, Choice, Message};
+use std::collections::BTreeSet;
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde_json::Value;
+
+use super::helpers::{
+    authenticate, bad_request, build_response, internal_error, not_found, parse_model, Access,
+    ApiResult, OnStep,
+};
+use super::stream::{single_piece, sse_response};
+use crate::api::chains::{build_step, ChainDefinition, DEFAULT_MAX_TOKENS, DEFAULT_TEMPERATURE};
+use crate::api::metrics::RequestMetrics;
+use crate::api::types::{
+    ChainList, ChainStepRequest, ChainSummary, ChatRequest, Message, RunChainRequest,
+    RunChainResponse,
+};
 use crate::api::ServerState;
-use crate::chain::{MultiChainStep, MultiChainStepBuilder, MultiChainStepMode, MultiPromptChain};
-
-const DEFAULT_TEMPERATURE: f32 = 0.7;
-const DEFAULT_MAX_TOKENS: u32 = 1000;
-
+use crate::chain::{
+    MultiChainStep, MultiChainStepBuilder, MultiChainStepMode, MultiPromptChain, PromptTemplate,
+};
+use crate::error::LLMError;
+
+/// Runs the chain; a streamed request receives the final output as a single chunk.
+///
+/// The key must be allowed to use every provider of the chain as a whole; token
+/// usage of chains is not reported by the chain runner, so only the request counts.
 pub async fn handle_chain_request(
     state: ServerState,
     req: ChatRequest,
-) -> ApiResult<Json<ChatResponse>> {
+    access: Access,
+    on_step: Option<OnStep>,
+) -> ApiResult<Response> {
+    let stream = req.stream;
     let last_step_id = resolve_last_step_id(&req)?;
     let mut provider_ids = Vec::new();
     let mut chain = MultiPromptChain::new(&state.llms);
@@ -23,6 +46,13 @@ pub async fn handle_chain_request(
 
     let steps = build_steps(req.steps, &mut provider_ids)?;
     chain = chain.chain(steps);
+    for provider_id in &provider_ids {
+        access.check(provider_id, None)?;
+    }
+    access.admit()?;
+    if let Some(on_step) = on_step {
+        chain = chain.on_step(move |id, output| on_step(id, output));
+    }
 
     let chain_result = chain
         .run()
@@ -36,10 +66,16 @@ pub async fn handle_chain_request(
         )
     })?;
 
-    Ok(Json(build_response(
-        provider_ids.join(","),
-        final_response.to_string(),
-    )))
+    let model = provider_ids.join(",");
+    if stream {
+        return Ok(sse_response(
+            single_piece(final_response.to_string()),
+            model,
+            false,
+        ));
+    }
+    let message = Message::assistant(final_response.to_string());
+    Ok(Json(build_response(model, message, "stop", None)).into_response())
 }
 
 fn resolve_last_step_id(req: &ChatRequest) -> ApiResult<String> {
@@ -64,16 +100,16 @@ fn add_initial_step<'a>(
     let prompt = last_message(req)
         .ok_or_else(|| bad_request("Initial model requires at least one message"))?;
 
-    let transform = req.response_transform.clone().unwrap_or_default();
-    let step = MultiChainStepBuilder::new(MultiChainStepMode::Chat)
+    let mut builder = MultiChainStepBuilder::new(MultiChainStepMode::Chat)
         .provider_id(provider_id)
         .id("initial")
-        .template(prompt)
+        .template(PromptTemplate::escape(&prompt))
         .max_tokens(req.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS))
-        .temperature(req.temperature.unwrap_or(DEFAULT_TEMPERATURE))
-        .response_transform(move |resp| transform_response(resp, &transform))
-        .build()
-        .map_err(|e| bad_request(e.to_string()))?;
+        .temperature(req.temperature.unwrap_or(DEFAULT_TEMPERATURE));
+    if let Some(pipeline) = req.response_transform.clone() {
+        builder = builder.try_response_transform(move |resp| pipeline.apply(&resp));
+    }
+    let step = builder.build().map_err(|e| bad_request(e.to_string()))?;
 
     Ok(chain.step(step))
 }
@@ -83,49 +119,120 @@ fn build_steps(
     provider_ids: &mut Vec<String>,
 ) -> ApiResult<Vec<MultiChainStep>> {
     steps
-        .into_iter()
-        .map(|step| build_step(step, provider_ids))
+        .iter()
+        .map(|step| {
+            provider_ids.push(step.provider_id.clone());
+            build_step(step).map_err(|e| bad_request(e.to_string()))
+        })
         .collect()
 }
 
-fn build_step(step: ChainStepRequest, provider_ids: &mut Vec<String>) -> ApiResult<MultiChainStep> {
-    provider_ids.push(step.provider_id.clone());
-    let transform = step.response_transform.unwrap_or_default();
-
-    MultiChainStepBuilder::new(MultiChainStepMode::Chat)
-        .provider_id(step.provider_id)
-        .id(step.id)
-        .template(step.template)
-        .temperature(step.temperature.unwrap_or(DEFAULT_TEMPERATURE))
-        .max_tokens(step.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS))
-        .response_transform(move |resp| transform_response(resp, &transform))
-        .build()
-        .map_err(|e| bad_request(e.to_string()))
-}
-
 fn last_message(req: &ChatRequest) -> Option<String> {
     req.messages
         .as_ref()
         .and_then(|messages| messages.last())
-        .map(|message| message.content.clone())
+        .map(Message::text)
+}
+
+/// Lists the named chains the key may run.
+pub async fn handle_chains(
+    State(state): State<ServerState>,
+    Extension(metrics): Extension<RequestMetrics>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ChainList>> {
+    let access = authenticate(&state, &headers, &metrics)?;
+
+    let data = state
+        .chains
+        .chains()
+        .into_iter()
+        .map(|chain| ChainSummary {
+            id: chain.id.clone(),
+            description: chain.description.clone(),
+            steps: chain.steps.iter().map(|step| step.id.clone()).collect(),
+            providers: chain_providers(chain).into_iter().collect(),
+        })
+        .filter(|chain| chain.providers.iter().all(|p| access.allows(p, None)))
+        .collect();
+    Ok(Json(ChainList {
+        object: "list".to_string(),
+        data,
+    }))
+}
+
+/// Runs the named chain with the request input as `{{input}}` and the request
+/// variables; undefined template variables are an error.
+pub async fn handle_run_chain(
+    State(state): State<ServerState>,
+    Path(id): Path<String>,
+    Extension(metrics): Extension<RequestMetrics>,
+    headers: HeaderMap,
+    Json(req): Json<RunChainRequest>,
+) -> ApiResult<Json<RunChainResponse>> {
+    let access = authenticate(&state, &headers, &metrics)?;
+    run_named_chain(state, id, req, access, None)
+        .await
+        .map(Json)
 }
 
-fn build_response(model: String, content: String) -> ChatResponse {
-    ChatResponse {
-        id: format!("chatcmpl-{}", Uuid::new_v4()),
-        object: "chat.completion".to_string(),
-        created: std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs(),
-        model,
-        choices: vec![Choice {
-            index: 0,
-            message: Message {
-                role: "assistant".to_string(),
-                content,
-            },
-            finish_reason: "stop".to_string(),
-        }],
+pub(crate) async fn run_named_chain(
+    state: ServerState,
+    id: String,
+    req: RunChainRequest,
+    access: Access,
+    on_step: Option<OnStep>,
+) -> ApiResult<RunChainResponse> {
+    let chain = state
+        .chains
+        .get(&id)
+        .ok_or_else(|| not_found(format!("Unknown chain: {id}")))?;
+    for provider_id in chain_providers(chain) {
+        access.check(&provider_id, None)?;
+    }
+    access.admit()?;
+
+    let steps = chain
+        .steps
+        .iter()
+        .map(build_step)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| internal_error(e.to_string()))?;
+    let mut inputs = req.variables;
+    if let Some(input) = req.input {
+        inputs.insert("input".to_string(), Value::String(input));
     }
+    let mut runner = MultiPromptChain::new(&state.llms).strict(true).chain(steps);
+    if let Some(on_step) = on_step {
+        runner = runner.on_step(move |id, output| on_step(id, output));
+    }
+    let steps = runner.run_with_inputs(inputs).await.map_err(|e| match e {
+        LLMError::InvalidRequest(msg) => bad_request(msg),
+        other => internal_error(other.to_string()),
+    })?;
+
+    Ok(RunChainResponse {
+        output: steps.get(chain.output_step()).cloned().unwrap_or_default(),
+        id,
+        steps,
+    })
+}
+
+/// Ids of the steps a chat request with steps runs, in order.
+pub(crate) fn chain_step_ids(req: &ChatRequest) -> Vec<String> {
+    if req.steps.is_empty() {
+        return Vec::new();
+    }
+    let initial = req.model.as_ref().map(|_| "initial".to_string());
+    initial
+        .into_iter()
+        .chain(req.steps.iter().map(|step| step.id.clone()))
+        .collect()
+}
+
+pub(crate) fn chain_providers(chain: &ChainDefinition) -> BTreeSet<String> {
+    chain
+        .steps
+        .iter()
+        .map(|step| step.provider_id.clone())
+        .collect()
 }
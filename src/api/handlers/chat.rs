@@ -1,96 +1,136 @@
-use axum::{extract::State, http::HeaderMap, Json};
-use uuid::Uuid;
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
 
 use super::chain::handle_chain_request;
-use super::helpers::{bad_request, internal_error, parse_model, unauthorized, ApiResult};
-use crate::api::types::{ChatRequest, ChatResponse, Choice, Message};
+use super::helpers::{
+    authenticate, bad_request, build_response, internal_error, parse_model, resolve_provider_with,
+    Access, ApiResult, OnStep,
+};
+use super::messages::{build_messages, response_format_instruction, take_system};
+use super::stream::{metered, open_stream, sse_response};
+use crate::api::metrics::RequestMetrics;
+use crate::api::providers::RequestSettings;
+use crate::api::types::{ChatRequest, Message, ResponseFormat};
 use crate::api::ServerState;
-use crate::chat::{ChatMessage, ChatRole, MessageType};
+use crate::chat::{ChatMessage, Tool};
+use crate::validated_llm::strip_code_fence;
 
 pub async fn handle_chat(
     State(state): State<ServerState>,
+    Extension(metrics): Extension<RequestMetrics>,
     headers: HeaderMap,
     Json(req): Json<ChatRequest>,
-) -> ApiResult<Json<ChatResponse>> {
-    validate_auth(&state, &headers)?;
+) -> ApiResult<Response> {
+    let access = authenticate(&state, &headers, &metrics)?;
+    chat_completion(state, req, access, None).await
+}
+
+/// Answers a chat completion or, when the request has steps, runs it as a chain
+/// reporting each completed step to `on_step`.
+pub(crate) async fn chat_completion(
+    state: ServerState,
+    req: ChatRequest,
+    access: Access,
+    on_step: Option<OnStep>,
+) -> ApiResult<Response> {
     if !req.steps.is_empty() {
-        return handle_chain_request(state, req).await;
+        return handle_chain_request(state, req, access, on_step).await;
     }
 
-    let messages = build_messages(req.messages);
+    let include_usage = include_usage(&req);
+    let tools_disabled = matches!(&req.tool_choice, Some(choice) if choice == "none");
+    let request_tools: Option<Vec<Tool>> = req
+        .tools
+        .map(|tools| tools.into_iter().map(Tool::from).collect())
+        .filter(|tools: &Vec<Tool>| !tools.is_empty());
+    let mut request_messages = req.messages.unwrap_or_default();
+    let system = take_system(&mut request_messages);
+    let conversation = build_messages(request_messages)?;
+    let json_output = matches!(
+        req.response_format,
+        Some(ResponseFormat::JsonObject | ResponseFormat::JsonSchema { .. })
+    );
+    let schema = match &req.response_format {
+        Some(ResponseFormat::JsonSchema { json_schema }) => Some(json_schema.clone()),
+        _ => None,
+    };
+
     let model = req.model.ok_or_else(|| bad_request("Model is required"))?;
     let (provider_id, model_name) = parse_model(&model)?;
-    let provider = state
-        .llms
-        .get(&provider_id)
-        .ok_or_else(|| bad_request(format!("Unknown provider: {provider_id}")))?;
-
-    let response = provider
-        .chat(&messages)
-        .await
-        .map_err(|e| internal_error(e.to_string()))?;
-
-    Ok(Json(build_response(
-        model_name,
-        response.text().unwrap_or_default(),
-    )))
-}
-
-fn validate_auth(state: &ServerState, headers: &HeaderMap) -> ApiResult<()> {
-    let Some(key) = &state.auth_key else {
-        return Ok(());
+    access.check(&provider_id, Some(&model_name))?;
+    access.admit()?;
+    let settings = RequestSettings {
+        schema,
+        max_tokens: req.max_tokens,
+        temperature: req.temperature,
     };
+    let (provider, applied) = resolve_provider_with(&state, &provider_id, &model_name, settings)?;
+    access.label(&provider_id, &model_name);
 
-    let auth_header = headers
-        .get("Authorization")
-        .ok_or_else(|| unauthorized("Missing authorization"))?;
-    let auth_str = auth_header
-        .to_str()
-        .map_err(|_| unauthorized("Invalid authorization header"))?;
-
-    if !auth_str.starts_with("Bearer ") || &auth_str[7..] != key {
-        return Err(unauthorized("Invalid API key"));
+    // Providers cannot take a system prompt per request, so it leads the
+    // conversation instead.
+    let mut messages: Vec<ChatMessage> = system
+        .map(|system| ChatMessage::user().content(system).build())
+        .into_iter()
+        .chain(conversation)
+        .collect();
+    if let Some(instruction) = req
+        .response_format
+        .as_ref()
+        .filter(|_| !applied.schema)
+        .and_then(response_format_instruction)
+    {
+        messages.push(ChatMessage::user().content(instruction).build());
     }
+    let tools = if tools_disabled {
+        None
+    } else {
+        request_tools.as_deref().or(provider.tools())
+    };
 
-    Ok(())
-}
+    if req.stream {
+        let pieces = open_stream(&*provider, &messages, tools)
+            .await
+            .map_err(|e| internal_error(e.to_string()))?;
+        return Ok(sse_response(
+            metered(pieces, access),
+            model_name,
+            include_usage,
+        ));
+    }
 
-fn build_messages(messages: Option<Vec<Message>>) -> Vec<ChatMessage> {
-    messages
-        .unwrap_or_default()
-        .into_iter()
-        .map(|msg| ChatMessage {
-            role: parse_role(&msg.role),
-            message_type: MessageType::Text,
-            content: msg.content,
-        })
-        .collect()
-}
+    let response = provider
+        .chat_with_tools(&messages, tools)
+        .await
+        .map_err(|e| internal_error(e.to_string()))?;
+    let usage = response.usage();
+    access.record(usage.as_ref());
 
-fn parse_role(role: &str) -> ChatRole {
-    match role {
-        "user" => ChatRole::User,
-        "assistant" => ChatRole::Assistant,
-        _ => ChatRole::User,
+    let tool_calls = response.tool_calls().filter(|calls| !calls.is_empty());
+    let mut text = response.text().unwrap_or_default();
+    if json_output {
+        text = strip_code_fence(&text).to_string();
     }
+    let (message, finish_reason) = match tool_calls {
+        Some(calls) => {
+            let mut message = Message::assistant(text);
+            message.content = message.content.filter(|c| !c.text().is_empty());
+            message.tool_calls = Some(calls);
+            (message, "tool_calls")
+        }
+        None => (Message::assistant(text), "stop"),
+    };
+    Ok(Json(build_response(model_name, message, finish_reason, usage)).into_response())
 }
 
-fn build_response(model: String, content: String) -> ChatResponse {
-    ChatResponse {
-        id: format!("chatcmpl-{}", Uuid::new_v4()),
-        object: "chat.completion".to_string(),
-        created: std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs(),
-        model,
-        choices: vec![Choice {
-            index: 0,
-            message: Message {
-                role: "assistant".to_string(),
-                content,
-            },
-            finish_reason: "stop".to_string(),
-        }],
-    }
+/// Usage is only streamed when the client asks for it, as with OpenAI.
+fn include_usage(req: &ChatRequest) -> bool {
+    req.stream_options
+        .as_ref()
+        .and_then(|options| options.include_usage)
+        .unwrap_or(false)
 }
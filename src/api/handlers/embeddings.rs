@@ -0,0 +1,69 @@
+use axum::{extract::State, http::HeaderMap, Extension, Json};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde_json::Value;
+
+use super::helpers::{
+    authenticate, bad_request, internal_error, parse_model, resolve_provider, ApiResult,
+};
+use crate::api::metrics::RequestMetrics;
+use crate::api::types::{EmbeddingData, EmbeddingInput, EmbeddingRequest, EmbeddingResponse};
+use crate::api::ServerState;
+
+pub async fn handle_embeddings(
+    State(state): State<ServerState>,
+    Extension(metrics): Extension<RequestMetrics>,
+    headers: HeaderMap,
+    Json(req): Json<EmbeddingRequest>,
+) -> ApiResult<Json<EmbeddingResponse>> {
+    let access = authenticate(&state, &headers, &metrics)?;
+
+    let base64 = match req.encoding_format.as_deref() {
+        None | Some("float") => false,
+        Some("base64") => true,
+        Some(other) => return Err(bad_request(format!("Unsupported encoding format: {other}"))),
+    };
+    let (provider_id, model_name) = parse_model(&req.model)?;
+    access.check(&provider_id, Some(&model_name))?;
+    access.admit()?;
+    let provider = resolve_provider(&state, &provider_id, &model_name)?;
+    access.label(&provider_id, &model_name);
+    let input = match req.input {
+        EmbeddingInput::Single(text) => vec![text],
+        EmbeddingInput::Batch(texts) => texts,
+    };
+    if input.is_empty() {
+        return Err(bad_request("Input is required"));
+    }
+
+    let embeddings = provider
+        .embed(input)
+        .await
+        .map_err(|e| internal_error(e.to_string()))?;
+
+    let data = embeddings
+        .into_iter()
+        .enumerate()
+        .map(|(index, vector)| EmbeddingData {
+            object: "embedding".to_string(),
+            index,
+            embedding: if base64 {
+                Value::String(encode_base64(&vector))
+            } else {
+                Value::from(vector)
+            },
+        })
+        .collect();
+
+    Ok(Json(EmbeddingResponse {
+        object: "list".to_string(),
+        data,
+        model: model_name,
+        usage: None,
+    }))
+}
+
+/// Little-endian `f32` bytes, as the OpenAI clients decode them.
+fn encode_base64(vector: &[f32]) -> String {
+    let bytes: Vec<u8> = vector.iter().flat_map(|v| v.to_le_bytes()).collect();
+    BASE64.encode(bytes)
+}
@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::future::join_all;
+use serde_json::json;
+
+use crate::api::ServerState;
+use crate::error::LLMError;
+
+/// Time a provider has to list its models before `/ready` reports it down.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Liveness: the process is up and serving requests.
+pub async fn handle_health() -> Json<serde_json::Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+/// Readiness: 200 when every provider answers, 503 otherwise.
+///
+/// Providers are only probed, through `list_models`, when the server config
+/// enables it; providers that cannot list models count as ready. The endpoint
+/// needs no key, so the body only carries the status and failing providers are
+/// logged instead.
+pub async fn handle_ready(State(state): State<ServerState>) -> Response {
+    if !state.probe_providers {
+        return Json(json!({ "status": "ready" })).into_response();
+    }
+
+    let providers = state.providers.all();
+    let probes = join_all(providers.iter().map(|(id, provider)| async move {
+        let result = tokio::time::timeout(PROBE_TIMEOUT, provider.list_models(None)).await;
+        let failure = match result {
+            Ok(Ok(_)) => return true,
+            Ok(Err(LLMError::Unsupported(_))) => return true,
+            Ok(Err(err)) => err.to_string(),
+            Err(_) => "timed out".to_string(),
+        };
+        log::warn!("Provider '{id}' is not ready: {failure}");
+        false
+    }))
+    .await;
+
+    if probes.into_iter().all(|up| up) {
+        Json(json!({ "status": "ready" })).into_response()
+    } else {
+        let body = json!({ "status": "unavailable" });
+        (StatusCode::SERVICE_UNAVAILABLE, Json(body)).into_response()
+    }
+}
+
+/// Request, error and token counters and request latencies in the Prometheus
+/// text format. Past the first 64 provider and model pairs, models are labelled
+/// `other`.
+pub async fn handle_metrics(State(state): State<ServerState>) -> Response {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(),
+    )
+        .into_response()
+}
@@ -1,7 +1,20 @@
-use axum::http::StatusCode;
+use std::sync::Arc;
+
+use axum::http::{HeaderMap, StatusCode};
+use uuid::Uuid;
+
+use crate::api::keys::{constant_time_eq, ApiKeyConfig, KeyStore};
+use crate::api::metrics::RequestMetrics;
+use crate::api::providers::{Applied, RequestSettings, ResolvedProvider};
+use crate::api::types::{ChatResponse, Choice, Message};
+use crate::api::ServerState;
+use crate::chat::Usage;
 
 pub type ApiResult<T> = Result<T, (StatusCode, String)>;
 
+/// Called with the id and output of each chain step as it completes.
+pub type OnStep = Arc<dyn Fn(&str, &str) + Send + Sync>;
+
 pub fn bad_request(msg: impl Into<String>) -> (StatusCode, String) {
     (StatusCode::BAD_REQUEST, msg.into())
 }
@@ -10,10 +23,210 @@ pub fn unauthorized(msg: impl Into<String>) -> (StatusCode, String) {
     (StatusCode::UNAUTHORIZED, msg.into())
 }
 
+pub fn not_found(msg: impl Into<String>) -> (StatusCode, String) {
+    (StatusCode::NOT_FOUND, msg.into())
+}
+
+pub fn forbidden(msg: impl Into<String>) -> (StatusCode, String) {
+    (StatusCode::FORBIDDEN, msg.into())
+}
+
+pub fn conflict(msg: impl Into<String>) -> (StatusCode, String) {
+    (StatusCode::CONFLICT, msg.into())
+}
+
+pub fn too_many_requests(msg: impl Into<String>) -> (StatusCode, String) {
+    (StatusCode::TOO_MANY_REQUESTS, msg.into())
+}
+
+pub fn service_unavailable(msg: impl Into<String>) -> (StatusCode, String) {
+    (StatusCode::SERVICE_UNAVAILABLE, msg.into())
+}
+
 pub fn internal_error(msg: impl Into<String>) -> (StatusCode, String) {
     (StatusCode::INTERNAL_SERVER_ERROR, msg.into())
 }
 
+/// Key of an authenticated request; unrestricted when the server has no key store.
+#[derive(Clone)]
+pub struct Access {
+    key: Option<(Arc<KeyStore>, Arc<ApiKeyConfig>)>,
+    metrics: Option<RequestMetrics>,
+    /// The request was already counted against the rate limits
+    admitted: bool,
+}
+
+impl Access {
+    /// Whether the key may use the provider, without counting a refusal.
+    pub fn allows(&self, provider_id: &str, model: Option<&str>) -> bool {
+        self.key
+            .as_ref()
+            .is_none_or(|(_, key)| key.allows(provider_id, model))
+    }
+
+    /// Checks the key may use the provider, counting refusals.
+    pub fn check(&self, provider_id: &str, model: Option<&str>) -> ApiResult<()> {
+        match &self.key {
+            Some((store, key)) if !store.check_allowed(key, provider_id, model) => {
+                let target =
+                    model.map_or(provider_id.to_string(), |m| format!("{provider_id}:{m}"));
+                Err(forbidden(format!(
+                    "API key '{}' may not use {target}",
+                    key.name
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Labels the request metrics with the model, once it resolved to a provider,
+    /// so unknown names sent by clients do not become labels.
+    pub fn label(&self, provider_id: &str, model: &str) {
+        if let Some(metrics) = &self.metrics {
+            metrics.set_target(provider_id, model);
+        }
+    }
+
+    /// Name of the key, when the server has a key store.
+    pub fn key_name(&self) -> Option<&str> {
+        self.key.as_ref().map(|(_, key)| key.name.as_str())
+    }
+
+    /// Counts the request against the rate limits of the key.
+    pub fn admit(&self) -> ApiResult<()> {
+        let Some((store, key)) = self.key.as_ref().filter(|_| !self.admitted) else {
+            return Ok(());
+        };
+        store.admit(key).map_err(|limit| {
+            too_many_requests(format!(
+                "API key '{}' exceeded its {limit} rate limit",
+                key.name
+            ))
+        })
+    }
+
+    /// Same access, for a request already counted by [`Access::admit`].
+    pub fn admitted(self) -> Self {
+        Self {
+            admitted: true,
+            ..self
+        }
+    }
+
+    pub fn record(&self, usage: Option<&Usage>) {
+        let Some(usage) = usage else {
+            return;
+        };
+        if let Some((store, key)) = &self.key {
+            store.record(key, usage);
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics.record_usage(usage);
+        }
+    }
+}
+
+/// Accepts the key as a bearer token or, as Anthropic clients send it, in
+/// `x-api-key`.
+pub fn authenticate(
+    state: &ServerState,
+    headers: &HeaderMap,
+    metrics: &RequestMetrics,
+) -> ApiResult<Access> {
+    Ok(Access {
+        key: authenticate_key(state, headers)?,
+        metrics: Some(metrics.clone()),
+        admitted: false,
+    })
+}
+
+fn authenticate_key(
+    state: &ServerState,
+    headers: &HeaderMap,
+) -> ApiResult<Option<(Arc<KeyStore>, Arc<ApiKeyConfig>)>> {
+    if state.keys.is_none() && state.auth_key.is_none() {
+        return Ok(None);
+    }
+
+    let provided = match (headers.get("Authorization"), headers.get("x-api-key")) {
+        (Some(auth_header), _) => auth_header
+            .to_str()
+            .map_err(|_| unauthorized("Invalid authorization header"))?
+            .strip_prefix("Bearer ")
+            .ok_or_else(|| unauthorized("Invalid API key"))?,
+        (None, Some(api_key)) => api_key
+            .to_str()
+            .map_err(|_| unauthorized("Invalid API key header"))?,
+        (None, None) => return Err(unauthorized("Missing authorization")),
+    };
+
+    if let Some(store) = &state.keys {
+        let key = store
+            .authenticate(provided)
+            .ok_or_else(|| unauthorized("Invalid API key"))?;
+        return Ok(Some((store.clone(), key)));
+    }
+    match &state.auth_key {
+        Some(key) if constant_time_eq(key.as_bytes(), provided.as_bytes()) => Ok(None),
+        _ => Err(unauthorized("Invalid API key")),
+    }
+}
+
+/// Provider serving `model` of `provider_id`.
+pub fn resolve_provider<'a>(
+    state: &'a ServerState,
+    provider_id: &str,
+    model: &str,
+) -> ApiResult<ResolvedProvider<'a>> {
+    state
+        .providers
+        .resolve(provider_id, model)
+        .map_err(|e| internal_error(e.to_string()))?
+        .ok_or_else(|| bad_request(format!("Unknown provider: {provider_id}")))
+}
+
+/// Like [`resolve_provider`], with the request `settings` applied where the
+/// provider allows it.
+pub fn resolve_provider_with<'a>(
+    state: &'a ServerState,
+    provider_id: &str,
+    model: &str,
+    settings: RequestSettings,
+) -> ApiResult<(ResolvedProvider<'a>, Applied)> {
+    state
+        .providers
+        .resolve_with(provider_id, model, settings)
+        .map_err(|e| internal_error(e.to_string()))?
+        .ok_or_else(|| bad_request(format!("Unknown provider: {provider_id}")))
+}
+
+pub fn build_response(
+    model: String,
+    message: Message,
+    finish_reason: &str,
+    usage: Option<Usage>,
+) -> ChatResponse {
+    ChatResponse {
+        id: format!("chatcmpl-{}", Uuid::new_v4()),
+        object: "chat.completion".to_string(),
+        created: unix_timestamp(),
+        model,
+        choices: vec![Choice {
+            index: 0,
+            message,
+            finish_reason: finish_reason.to_string(),
+        }],
+        usage,
+    }
+}
+
+pub fn unix_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
 pub fn parse_model(model: &str) -> ApiResult<(String, String)> {
     let (provider_id, model_name) = model
         .split_once(':')
@@ -23,25 +236,3 @@ pub fn parse_model(model: &str) -> ApiResult<(String, String)> {
     }
     Ok((provider_id.to_string(), model_name.to_string()))
 }
-
-pub fn transform_response(resp: String, transform: &str) -> String {
-    match transform {
-        "extract_think" => resp
-            .lines()
-            .skip_while(|line| !line.contains("<think>"))
-            .take_while(|line| !line.contains("</think>"))
-            .map(|line| line.replace("<think>", "").trim().to_string())
-            .filter(|line| !line.is_empty())
-            .collect::<Vec<_>>()
-            .join("\n"),
-        "trim_whitespace" => resp.trim().to_string(),
-        "extract_json" => {
-            let json_start = resp.find("```json").unwrap_or(0);
-            let json_end = resp.find("```").unwrap_or(resp.len());
-            let json_str = &resp[json_start..json_end];
-            serde_json::from_str::<String>(json_str)
-                .unwrap_or_else(|_| "Invalid JSON response".to_string())
-        }
-        _ => resp,
-    }
-}
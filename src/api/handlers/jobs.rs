@@ -0,0 +1,163 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+use serde_json::Value;
+
+use super::chain::{chain_providers, chain_step_ids, run_named_chain};
+use super::chat::chat_completion;
+use super::helpers::{
+    authenticate, bad_request, internal_error, not_found, parse_model, service_unavailable, Access,
+    ApiResult, OnStep,
+};
+use crate::api::jobs::{Job, JobError, Jobs};
+use crate::api::metrics::RequestMetrics;
+use crate::api::types::JobRequest;
+use crate::api::ServerState;
+
+/// Queues a chat or chain request and answers 202 with the job to poll.
+///
+/// The key allowlist and rate limits apply when the job is submitted, not when it
+/// runs.
+pub async fn handle_create_job(
+    State(state): State<ServerState>,
+    Extension(metrics): Extension<RequestMetrics>,
+    headers: HeaderMap,
+    Json(req): Json<JobRequest>,
+) -> ApiResult<(StatusCode, Json<Job>)> {
+    let jobs = enabled(&state)?;
+    let access = authenticate(&state, &headers, &metrics)?;
+
+    let steps = match &req {
+        JobRequest::Chain { chain, .. } => {
+            let chain = state
+                .chains
+                .get(chain)
+                .ok_or_else(|| not_found(format!("Unknown chain: {chain}")))?;
+            for provider_id in chain_providers(chain) {
+                access.check(&provider_id, None)?;
+            }
+            chain.steps.iter().map(|step| step.id.clone()).collect()
+        }
+        JobRequest::Chat(req) => {
+            if req.stream {
+                return Err(bad_request("Jobs cannot stream"));
+            }
+            if req.model.is_none() && req.steps.is_empty() {
+                return Err(bad_request("Model is required"));
+            }
+            let initial = req.model.as_deref().map(parse_model).transpose()?;
+            match initial {
+                Some((provider_id, model)) if req.steps.is_empty() => {
+                    access.check(&provider_id, Some(&model))?;
+                }
+                initial => {
+                    let steps = req.steps.iter().map(|step| step.provider_id.clone());
+                    for provider_id in initial.map(|(id, _)| id).into_iter().chain(steps) {
+                        access.check(&provider_id, None)?;
+                    }
+                }
+            }
+            chain_step_ids(req)
+        }
+    };
+    access.admit()?;
+    let access = access.admitted();
+    let job = Job::new(access.key_name().map(str::to_string), steps);
+    let on_step: OnStep = {
+        let jobs = jobs.clone();
+        let id = job.id.clone();
+        Arc::new(move |step, output| jobs.step_completed(&id, step, output))
+    };
+
+    let work = run(state.clone(), req, access, on_step);
+    let job = jobs
+        .submit(job, async move {
+            work.await.map_err(|(status, message)| JobError {
+                status: status.as_u16(),
+                message,
+            })
+        })
+        .await
+        .map_err(|e| internal_error(e.to_string()))?
+        .ok_or_else(|| service_unavailable("Too many jobs are queued"))?;
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+/// Status, step progress and, once finished, the result or error of a job.
+pub async fn handle_get_job(
+    State(state): State<ServerState>,
+    Path(id): Path<String>,
+    Extension(metrics): Extension<RequestMetrics>,
+    headers: HeaderMap,
+) -> ApiResult<Json<Job>> {
+    let jobs = enabled(&state)?;
+    let access = authenticate(&state, &headers, &metrics)?;
+    owned_job(jobs, &id, &access).await.map(Json)
+}
+
+/// Cancels a queued or running job, which is kept as cancelled; a finished job
+/// is removed.
+pub async fn handle_cancel_job(
+    State(state): State<ServerState>,
+    Path(id): Path<String>,
+    Extension(metrics): Extension<RequestMetrics>,
+    headers: HeaderMap,
+) -> ApiResult<Json<Job>> {
+    let jobs = enabled(&state)?;
+    let access = authenticate(&state, &headers, &metrics)?;
+    let job = owned_job(jobs, &id, &access).await?;
+
+    if job.status.is_finished() {
+        jobs.remove(&id)
+            .await
+            .map_err(|e| internal_error(e.to_string()))?;
+        return Ok(Json(job));
+    }
+    jobs.cancel(&id)
+        .await
+        .map_err(|e| internal_error(e.to_string()))?
+        .map(Json)
+        .ok_or_else(|| not_found(format!("Unknown job: {id}")))
+}
+
+fn enabled(state: &ServerState) -> ApiResult<&Arc<Jobs>> {
+    state
+        .jobs
+        .as_ref()
+        .ok_or_else(|| not_found("Jobs are not enabled"))
+}
+
+/// Jobs of other keys are reported as unknown.
+async fn owned_job(jobs: &Jobs, id: &str, access: &Access) -> ApiResult<Job> {
+    jobs.get(id)
+        .await
+        .map_err(|e| internal_error(e.to_string()))?
+        .filter(|job| job.owner.as_deref() == access.key_name())
+        .ok_or_else(|| not_found(format!("Unknown job: {id}")))
+}
+
+/// Answers the request as its synchronous endpoint would.
+async fn run(
+    state: ServerState,
+    req: JobRequest,
+    access: Access,
+    on_step: OnStep,
+) -> ApiResult<Value> {
+    match req {
+        JobRequest::Chain { chain, request } => {
+            let response = run_named_chain(state, chain, request, access, Some(on_step)).await?;
+            serde_json::to_value(response).map_err(|e| internal_error(e.to_string()))
+        }
+        JobRequest::Chat(req) => {
+            let response = chat_completion(state, *req, access, Some(on_step)).await?;
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .map_err(|e| internal_error(e.to_string()))?;
+            serde_json::from_slice(&body).map_err(|e| internal_error(e.to_string()))
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+
+use super::helpers::{bad_request, ApiResult};
+use crate::api::types::{ContentPart, Message, MessageContent, ResponseFormat};
+use crate::chat::{ChatMessage, ChatMessageBuilder, ChatRole, ImageMime};
+use crate::{FunctionCall, ToolCall};
+
+/// Converts OpenAI-style messages into chat messages.
+///
+/// Consecutive `tool` messages become a single tool result message, and every
+/// image part becomes its own message, the first one carrying the text.
+pub fn build_messages(messages: Vec<Message>) -> ApiResult<Vec<ChatMessage>> {
+    let mut converted = Vec::new();
+    let mut tool_names = HashMap::new();
+    let mut results = Vec::new();
+
+    for message in messages {
+        let text = message.text();
+        if message.role == "tool" {
+            let id = message
+                .tool_call_id
+                .ok_or_else(|| bad_request("Tool message without tool_call_id"))?;
+            let name = tool_names
+                .get(&id)
+                .cloned()
+                .or(message.name)
+                .unwrap_or_default();
+            results.push(ToolCall {
+                id,
+                call_type: "function".to_string(),
+                function: FunctionCall {
+                    name,
+                    arguments: text,
+                },
+            });
+            continue;
+        }
+        flush_results(&mut converted, &mut results);
+
+        let role = parse_role(&message.role);
+        match message.tool_calls.filter(|calls| !calls.is_empty()) {
+            Some(calls) if role == ChatRole::Assistant => {
+                for call in &calls {
+                    tool_names.insert(call.id.clone(), call.function.name.clone());
+                }
+                converted.push(
+                    ChatMessage::assistant()
+                        .tool_use(calls)
+                        .content(text)
+                        .build(),
+                );
+            }
+            _ => push_content(&mut converted, role, message.content, text)?,
+        }
+    }
+    flush_results(&mut converted, &mut results);
+    Ok(converted)
+}
+
+fn flush_results(converted: &mut Vec<ChatMessage>, results: &mut Vec<ToolCall>) {
+    if !results.is_empty() {
+        converted.push(
+            ChatMessage::user()
+                .tool_result(std::mem::take(results))
+                .build(),
+        );
+    }
+}
+
+fn push_content(
+    converted: &mut Vec<ChatMessage>,
+    role: ChatRole,
+    content: Option<MessageContent>,
+    text: String,
+) -> ApiResult<()> {
+    let images: Vec<String> = match content {
+        Some(MessageContent::Parts(parts)) => parts
+            .into_iter()
+            .filter_map(|part| match part {
+                ContentPart::ImageUrl { image_url } => Some(image_url.url),
+                ContentPart::Text { .. } => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    if images.is_empty() {
+        converted.push(ChatMessageBuilder::new(role).content(text).build());
+        return Ok(());
+    }
+    for (index, url) in images.into_iter().enumerate() {
+        let builder = ChatMessageBuilder::new(role.clone());
+        let builder = if index == 0 {
+            builder.content(text.clone())
+        } else {
+            builder
+        };
+        converted.push(image_message(builder, url)?.build());
+    }
+    Ok(())
+}
+
+/// Decodes `data:` URLs; other URLs are passed to the provider as is.
+fn image_message(builder: ChatMessageBuilder, url: String) -> ApiResult<ChatMessageBuilder> {
+    let Some(data) = url.strip_prefix("data:") else {
+        return Ok(builder.image_url(url));
+    };
+    let (mime, payload) = data
+        .split_once(";base64,")
+        .ok_or_else(|| bad_request("Image data URLs must be base64 encoded"))?;
+    let (mime, bytes) = decode_image(mime, payload)?;
+    Ok(builder.image(mime, bytes))
+}
+
+/// Decodes a base64 image of a supported media type.
+pub fn decode_image(media_type: &str, data: &str) -> ApiResult<(ImageMime, Vec<u8>)> {
+    let mime = match media_type {
+        "image/jpeg" | "image/jpg" => ImageMime::JPEG,
+        "image/png" => ImageMime::PNG,
+        "image/gif" => ImageMime::GIF,
+        "image/webp" => ImageMime::WEBP,
+        other => return Err(bad_request(format!("Unsupported image type: {other}"))),
+    };
+    let bytes = BASE64
+        .decode(data)
+        .map_err(|e| bad_request(format!("Invalid image data: {e}")))?;
+    Ok((mime, bytes))
+}
+
+/// Removes the system and developer messages, returning their text joined by blank
+/// lines.
+pub fn take_system(messages: &mut Vec<Message>) -> Option<String> {
+    let mut system = Vec::new();
+    messages.retain(|message| {
+        let is_system = matches!(message.role.as_str(), "system" | "developer");
+        if is_system {
+            system.push(message.text());
+        }
+        !is_system
+    });
+    Some(system.join("\n\n")).filter(|text| !text.is_empty())
+}
+
+/// Unknown roles are sent as user messages; system messages are taken out first
+/// with [`take_system`].
+fn parse_role(role: &str) -> ChatRole {
+    match role {
+        "assistant" => ChatRole::Assistant,
+        _ => ChatRole::User,
+    }
+}
+
+/// Instruction appended to the conversation for a requested response format, when
+/// the provider cannot enforce it natively. The handler strips code fences around
+/// the answer.
+pub fn response_format_instruction(format: &ResponseFormat) -> Option<String> {
+    match format {
+        ResponseFormat::Text => None,
+        ResponseFormat::JsonObject => {
+            Some("Respond with a single JSON object and nothing else.".to_string())
+        }
+        ResponseFormat::JsonSchema { json_schema } => {
+            let schema = json_schema
+                .schema
+                .as_ref()
+                .map(|schema| format!(":\n{schema}"))
+                .unwrap_or_default();
+            Some(format!(
+                "Respond with a single JSON value matching the schema '{}' and nothing else{schema}",
+                json_schema.name
+            ))
+        }
+    }
+}
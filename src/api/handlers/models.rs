@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use axum::{extract::State, http::HeaderMap, Extension, Json};
+use futures::future::join_all;
+
+use super::helpers::{authenticate, ApiResult};
+use crate::api::metrics::RequestMetrics;
+use crate::api::types::{ModelList, ModelObject};
+use crate::api::ServerState;
+
+/// Time a provider has to list its models before `/v1/models` leaves it out.
+const LIST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Lists the models of every provider the key may use as "provider:model";
+/// providers that cannot list their models in time are left out.
+pub async fn handle_models(
+    State(state): State<ServerState>,
+    Extension(metrics): Extension<RequestMetrics>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ModelList>> {
+    let access = authenticate(&state, &headers, &metrics)?;
+
+    let providers = state.providers.all();
+    let listings = join_all(providers.iter().map(|(id, provider)| async move {
+        let listing = tokio::time::timeout(LIST_TIMEOUT, provider.list_models(None)).await;
+        (id.as_str(), listing)
+    }))
+    .await;
+
+    let mut data = Vec::new();
+    for (provider_id, listing) in listings {
+        let models = match listing {
+            Ok(Ok(models)) => models.get_models_raw(),
+            Ok(Err(err)) => {
+                log::warn!("Could not list models of provider '{provider_id}': {err}");
+                continue;
+            }
+            Err(_) => {
+                log::warn!("Listing the models of provider '{provider_id}' timed out");
+                continue;
+            }
+        };
+        for model in models {
+            let model_id = model.get_id();
+            if !access.allows(provider_id, Some(&model_id)) {
+                continue;
+            }
+            data.push(ModelObject {
+                id: format!("{provider_id}:{model_id}"),
+                object: "model".to_string(),
+                created: model.get_created_at().timestamp(),
+                owned_by: provider_id.to_string(),
+            });
+        }
+    }
+    data.sort_by(|a, b| a.id.cmp(&b.id));
+
+    Ok(Json(ModelList {
+        object: "list".to_string(),
+        data,
+    }))
+}
@@ -0,0 +1,290 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{
+        ws::{Message as Frame, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::{HeaderMap, HeaderValue},
+    response::Response,
+    Extension,
+};
+use futures::StreamExt;
+use serde::Deserialize;
+use tokio::sync::OwnedMutexGuard;
+
+use super::helpers::{
+    authenticate, bad_request, conflict, internal_error, not_found, parse_model,
+    service_unavailable, unauthorized, Access, ApiResult,
+};
+use crate::api::metrics::RequestMetrics;
+use crate::api::sessions::{Session, SessionState, Sessions};
+use crate::api::types::{Message, MessageContent, SessionClientEvent, SessionEvent};
+use crate::api::ServerState;
+use crate::chat::{ChatMessage, ChatProvider, ChatRole, MessageType, StreamChunk, Tool};
+use crate::{FunctionCall, ToolCall};
+
+/// Query of the session endpoints; browsers cannot set headers on a WebSocket, so
+/// the key may also be passed as `api_key`.
+#[derive(Deserialize)]
+pub struct SessionQuery {
+    model: Option<String>,
+    api_key: Option<String>,
+}
+
+/// Opens a session on `?model=provider:model` and upgrades to a WebSocket.
+pub async fn handle_new_session(
+    State(state): State<ServerState>,
+    Extension(metrics): Extension<RequestMetrics>,
+    Query(query): Query<SessionQuery>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> ApiResult<Response> {
+    let sessions = enabled(&state)?;
+    let headers = with_query_key(headers, query.api_key.as_deref())?;
+    let access = authenticate(&state, &headers, &metrics)?;
+
+    let model = query
+        .model
+        .ok_or_else(|| bad_request("Model is required"))?;
+    let (provider_id, model_name) = parse_model(&model)?;
+    access.check(&provider_id, Some(&model_name))?;
+    let provider = state
+        .providers
+        .resolve_shared(&provider_id, &model_name)
+        .map_err(|e| internal_error(e.to_string()))?
+        .ok_or_else(|| bad_request(format!("Unknown provider: {provider_id}")))?;
+    access.label(&provider_id, &model_name);
+    let session = sessions
+        .create(access.key_name().map(str::to_string), model, provider)
+        .ok_or_else(|| service_unavailable("Too many sessions are open"))?;
+    connect(ws, session, access, false)
+}
+
+/// Reconnects to a session, replaying its history and pending tool calls.
+pub async fn handle_resume_session(
+    State(state): State<ServerState>,
+    Path(id): Path<String>,
+    Extension(metrics): Extension<RequestMetrics>,
+    Query(query): Query<SessionQuery>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> ApiResult<Response> {
+    let sessions = enabled(&state)?;
+    let headers = with_query_key(headers, query.api_key.as_deref())?;
+    let access = authenticate(&state, &headers, &metrics)?;
+
+    let session = sessions
+        .get(&id)
+        .filter(|session| session.owner.as_deref() == access.key_name())
+        .ok_or_else(|| not_found(format!("Unknown session: {id}")))?;
+    connect(ws, session, access, true)
+}
+
+fn enabled(state: &ServerState) -> ApiResult<&Arc<Sessions>> {
+    state
+        .sessions
+        .as_ref()
+        .ok_or_else(|| not_found("Sessions are not enabled"))
+}
+
+fn with_query_key(mut headers: HeaderMap, key: Option<&str>) -> ApiResult<HeaderMap> {
+    let missing = !headers.contains_key("Authorization") && !headers.contains_key("x-api-key");
+    if let (Some(key), true) = (key, missing) {
+        let value = HeaderValue::from_str(key).map_err(|_| unauthorized("Invalid API key"))?;
+        headers.insert("x-api-key", value);
+    }
+    Ok(headers)
+}
+
+/// Takes the session for this client, refusing a second connection.
+fn connect(
+    ws: WebSocketUpgrade,
+    session: Arc<Session>,
+    access: Access,
+    resumed: bool,
+) -> ApiResult<Response> {
+    let state = session
+        .state
+        .clone()
+        .try_lock_owned()
+        .map_err(|_| conflict("Session is already connected"))?;
+    Ok(ws.on_upgrade(move |socket| run_session(socket, session, state, access, resumed)))
+}
+
+async fn run_session(
+    mut socket: WebSocket,
+    session: Arc<Session>,
+    mut state: OwnedMutexGuard<SessionState>,
+    access: Access,
+    resumed: bool,
+) {
+    session.touch();
+    let history = if resumed {
+        history(session.chat.memory_contents().await)
+    } else {
+        Vec::new()
+    };
+    let hello = SessionEvent::Session {
+        id: session.id.clone(),
+        model: session.model.clone(),
+        resumed,
+        history,
+        pending_tool_calls: state.pending.clone(),
+    };
+
+    let mut connected = send(&mut socket, &hello).await;
+    while connected {
+        let text = match socket.recv().await {
+            Some(Ok(Frame::Text(text))) => text,
+            Some(Ok(Frame::Close(_))) | Some(Err(_)) | None => break,
+            Some(Ok(_)) => continue,
+        };
+        let turn = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid session message: {e}"))
+            .and_then(|event| next_turn(&mut state, &access, event));
+        connected = match turn {
+            Ok(Some(message)) => {
+                run_turn(&mut socket, &session, &mut state, &access, message).await
+            }
+            Ok(None) => true,
+            Err(message) => send(&mut socket, &SessionEvent::Error { message }).await,
+        };
+    }
+    session.touch();
+}
+
+/// The message starting the next turn, once the client has said or answered
+/// everything the model waits for.
+///
+/// The turn is counted against the rate limits of the key before the session state
+/// changes, so a refused turn leaves the message or last tool result to be sent again.
+fn next_turn(
+    state: &mut SessionState,
+    access: &Access,
+    event: SessionClientEvent,
+) -> Result<Option<ChatMessage>, String> {
+    match event {
+        SessionClientEvent::Message { content, tools } => {
+            if !state.pending.is_empty() {
+                return Err("Tool calls are waiting for a result".to_string());
+            }
+            access.admit().map_err(|(_, message)| message)?;
+            if let Some(tools) = tools {
+                state.tools = Some(tools.into_iter().map(Tool::from).collect())
+                    .filter(|tools: &Vec<Tool>| !tools.is_empty());
+            }
+            Ok(Some(ChatMessage::user().content(content).build()))
+        }
+        SessionClientEvent::ToolResult {
+            tool_call_id,
+            content,
+        } => {
+            let position = state
+                .pending
+                .iter()
+                .position(|call| call.id == tool_call_id)
+                .ok_or_else(|| format!("Unknown tool call: {tool_call_id}"))?;
+            if state.pending.len() == 1 {
+                access.admit().map_err(|(_, message)| message)?;
+            }
+            let call = state.pending.remove(position);
+            state.results.push(ToolCall {
+                id: call.id,
+                call_type: "function".to_string(),
+                function: FunctionCall {
+                    name: call.function.name,
+                    arguments: content,
+                },
+            });
+            if !state.pending.is_empty() {
+                return Ok(None);
+            }
+            let results = std::mem::take(&mut state.results);
+            Ok(Some(ChatMessage::user().tool_result(results).build()))
+        }
+    }
+}
+
+/// Streams the assistant turn to the client. The turn is read to the end even if
+/// the client leaves, so the session remembers it for the next connection.
+async fn run_turn(
+    socket: &mut WebSocket,
+    session: &Session,
+    state: &mut SessionState,
+    access: &Access,
+    message: ChatMessage,
+) -> bool {
+    let chunks = session
+        .chat
+        .chat_stream_with_tools(&[message], state.tools.as_deref())
+        .await;
+    let mut chunks = match chunks {
+        Ok(chunks) => chunks,
+        Err(err) => {
+            let message = err.to_string();
+            return send(socket, &SessionEvent::Error { message }).await;
+        }
+    };
+
+    let mut connected = true;
+    while let Some(chunk) = chunks.next().await {
+        let event = match chunk {
+            Ok(chunk) => {
+                match &chunk {
+                    StreamChunk::ToolUseComplete { tool_call, .. } => {
+                        state.pending.push(tool_call.clone())
+                    }
+                    StreamChunk::Usage(usage) => access.record(Some(usage)),
+                    _ => {}
+                }
+                SessionEvent::Chunk { chunk }
+            }
+            Err(err) => SessionEvent::Error {
+                message: err.to_string(),
+            },
+        };
+        if connected {
+            connected = send(socket, &event).await;
+        }
+    }
+    connected
+}
+
+async fn send(socket: &mut WebSocket, event: &SessionEvent) -> bool {
+    let Ok(text) = serde_json::to_string(event) else {
+        return false;
+    };
+    socket.send(Frame::Text(text)).await.is_ok()
+}
+
+/// Remembered messages in the chat completions format.
+fn history(messages: Vec<ChatMessage>) -> Vec<Message> {
+    messages
+        .into_iter()
+        .flat_map(|message| match message.message_type {
+            MessageType::ToolResult(results) => results
+                .into_iter()
+                .map(|result| Message {
+                    role: "tool".to_string(),
+                    content: Some(MessageContent::Text(result.function.arguments)),
+                    tool_calls: None,
+                    tool_call_id: Some(result.id),
+                    name: Some(result.function.name),
+                })
+                .collect(),
+            MessageType::ToolUse(calls) => {
+                let mut assistant = Message::assistant(message.content);
+                assistant.tool_calls = Some(calls);
+                vec![assistant]
+            }
+            _ => {
+                let mut text = Message::assistant(message.content);
+                if message.role == ChatRole::User {
+                    text.role = "user".to_string();
+                }
+                vec![text]
+            }
+        })
+        .collect()
+}
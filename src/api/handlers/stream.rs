@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::pin::Pin;
+
+use axum::response::{
+    sse::{Event, KeepAlive, Sse},
+    IntoResponse, Response,
+};
+use futures::stream::{self, Stream, StreamExt};
+use serde_json::json;
+use uuid::Uuid;
+
+use super::helpers::{unix_timestamp, Access};
+use crate::api::types::{
+    ChatCompletionChunk, ChunkChoice, ChunkDelta, FunctionCallDelta, ToolCallDelta,
+};
+use crate::chat::{ChatMessage, StreamChunk, StreamResponse, Tool, Usage};
+use crate::error::LLMError;
+use crate::{LLMProvider, ToolCall};
+
+pub type PieceStream = Pin<Box<dyn Stream<Item = Result<Piece, LLMError>> + Send>>;
+
+/// Provider stream item, whichever streaming method produced it.
+pub enum Piece {
+    Chunk(StreamChunk),
+    /// A complete tool call from a structured stream, which has no block index.
+    ToolCall(ToolCall),
+    Usage(Usage),
+}
+
+impl From<StreamChunk> for Piece {
+    fn from(chunk: StreamChunk) -> Self {
+        match chunk {
+            StreamChunk::Usage(usage) => Piece::Usage(usage),
+            chunk => Piece::Chunk(chunk),
+        }
+    }
+}
+
+/// Opens the richest stream the provider supports: tool-aware chunks when tools are
+/// given, otherwise structured deltas (which carry usage), then tool-aware chunks,
+/// then plain text.
+pub async fn open_stream(
+    provider: &dyn LLMProvider,
+    messages: &[ChatMessage],
+    tools: Option<&[Tool]>,
+) -> Result<PieceStream, LLMError> {
+    if tools.is_none() {
+        match provider.chat_stream_struct(messages).await {
+            Ok(responses) => return Ok(Box::pin(responses.flat_map(struct_pieces))),
+            Err(err) if !is_unsupported(&err) => return Err(err),
+            Err(_) => {}
+        }
+    }
+    match provider.chat_stream_with_tools(messages, tools).await {
+        Ok(chunks) => return Ok(Box::pin(chunks.map(|c| c.map(Piece::from)))),
+        Err(err) if tools.is_some() || !is_unsupported(&err) => return Err(err),
+        Err(_) => {}
+    }
+    let texts = provider.chat_stream(messages).await?;
+    Ok(Box::pin(texts.map(|t| {
+        t.map(|text| Piece::Chunk(StreamChunk::Text(text)))
+    })))
+}
+
+/// Records the usage reported by the stream against the key of the request.
+pub fn metered(pieces: PieceStream, access: Access) -> PieceStream {
+    Box::pin(pieces.inspect(move |piece| {
+        if let Ok(Piece::Usage(usage) | Piece::Chunk(StreamChunk::Usage(usage))) = piece {
+            access.record(Some(usage));
+        }
+    }))
+}
+
+/// Streams a complete answer, for responses that cannot be produced incrementally.
+pub fn single_piece(text: String) -> PieceStream {
+    Box::pin(stream::iter([
+        Ok(Piece::Chunk(StreamChunk::Text(text))),
+        Ok(Piece::Chunk(StreamChunk::Done {
+            stop_reason: "stop".to_string(),
+        })),
+    ]))
+}
+
+/// Encodes a provider stream as OpenAI `chat.completion.chunk` server-sent events
+/// terminated by `[DONE]`.
+pub fn sse_response(pieces: PieceStream, model: String, include_usage: bool) -> Response {
+    let encoder = ChunkEncoder::new(model, include_usage);
+    let events = stream::unfold(Some((pieces, encoder)), |state| async move {
+        let (mut pieces, mut encoder) = state?;
+        match pieces.next().await {
+            Some(Ok(piece)) => {
+                let events = encoder.piece(piece).into_iter().map(chunk_event).collect();
+                Some((events, Some((pieces, encoder))))
+            }
+            Some(Err(err)) => {
+                let error =
+                    json!({ "error": { "message": err.to_string(), "type": "server_error" } });
+                let events = vec![Event::default().data(error.to_string()), done_event()];
+                Some((events, None))
+            }
+            None => {
+                let mut events: Vec<Event> =
+                    encoder.finish().into_iter().map(chunk_event).collect();
+                events.push(done_event());
+                Some((events, None))
+            }
+        }
+    })
+    .flat_map(|events: Vec<Event>| stream::iter(events.into_iter().map(Ok::<_, Infallible>)));
+
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+fn chunk_event(chunk: ChatCompletionChunk) -> Event {
+    Event::default().data(serde_json::to_string(&chunk).unwrap_or_default())
+}
+
+fn done_event() -> Event {
+    Event::default().data("[DONE]")
+}
+
+fn struct_pieces(
+    response: Result<StreamResponse, LLMError>,
+) -> impl Stream<Item = Result<Piece, LLMError>> {
+    let pieces = match response {
+        Err(err) => vec![Err(err)],
+        Ok(response) => {
+            let mut pieces = Vec::new();
+            for choice in response.choices {
+                if let Some(text) = choice.delta.content.filter(|t| !t.is_empty()) {
+                    pieces.push(Ok(Piece::Chunk(StreamChunk::Text(text))));
+                }
+                for tool_call in choice.delta.tool_calls.unwrap_or_default() {
+                    pieces.push(Ok(Piece::ToolCall(tool_call)));
+                }
+            }
+            if let Some(usage) = response.usage {
+                pieces.push(Ok(Piece::Usage(usage)));
+            }
+            pieces
+        }
+    };
+    stream::iter(pieces)
+}
+
+/// Matches the errors returned by the default `ChatProvider` streaming methods.
+fn is_unsupported(err: &LLMError) -> bool {
+    matches!(err, LLMError::Unsupported(_))
+}
+
+/// Turns provider stream items into OpenAI chunks.
+struct ChunkEncoder {
+    id: String,
+    created: u64,
+    model: String,
+    include_usage: bool,
+    role_sent: bool,
+    /// Provider block index to OpenAI tool call index, for calls already announced.
+    tool_indices: HashMap<usize, usize>,
+    tool_count: usize,
+    stop_reason: Option<String>,
+    usage: Option<Usage>,
+}
+
+impl ChunkEncoder {
+    fn new(model: String, include_usage: bool) -> Self {
+        Self {
+            id: format!("chatcmpl-{}", Uuid::new_v4()),
+            created: unix_timestamp(),
+            model,
+            include_usage,
+            role_sent: false,
+            tool_indices: HashMap::new(),
+            tool_count: 0,
+            stop_reason: None,
+            usage: None,
+        }
+    }
+
+    fn piece(&mut self, piece: Piece) -> Vec<ChatCompletionChunk> {
+        let delta = match piece {
+            Piece::Usage(usage) | Piece::Chunk(StreamChunk::Usage(usage)) => {
+                self.usage = Some(usage);
+                return Vec::new();
+            }
+            Piece::Chunk(StreamChunk::Done { stop_reason }) => {
+                self.stop_reason = Some(stop_reason);
+                return Vec::new();
+            }
+            Piece::Chunk(StreamChunk::Text(text)) => ChunkDelta {
+                content: Some(text),
+                ..ChunkDelta::default()
+            },
+            Piece::Chunk(StreamChunk::ToolUseStart { index, id, name }) => {
+                let position = self.tool_position(index);
+                tool_delta(position, Some(id), Some(name), Some(String::new()))
+            }
+            Piece::Chunk(StreamChunk::ToolUseInputDelta {
+                index,
+                partial_json,
+            }) => {
+                let position = self.tool_position(index);
+                tool_delta(position, None, None, Some(partial_json))
+            }
+            Piece::Chunk(StreamChunk::ToolUseComplete { index, tool_call }) => {
+                // Already streamed through start and input deltas.
+                if self.tool_indices.contains_key(&index) {
+                    return Vec::new();
+                }
+                let position = self.tool_position(index);
+                complete_tool_delta(position, tool_call)
+            }
+            Piece::ToolCall(tool_call) => {
+                let position = self.next_tool_position();
+                complete_tool_delta(position, tool_call)
+            }
+        };
+        vec![self.chunk(delta, None)]
+    }
+
+    /// The final choice chunk with the finish reason, then the usage chunk.
+    fn finish(&mut self) -> Vec<ChatCompletionChunk> {
+        let finish_reason = finish_reason(self.stop_reason.as_deref(), self.tool_count > 0);
+        let mut chunks = vec![self.chunk(ChunkDelta::default(), Some(finish_reason))];
+        if let Some(usage) = self.usage.take().filter(|_| self.include_usage) {
+            let mut chunk = self.chunk(ChunkDelta::default(), None);
+            chunk.choices.clear();
+            chunk.usage = Some(usage);
+            chunks.push(chunk);
+        }
+        chunks
+    }
+
+    fn chunk(
+        &mut self,
+        mut delta: ChunkDelta,
+        finish_reason: Option<String>,
+    ) -> ChatCompletionChunk {
+        if !self.role_sent {
+            self.role_sent = true;
+            delta.role = Some("assistant".to_string());
+        }
+        ChatCompletionChunk {
+            id: self.id.clone(),
+            object: "chat.completion.chunk".to_string(),
+            created: self.created,
+            model: self.model.clone(),
+            choices: vec![ChunkChoice {
+                index: 0,
+                delta,
+                finish_reason,
+            }],
+            usage: None,
+        }
+    }
+
+    fn tool_position(&mut self, index: usize) -> usize {
+        if let Some(position) = self.tool_indices.get(&index) {
+            return *position;
+        }
+        let position = self.next_tool_position();
+        self.tool_indices.insert(index, position);
+        position
+    }
+
+    fn next_tool_position(&mut self) -> usize {
+        self.tool_count += 1;
+        self.tool_count - 1
+    }
+}
+
+fn tool_delta(
+    index: usize,
+    id: Option<String>,
+    name: Option<String>,
+    arguments: Option<String>,
+) -> ChunkDelta {
+    let call_type = id.as_ref().map(|_| "function".to_string());
+    ChunkDelta {
+        tool_calls: Some(vec![ToolCallDelta {
+            index,
+            id,
+            call_type,
+            function: FunctionCallDelta { name, arguments },
+        }]),
+        ..ChunkDelta::default()
+    }
+}
+
+fn complete_tool_delta(index: usize, call: ToolCall) -> ChunkDelta {
+    tool_delta(
+        index,
+        Some(call.id),
+        Some(call.function.name),
+        Some(call.function.arguments),
+    )
+}
+
+/// Maps provider stop reasons to OpenAI finish reasons.
+fn finish_reason(stop_reason: Option<&str>, has_tool_calls: bool) -> String {
+    match stop_reason {
+        _ if has_tool_calls => "tool_calls",
+        Some("tool_use" | "tool_calls") => "tool_calls",
+        Some("max_tokens" | "length") => "length",
+        _ => "stop",
+    }
+    .to_string()
+}
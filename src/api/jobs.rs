@@ -0,0 +1,507 @@
+//! Background jobs running chat and chain requests, polled on `/v1/jobs/{id}`.
+
+#[path = "jobs/store.rs"]
+mod store;
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Semaphore;
+use tokio::task::AbortHandle;
+use uuid::Uuid;
+
+use crate::api::handlers::helpers::unix_timestamp;
+use crate::error::LLMError;
+
+pub use store::{FileJobStore, JobStore, MemoryJobStore};
+
+/// Lifecycle of a job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Waiting for a free worker
+    Queued,
+    /// Being run by a worker
+    Running,
+    /// Finished with a result
+    Succeeded,
+    /// Finished with an error
+    Failed,
+    /// Stopped by the client before it finished
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Whether the job will not change anymore.
+    pub fn is_finished(self) -> bool {
+        matches!(self, Self::Succeeded | Self::Failed | Self::Cancelled)
+    }
+}
+
+/// Progress of a chain step of a job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    /// Not started yet
+    Pending,
+    /// Being run
+    Running,
+    /// Finished with an output
+    Completed,
+    /// Running when the job failed
+    Failed,
+}
+
+/// A chain step of a job and its output.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobStep {
+    /// Id of the step in the chain
+    pub id: String,
+    /// Progress of the step
+    pub status: StepStatus,
+    /// Output of the step, once completed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+}
+
+/// Error a failed job would have answered synchronously.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobError {
+    /// HTTP status code
+    pub status: u16,
+    /// Error message
+    pub message: String,
+}
+
+/// A chat or chain request run in the background.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    /// Job id, `job_` followed by a UUID
+    pub id: String,
+    /// Always `"job"`
+    pub object: String,
+    /// Lifecycle of the job
+    pub status: JobStatus,
+    /// Submission time, in Unix seconds
+    pub created_at: u64,
+    /// Time a worker started the job, in Unix seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<u64>,
+    /// Time the job succeeded, failed or was cancelled, in Unix seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<u64>,
+    /// Name of the API key that submitted the job
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Chain steps in the order they run; empty for plain chat requests
+    #[serde(default)]
+    pub steps: Vec<JobStep>,
+    /// Body the synchronous endpoint would have answered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    /// Why the job failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<JobError>,
+}
+
+impl Job {
+    /// A queued job running the steps with ids `steps`.
+    pub fn new(owner: Option<String>, steps: Vec<String>) -> Self {
+        Self {
+            id: format!("job_{}", Uuid::new_v4().simple()),
+            object: "job".to_string(),
+            status: JobStatus::Queued,
+            created_at: unix_timestamp(),
+            started_at: None,
+            finished_at: None,
+            owner,
+            steps: steps
+                .into_iter()
+                .map(|id| JobStep {
+                    id,
+                    status: StepStatus::Pending,
+                    output: None,
+                })
+                .collect(),
+            result: None,
+            error: None,
+        }
+    }
+
+    fn start(&mut self) {
+        if self.status != JobStatus::Queued {
+            return;
+        }
+        self.status = JobStatus::Running;
+        self.started_at = Some(unix_timestamp());
+        self.advance();
+    }
+
+    fn complete_step(&mut self, id: &str, output: &str) {
+        if self.status != JobStatus::Running {
+            return;
+        }
+        if let Some(step) = self.steps.iter_mut().find(|step| step.id == id) {
+            step.status = StepStatus::Completed;
+            step.output = Some(output.to_string());
+        }
+        self.advance();
+    }
+
+    /// Marks the first pending step running unless a step already is.
+    fn advance(&mut self) {
+        if self.steps.iter().any(|s| s.status == StepStatus::Running) {
+            return;
+        }
+        if let Some(step) = self
+            .steps
+            .iter_mut()
+            .find(|s| s.status == StepStatus::Pending)
+        {
+            step.status = StepStatus::Running;
+        }
+    }
+
+    fn finish(&mut self, result: Result<Value, JobError>) {
+        if self.status != JobStatus::Running {
+            return;
+        }
+        match result {
+            Ok(value) => {
+                self.status = JobStatus::Succeeded;
+                self.result = Some(value);
+                self.finished_at = Some(unix_timestamp());
+            }
+            Err(error) => self.fail(error),
+        }
+    }
+
+    fn fail(&mut self, error: JobError) {
+        self.status = JobStatus::Failed;
+        self.error = Some(error);
+        self.finished_at = Some(unix_timestamp());
+        self.reset_running(StepStatus::Failed);
+    }
+
+    fn cancel(&mut self) {
+        if self.status.is_finished() {
+            return;
+        }
+        self.status = JobStatus::Cancelled;
+        self.finished_at = Some(unix_timestamp());
+        self.reset_running(StepStatus::Pending);
+    }
+
+    fn reset_running(&mut self, status: StepStatus) {
+        for step in &mut self.steps {
+            if step.status == StepStatus::Running {
+                step.status = status;
+            }
+        }
+    }
+}
+
+/// Worker pool and storage of the jobs API.
+///
+/// ```no_run
+/// use llm::api::{FileJobStore, JobConfig};
+///
+/// # fn run() -> Result<(), llm::error::LLMError> {
+/// let jobs = JobConfig::new()
+///     .workers(8)
+///     .max_pending(500)
+///     .retention(std::time::Duration::from_secs(7 * 24 * 60 * 60))
+///     .store(FileJobStore::open("/var/lib/llm/jobs")?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct JobConfig {
+    workers: usize,
+    max_pending: usize,
+    retention: Duration,
+    store: Arc<dyn JobStore>,
+}
+
+impl Default for JobConfig {
+    fn default() -> Self {
+        Self {
+            workers: 4,
+            max_pending: 100,
+            retention: Duration::from_secs(24 * 60 * 60),
+            store: Arc::new(MemoryJobStore::default()),
+        }
+    }
+}
+
+impl std::fmt::Debug for JobConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JobConfig")
+            .field("workers", &self.workers)
+            .field("max_pending", &self.max_pending)
+            .field("retention", &self.retention)
+            .finish_non_exhaustive()
+    }
+}
+
+impl JobConfig {
+    /// Four workers, up to 100 queued or running jobs, kept in memory for a day
+    /// after they finish.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Jobs running at the same time; further jobs wait in the queue.
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Jobs queued or running at the same time; submissions beyond it get a 503.
+    pub fn max_pending(mut self, max_pending: usize) -> Self {
+        self.max_pending = max_pending.max(1);
+        self
+    }
+
+    /// How long a finished job can still be fetched before it is removed.
+    pub fn retention(mut self, retention: Duration) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Where jobs are kept, e.g. a [`FileJobStore`] to keep results across restarts.
+    pub fn store(mut self, store: impl JobStore + 'static) -> Self {
+        self.store = Arc::new(store);
+        self
+    }
+}
+
+/// Finished jobs are removed from the store at most this often.
+const PURGE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Runs submitted jobs on a bounded number of workers.
+///
+/// Queued and running jobs are kept in memory, where their changes apply, and
+/// each change is written to the store on the blocking thread pool.
+pub(crate) struct Jobs {
+    store: Arc<dyn JobStore>,
+    workers: Arc<Semaphore>,
+    max_pending: usize,
+    retention: Duration,
+    /// Jobs not finished, or finished and not yet written, by id
+    live: Mutex<HashMap<String, LiveJob>>,
+    last_purge: Mutex<Option<Instant>>,
+}
+
+struct LiveJob {
+    job: Job,
+    /// Number of changes made to the job
+    version: u64,
+    /// Version of the job last written to the store, so a late write of an older
+    /// version is skipped
+    written: Arc<Mutex<u64>>,
+    task: Option<AbortHandle>,
+}
+
+impl Jobs {
+    pub(crate) fn new(config: JobConfig) -> Self {
+        Self {
+            store: config.store,
+            workers: Arc::new(Semaphore::new(config.workers)),
+            max_pending: config.max_pending,
+            retention: config.retention,
+            live: Mutex::new(HashMap::new()),
+            last_purge: Mutex::new(None),
+        }
+    }
+
+    pub(crate) async fn get(&self, id: &str) -> Result<Option<Job>, LLMError> {
+        if let Some(entry) = self.live().get(id) {
+            return Ok(Some(entry.job.clone()));
+        }
+        let store = self.store.clone();
+        let id = id.to_string();
+        blocking(move || store.get(&id)).await
+    }
+
+    /// Stores the job and runs `work` once a worker is free; `Ok(None)` when the
+    /// queue is full.
+    pub(crate) async fn submit<F>(
+        self: &Arc<Self>,
+        job: Job,
+        work: F,
+    ) -> Result<Option<Job>, LLMError>
+    where
+        F: Future<Output = Result<Value, JobError>> + Send + 'static,
+    {
+        self.purge();
+        let written = Arc::new(Mutex::new(0));
+        {
+            let mut live = self.live();
+            let pending = live.values().filter(|e| !e.job.status.is_finished());
+            if pending.count() >= self.max_pending {
+                return Ok(None);
+            }
+            let entry = LiveJob {
+                job: job.clone(),
+                version: 1,
+                written: written.clone(),
+                task: None,
+            };
+            live.insert(job.id.clone(), entry);
+        }
+        let saved = {
+            let (store, job) = (self.store.clone(), job.clone());
+            blocking(move || write(&*store, &job, 1, &written)).await
+        };
+        if let Err(e) = saved {
+            self.live().remove(&job.id);
+            return Err(e);
+        }
+
+        let jobs = self.clone();
+        let id = job.id.clone();
+        // The handle is stored before the task can change the job, so a cancel
+        // either finds it or lands before the job starts.
+        let mut live = self.live();
+        let task = tokio::spawn(async move {
+            let Ok(_permit) = jobs.workers.clone().acquire_owned().await else {
+                return;
+            };
+            let started = jobs.record(&id, Job::start);
+            if !matches!(started, Some(job) if job.status == JobStatus::Running) {
+                return;
+            }
+            let result = work.await;
+            jobs.record(&id, |job| job.finish(result));
+        });
+        if let Some(entry) = live.get_mut(&job.id) {
+            entry.task = Some(task.abort_handle());
+        }
+        Ok(Some(job))
+    }
+
+    /// Records the output of a completed chain step.
+    pub(crate) fn step_completed(self: &Arc<Self>, id: &str, step: &str, output: &str) {
+        self.record(id, |job| job.complete_step(step, output));
+    }
+
+    /// Stops the job if it is queued or running; finished jobs are returned as
+    /// they are.
+    pub(crate) async fn cancel(self: &Arc<Self>, id: &str) -> Result<Option<Job>, LLMError> {
+        let task = self.live().get_mut(id).and_then(|entry| entry.task.take());
+        if let Some(task) = task {
+            task.abort();
+        }
+        match self.record(id, Job::cancel) {
+            Some(job) => Ok(Some(job)),
+            None => self.get(id).await,
+        }
+    }
+
+    pub(crate) async fn remove(&self, id: &str) -> Result<(), LLMError> {
+        let written = self.live().remove(id).map(|entry| entry.written);
+        let store = self.store.clone();
+        let id = id.to_string();
+        blocking(move || {
+            // Pending writes of the job must not bring it back.
+            let _written = written.as_deref().map(|written| {
+                let mut written = lock(written);
+                *written = u64::MAX;
+                written
+            });
+            store.remove(&id)
+        })
+        .await
+    }
+
+    /// Applies `change` to a live job and writes the result to the store in the
+    /// background; `None` when the job is not live.
+    fn record(self: &Arc<Self>, id: &str, change: impl FnOnce(&mut Job)) -> Option<Job> {
+        let mut live = self.live();
+        let entry = live.get_mut(id)?;
+        change(&mut entry.job);
+        entry.version += 1;
+        let (job, version, written) = (entry.job.clone(), entry.version, entry.written.clone());
+        drop(live);
+
+        let jobs = self.clone();
+        let snapshot = job.clone();
+        tokio::spawn(async move {
+            let store = jobs.store.clone();
+            let id = snapshot.id.clone();
+            let finished = snapshot.status.is_finished();
+            let saved = blocking(move || write(&*store, &snapshot, version, &written)).await;
+            if let Err(e) = saved {
+                log::warn!("Cannot update job {id}: {e}");
+            }
+            if finished {
+                let mut live = jobs.live();
+                if live.get(&id).is_some_and(|entry| entry.version == version) {
+                    live.remove(&id);
+                }
+            }
+        });
+        Some(job)
+    }
+
+    /// Removes the jobs finished longer than the retention ago, at most once per
+    /// [`PURGE_INTERVAL`].
+    fn purge(&self) {
+        {
+            let mut last_purge = lock(&self.last_purge);
+            if last_purge.is_some_and(|at| at.elapsed() < PURGE_INTERVAL) {
+                return;
+            }
+            *last_purge = Some(Instant::now());
+        }
+        let store = self.store.clone();
+        let before = unix_timestamp().saturating_sub(self.retention.as_secs());
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = store.remove_finished(before) {
+                log::warn!("Cannot remove finished jobs: {e}");
+            }
+        });
+    }
+
+    fn live(&self) -> MutexGuard<'_, HashMap<String, LiveJob>> {
+        lock(&self.live)
+    }
+}
+
+/// Saves `version` of the job unless a later one was written already.
+fn write(
+    store: &dyn JobStore,
+    job: &Job,
+    version: u64,
+    written: &Mutex<u64>,
+) -> Result<(), LLMError> {
+    let mut written = lock(written);
+    if *written >= version {
+        return Ok(());
+    }
+    store.save(job)?;
+    *written = version;
+    Ok(())
+}
+
+/// Runs a store call on the blocking thread pool.
+async fn blocking<T: Send + 'static>(
+    call: impl FnOnce() -> Result<T, LLMError> + Send + 'static,
+) -> Result<T, LLMError> {
+    tokio::task::spawn_blocking(call)
+        .await
+        .map_err(|e| LLMError::Generic(format!("job store task failed: {e}")))?
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+#[path = "jobs/tests.rs"]
+mod tests;
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use super::{Job, JobError};
+use crate::error::LLMError;
+
+/// Storage of job records.
+pub trait JobStore: Send + Sync {
+    /// Inserts or replaces the job.
+    fn save(&self, job: &Job) -> Result<(), LLMError>;
+
+    fn get(&self, id: &str) -> Result<Option<Job>, LLMError>;
+
+    /// Removes the job; removing an unknown job is not an error.
+    fn remove(&self, id: &str) -> Result<(), LLMError>;
+
+    /// Removes the jobs that finished before `before`, in Unix seconds.
+    fn remove_finished(&self, before: u64) -> Result<(), LLMError>;
+}
+
+fn finished_before(job: &Job, before: u64) -> bool {
+    job.status.is_finished() && job.finished_at.is_some_and(|at| at < before)
+}
+
+/// Jobs kept in memory until removed; they are lost when the server stops.
+#[derive(Debug, Default)]
+pub struct MemoryJobStore {
+    jobs: RwLock<HashMap<String, Job>>,
+}
+
+impl JobStore for MemoryJobStore {
+    fn save(&self, job: &Job) -> Result<(), LLMError> {
+        let mut jobs = self.jobs.write().unwrap_or_else(|e| e.into_inner());
+        jobs.insert(job.id.clone(), job.clone());
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Job>, LLMError> {
+        let jobs = self.jobs.read().unwrap_or_else(|e| e.into_inner());
+        Ok(jobs.get(id).cloned())
+    }
+
+    fn remove(&self, id: &str) -> Result<(), LLMError> {
+        let mut jobs = self.jobs.write().unwrap_or_else(|e| e.into_inner());
+        jobs.remove(id);
+        Ok(())
+    }
+
+    fn remove_finished(&self, before: u64) -> Result<(), LLMError> {
+        let mut jobs = self.jobs.write().unwrap_or_else(|e| e.into_inner());
+        jobs.retain(|_, job| !finished_before(job, before));
+        Ok(())
+    }
+}
+
+/// Jobs kept as one JSON file each in a directory, so results outlive the server.
+#[derive(Debug)]
+pub struct FileJobStore {
+    dir: PathBuf,
+}
+
+impl FileJobStore {
+    /// Opens the directory, creating it if needed. Jobs left queued or running by
+    /// a previous server are marked failed since nothing will finish them.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, LLMError> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir).map_err(|e| io_error(&dir, e))?;
+        let store = Self { dir };
+
+        for path in store.files()? {
+            let mut job = read_job(&path)?;
+            if !job.status.is_finished() {
+                job.fail(JobError {
+                    status: 503,
+                    message: "Job was interrupted by a server restart".to_string(),
+                });
+                store.save(&job)?;
+            }
+        }
+        Ok(store)
+    }
+
+    /// Paths of the job records.
+    fn files(&self) -> Result<Vec<PathBuf>, LLMError> {
+        let entries = std::fs::read_dir(&self.dir).map_err(|e| io_error(&self.dir, e))?;
+        let mut files = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(|e| io_error(&self.dir, e))?.path();
+            if path.extension().and_then(|e| e.to_str()) == Some("json") {
+                files.push(path);
+            }
+        }
+        Ok(files)
+    }
+
+    fn path(&self, id: &str) -> Result<PathBuf, LLMError> {
+        let valid = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid {
+            return Err(LLMError::InvalidRequest(format!("Invalid job id: {id}")));
+        }
+        Ok(self.dir.join(format!("{id}.json")))
+    }
+}
+
+impl JobStore for FileJobStore {
+    /// Writes a temporary file renamed over the record, so readers never see a
+    /// partial job.
+    fn save(&self, job: &Job) -> Result<(), LLMError> {
+        let path = self.path(&job.id)?;
+        let tmp = path.with_extension("json.tmp");
+        let content = serde_json::to_vec_pretty(job)?;
+        std::fs::write(&tmp, content).map_err(|e| io_error(&tmp, e))?;
+        std::fs::rename(&tmp, &path).map_err(|e| io_error(&path, e))
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Job>, LLMError> {
+        let Ok(path) = self.path(id) else {
+            return Ok(None);
+        };
+        if !path.exists() {
+            return Ok(None);
+        }
+        read_job(&path).map(Some)
+    }
+
+    fn remove(&self, id: &str) -> Result<(), LLMError> {
+        let path = self.path(id)?;
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(io_error(&path, e)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Files that cannot be read are kept and logged.
+    fn remove_finished(&self, before: u64) -> Result<(), LLMError> {
+        for path in self.files()? {
+            match read_job(&path) {
+                Ok(job) if finished_before(&job, before) => self.remove(&job.id)?,
+                Ok(_) => {}
+                Err(e) => log::warn!("Skipping job file: {e}"),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_job(path: &Path) -> Result<Job, LLMError> {
+    let content = std::fs::read(path).map_err(|e| io_error(path, e))?;
+    serde_json::from_slice(&content)
+        .map_err(|e| LLMError::Generic(format!("invalid job file {}: {e}", path.display())))
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> LLMError {
+    LLMError::Generic(format!("job store {}: {e}", path.display()))
+}
@@ -0,0 +1,234 @@
+use serde_json::json;
+
+use super::*;
+
+fn job(steps: &[&str]) -> Job {
+    Job::new(
+        Some("team".to_string()),
+        steps.iter().map(|s| s.to_string()).collect(),
+    )
+}
+
+#[test]
+fn job_tracks_step_progress_until_it_finishes() {
+    let mut job = job(&["summary", "french"]);
+    assert_eq!(job.status, JobStatus::Queued);
+    assert!(job.id.starts_with("job_"));
+
+    job.start();
+    assert_eq!(job.status, JobStatus::Running);
+    assert_eq!(job.steps[0].status, StepStatus::Running);
+    assert_eq!(job.steps[1].status, StepStatus::Pending);
+
+    job.complete_step("summary", "short");
+    assert_eq!(job.steps[0].status, StepStatus::Completed);
+    assert_eq!(job.steps[0].output.as_deref(), Some("short"));
+    assert_eq!(job.steps[1].status, StepStatus::Running);
+
+    job.finish(Err(JobError {
+        status: 500,
+        message: "boom".to_string(),
+    }));
+    assert_eq!(job.status, JobStatus::Failed);
+    assert_eq!(job.steps[1].status, StepStatus::Failed);
+    assert!(job.finished_at.is_some());
+
+    job.cancel();
+    job.finish(Ok(json!({})));
+    assert_eq!(job.status, JobStatus::Failed, "finished jobs do not change");
+}
+
+#[test]
+fn cancelled_job_ignores_late_results() {
+    let mut job = job(&["summary"]);
+    job.start();
+    job.cancel();
+    assert_eq!(job.status, JobStatus::Cancelled);
+    assert_eq!(job.steps[0].status, StepStatus::Pending);
+
+    job.complete_step("summary", "late");
+    job.finish(Ok(json!({ "output": "late" })));
+    assert_eq!(job.status, JobStatus::Cancelled);
+    assert!(job.result.is_none());
+    assert!(job.steps[0].output.is_none());
+}
+
+#[test]
+fn memory_store_saves_and_removes_jobs() {
+    let store = MemoryJobStore::default();
+    let mut job = job(&[]);
+    store.save(&job).unwrap();
+    job.start();
+    store.save(&job).unwrap();
+
+    let saved = store.get(&job.id).unwrap().unwrap();
+    assert_eq!(saved.status, JobStatus::Running);
+
+    store.remove(&job.id).unwrap();
+    store.remove(&job.id).unwrap();
+    assert!(store.get(&job.id).unwrap().is_none());
+}
+
+#[test]
+fn file_store_keeps_jobs_across_opens() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut job = job(&["summary"]);
+    job.start();
+    job.complete_step("summary", "short");
+    job.finish(Ok(json!({ "output": "short" })));
+    FileJobStore::open(dir.path()).unwrap().save(&job).unwrap();
+
+    let store = FileJobStore::open(dir.path()).unwrap();
+    let saved = store.get(&job.id).unwrap().unwrap();
+    assert_eq!(saved.status, JobStatus::Succeeded);
+    assert_eq!(saved.result, Some(json!({ "output": "short" })));
+    assert_eq!(saved.steps[0].output.as_deref(), Some("short"));
+    assert_eq!(saved.owner.as_deref(), Some("team"));
+
+    store.remove(&job.id).unwrap();
+    assert!(store.get(&job.id).unwrap().is_none());
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn file_store_fails_jobs_interrupted_by_a_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let queued = job(&[]);
+    let mut running = job(&["summary"]);
+    running.start();
+    let store = FileJobStore::open(dir.path()).unwrap();
+    store.save(&queued).unwrap();
+    store.save(&running).unwrap();
+
+    let store = FileJobStore::open(dir.path()).unwrap();
+    for id in [&queued.id, &running.id] {
+        let job = store.get(id).unwrap().unwrap();
+        assert_eq!(job.status, JobStatus::Failed);
+        assert!(job.error.unwrap().message.contains("restart"));
+    }
+    let running = store.get(&running.id).unwrap().unwrap();
+    assert_eq!(running.steps[0].status, StepStatus::Failed);
+}
+
+#[test]
+fn file_store_rejects_ids_outside_its_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = FileJobStore::open(dir.path()).unwrap();
+    let mut job = job(&[]);
+    job.id = "../escape".to_string();
+
+    assert!(store.save(&job).is_err());
+    assert!(store.get("../escape").unwrap().is_none());
+    assert!(store.remove("../escape").is_err());
+}
+
+#[test]
+fn stores_remove_jobs_finished_before_the_cutoff() {
+    let dir = tempfile::tempdir().unwrap();
+    let file_store = FileJobStore::open(dir.path()).unwrap();
+    let memory_store = MemoryJobStore::default();
+    let stores: [&dyn JobStore; 2] = [&memory_store, &file_store];
+    for store in stores {
+        let mut old = job(&[]);
+        old.start();
+        old.finish(Ok(json!({})));
+        old.finished_at = Some(100);
+        let mut recent = job(&[]);
+        recent.start();
+        recent.finish(Ok(json!({})));
+        let queued = job(&[]);
+        for job in [&old, &recent, &queued] {
+            store.save(job).unwrap();
+        }
+
+        store.remove_finished(unix_timestamp() - 60).unwrap();
+        assert!(store.get(&old.id).unwrap().is_none());
+        assert!(store.get(&recent.id).unwrap().is_some());
+        assert!(store.get(&queued.id).unwrap().is_some());
+    }
+}
+
+#[tokio::test]
+async fn finished_jobs_are_written_to_the_store() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = FileJobStore::open(dir.path()).unwrap();
+    let jobs = Arc::new(Jobs::new(JobConfig::new().store(store)));
+    let job = jobs
+        .submit(job(&[]), async { Ok(json!({ "output": "done" })) })
+        .await
+        .unwrap()
+        .unwrap();
+
+    for _ in 0..200 {
+        if !jobs.live().contains_key(&job.id) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let saved = FileJobStore::open(dir.path())
+        .unwrap()
+        .get(&job.id)
+        .unwrap()
+        .unwrap();
+    assert_eq!(saved.status, JobStatus::Succeeded);
+    assert_eq!(saved.result, Some(json!({ "output": "done" })));
+    assert_eq!(
+        jobs.get(&job.id).await.unwrap().unwrap().status,
+        JobStatus::Succeeded
+    );
+}
+
+/// Store taking a while to save, so a job can be cancelled while it is submitted.
+struct SlowStore(MemoryJobStore);
+
+impl JobStore for SlowStore {
+    fn save(&self, job: &Job) -> Result<(), LLMError> {
+        std::thread::sleep(Duration::from_millis(100));
+        self.0.save(job)
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Job>, LLMError> {
+        self.0.get(id)
+    }
+
+    fn remove(&self, id: &str) -> Result<(), LLMError> {
+        self.0.remove(id)
+    }
+
+    fn remove_finished(&self, before: u64) -> Result<(), LLMError> {
+        self.0.remove_finished(before)
+    }
+}
+
+#[tokio::test]
+async fn jobs_cancelled_while_submitted_never_run() {
+    let jobs = Arc::new(Jobs::new(
+        JobConfig::new().store(SlowStore(MemoryJobStore::default())),
+    ));
+    let ran = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let job = job(&[]);
+    let id = job.id.clone();
+
+    let work = {
+        let ran = ran.clone();
+        async move {
+            ran.store(true, std::sync::atomic::Ordering::SeqCst);
+            Ok(json!({}))
+        }
+    };
+    let submit = tokio::spawn({
+        let jobs = jobs.clone();
+        async move { jobs.submit(job, work).await }
+    });
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    let cancelled = jobs.cancel(&id).await.unwrap().unwrap();
+    assert_eq!(cancelled.status, JobStatus::Cancelled);
+    submit.await.unwrap().unwrap().unwrap();
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(!ran.load(std::sync::atomic::Ordering::SeqCst));
+    assert_eq!(
+        jobs.get(&id).await.unwrap().unwrap().status,
+        JobStatus::Cancelled
+    );
+}
@@ -0,0 +1,246 @@
+//! API keys of the server: allowlists, rate limits and usage accounting.
+
+#[path = "keys/config.rs"]
+mod config;
+
+#[path = "keys/limits.rs"]
+mod limits;
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use secrecy::ExposeSecret;
+
+use crate::chat::Usage;
+use crate::error::LLMError;
+
+pub use config::ApiKeyConfig;
+pub use limits::KeyUsage;
+use limits::RateWindow;
+
+/// Keys accepted by the server.
+///
+/// Keys are compared in constant time. Rate windows and usage totals are kept by
+/// key name across reloads.
+///
+/// ```no_run
+/// use std::{sync::Arc, time::Duration};
+/// use llm::api::KeyStore;
+///
+/// # fn run() -> Result<(), llm::error::LLMError> {
+/// let keys = Arc::new(KeyStore::from_file("keys.yaml")?);
+/// keys.clone().watch(Duration::from_secs(5));
+/// # Ok(())
+/// # }
+/// ```
+pub struct KeyStore {
+    keys: RwLock<Vec<Arc<ApiKeyConfig>>>,
+    state: Mutex<HashMap<String, KeyState>>,
+    path: Option<PathBuf>,
+    modified: Mutex<Option<SystemTime>>,
+}
+
+#[derive(Default)]
+struct KeyState {
+    window: RateWindow,
+    usage: KeyUsage,
+}
+
+impl KeyStore {
+    /// Store accepting `keys`; fails if a secret is empty or a name or secret is
+    /// used twice.
+    pub fn new(keys: Vec<ApiKeyConfig>) -> Result<Self, LLMError> {
+        validate(&keys)?;
+        Ok(Self {
+            keys: RwLock::new(keys.into_iter().map(Arc::new).collect()),
+            state: Mutex::new(HashMap::new()),
+            path: None,
+            modified: Mutex::new(None),
+        })
+    }
+
+    /// Loads `{ "keys": [...] }` from a JSON, YAML (`.yaml`, `.yml`) or TOML
+    /// (`.toml`) file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, LLMError> {
+        let path = path.as_ref();
+        let mut store = Self::new(config::read_keys(path)?)?;
+        store.path = Some(path.to_path_buf());
+        *store.modified() = modified_at(path);
+        Ok(store)
+    }
+
+    /// Replaces the keys; on error the current keys stay in place.
+    pub fn replace(&self, keys: Vec<ApiKeyConfig>) -> Result<(), LLMError> {
+        validate(&keys)?;
+        *self.keys.write().unwrap_or_else(|e| e.into_inner()) =
+            keys.into_iter().map(Arc::new).collect();
+        Ok(())
+    }
+
+    /// Reloads the keys from the file the store was loaded from.
+    pub fn reload(&self) -> Result<(), LLMError> {
+        let path = self.path.as_deref().ok_or_else(|| {
+            LLMError::InvalidRequest("key store was not loaded from a file".to_string())
+        })?;
+        let modified = modified_at(path);
+        self.replace(config::read_keys(path)?)?;
+        *self.modified() = modified;
+        log::info!("Reloaded API keys from {}", path.display());
+        Ok(())
+    }
+
+    /// Reloads the key file whenever its modification time changes, checking every
+    /// `interval`. Invalid files are logged and ignored.
+    pub fn watch(self: Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let Some(path) = self.path.as_deref() else {
+                    return;
+                };
+                if modified_at(path) == *self.modified() {
+                    continue;
+                }
+                if let Err(err) = self.reload() {
+                    log::warn!("Keeping the current API keys: {err}");
+                }
+            }
+        })
+    }
+
+    /// Names of the current keys.
+    pub fn names(&self) -> Vec<String> {
+        self.keys().iter().map(|key| key.name.clone()).collect()
+    }
+
+    /// Usage totals by key name.
+    pub fn usage(&self) -> HashMap<String, KeyUsage> {
+        self.state()
+            .iter()
+            .map(|(name, state)| (name.clone(), state.usage.clone()))
+            .collect()
+    }
+
+    /// The key matching `presented`, comparing against every key.
+    pub(crate) fn authenticate(&self, presented: &str) -> Option<Arc<ApiKeyConfig>> {
+        let mut found = None;
+        for key in self.keys().iter() {
+            if constant_time_eq(key.key.expose_secret().as_bytes(), presented.as_bytes()) {
+                found = Some(key.clone());
+            }
+        }
+        found
+    }
+
+    /// Checks the allowlist of `key`, counting refusals.
+    pub(crate) fn check_allowed(
+        &self,
+        key: &ApiKeyConfig,
+        provider_id: &str,
+        model: Option<&str>,
+    ) -> bool {
+        let allowed = key.allows(provider_id, model);
+        if !allowed {
+            self.state()
+                .entry(key.name.clone())
+                .or_default()
+                .usage
+                .denied += 1;
+        }
+        allowed
+    }
+
+    /// Counts a request against the rate limits of `key`; the error names the
+    /// exceeded limit.
+    pub(crate) fn admit(&self, key: &ApiKeyConfig) -> Result<(), &'static str> {
+        let mut state = self.state();
+        let state = state.entry(key.name.clone()).or_default();
+        match state.window.admit(
+            Instant::now(),
+            key.requests_per_minute,
+            key.tokens_per_minute,
+        ) {
+            Ok(()) => {
+                state.usage.requests += 1;
+                Ok(())
+            }
+            Err(limit) => {
+                state.usage.rate_limited += 1;
+                Err(limit)
+            }
+        }
+    }
+
+    pub(crate) fn record(&self, key: &ApiKeyConfig, usage: &Usage) {
+        let mut state = self.state();
+        let state = state.entry(key.name.clone()).or_default();
+        let total = usage
+            .total_tokens
+            .max(usage.prompt_tokens + usage.completion_tokens);
+        state.window.add_tokens(Instant::now(), total);
+        state.usage.prompt_tokens += u64::from(usage.prompt_tokens);
+        state.usage.completion_tokens += u64::from(usage.completion_tokens);
+        state.usage.total_tokens += u64::from(total);
+    }
+
+    fn keys(&self) -> std::sync::RwLockReadGuard<'_, Vec<Arc<ApiKeyConfig>>> {
+        self.keys.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn state(&self) -> MutexGuard<'_, HashMap<String, KeyState>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn modified(&self) -> MutexGuard<'_, Option<SystemTime>> {
+        self.modified.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn validate(keys: &[ApiKeyConfig]) -> Result<(), LLMError> {
+    let mut names = HashSet::new();
+    let mut secrets = HashSet::new();
+    for key in keys {
+        if key.key.expose_secret().is_empty() {
+            return Err(LLMError::InvalidRequest(format!(
+                "API key '{}' is empty",
+                key.name
+            )));
+        }
+        if !names.insert(key.name.as_str()) {
+            return Err(LLMError::InvalidRequest(format!(
+                "duplicate API key name '{}'",
+                key.name
+            )));
+        }
+        if !secrets.insert(key.key.expose_secret().as_str()) {
+            return Err(LLMError::InvalidRequest(format!(
+                "API key '{}' reuses the secret of another key",
+                key.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Compares without returning early, so timing does not reveal how much of a key
+/// matched.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let mut diff = a.len() ^ b.len();
+    for i in 0..a.len().max(b.len()) {
+        let x = a.get(i).copied().unwrap_or(0);
+        let y = b.get(i).copied().unwrap_or(0);
+        diff |= usize::from(x ^ y);
+    }
+    diff == 0
+}
+
+#[cfg(test)]
+#[path = "keys/tests.rs"]
+mod tests;
@@ -0,0 +1,98 @@
+use std::path::Path;
+
+use secrecy::SecretString;
+use serde::{Deserialize, Serialize};
+
+use crate::error::LLMError;
+
+/// Settings of an API key.
+///
+/// Allowlist entries are a provider id (`"openai"`, any model), a
+/// `"provider:model"` pair, `"provider:*"`, or `"*"` for everything. Without an
+/// allowlist the key can use every provider.
+///
+/// The secret is redacted from `Debug` output and left out when serialized.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyConfig {
+    /// Name used in logs and usage reports.
+    pub name: String,
+    /// The secret clients send.
+    #[serde(skip_serializing)]
+    pub key: SecretString,
+    /// Providers and models the key may use, in the syntax above; `None` allows
+    /// everything.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow: Option<Vec<String>>,
+    /// Limit on admitted requests over the last minute.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_minute: Option<u32>,
+    /// Limit on prompt plus completion tokens reported by the providers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens_per_minute: Option<u32>,
+}
+
+impl ApiKeyConfig {
+    /// Key named `name` with the secret `key`, allowed everything without limits.
+    pub fn new(name: impl Into<String>, key: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            key: SecretString::new(key.into()),
+            allow: None,
+            requests_per_minute: None,
+            tokens_per_minute: None,
+        }
+    }
+
+    /// Adds an allowlist entry, in the syntax described on [`ApiKeyConfig`]. The
+    /// first entry restricts the key to what the allowlist names.
+    pub fn allow(mut self, entry: impl Into<String>) -> Self {
+        self.allow.get_or_insert_with(Vec::new).push(entry.into());
+        self
+    }
+
+    /// Limits the requests the key may make per minute.
+    pub fn requests_per_minute(mut self, limit: u32) -> Self {
+        self.requests_per_minute = Some(limit);
+        self
+    }
+
+    /// Limits the prompt plus completion tokens the key may use per minute.
+    pub fn tokens_per_minute(mut self, limit: u32) -> Self {
+        self.tokens_per_minute = Some(limit);
+        self
+    }
+
+    /// Whether the key may use `model` of `provider_id`; `None` asks for the
+    /// provider as a whole, as chain steps do.
+    pub fn allows(&self, provider_id: &str, model: Option<&str>) -> bool {
+        let Some(allow) = &self.allow else {
+            return true;
+        };
+        allow.iter().any(|entry| {
+            let (provider, pattern) = entry.split_once(':').unwrap_or((entry, "*"));
+            (entry == "*" || provider == provider_id)
+                && (pattern == "*" || model.is_some_and(|model| model == pattern))
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct KeyFile {
+    keys: Vec<ApiKeyConfig>,
+}
+
+/// Reads `{ "keys": [...] }` from a JSON, YAML or TOML file, by extension.
+pub(super) fn read_keys(path: &Path) -> Result<Vec<ApiKeyConfig>, LLMError> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        LLMError::InvalidRequest(format!("cannot read key file {}: {e}", path.display()))
+    })?;
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("json");
+    let parsed: Result<KeyFile, String> = match extension {
+        "yaml" | "yml" => serde_yaml::from_str(&content).map_err(|e| e.to_string()),
+        "toml" => toml::from_str(&content).map_err(|e| e.to_string()),
+        _ => serde_json::from_str(&content).map_err(|e| e.to_string()),
+    };
+    parsed
+        .map(|file| file.keys)
+        .map_err(|e| LLMError::InvalidRequest(format!("invalid key file {}: {e}", path.display())))
+}
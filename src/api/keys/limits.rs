@@ -0,0 +1,67 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Requests and tokens of a key over the last minute.
+#[derive(Default)]
+pub(super) struct RateWindow {
+    requests: VecDeque<Instant>,
+    tokens: VecDeque<(Instant, u32)>,
+}
+
+impl RateWindow {
+    /// Counts a request if both limits leave room for it, returning the exceeded
+    /// limit otherwise. Tokens are only known afterwards, so the token limit stops
+    /// the requests following the one that crossed it.
+    pub(super) fn admit(
+        &mut self,
+        now: Instant,
+        requests_per_minute: Option<u32>,
+        tokens_per_minute: Option<u32>,
+    ) -> Result<(), &'static str> {
+        self.expire(now);
+        if requests_per_minute.is_some_and(|limit| self.requests.len() >= limit as usize) {
+            return Err("request");
+        }
+        let tokens: u64 = self.tokens.iter().map(|(_, t)| u64::from(*t)).sum();
+        if tokens_per_minute.is_some_and(|limit| tokens >= u64::from(limit)) {
+            return Err("token");
+        }
+        self.requests.push_back(now);
+        Ok(())
+    }
+
+    pub(super) fn add_tokens(&mut self, now: Instant, tokens: u32) {
+        self.tokens.push_back((now, tokens));
+    }
+
+    fn expire(&mut self, now: Instant) {
+        let expired = |at: &Instant| now.duration_since(*at) >= WINDOW;
+        while self.requests.front().is_some_and(expired) {
+            self.requests.pop_front();
+        }
+        while self.tokens.front().is_some_and(|(at, _)| expired(at)) {
+            self.tokens.pop_front();
+        }
+    }
+}
+
+/// Totals recorded for a key since the server started.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct KeyUsage {
+    /// Requests admitted by the rate limits.
+    pub requests: u64,
+    /// Requests refused by a rate limit.
+    pub rate_limited: u64,
+    /// Requests refused by the allowlist.
+    pub denied: u64,
+    /// Prompt tokens reported by the providers.
+    pub prompt_tokens: u64,
+    /// Completion tokens reported by the providers.
+    pub completion_tokens: u64,
+    /// Total tokens reported by the providers, at least prompt plus completion.
+    pub total_tokens: u64,
+}
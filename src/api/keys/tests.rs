@@ -0,0 +1,161 @@
+use super::*;
+
+fn usage(total: u32) -> Usage {
+    Usage {
+        prompt_tokens: total / 2,
+        completion_tokens: total - total / 2,
+        total_tokens: total,
+        completion_tokens_details: None,
+        prompt_tokens_details: None,
+    }
+}
+
+#[test]
+fn allowlist_entries_match_providers_and_models() {
+    let key = ApiKeyConfig::new("team", "k")
+        .allow("openai:gpt-4.1-mini")
+        .allow("anthropic")
+        .allow("ollama:*");
+    assert!(key.allows("openai", Some("gpt-4.1-mini")));
+    assert!(!key.allows("openai", Some("gpt-4.1")));
+    assert!(!key.allows("openai", None));
+    assert!(key.allows("anthropic", Some("claude")));
+    assert!(key.allows("anthropic", None));
+    assert!(key.allows("ollama", Some("llama3")));
+    assert!(!key.allows("google", Some("gemini")));
+    assert!(ApiKeyConfig::new("admin", "k").allows("google", None));
+    assert!(ApiKeyConfig::new("all", "k")
+        .allow("*")
+        .allows("google", None));
+}
+
+#[test]
+fn compares_keys_in_full() {
+    assert!(constant_time_eq(b"secret", b"secret"));
+    assert!(!constant_time_eq(b"secret", b"secreT"));
+    assert!(!constant_time_eq(b"secret", b"secret2"));
+    assert!(!constant_time_eq(b"", b"s"));
+}
+
+#[test]
+fn secrets_stay_out_of_debug_output_and_serialization() {
+    let key = ApiKeyConfig::new("alice", "sk-very-secret").requests_per_minute(10);
+    assert!(!format!("{key:?}").contains("sk-very-secret"));
+    let serialized = serde_json::to_value(&key).unwrap();
+    assert_eq!(
+        serialized,
+        serde_json::json!({ "name": "alice", "requests_per_minute": 10 })
+    );
+}
+
+#[test]
+fn authenticates_the_matching_key() {
+    let store = KeyStore::new(vec![
+        ApiKeyConfig::new("alice", "key-a"),
+        ApiKeyConfig::new("bob", "key-b"),
+    ])
+    .unwrap();
+    assert_eq!(store.authenticate("key-b").unwrap().name, "bob");
+    assert!(store.authenticate("key-c").is_none());
+    assert!(store.authenticate("").is_none());
+}
+
+#[test]
+fn rejects_duplicate_and_empty_keys() {
+    assert!(KeyStore::new(vec![
+        ApiKeyConfig::new("a", "k1"),
+        ApiKeyConfig::new("a", "k2")
+    ])
+    .is_err());
+    assert!(KeyStore::new(vec![
+        ApiKeyConfig::new("a", "k"),
+        ApiKeyConfig::new("b", "k")
+    ])
+    .is_err());
+    assert!(KeyStore::new(vec![ApiKeyConfig::new("a", "")]).is_err());
+}
+
+#[test]
+fn enforces_request_and_token_limits() {
+    let limited = ApiKeyConfig::new("limited", "k1").requests_per_minute(2);
+    let metered = ApiKeyConfig::new("metered", "k2").tokens_per_minute(100);
+    let store = KeyStore::new(vec![limited.clone(), metered.clone()]).unwrap();
+
+    assert!(store.admit(&limited).is_ok());
+    assert!(store.admit(&limited).is_ok());
+    assert_eq!(store.admit(&limited), Err("request"));
+
+    assert!(store.admit(&metered).is_ok());
+    store.record(&metered, &usage(60));
+    assert!(store.admit(&metered).is_ok());
+    store.record(&metered, &usage(60));
+    assert_eq!(store.admit(&metered), Err("token"));
+
+    let usage = store.usage();
+    assert_eq!(usage["limited"].requests, 2);
+    assert_eq!(usage["limited"].rate_limited, 1);
+    assert_eq!(usage["metered"].total_tokens, 120);
+    assert_eq!(usage["metered"].prompt_tokens, 60);
+}
+
+#[test]
+fn window_forgets_requests_after_a_minute() {
+    let mut window = RateWindow::default();
+    let start = Instant::now();
+    assert!(window.admit(start, Some(1), None).is_ok());
+    assert!(window
+        .admit(start + Duration::from_secs(30), Some(1), None)
+        .is_err());
+    assert!(window
+        .admit(start + Duration::from_secs(61), Some(1), None)
+        .is_ok());
+}
+
+#[test]
+fn reloads_keys_from_file_and_keeps_usage() {
+    let dir = std::env::temp_dir().join(format!("llm-keys-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("keys.yaml");
+    std::fs::write(
+        &path,
+        "keys:\n  - name: alice\n    key: key-a\n    allow: [openai]\n    requests_per_minute: 10\n",
+    )
+    .unwrap();
+
+    let store = KeyStore::from_file(&path).unwrap();
+    let alice = store.authenticate("key-a").unwrap();
+    assert_eq!(alice.requests_per_minute, Some(10));
+    store.admit(&alice).unwrap();
+
+    std::fs::write(
+        &path,
+        "keys:\n  - name: alice\n    key: key-a2\n  - name: bob\n    key: key-b\n",
+    )
+    .unwrap();
+    store.reload().unwrap();
+    assert!(store.authenticate("key-a").is_none());
+    assert!(store.authenticate("key-a2").unwrap().allow.is_none());
+    assert_eq!(store.names(), ["alice", "bob"]);
+    assert_eq!(store.usage()["alice"].requests, 1);
+
+    std::fs::write(&path, "keys: [").unwrap();
+    assert!(store.reload().is_err());
+    assert!(store.authenticate("key-b").is_some());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn reads_json_key_files() {
+    let path = std::env::temp_dir().join(format!("llm-keys-{}.json", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"{"keys": [{"name": "ci", "key": "key-ci", "tokens_per_minute": 5000}]}"#,
+    )
+    .unwrap();
+    let store = KeyStore::from_file(&path).unwrap();
+    assert_eq!(
+        store.authenticate("key-ci").unwrap().tokens_per_minute,
+        Some(5000)
+    );
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,261 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::Response;
+
+use crate::chat::Usage;
+
+/// Upper bounds in seconds of the request duration histogram buckets.
+const BUCKETS: [f64; 11] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+
+/// Distinct provider and model pairs labelled at most. Clients choose the model
+/// name, so later models are counted as `model="other"` to bound the series.
+const MAX_MODEL_LABELS: usize = 64;
+
+/// Label of the models beyond [`MAX_MODEL_LABELS`].
+const OTHER_MODEL: &str = "other";
+
+/// Route, provider and model of a request; the provider and model are empty until a
+/// handler knows them.
+type Target = (String, String, String);
+
+#[derive(Default)]
+struct Histogram {
+    buckets: [u64; BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+#[derive(Default)]
+struct Registry {
+    requests: BTreeMap<(Target, u16), u64>,
+    durations: BTreeMap<Target, Histogram>,
+    tokens: BTreeMap<(String, String, &'static str), u64>,
+    /// Provider and model pairs used as labels so far
+    models: HashSet<(String, String)>,
+}
+
+/// Request counts, latencies and token usage of the server, rendered in the
+/// Prometheus text format.
+#[derive(Default)]
+pub(crate) struct Metrics {
+    registry: Mutex<Registry>,
+}
+
+impl Metrics {
+    /// Model label of `model` of `provider`: the model itself while there is room
+    /// for another label, else [`OTHER_MODEL`].
+    fn model_label(&self, provider: &str, model: &str) -> String {
+        let mut registry = self.registry();
+        let pair = (provider.to_string(), model.to_string());
+        if registry.models.contains(&pair) {
+            return model.to_string();
+        }
+        if registry.models.len() >= MAX_MODEL_LABELS {
+            return OTHER_MODEL.to_string();
+        }
+        registry.models.insert(pair);
+        model.to_string()
+    }
+
+    fn record_request(&self, target: Target, status: StatusCode, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let mut registry = self.registry();
+        *registry
+            .requests
+            .entry((target.clone(), status.as_u16()))
+            .or_default() += 1;
+        let histogram = registry.durations.entry(target).or_default();
+        for (bucket, bound) in histogram.buckets.iter_mut().zip(BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        histogram.sum += seconds;
+        histogram.count += 1;
+    }
+
+    fn record_tokens(&self, provider: &str, model: &str, usage: &Usage) {
+        let mut registry = self.registry();
+        for (kind, count) in [
+            ("prompt", usage.prompt_tokens),
+            ("completion", usage.completion_tokens),
+        ] {
+            *registry
+                .tokens
+                .entry((provider.to_string(), model.to_string(), kind))
+                .or_default() += u64::from(count);
+        }
+    }
+
+    /// Every metric in the Prometheus text exposition format.
+    pub(crate) fn render(&self) -> String {
+        let registry = self.registry();
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "llm_api_requests_total",
+            "counter",
+            "Requests handled, by route, provider, model and status.",
+        );
+        for ((target, status), count) in &registry.requests {
+            let labels = target_labels(target);
+            let _ = writeln!(
+                out,
+                "llm_api_requests_total{{{labels},status=\"{status}\"}} {count}"
+            );
+        }
+
+        header(
+            &mut out,
+            "llm_api_errors_total",
+            "counter",
+            "Requests answered with an error status, by route, provider, model and status.",
+        );
+        for ((target, status), count) in &registry.requests {
+            if *status >= 400 {
+                let labels = target_labels(target);
+                let _ = writeln!(
+                    out,
+                    "llm_api_errors_total{{{labels},status=\"{status}\"}} {count}"
+                );
+            }
+        }
+
+        header(
+            &mut out,
+            "llm_api_request_duration_seconds",
+            "histogram",
+            "Time until the response head was sent; streamed bodies continue after it.",
+        );
+        for (target, histogram) in &registry.durations {
+            let labels = target_labels(target);
+            for (count, bound) in histogram.buckets.iter().zip(BUCKETS) {
+                let _ = writeln!(
+                    out,
+                    "llm_api_request_duration_seconds_bucket{{{labels},le=\"{bound}\"}} {count}"
+                );
+            }
+            let _ = writeln!(
+                out,
+                "llm_api_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}",
+                histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "llm_api_request_duration_seconds_sum{{{labels}}} {}",
+                histogram.sum
+            );
+            let _ = writeln!(
+                out,
+                "llm_api_request_duration_seconds_count{{{labels}}} {}",
+                histogram.count
+            );
+        }
+
+        header(
+            &mut out,
+            "llm_api_tokens_total",
+            "counter",
+            "Tokens reported by providers, by provider, model and type.",
+        );
+        for ((provider, model, kind), count) in &registry.tokens {
+            let _ = writeln!(
+                out,
+                "llm_api_tokens_total{{provider=\"{}\",model=\"{}\",type=\"{kind}\"}} {count}",
+                escape(provider),
+                escape(model)
+            );
+        }
+        out
+    }
+
+    fn registry(&self) -> MutexGuard<'_, Registry> {
+        self.registry
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn target_labels((route, provider, model): &Target) -> String {
+    format!(
+        "route=\"{}\",provider=\"{}\",model=\"{}\"",
+        escape(route),
+        escape(provider),
+        escape(model)
+    )
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Metrics of one request, handed to its handler as a request extension.
+#[derive(Clone)]
+pub(crate) struct RequestMetrics {
+    metrics: Arc<Metrics>,
+    target: Arc<Mutex<(String, String)>>,
+}
+
+impl RequestMetrics {
+    /// Labels the request with the provider and model it uses.
+    pub(crate) fn set_target(&self, provider: &str, model: &str) {
+        let model = self.metrics.model_label(provider, model);
+        *self.target() = (provider.to_string(), model);
+    }
+
+    /// Adds token usage under the request's provider and model, also after the
+    /// response head was sent.
+    pub(crate) fn record_usage(&self, usage: &Usage) {
+        let (provider, model) = self.target().clone();
+        self.metrics.record_tokens(&provider, &model, usage);
+    }
+
+    fn target(&self) -> MutexGuard<'_, (String, String)> {
+        self.target
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Middleware counting every request and timing it until its response head.
+pub(crate) async fn track(
+    State(metrics): State<Arc<Metrics>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", |path| path.as_str())
+        .to_string();
+    let request = RequestMetrics {
+        metrics: metrics.clone(),
+        target: Arc::default(),
+    };
+    req.extensions_mut().insert(request.clone());
+
+    let started = Instant::now();
+    let response = next.run(req).await;
+    let (provider, model) = request.target().clone();
+    metrics.record_request(
+        (route, provider, model),
+        response.status(),
+        started.elapsed(),
+    );
+    response
+}
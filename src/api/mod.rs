@@ -1,19 +1,54 @@
 //! Server module for exposing LLM functionality via REST API
 //!
 //! Provides a REST API server that exposes LLM functionality through standardized endpoints.
-//! Supports authentication, CORS, and handles chat completion requests.
+//! Supports authentication, CORS, TLS and request limits, and handles chat completion
+//! requests.
 
+mod anthropic;
+mod chains;
+mod config;
 mod handlers;
+mod jobs;
+mod keys;
+mod metrics;
+mod providers;
+mod sessions;
+#[cfg(feature = "rustls-tls")]
+mod tls;
 mod types;
 
 use axum::Router;
+use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Arc;
-use tower_http::cors::CorsLayer;
 
+use crate::builder::LLMBuilder;
 use crate::chain::LLMRegistry;
-use handlers::handle_chat;
+use anthropic::handle_messages;
+use handlers::{
+    handle_cancel_job, handle_chains, handle_chat, handle_create_job, handle_embeddings,
+    handle_get_job, handle_health, handle_metrics, handle_models, handle_new_session, handle_ready,
+    handle_resume_session, handle_run_chain,
+};
+use jobs::Jobs;
+use metrics::Metrics;
+use providers::ModelProviders;
+use sessions::Sessions;
 
-pub use types::{ChatRequest, ChatResponse, Message};
+pub use chains::{ChainDefinition, ChainStore};
+pub use config::ServerConfig;
+pub use jobs::{
+    FileJobStore, Job, JobConfig, JobError, JobStatus, JobStep, JobStore, MemoryJobStore,
+    StepStatus,
+};
+pub use keys::{ApiKeyConfig, KeyStore, KeyUsage};
+pub use sessions::{MemoryFactory, SessionConfig};
+#[cfg(feature = "rustls-tls")]
+pub use tls::TlsConfig;
+pub use types::{
+    ChainStepRequest, ChatRequest, ChatResponse, JobRequest, Message, MessageContent,
+    RunChainRequest, RunChainResponse, SessionClientEvent, SessionEvent,
+};
 
 /// Main server struct that manages LLM registry and authentication
 pub struct Server {
@@ -21,6 +56,20 @@ pub struct Server {
     llms: Arc<LLMRegistry>,
     /// Optional authentication key for API requests
     pub auth_key: Option<String>,
+    /// Builders for providers serving the model named in each request
+    templates: HashMap<String, LLMBuilder>,
+    /// Keys with their own allowlists and limits, replacing `auth_key`
+    keys: Option<Arc<KeyStore>>,
+    /// Transport settings and request limits
+    config: ServerConfig,
+    /// Named chains clients run by id
+    chains: ChainStore,
+    /// Worker pool and storage of `/v1/jobs`; disabled when unset
+    jobs: Option<JobConfig>,
+    /// Memory and lifetime of `/v1/sessions`; disabled when unset
+    sessions: Option<SessionConfig>,
+    /// Provider id serving `/v1/messages` models named without one
+    default_provider: Option<String>,
 }
 
 /// Internal server state shared between request handlers
@@ -30,6 +79,22 @@ struct ServerState {
     llms: Arc<LLMRegistry>,
     /// Optional authentication key
     auth_key: Option<String>,
+    /// Providers resolved from "provider:model"
+    providers: Arc<ModelProviders>,
+    /// Optional key store
+    keys: Option<Arc<KeyStore>>,
+    /// Counters and latencies served on `/metrics`
+    metrics: Arc<Metrics>,
+    /// Whether `/ready` lists the models of every provider
+    probe_providers: bool,
+    /// Named chains
+    chains: Arc<ChainStore>,
+    /// Background jobs, when enabled
+    jobs: Option<Arc<Jobs>>,
+    /// WebSocket chat sessions, when enabled
+    sessions: Option<Arc<Sessions>>,
+    /// Provider id for `/v1/messages` models without one
+    default_provider: Option<String>,
 }
 
 impl Server {
@@ -41,6 +106,13 @@ impl Server {
         Self {
             llms: Arc::new(llms),
             auth_key: None,
+            templates: HashMap::new(),
+            keys: None,
+            config: ServerConfig::default(),
+            chains: ChainStore::default(),
+            jobs: None,
+            sessions: None,
+            default_provider: None,
         }
     }
 
@@ -53,25 +125,108 @@ impl Server {
     /// * `Ok(())` if server starts successfully
     /// * `Err(LLMError)` if server fails to start
     pub async fn run(self, addr: &str) -> Result<(), crate::error::LLMError> {
-        let app = Router::new()
-            .route("/v1/chat/completions", axum::routing::post(handle_chat))
-            .layer(CorsLayer::permissive())
-            .with_state(ServerState {
-                llms: self.llms,
-                auth_key: self.auth_key,
-            });
+        self.run_with_shutdown(addr, std::future::pending()).await
+    }
+
+    /// Starts the server and stops accepting connections once `signal` resolves
+    ///
+    /// Requests in flight when the signal fires are completed before this returns.
+    /// Pass `tokio::signal::ctrl_c()` mapped to `()`, or `token.cancelled_owned()`
+    /// for a `CancellationToken`.
+    ///
+    /// # Arguments
+    /// * `addr` - Address to bind to (e.g. "127.0.0.1:3000")
+    /// * `signal` - Future that resolves when the server should shut down
+    pub async fn run_with_shutdown(
+        self,
+        addr: &str,
+        signal: impl Future<Output = ()> + Send + 'static,
+    ) -> Result<(), crate::error::LLMError> {
+        #[cfg(feature = "rustls-tls")]
+        let tls = match &self.config.tls {
+            Some(tls) => Some(tls.acceptor()?),
+            None => None,
+        };
 
         let listener = tokio::net::TcpListener::bind(addr)
             .await
             .map_err(|e| crate::error::LLMError::InvalidRequest(e.to_string()))?;
+        let app = self.router();
+
+        #[cfg(feature = "rustls-tls")]
+        if let Some(acceptor) = tls {
+            tls::serve(listener, acceptor, app, signal).await;
+            return Ok(());
+        }
 
         axum::serve(listener, app)
+            .with_graceful_shutdown(signal)
             .await
             .map_err(|e| crate::error::LLMError::InvalidRequest(e.to_string()))?;
 
         Ok(())
     }
 
+    /// Builds the router serving the API, to run it yourself or mount it in another app
+    ///
+    /// The CORS, body size, timeout and concurrency settings of the configuration are
+    /// applied to the API routes; `/health`, `/ready` and `/metrics` are served outside
+    /// of them so probes and scrapes are never queued or rejected. TLS is only used by
+    /// [`Server::run`].
+    pub fn router(self) -> Router {
+        let metrics = Arc::new(Metrics::default());
+        let api = Router::new()
+            .route("/v1/chat/completions", axum::routing::post(handle_chat))
+            .route("/v1/embeddings", axum::routing::post(handle_embeddings))
+            .route("/v1/models", axum::routing::get(handle_models))
+            .route("/v1/messages", axum::routing::post(handle_messages))
+            .route("/v1/chains", axum::routing::get(handle_chains))
+            .route("/v1/chains/:id", axum::routing::post(handle_run_chain))
+            .route("/v1/jobs", axum::routing::post(handle_create_job))
+            .route(
+                "/v1/jobs/:id",
+                axum::routing::get(handle_get_job).delete(handle_cancel_job),
+            )
+            .route("/v1/sessions", axum::routing::get(handle_new_session))
+            .route(
+                "/v1/sessions/:id",
+                axum::routing::get(handle_resume_session),
+            );
+        let api = self
+            .config
+            .apply(api)
+            .layer(axum::middleware::from_fn_with_state(
+                metrics.clone(),
+                metrics::track,
+            ));
+        Router::new()
+            .route("/health", axum::routing::get(handle_health))
+            .route("/ready", axum::routing::get(handle_ready))
+            .route("/metrics", axum::routing::get(handle_metrics))
+            .merge(api)
+            .with_state(ServerState {
+                providers: Arc::new(ModelProviders::new(self.llms.clone(), self.templates)),
+                llms: self.llms,
+                auth_key: self.auth_key,
+                keys: self.keys,
+                metrics,
+                probe_providers: self.config.probe_providers,
+                chains: Arc::new(self.chains),
+                jobs: self.jobs.map(|config| Arc::new(Jobs::new(config))),
+                sessions: self.sessions.map(|config| Arc::new(Sessions::new(config))),
+                default_provider: self.default_provider,
+            })
+    }
+
+    /// Sets CORS origins, TLS, body size, timeout and concurrency limits
+    ///
+    /// # Arguments
+    /// * `config` - Settings replacing the defaults
+    pub fn with_config(mut self, config: ServerConfig) -> Self {
+        self.config = config;
+        self
+    }
+
     /// Sets the authentication key required for API requests
     ///
     /// # Arguments
@@ -80,4 +235,109 @@ impl Server {
         self.auth_key = Some(key.into());
         self
     }
+
+    /// Authenticates requests with a key store instead of a single key
+    ///
+    /// Each key has its own provider allowlist, rate limits and usage totals. Keep a
+    /// clone of the store to read usage or reload the keys while the server runs.
+    ///
+    /// # Arguments
+    /// * `keys` - Keys clients may present as a bearer token or in `x-api-key`
+    pub fn with_key_store(mut self, keys: Arc<KeyStore>) -> Self {
+        self.keys = Some(keys);
+        self
+    }
+
+    /// Serves named chains on `/v1/chains`, run by id on `/v1/chains/{id}`
+    ///
+    /// # Arguments
+    /// * `chains` - Chains, e.g. loaded with [`ChainStore::from_file`]
+    pub fn with_chains(mut self, chains: ChainStore) -> Self {
+        self.chains = chains;
+        self
+    }
+
+    /// Runs chat and chain requests in the background on `/v1/jobs`
+    ///
+    /// `POST /v1/jobs` takes a chat completion request or `{"chain": id, "input": ...}`
+    /// and answers 202 with a job id. `GET /v1/jobs/{id}` reports the status, the
+    /// output of each completed chain step and, once done, the body the synchronous
+    /// endpoint would have answered. `DELETE /v1/jobs/{id}` cancels a job, or removes
+    /// it once finished. Jobs are only visible to the key that submitted them.
+    ///
+    /// # Arguments
+    /// * `config` - Number of workers, queue bound and job storage
+    pub fn with_jobs(mut self, config: JobConfig) -> Self {
+        self.jobs = Some(config);
+        self
+    }
+
+    /// Serves chat sessions over WebSockets on `/v1/sessions`
+    ///
+    /// Connecting to `/v1/sessions?model=provider:model` opens a session whose
+    /// messages are kept in its own memory; the server first sends
+    /// `{"type": "session", "id": ...}`. Clients send `{"type": "message", "content":
+    /// ..., "tools": [...]}` and receive each `StreamChunk` of the answer as
+    /// `{"type": "chunk", "chunk": ...}`. Tool calls are answered with
+    /// `{"type": "tool_result", "tool_call_id": ..., "content": ...}`; the model
+    /// continues once every call of the turn has a result. Reconnecting to
+    /// `/v1/sessions/{id}` resumes the session with its history and pending tool
+    /// calls. Browsers may pass the key as `?api_key=`.
+    ///
+    /// # Arguments
+    /// * `config` - Memory of each session and how long idle sessions are kept
+    pub fn with_sessions(mut self, config: SessionConfig) -> Self {
+        self.sessions = Some(config);
+        self
+    }
+
+    /// Serves `/v1/messages` models named without a provider id, such as
+    /// `claude-sonnet-4-5`, with `provider_id`
+    ///
+    /// Anthropic clients send plain model names; without a default provider those
+    /// requests are rejected. A registered provider ignores the `max_tokens` and
+    /// `temperature` of the request; use [`with_model_template`](Self::with_model_template)
+    /// for them to apply.
+    ///
+    /// # Arguments
+    /// * `provider_id` - Registered or templated provider id
+    pub fn with_default_provider(mut self, provider_id: impl Into<String>) -> Self {
+        self.default_provider = Some(provider_id.into());
+        self
+    }
+
+    /// Serves `provider_id` with one provider per requested model, built from
+    /// `template` with the model set and cached
+    ///
+    /// With a template for "openai", `openai:gpt-4.1` and `openai:gpt-4.1-mini` are
+    /// served by different models. Without one, the registry entry serves every
+    /// model of its provider id. Chain steps keep using the registry.
+    ///
+    /// The response schema, `max_tokens` and `temperature` of a request are set on a
+    /// provider built for them. Each combination costs one build, with its own HTTP
+    /// client, and is cached apart from the per-model providers. The system prompt
+    /// is sent as the first message, so varying it builds nothing. Registry
+    /// providers ignore `max_tokens` and `temperature`.
+    ///
+    /// Memory set on the template is dropped, as every model and client would
+    /// share it; use [`with_sessions`](Self::with_sessions) for conversation memory.
+    ///
+    /// # Arguments
+    /// * `provider_id` - Provider id clients use in the model name
+    /// * `template` - Builder configured with everything but the model
+    pub fn with_model_template(
+        mut self,
+        provider_id: impl Into<String>,
+        mut template: LLMBuilder,
+    ) -> Self {
+        let provider_id = provider_id.into();
+        if template.take_memory() {
+            log::warn!("Ignoring the memory of the model template for '{provider_id}'");
+        }
+        self.templates.insert(provider_id, template);
+        self
+    }
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,355 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Deref;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use futures::Stream;
+
+use crate::builder::LLMBuilder;
+use crate::chain::LLMRegistry;
+use crate::chat::{
+    ChatMessage, ChatProvider, ChatResponse, StreamChunk, StreamResponse, StructuredOutputFormat,
+    Tool,
+};
+use crate::completion::{CompletionProvider, CompletionRequest, CompletionResponse};
+use crate::embedding::EmbeddingProvider;
+use crate::error::LLMError;
+use crate::models::{ModelListRequest, ModelListResponse, ModelsProvider};
+use crate::stt::SpeechToTextProvider;
+use crate::tts::TextToSpeechProvider;
+use crate::LLMProvider;
+
+/// Providers kept at most in each cache; the least recently used one is dropped first.
+const MAX_CACHED_MODELS: usize = 64;
+
+/// Resolves `provider:model` to a provider.
+///
+/// Provider ids with a template builder get one provider per model, built on first
+/// use and cached; the other ids are served by their registry entry, whatever model
+/// it was built with.
+pub(crate) struct ModelProviders {
+    registry: Arc<LLMRegistry>,
+    templates: HashMap<String, LLMBuilder>,
+    built: Mutex<BuiltProviders>,
+    /// Providers built with request settings, kept apart so requests varying them
+    /// never push out the per-model providers
+    tuned: Mutex<BuiltProviders>,
+    /// Templates built with their own model, for requests that do not name one
+    defaults: Mutex<HashMap<String, Arc<dyn LLMProvider>>>,
+}
+
+/// Providers built from templates, by "provider:model" and the request settings
+/// they apply, in least recently used order.
+#[derive(Default)]
+struct BuiltProviders {
+    entries: HashMap<String, (Arc<dyn LLMProvider>, u64)>,
+    order: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl BuiltProviders {
+    fn get(&mut self, key: &str) -> Option<Arc<dyn LLMProvider>> {
+        let (provider, tick) = self.entries.get_mut(key)?;
+        self.order.remove(tick);
+        self.tick += 1;
+        *tick = self.tick;
+        self.order.insert(self.tick, key.to_string());
+        Some(provider.clone())
+    }
+
+    /// Caches `provider` unless another request built one first, which is returned.
+    fn insert(&mut self, key: String, provider: Arc<dyn LLMProvider>) -> Arc<dyn LLMProvider> {
+        if let Some(cached) = self.get(&key) {
+            return cached;
+        }
+        while self.entries.len() >= MAX_CACHED_MODELS {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+        self.tick += 1;
+        self.order.insert(self.tick, key.clone());
+        self.entries.insert(key, (provider.clone(), self.tick));
+        provider
+    }
+}
+
+/// Settings of a request that a provider built from a template takes over.
+///
+/// Each combination builds its own provider, so the system prompt, which clients
+/// vary per user, is not one of them: the handlers send it as a message. Registry
+/// providers were built once and ignore all of them, so `max_tokens` and
+/// `temperature` are lost.
+#[derive(Default)]
+pub(crate) struct RequestSettings {
+    /// Response schema, for backends that enforce one natively
+    pub(crate) schema: Option<StructuredOutputFormat>,
+    /// Maximum tokens to generate, replacing the one of the template
+    pub(crate) max_tokens: Option<u32>,
+    /// Sampling temperature, replacing the one of the template
+    pub(crate) temperature: Option<f32>,
+}
+
+impl RequestSettings {
+    fn is_empty(&self) -> bool {
+        self.schema.is_none() && self.max_tokens.is_none() && self.temperature.is_none()
+    }
+
+    /// Distinguishes providers built with different settings in the cache.
+    fn cache_key(&self) -> String {
+        serde_json::json!([self.schema, self.max_tokens, self.temperature]).to_string()
+    }
+}
+
+/// Which [`RequestSettings`] the resolved provider applies.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Applied {
+    pub(crate) schema: bool,
+}
+
+/// Provider borrowed from the registry or built from a template.
+pub(crate) enum ResolvedProvider<'a> {
+    Registered(&'a (dyn LLMProvider + 'static)),
+    Built(Arc<dyn LLMProvider>),
+}
+
+impl Deref for ResolvedProvider<'_> {
+    type Target = dyn LLMProvider;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            ResolvedProvider::Registered(provider) => *provider,
+            ResolvedProvider::Built(provider) => provider.as_ref(),
+        }
+    }
+}
+
+impl ModelProviders {
+    pub(crate) fn new(registry: Arc<LLMRegistry>, templates: HashMap<String, LLMBuilder>) -> Self {
+        Self {
+            registry,
+            templates,
+            built: Mutex::new(BuiltProviders::default()),
+            tuned: Mutex::new(BuiltProviders::default()),
+            defaults: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// `Ok(None)` when no provider is registered under `provider_id`.
+    pub(crate) fn resolve(
+        &self,
+        provider_id: &str,
+        model: &str,
+    ) -> Result<Option<ResolvedProvider<'_>>, LLMError> {
+        let Some(template) = self.templates.get(provider_id) else {
+            let registered = self.registry.backends.get(provider_id);
+            return Ok(registered.map(|provider| ResolvedProvider::Registered(provider.as_ref())));
+        };
+
+        let key = format!("{provider_id}:{model}");
+        if let Some(provider) = lock(&self.built).get(&key) {
+            return Ok(Some(ResolvedProvider::Built(provider)));
+        }
+        let provider: Arc<dyn LLMProvider> = Arc::from(template.clone().model(model).build()?);
+        let provider = lock(&self.built).insert(key, provider);
+        Ok(Some(ResolvedProvider::Built(provider)))
+    }
+
+    /// Like [`ModelProviders::resolve`], with `settings` applied to providers built
+    /// from a template. Those are cached per model and settings, so requests repeating
+    /// them share one provider; registry entries are served as they are and apply
+    /// nothing.
+    pub(crate) fn resolve_with(
+        &self,
+        provider_id: &str,
+        model: &str,
+        mut settings: RequestSettings,
+    ) -> Result<Option<(ResolvedProvider<'_>, Applied)>, LLMError> {
+        let template = self.templates.get(provider_id);
+        settings.schema = settings
+            .schema
+            .filter(|_| template.is_some_and(LLMBuilder::supports_structured_output));
+        let template = match template {
+            Some(template) if !settings.is_empty() => template,
+            _ => {
+                let provider = self.resolve(provider_id, model)?;
+                return Ok(provider.map(|provider| (provider, Applied::default())));
+            }
+        };
+
+        let applied = Applied {
+            schema: settings.schema.is_some(),
+        };
+        let key = format!("{provider_id}:{model}:{}", settings.cache_key());
+        if let Some(provider) = lock(&self.tuned).get(&key) {
+            return Ok(Some((ResolvedProvider::Built(provider), applied)));
+        }
+        let mut builder = template.clone().model(model);
+        if let Some(schema) = settings.schema {
+            builder = builder.schema(schema);
+        }
+        if let Some(max_tokens) = settings.max_tokens {
+            builder = builder.max_tokens(max_tokens);
+        }
+        if let Some(temperature) = settings.temperature {
+            builder = builder.temperature(temperature);
+        }
+        let provider: Arc<dyn LLMProvider> = Arc::from(builder.build()?);
+        let provider = lock(&self.tuned).insert(key, provider);
+        Ok(Some((ResolvedProvider::Built(provider), applied)))
+    }
+
+    /// Like [`ModelProviders::resolve`], as a provider that can outlive the request.
+    pub(crate) fn resolve_shared(
+        &self,
+        provider_id: &str,
+        model: &str,
+    ) -> Result<Option<Arc<dyn LLMProvider>>, LLMError> {
+        Ok(self
+            .resolve(provider_id, model)?
+            .map(|provider| match provider {
+                ResolvedProvider::Registered(_) => Arc::new(RegisteredProvider {
+                    registry: self.registry.clone(),
+                    id: provider_id.to_string(),
+                }) as Arc<dyn LLMProvider>,
+                ResolvedProvider::Built(provider) => provider,
+            }))
+    }
+
+    /// One provider per id, for requests that do not name a model.
+    pub(crate) fn all(&self) -> Vec<(String, ResolvedProvider<'_>)> {
+        let mut providers: Vec<(String, ResolvedProvider<'_>)> = self
+            .registry
+            .backends
+            .iter()
+            .filter(|(id, _)| !self.templates.contains_key(id.as_str()))
+            .map(|(id, provider)| {
+                (
+                    id.as_str().to_string(),
+                    ResolvedProvider::Registered(provider.as_ref()),
+                )
+            })
+            .collect();
+        let mut defaults = lock(&self.defaults);
+        for (id, template) in &self.templates {
+            if let Some(provider) = defaults.get(id) {
+                providers.push((id.clone(), ResolvedProvider::Built(provider.clone())));
+                continue;
+            }
+            match template.clone().build() {
+                Ok(provider) => {
+                    let provider: Arc<dyn LLMProvider> = Arc::from(provider);
+                    defaults.insert(id.clone(), provider.clone());
+                    providers.push((id.clone(), ResolvedProvider::Built(provider)));
+                }
+                Err(err) => log::warn!("Could not build provider '{id}': {err}"),
+            }
+        }
+        providers
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Registry entry held through a shared handle on the registry.
+struct RegisteredProvider {
+    registry: Arc<LLMRegistry>,
+    id: String,
+}
+
+impl RegisteredProvider {
+    fn provider(&self) -> &dyn LLMProvider {
+        // Entries are never removed from the registry of a running server.
+        self.registry.backends[self.id.as_str()].as_ref()
+    }
+}
+
+#[async_trait]
+impl ChatProvider for RegisteredProvider {
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        self.provider().chat_with_tools(messages, tools).await
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError> {
+        self.provider().chat_stream(messages).await
+    }
+
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>, LLMError>
+    {
+        self.provider().chat_stream_struct(messages).await
+    }
+
+    async fn chat_stream_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk, LLMError>> + Send>>, LLMError> {
+        self.provider()
+            .chat_stream_with_tools(messages, tools)
+            .await
+    }
+
+    async fn summarize_history(&self, msgs: &[ChatMessage]) -> Result<String, LLMError> {
+        self.provider().summarize_history(msgs).await
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for RegisteredProvider {
+    async fn complete(&self, req: &CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        self.provider().complete(req).await
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for RegisteredProvider {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        self.provider().embed(input).await
+    }
+}
+
+#[async_trait]
+impl SpeechToTextProvider for RegisteredProvider {
+    async fn transcribe(&self, audio: Vec<u8>) -> Result<String, LLMError> {
+        self.provider().transcribe(audio).await
+    }
+}
+
+#[async_trait]
+impl TextToSpeechProvider for RegisteredProvider {
+    async fn speech(&self, text: &str) -> Result<Vec<u8>, LLMError> {
+        self.provider().speech(text).await
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for RegisteredProvider {
+    async fn list_models(
+        &self,
+        request: Option<&ModelListRequest>,
+    ) -> Result<Box<dyn ModelListResponse>, LLMError> {
+        self.provider().list_models(request).await
+    }
+}
+
+impl LLMProvider for RegisteredProvider {
+    fn tools(&self) -> Option<&[Tool]> {
+        self.provider().tools()
+    }
+}
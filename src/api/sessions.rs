@@ -0,0 +1,170 @@
+//! Chat sessions kept on the server and driven over a WebSocket on `/v1/sessions`.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::chat::Tool;
+use crate::memory::{ChatWithMemory, MemoryProvider, SlidingWindowMemory};
+use crate::{LLMProvider, ToolCall};
+
+/// Builds the memory of each new session.
+pub type MemoryFactory = Arc<dyn Fn() -> Box<dyn MemoryProvider> + Send + Sync>;
+
+/// Memory and lifetime of the sessions of `/v1/sessions`.
+///
+/// ```
+/// use std::time::Duration;
+/// use llm::api::SessionConfig;
+/// use llm::memory::SlidingWindowMemory;
+///
+/// let sessions = SessionConfig::new()
+///     .memory(|| Box::new(SlidingWindowMemory::new(100)))
+///     .idle_timeout(Duration::from_secs(600));
+/// ```
+#[derive(Clone)]
+pub struct SessionConfig {
+    memory: MemoryFactory,
+    idle_timeout: Duration,
+    max_sessions: usize,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            memory: Arc::new(|| Box::new(SlidingWindowMemory::new(50))),
+            idle_timeout: Duration::from_secs(30 * 60),
+            max_sessions: 1000,
+        }
+    }
+}
+
+impl std::fmt::Debug for SessionConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionConfig")
+            .field("idle_timeout", &self.idle_timeout)
+            .field("max_sessions", &self.max_sessions)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SessionConfig {
+    /// Sessions remember their last 50 messages and are dropped 30 minutes after
+    /// their client disconnects; at most 1000 are kept.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Memory given to each new session.
+    pub fn memory<F>(mut self, memory: F) -> Self
+    where
+        F: Fn() -> Box<dyn MemoryProvider> + Send + Sync + 'static,
+    {
+        self.memory = Arc::new(memory);
+        self
+    }
+
+    /// How long a session without a connected client can still be resumed.
+    pub fn idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Sessions kept at the same time; new sessions beyond it get a 503.
+    pub fn max_sessions(mut self, max_sessions: usize) -> Self {
+        self.max_sessions = max_sessions.max(1);
+        self
+    }
+}
+
+/// Conversation of one client, resumable by id.
+pub(crate) struct Session {
+    pub(crate) id: String,
+    /// Name of the API key that opened the session
+    pub(crate) owner: Option<String>,
+    /// Model as "provider:model"
+    pub(crate) model: String,
+    pub(crate) chat: ChatWithMemory,
+    /// Locked by the connected client, so a session has one client at a time
+    pub(crate) state: Arc<tokio::sync::Mutex<SessionState>>,
+    last_seen: Mutex<Instant>,
+}
+
+#[derive(Default)]
+pub(crate) struct SessionState {
+    /// Client-side tools offered to the model
+    pub(crate) tools: Option<Vec<Tool>>,
+    /// Tool calls of the last turn still waiting for a result
+    pub(crate) pending: Vec<ToolCall>,
+    /// Results received for the tool calls of the last turn
+    pub(crate) results: Vec<ToolCall>,
+}
+
+impl Session {
+    pub(crate) fn touch(&self) {
+        *self.last_seen.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+    }
+
+    fn expired(&self, idle_timeout: Duration) -> bool {
+        let last_seen = *self.last_seen.lock().unwrap_or_else(|e| e.into_inner());
+        last_seen.elapsed() > idle_timeout && self.state.try_lock().is_ok()
+    }
+}
+
+pub(crate) struct Sessions {
+    config: SessionConfig,
+    sessions: Mutex<HashMap<String, Arc<Session>>>,
+}
+
+impl Sessions {
+    pub(crate) fn new(config: SessionConfig) -> Self {
+        Self {
+            config,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Opens a session on `provider`; `None` when `max_sessions` are open.
+    pub(crate) fn create(
+        &self,
+        owner: Option<String>,
+        model: String,
+        provider: Arc<dyn LLMProvider>,
+    ) -> Option<Arc<Session>> {
+        let memory = Arc::new(RwLock::new((self.config.memory)()));
+        let session = Arc::new(Session {
+            id: format!("sess_{}", Uuid::new_v4().simple()),
+            owner,
+            model,
+            chat: ChatWithMemory::new(provider, memory),
+            state: Arc::new(tokio::sync::Mutex::new(SessionState::default())),
+            last_seen: Mutex::new(Instant::now()),
+        });
+
+        let mut sessions = self.sessions();
+        let idle_timeout = self.config.idle_timeout;
+        sessions.retain(|_, session| !session.expired(idle_timeout));
+        if sessions.len() >= self.config.max_sessions {
+            return None;
+        }
+        sessions.insert(session.id.clone(), session.clone());
+        Some(session)
+    }
+
+    pub(crate) fn get(&self, id: &str) -> Option<Arc<Session>> {
+        let mut sessions = self.sessions();
+        let session = sessions.get(id)?.clone();
+        if session.expired(self.config.idle_timeout) {
+            sessions.remove(id);
+            return None;
+        }
+        Some(session)
+    }
+
+    fn sessions(&self) -> MutexGuard<'_, HashMap<String, Arc<Session>>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
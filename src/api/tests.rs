@@ -0,0 +1,1907 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::Router;
+use serde_json::{json, Value};
+
+use super::{
+    ApiKeyConfig, ChainDefinition, ChainStore, JobConfig, KeyStore, Server, ServerConfig,
+    SessionConfig,
+};
+use crate::{
+    chain::LLMRegistryBuilder,
+    chat::{ChatRole, ImageMime, MessageType, StreamChunk, Usage},
+    testing::{MockCall, MockLLM, MockResponse},
+    FunctionCall, ToolCall,
+};
+
+/// Serves `mock` as provider `mock` on a random local port.
+pub(super) async fn serve(mock: MockLLM) -> String {
+    let registry = LLMRegistryBuilder::new()
+        .register("mock", Box::new(mock))
+        .build();
+    let app = Server::new(registry).router();
+    format!("http://{}", spawn_app(app).await)
+}
+
+/// Serves `app` on a random local port.
+pub(super) async fn spawn_app(app: Router) -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    addr
+}
+
+async fn post(base: &str, body: Value) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("{base}/v1/chat/completions"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap()
+}
+
+/// Data payloads of a server-sent event body.
+async fn events(response: reqwest::Response) -> Vec<String> {
+    response
+        .text()
+        .await
+        .unwrap()
+        .lines()
+        .filter_map(|line| line.strip_prefix("data: "))
+        .map(str::to_string)
+        .collect()
+}
+
+fn chunks(events: &[String]) -> Vec<Value> {
+    events
+        .iter()
+        .filter(|e| *e != "[DONE]")
+        .map(|e| serde_json::from_str(e).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn non_streaming_chat_is_unchanged() {
+    let base = serve(MockLLM::new().with_fallback(MockResponse::text("hello"))).await;
+    let body: Value = post(
+        &base,
+        json!({ "model": "mock:test", "messages": [{ "role": "user", "content": "hi" }] }),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(body["object"], "chat.completion");
+    assert_eq!(body["choices"][0]["message"]["content"], "hello");
+}
+
+#[tokio::test]
+async fn streams_text_chunks_usage_and_done() {
+    let usage = Usage {
+        prompt_tokens: 4,
+        completion_tokens: 2,
+        total_tokens: 6,
+        completion_tokens_details: None,
+        prompt_tokens_details: None,
+    };
+    let base =
+        serve(MockLLM::new().with_fallback(MockResponse::text("hello").with_usage(usage))).await;
+    let response = post(
+        &base,
+        json!({
+            "model": "mock:test",
+            "stream": true,
+            "stream_options": { "include_usage": true },
+            "messages": [{ "role": "user", "content": "hi" }]
+        }),
+    )
+    .await;
+    assert!(response.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/event-stream"));
+
+    let events = events(response).await;
+    assert_eq!(events.last().unwrap(), "[DONE]");
+    let chunks = chunks(&events);
+    assert!(chunks
+        .iter()
+        .all(|c| c["object"] == "chat.completion.chunk"));
+    assert!(chunks.iter().all(|c| c["id"] == chunks[0]["id"]));
+    assert_eq!(chunks[0]["model"], "test");
+    assert_eq!(chunks[0]["choices"][0]["delta"]["role"], "assistant");
+    assert_eq!(chunks[0]["choices"][0]["delta"]["content"], "hello");
+    assert_eq!(chunks[1]["choices"][0]["finish_reason"], "stop");
+    assert_eq!(chunks[2]["choices"], json!([]));
+    assert_eq!(chunks[2]["usage"]["total_tokens"], 6);
+}
+
+#[tokio::test]
+async fn usage_chunk_is_opt_in() {
+    let usage = Usage {
+        prompt_tokens: 1,
+        completion_tokens: 1,
+        total_tokens: 2,
+        completion_tokens_details: None,
+        prompt_tokens_details: None,
+    };
+    let base = serve(MockLLM::new().with_fallback(MockResponse::text("x").with_usage(usage))).await;
+    let events = events(
+        post(
+            &base,
+            json!({
+                "model": "mock:test",
+                "stream": true,
+                "messages": [{ "role": "user", "content": "hi" }]
+            }),
+        )
+        .await,
+    )
+    .await;
+    assert!(chunks(&events).iter().all(|c| c.get("usage").is_none()));
+}
+
+#[tokio::test]
+async fn streams_tool_call_deltas() {
+    let call = ToolCall {
+        id: "call_1".to_string(),
+        call_type: "function".to_string(),
+        function: FunctionCall {
+            name: "weather".to_string(),
+            arguments: r#"{"city":"Paris"}"#.to_string(),
+        },
+    };
+    let mock =
+        MockLLM::new().with_tools(vec![crate::builder::FunctionBuilder::new("weather").build()]);
+    mock.push_stream(vec![
+        StreamChunk::Text("Checking".to_string()),
+        StreamChunk::ToolUseStart {
+            index: 1,
+            id: "call_1".to_string(),
+            name: "weather".to_string(),
+        },
+        StreamChunk::ToolUseInputDelta {
+            index: 1,
+            partial_json: r#"{"city":"#.to_string(),
+        },
+        StreamChunk::ToolUseInputDelta {
+            index: 1,
+            partial_json: r#""Paris"}"#.to_string(),
+        },
+        StreamChunk::ToolUseComplete {
+            index: 1,
+            tool_call: call,
+        },
+        StreamChunk::Done {
+            stop_reason: "tool_use".to_string(),
+        },
+    ]);
+    let base = serve(mock).await;
+    let events = events(
+        post(
+            &base,
+            json!({ "model": "mock:test", "stream": true, "messages": [{ "role": "user", "content": "weather?" }] }),
+        )
+        .await,
+    )
+    .await;
+    let chunks = chunks(&events);
+    let deltas: Vec<&Value> = chunks
+        .iter()
+        .filter_map(|c| c["choices"][0]["delta"]["tool_calls"].get(0))
+        .collect();
+    assert_eq!(deltas.len(), 3);
+    assert_eq!(deltas[0]["index"], 0);
+    assert_eq!(deltas[0]["id"], "call_1");
+    assert_eq!(deltas[0]["type"], "function");
+    assert_eq!(deltas[0]["function"]["name"], "weather");
+    let arguments: String = deltas
+        .iter()
+        .filter_map(|d| d["function"]["arguments"].as_str())
+        .collect();
+    assert_eq!(arguments, r#"{"city":"Paris"}"#);
+    assert!(deltas[1].get("id").is_none());
+    assert_eq!(
+        chunks.last().unwrap()["choices"][0]["finish_reason"],
+        "tool_calls"
+    );
+}
+
+#[tokio::test]
+async fn failing_to_open_a_stream_returns_an_error_status() {
+    let mock = MockLLM::new();
+    mock.push_error(crate::error::LLMError::ProviderError(
+        "overloaded".to_string(),
+    ));
+    let base = serve(mock).await;
+    let response = post(
+        &base,
+        json!({ "model": "mock:test", "stream": true, "messages": [{ "role": "user", "content": "hi" }] }),
+    )
+    .await;
+    assert_eq!(response.status(), 500);
+}
+
+fn weather_call() -> ToolCall {
+    ToolCall {
+        id: "call_1".to_string(),
+        call_type: "function".to_string(),
+        function: FunctionCall {
+            name: "weather".to_string(),
+            arguments: r#"{"city":"Paris"}"#.to_string(),
+        },
+    }
+}
+
+#[tokio::test]
+async fn passes_request_tools_and_returns_tool_calls_with_usage() {
+    let usage = Usage {
+        prompt_tokens: 3,
+        completion_tokens: 5,
+        total_tokens: 8,
+        completion_tokens_details: None,
+        prompt_tokens_details: None,
+    };
+    let mock = MockLLM::new();
+    mock.push_response(MockResponse::tool_calls(vec![weather_call()]).with_usage(usage));
+    let base = serve(mock.clone()).await;
+    let body: Value = post(
+        &base,
+        json!({
+            "model": "mock:test",
+            "messages": [{ "role": "user", "content": "weather?" }],
+            "tools": [{
+                "type": "function",
+                "function": {
+                    "name": "weather",
+                    "description": "Current weather",
+                    "parameters": { "type": "object", "properties": { "city": { "type": "string" } } }
+                }
+            }]
+        }),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+
+    let choice = &body["choices"][0];
+    assert_eq!(choice["finish_reason"], "tool_calls");
+    assert_eq!(choice["message"]["content"], Value::Null);
+    assert_eq!(choice["message"]["tool_calls"][0]["id"], "call_1");
+    assert_eq!(
+        choice["message"]["tool_calls"][0]["function"]["name"],
+        "weather"
+    );
+    assert_eq!(body["usage"]["total_tokens"], 8);
+
+    let MockCall::Chat { tools, .. } = &mock.calls()[0] else {
+        panic!("expected a chat call");
+    };
+    let tools = tools.as_ref().unwrap();
+    assert_eq!(tools[0].function.name, "weather");
+    assert_eq!(
+        tools[0].function.parameters["properties"]["city"]["type"],
+        "string"
+    );
+}
+
+#[tokio::test]
+async fn tool_choice_none_disables_tools() {
+    let mock = MockLLM::new().with_fallback(MockResponse::text("ok"));
+    let base = serve(mock.clone()).await;
+    post(
+        &base,
+        json!({
+            "model": "mock:test",
+            "messages": [{ "role": "user", "content": "hi" }],
+            "tools": [{ "type": "function", "function": { "name": "weather" } }],
+            "tool_choice": "none"
+        }),
+    )
+    .await;
+    let MockCall::Chat { tools, .. } = &mock.calls()[0] else {
+        panic!("expected a chat call");
+    };
+    assert!(tools.is_none());
+}
+
+#[tokio::test]
+async fn converts_tool_messages_into_tool_use_and_results() {
+    let mock = MockLLM::new().with_fallback(MockResponse::text("It is sunny."));
+    let base = serve(mock.clone()).await;
+    let body: Value = post(
+        &base,
+        json!({
+            "model": "mock:test",
+            "messages": [
+                { "role": "system", "content": "Be brief." },
+                { "role": "user", "content": "weather?" },
+                { "role": "assistant", "content": null, "tool_calls": [weather_call()] },
+                { "role": "tool", "tool_call_id": "call_1", "content": "sunny" }
+            ]
+        }),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(body["choices"][0]["message"]["content"], "It is sunny.");
+
+    let messages = mock.last_messages().unwrap();
+    assert_eq!(messages.len(), 4);
+    assert_eq!(messages[0].role, ChatRole::User);
+    assert_eq!(
+        messages[2].message_type,
+        MessageType::ToolUse(vec![weather_call()])
+    );
+    let MessageType::ToolResult(results) = &messages[3].message_type else {
+        panic!("expected tool results");
+    };
+    assert_eq!(results[0].id, "call_1");
+    assert_eq!(results[0].function.name, "weather");
+    assert_eq!(results[0].function.arguments, "sunny");
+}
+
+#[tokio::test]
+async fn converts_image_parts() {
+    let mock = MockLLM::new().with_fallback(MockResponse::text("a cat"));
+    let base = serve(mock.clone()).await;
+    let response = post(
+        &base,
+        json!({
+            "model": "mock:test",
+            "messages": [{
+                "role": "user",
+                "content": [
+                    { "type": "text", "text": "What is this?" },
+                    { "type": "image_url", "image_url": { "url": "data:image/png;base64,AQID" } },
+                    { "type": "image_url", "image_url": { "url": "https://example.com/cat.jpg" } }
+                ]
+            }]
+        }),
+    )
+    .await;
+    assert_eq!(response.status(), 200);
+
+    let messages = mock.last_messages().unwrap();
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0].content, "What is this?");
+    assert_eq!(
+        messages[0].message_type,
+        MessageType::Image((ImageMime::PNG, vec![1, 2, 3]))
+    );
+    assert_eq!(
+        messages[1].message_type,
+        MessageType::ImageURL("https://example.com/cat.jpg".to_string())
+    );
+}
+
+#[tokio::test]
+async fn rejects_unsupported_image_data() {
+    let base = serve(MockLLM::new().with_fallback(MockResponse::text("x"))).await;
+    let response = post(
+        &base,
+        json!({
+            "model": "mock:test",
+            "messages": [{
+                "role": "user",
+                "content": [{ "type": "image_url", "image_url": { "url": "data:image/bmp;base64,AQID" } }]
+            }]
+        }),
+    )
+    .await;
+    assert_eq!(response.status(), 400);
+}
+
+#[tokio::test]
+async fn response_format_asks_for_json_and_strips_fences() {
+    let mock = MockLLM::new().with_fallback(MockResponse::text("```json\n{\"answer\": 4}\n```"));
+    let base = serve(mock.clone()).await;
+    let body: Value = post(
+        &base,
+        json!({
+            "model": "mock:test",
+            "messages": [{ "role": "user", "content": "2 + 2?" }],
+            "response_format": {
+                "type": "json_schema",
+                "json_schema": {
+                    "name": "answer",
+                    "schema": { "type": "object", "properties": { "answer": { "type": "integer" } } }
+                }
+            }
+        }),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(body["choices"][0]["message"]["content"], "{\"answer\": 4}");
+    mock.assert_last_user_message_contains("schema 'answer'");
+}
+
+#[tokio::test]
+async fn lists_models_of_every_provider() {
+    let listed = MockLLM::new().with_models(
+        crate::builder::LLMBackend::OpenAI,
+        vec!["gpt-a".to_string(), "gpt-b".to_string()],
+    );
+    let registry = LLMRegistryBuilder::new()
+        .register("listed", Box::new(listed))
+        .register("unlisted", Box::new(MockLLM::new()))
+        .build();
+    let app = Server::new(registry).router();
+    let addr = spawn_app(app).await;
+
+    let body: Value = reqwest::get(format!("http://{addr}/v1/models"))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["object"], "list");
+    let ids: Vec<&str> = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| m["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, ["listed:gpt-a", "listed:gpt-b"]);
+    assert_eq!(body["data"][0]["owned_by"], "listed");
+    assert_eq!(body["data"][0]["object"], "model");
+}
+
+#[tokio::test(start_paused = true)]
+async fn lists_models_without_waiting_for_hung_providers() {
+    let models = vec!["gpt-a".to_string()];
+    let listed = MockLLM::new().with_models(crate::builder::LLMBackend::OpenAI, models.clone());
+    let hung = MockLLM::new()
+        .with_models(crate::builder::LLMBackend::OpenAI, models)
+        .with_latency(Duration::from_secs(3600));
+    let registry = LLMRegistryBuilder::new()
+        .register("listed", Box::new(listed))
+        .register("hung", Box::new(hung))
+        .build();
+    let app = Server::new(registry).router();
+    let addr = spawn_app(app).await;
+
+    let body: Value = reqwest::get(format!("http://{addr}/v1/models"))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    assert_eq!(body["data"][0]["id"], "listed:gpt-a");
+}
+
+#[tokio::test]
+async fn embeds_inputs_as_floats_or_base64() {
+    let mock = MockLLM::new();
+    mock.push_embeddings(Ok(vec![vec![0.5, 1.0], vec![2.0, -1.0]]));
+    mock.push_embeddings(Ok(vec![vec![1.0]]));
+    let base = serve(mock.clone()).await;
+    let client = reqwest::Client::new();
+
+    let body: Value = client
+        .post(format!("{base}/v1/embeddings"))
+        .json(&json!({ "model": "mock:embed", "input": ["a", "b"] }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["object"], "list");
+    assert_eq!(body["model"], "embed");
+    assert_eq!(body["data"][1]["index"], 1);
+    assert_eq!(body["data"][1]["embedding"], json!([2.0, -1.0]));
+
+    let body: Value = client
+        .post(format!("{base}/v1/embeddings"))
+        .json(&json!({ "model": "mock:embed", "input": "a", "encoding_format": "base64" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"][0]["embedding"], "AACAPw==");
+    assert!(body.get("usage").is_none());
+    assert!(matches!(&mock.calls()[1], MockCall::Embed(input) if input == &["a".to_string()]));
+}
+
+/// Ollama-compatible stub answering every chat with the model it was asked for.
+#[cfg(feature = "ollama")]
+async fn serve_model_echo() -> String {
+    use axum::{routing::post, Json};
+
+    let app = Router::new().route(
+        "/api/chat",
+        post(|Json(body): Json<Value>| async move {
+            Json(json!({ "message": { "content": body["model"] } }))
+        }),
+    );
+    format!("http://{}", spawn_app(app).await)
+}
+
+#[cfg(feature = "ollama")]
+#[tokio::test]
+async fn model_templates_serve_the_requested_model() {
+    use crate::builder::{LLMBackend, LLMBuilder};
+
+    let upstream = serve_model_echo().await;
+    let template = LLMBuilder::new()
+        .backend(LLMBackend::Ollama)
+        .base_url(upstream);
+    let registry = LLMRegistryBuilder::new()
+        .register(
+            "mock",
+            Box::new(MockLLM::new().with_fallback(MockResponse::text("mocked"))),
+        )
+        .build();
+    let app = Server::new(registry)
+        .with_model_template("ollama", template)
+        .router();
+    let base = format!("http://{}", spawn_app(app).await);
+
+    for (model, expected) in [
+        ("ollama:llama3.2", "llama3.2"),
+        ("ollama:qwen3", "qwen3"),
+        ("ollama:llama3.2", "llama3.2"),
+        ("mock:anything", "mocked"),
+    ] {
+        let body: Value = post(
+            &base,
+            json!({ "model": model, "messages": [{ "role": "user", "content": "hi" }] }),
+        )
+        .await
+        .json()
+        .await
+        .unwrap();
+        assert_eq!(body["choices"][0]["message"]["content"], expected);
+    }
+}
+
+/// Ollama-compatible stub answering every chat with the request it received.
+#[cfg(feature = "ollama")]
+async fn serve_request_echo() -> String {
+    use axum::{routing::post, Json};
+
+    let app = Router::new().route(
+        "/api/chat",
+        post(|Json(body): Json<Value>| async move {
+            Json(json!({ "message": { "content": body.to_string() } }))
+        }),
+    );
+    format!("http://{}", spawn_app(app).await)
+}
+
+#[cfg(feature = "ollama")]
+#[test]
+fn model_template_cache_drops_the_least_recently_used_model() {
+    use super::providers::{ModelProviders, ResolvedProvider};
+    use crate::builder::{LLMBackend, LLMBuilder};
+    use std::collections::HashMap;
+
+    let template = LLMBuilder::new()
+        .backend(LLMBackend::Ollama)
+        .base_url("http://127.0.0.1:1");
+    let providers = ModelProviders::new(
+        Arc::new(LLMRegistryBuilder::new().build()),
+        HashMap::from([("ollama".to_string(), template)]),
+    );
+    let resolve = |model: &str| match providers.resolve("ollama", model).unwrap() {
+        Some(ResolvedProvider::Built(provider)) => provider,
+        _ => panic!("expected a provider built from the template"),
+    };
+
+    let first = resolve("model-0");
+    let second = resolve("model-1");
+    for i in 2..64 {
+        resolve(&format!("model-{i}"));
+    }
+    assert!(Arc::ptr_eq(&first, &resolve("model-0")));
+    resolve("model-64");
+    assert!(Arc::ptr_eq(&first, &resolve("model-0")));
+    assert!(!Arc::ptr_eq(&second, &resolve("model-1")));
+}
+
+#[cfg(feature = "ollama")]
+#[test]
+fn model_template_cache_keeps_providers_per_request_settings() {
+    use super::providers::{ModelProviders, RequestSettings, ResolvedProvider};
+    use crate::builder::{LLMBackend, LLMBuilder};
+    use std::collections::HashMap;
+
+    let template = LLMBuilder::new()
+        .backend(LLMBackend::Ollama)
+        .base_url("http://127.0.0.1:1");
+    let providers = ModelProviders::new(
+        Arc::new(LLMRegistryBuilder::new().build()),
+        HashMap::from([("ollama".to_string(), template)]),
+    );
+    let resolve = |max_tokens: Option<u32>, temperature: Option<f32>| {
+        let settings = RequestSettings {
+            max_tokens,
+            temperature,
+            ..Default::default()
+        };
+        match providers
+            .resolve_with("ollama", "llama3.2", settings)
+            .unwrap()
+        {
+            Some((ResolvedProvider::Built(provider), _)) => provider,
+            _ => panic!("expected a provider built from the template"),
+        }
+    };
+
+    let plain = resolve(None, None);
+    let short = resolve(Some(256), None);
+    assert!(Arc::ptr_eq(&short, &resolve(Some(256), None)));
+    assert!(!Arc::ptr_eq(&short, &resolve(Some(512), None)));
+    assert!(!Arc::ptr_eq(&short, &resolve(Some(256), Some(0.5))));
+    assert!(!Arc::ptr_eq(&short, &plain));
+
+    for max_tokens in 0..64 {
+        resolve(Some(max_tokens), None);
+    }
+    assert!(
+        Arc::ptr_eq(&plain, &resolve(None, None)),
+        "providers built with settings do not push out the per-model ones"
+    );
+}
+
+#[cfg(feature = "ollama")]
+#[tokio::test]
+async fn model_templates_take_the_schema_natively_and_the_system_prompt_as_a_message() {
+    use crate::builder::{LLMBackend, LLMBuilder};
+
+    let template = LLMBuilder::new()
+        .backend(LLMBackend::Ollama)
+        .base_url(serve_request_echo().await);
+    let app = Server::new(LLMRegistryBuilder::new().build())
+        .with_model_template("ollama", template)
+        .router();
+    let addr = spawn_app(app).await;
+
+    let schema = json!({ "type": "object", "properties": { "answer": { "type": "integer" } } });
+    let body: Value = post(
+        &format!("http://{addr}"),
+        json!({
+            "model": "ollama:llama3.2",
+            "messages": [
+                { "role": "system", "content": "Be brief." },
+                { "role": "user", "content": "2 + 2?" }
+            ],
+            "response_format": {
+                "type": "json_schema",
+                "json_schema": { "name": "answer", "schema": schema }
+            }
+        }),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+    let upstream: Value =
+        serde_json::from_str(body["choices"][0]["message"]["content"].as_str().unwrap()).unwrap();
+    assert_eq!(upstream["format"], schema);
+    assert_eq!(
+        upstream["messages"],
+        json!([
+            { "role": "user", "content": "Be brief." },
+            { "role": "user", "content": "2 + 2?" }
+        ])
+    );
+}
+
+#[cfg(feature = "groq")]
+#[tokio::test]
+async fn model_templates_take_max_tokens_and_temperature() {
+    use crate::builder::{LLMBackend, LLMBuilder};
+    use axum::{routing::post as route_post, Json};
+
+    let upstream = Router::new().route(
+        "/chat/completions",
+        route_post(|Json(body): Json<Value>| async move {
+            Json(json!({
+                "choices": [{ "message": { "role": "assistant", "content": body.to_string() } }]
+            }))
+        }),
+    );
+    let upstream_addr = spawn_app(upstream).await;
+    let template = LLMBuilder::new()
+        .backend(LLMBackend::Groq)
+        .api_key("upstream-key")
+        .base_url(format!("http://{upstream_addr}/"));
+    let app = Server::new(LLMRegistryBuilder::new().build())
+        .with_model_template("groq", template)
+        .router();
+    let addr = spawn_app(app).await;
+
+    let body: Value = post(
+        &format!("http://{addr}"),
+        json!({
+            "model": "groq:llama-3.3",
+            "max_tokens": 42,
+            "temperature": 0.5,
+            "messages": [{ "role": "user", "content": "hi" }]
+        }),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+    let sent: Value =
+        serde_json::from_str(body["choices"][0]["message"]["content"].as_str().unwrap()).unwrap();
+    assert_eq!(sent["model"], "llama-3.3");
+    assert_eq!(sent["max_tokens"], 42);
+    assert_eq!(sent["temperature"], 0.5);
+}
+
+#[tokio::test]
+async fn unknown_providers_are_rejected() {
+    let base = serve(MockLLM::new()).await;
+    let response = post(
+        &base,
+        json!({ "model": "nope:model", "messages": [{ "role": "user", "content": "hi" }] }),
+    )
+    .await;
+    assert_eq!(response.status(), 400);
+}
+
+async fn post_with_key(base: &str, key: &str, model: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("{base}/v1/chat/completions"))
+        .bearer_auth(key)
+        .json(&json!({ "model": model, "messages": [{ "role": "user", "content": "hi" }] }))
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn key_store_enforces_allowlists_limits_and_records_usage() {
+    let usage = Usage {
+        prompt_tokens: 7,
+        completion_tokens: 3,
+        total_tokens: 10,
+        completion_tokens_details: None,
+        prompt_tokens_details: None,
+    };
+    let mock = MockLLM::new().with_fallback(MockResponse::text("ok").with_usage(usage));
+    let keys = vec![
+        ApiKeyConfig::new("team", "key-team")
+            .allow("mock:small")
+            .requests_per_minute(2),
+        ApiKeyConfig::new("admin", "key-admin"),
+    ];
+    let store = Arc::new(KeyStore::new(keys).unwrap());
+    let registry = LLMRegistryBuilder::new()
+        .register("mock", Box::new(mock.clone()))
+        .register("other", Box::new(mock))
+        .build();
+    let app = Server::new(registry).with_key_store(store.clone()).router();
+    let base = format!("http://{}", spawn_app(app).await);
+
+    assert_eq!(
+        post_with_key(&base, "wrong", "mock:small").await.status(),
+        401
+    );
+    assert_eq!(
+        post_with_key(&base, "key-team", "mock:large")
+            .await
+            .status(),
+        403
+    );
+    assert_eq!(
+        post_with_key(&base, "key-team", "other:small")
+            .await
+            .status(),
+        403
+    );
+    assert_eq!(
+        post_with_key(&base, "key-team", "mock:small")
+            .await
+            .status(),
+        200
+    );
+    assert_eq!(
+        post_with_key(&base, "key-team", "mock:small")
+            .await
+            .status(),
+        200
+    );
+    assert_eq!(
+        post_with_key(&base, "key-team", "mock:small")
+            .await
+            .status(),
+        429
+    );
+    assert_eq!(
+        post_with_key(&base, "key-admin", "other:large")
+            .await
+            .status(),
+        200
+    );
+
+    let usage = store.usage();
+    assert_eq!(usage["team"].requests, 2);
+    assert_eq!(usage["team"].denied, 2);
+    assert_eq!(usage["team"].rate_limited, 1);
+    assert_eq!(usage["team"].prompt_tokens, 14);
+    assert_eq!(usage["team"].total_tokens, 20);
+    assert_eq!(usage["admin"].requests, 1);
+}
+
+#[tokio::test]
+async fn key_store_records_streamed_usage_and_filters_models() {
+    let usage = Usage {
+        prompt_tokens: 2,
+        completion_tokens: 2,
+        total_tokens: 4,
+        completion_tokens_details: None,
+        prompt_tokens_details: None,
+    };
+    let mock = MockLLM::new()
+        .with_fallback(MockResponse::text("ok").with_usage(usage))
+        .with_models(
+            crate::builder::LLMBackend::OpenAI,
+            vec!["small".to_string(), "large".to_string()],
+        );
+    let keys = vec![ApiKeyConfig::new("team", "key-team").allow("mock:small")];
+    let store = Arc::new(KeyStore::new(keys).unwrap());
+    let registry = LLMRegistryBuilder::new()
+        .register("mock", Box::new(mock.clone()))
+        .register("other", Box::new(mock))
+        .build();
+    let app = Server::new(registry).with_key_store(store.clone()).router();
+    let base = format!("http://{}", spawn_app(app).await);
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("{base}/v1/chat/completions"))
+        .header("x-api-key", "key-team")
+        .json(&json!({ "model": "mock:small", "stream": true, "messages": [{ "role": "user", "content": "hi" }] }))
+        .send()
+        .await
+        .unwrap();
+    events(response).await;
+    assert_eq!(store.usage()["team"].total_tokens, 4);
+
+    let models: Value = client
+        .get(format!("{base}/v1/models"))
+        .bearer_auth("key-team")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(models["data"].as_array().unwrap().len(), 1);
+    assert_eq!(models["data"][0]["id"], "mock:small");
+}
+
+fn hello() -> Value {
+    json!({ "model": "mock:m", "messages": [{ "role": "user", "content": "hi" }] })
+}
+
+/// Address nothing listens on, for servers started with `Server::run`.
+fn free_addr() -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn config_limits_body_size_and_request_time() {
+    let mock = MockLLM::new()
+        .with_fallback(MockResponse::text("ok"))
+        .with_latency(Duration::from_millis(300));
+    let registry = LLMRegistryBuilder::new()
+        .register("mock", Box::new(mock))
+        .build();
+    let config = ServerConfig::new()
+        .max_body_size(256)
+        .request_timeout(Duration::from_millis(50));
+    let app = Server::new(registry).with_config(config).router();
+    let base = format!("http://{}", spawn_app(app).await);
+
+    let large = json!({
+        "model": "mock:m",
+        "messages": [{ "role": "user", "content": "x".repeat(1024) }]
+    });
+    assert_eq!(post(&base, large).await.status(), 413);
+    assert_eq!(post(&base, hello()).await.status(), 408);
+}
+
+#[tokio::test]
+async fn config_limits_concurrent_requests() {
+    let mock = MockLLM::new()
+        .with_fallback(MockResponse::text("ok"))
+        .with_latency(Duration::from_millis(200));
+    let registry = LLMRegistryBuilder::new()
+        .register("mock", Box::new(mock))
+        .build();
+    let config = ServerConfig::new().concurrency_limit(1);
+    let app = Server::new(registry).with_config(config).router();
+    let base = format!("http://{}", spawn_app(app).await);
+
+    let started = Instant::now();
+    let (first, second) = tokio::join!(post(&base, hello()), post(&base, hello()));
+    assert_eq!(first.status(), 200);
+    assert_eq!(second.status(), 200);
+    assert!(started.elapsed() >= Duration::from_millis(400));
+}
+
+#[tokio::test]
+async fn config_restricts_cors_origins() {
+    let mock = MockLLM::new().with_fallback(MockResponse::text("ok"));
+    let registry = LLMRegistryBuilder::new()
+        .register("mock", Box::new(mock))
+        .build();
+    let config = ServerConfig::new().cors_origin("https://app.example.com");
+    let app = Server::new(registry).with_config(config).router();
+    let base = format!("http://{}", spawn_app(app).await);
+    let preflight = |origin: &'static str| {
+        reqwest::Client::new()
+            .request(
+                reqwest::Method::OPTIONS,
+                format!("{base}/v1/chat/completions"),
+            )
+            .header("origin", origin)
+            .header("access-control-request-method", "POST")
+            .send()
+    };
+
+    let allowed = preflight("https://app.example.com").await.unwrap();
+    assert_eq!(
+        allowed.headers()["access-control-allow-origin"],
+        "https://app.example.com"
+    );
+    let denied = preflight("https://evil.example.com").await.unwrap();
+    assert!(denied
+        .headers()
+        .get("access-control-allow-origin")
+        .is_none());
+}
+
+#[tokio::test]
+async fn run_with_shutdown_finishes_requests_in_flight() {
+    let mock = MockLLM::new()
+        .with_fallback(MockResponse::text("done"))
+        .with_latency(Duration::from_millis(200));
+    let registry = LLMRegistryBuilder::new()
+        .register("mock", Box::new(mock))
+        .build();
+    let addr = free_addr();
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let server = tokio::spawn({
+        let addr = addr.clone();
+        async move {
+            Server::new(registry)
+                .run_with_shutdown(&addr, async {
+                    let _ = stopped.await;
+                })
+                .await
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let base = format!("http://{addr}");
+    let request = tokio::spawn(async move { post(&base, hello()).await });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    stop.send(()).unwrap();
+
+    let response = request.await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["choices"][0]["message"]["content"], "done");
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+}
+
+#[cfg(feature = "rustls-tls")]
+#[tokio::test]
+async fn serves_https_with_tls_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let key = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let (cert_path, key_path) = (dir.path().join("cert.pem"), dir.path().join("key.pem"));
+    std::fs::write(&cert_path, key.cert.pem()).unwrap();
+    std::fs::write(&key_path, key.signing_key.serialize_pem()).unwrap();
+
+    let registry = || {
+        LLMRegistryBuilder::new()
+            .register(
+                "mock",
+                Box::new(MockLLM::new().with_fallback(MockResponse::text("secure"))),
+            )
+            .build()
+    };
+    let missing = ServerConfig::new().tls(dir.path().join("none.pem"), &key_path);
+    let result = Server::new(registry())
+        .with_config(missing)
+        .run(&free_addr())
+        .await;
+    assert!(result.is_err());
+
+    let addr = free_addr();
+    let port = addr.rsplit(':').next().unwrap().to_string();
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let server = tokio::spawn(async move {
+        Server::new(registry())
+            .with_config(ServerConfig::new().tls(cert_path, key_path))
+            .run_with_shutdown(&addr, async {
+                let _ = stopped.await;
+            })
+            .await
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let client = reqwest::Client::builder()
+        .add_root_certificate(reqwest::Certificate::from_pem(key.cert.pem().as_bytes()).unwrap())
+        .build()
+        .unwrap();
+    let body: Value = client
+        .post(format!("https://localhost:{port}/v1/chat/completions"))
+        .json(&hello())
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["choices"][0]["message"]["content"], "secure");
+
+    stop.send(()).unwrap();
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn health_and_ready_answer_without_auth() {
+    let registry = LLMRegistryBuilder::new()
+        .register("mock", Box::new(MockLLM::new()))
+        .build();
+    let app = Server::new(registry).with_auth_key("secret").router();
+    let addr = spawn_app(app).await;
+
+    let health: Value = reqwest::get(format!("http://{addr}/health"))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(health["status"], "ok");
+    let ready = reqwest::get(format!("http://{addr}/ready")).await.unwrap();
+    assert_eq!(ready.status(), 200);
+}
+
+#[tokio::test]
+async fn ready_probes_providers_when_configured() {
+    let listing = MockLLM::new().with_models(
+        crate::builder::LLMBackend::OpenAI,
+        vec!["small".to_string()],
+    );
+    let registry = LLMRegistryBuilder::new()
+        .register("up", Box::new(listing))
+        .register("down", Box::new(MockLLM::new()))
+        .build();
+    let app = Server::new(registry)
+        .with_config(ServerConfig::new().probe_providers(true))
+        .router();
+    let addr = spawn_app(app).await;
+
+    let response = reqwest::get(format!("http://{addr}/ready")).await.unwrap();
+    assert_eq!(response.status(), 503);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body, json!({ "status": "unavailable" }));
+}
+
+#[tokio::test]
+async fn ready_counts_providers_without_model_listing_as_up() {
+    use crate::record_replay::{Cassette, ReplayLLM};
+
+    let registry = LLMRegistryBuilder::new()
+        .register("replay", Box::new(ReplayLLM::new(Cassette::default())))
+        .build();
+    let app = Server::new(registry)
+        .with_config(ServerConfig::new().probe_providers(true))
+        .router();
+    let addr = spawn_app(app).await;
+
+    let response = reqwest::get(format!("http://{addr}/ready")).await.unwrap();
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn metrics_count_requests_errors_and_tokens() {
+    let usage = Usage {
+        prompt_tokens: 5,
+        completion_tokens: 2,
+        total_tokens: 7,
+        completion_tokens_details: None,
+        prompt_tokens_details: None,
+    };
+    let mock = MockLLM::new().with_fallback(MockResponse::text("ok").with_usage(usage));
+    let base = serve(mock).await;
+
+    assert_eq!(post(&base, hello()).await.status(), 200);
+    let stream = json!({
+        "model": "mock:m",
+        "stream": true,
+        "messages": [{ "role": "user", "content": "hi" }]
+    });
+    events(post(&base, stream).await).await;
+    let unknown = json!({ "model": "nope:x", "messages": [{ "role": "user", "content": "hi" }] });
+    assert_eq!(post(&base, unknown).await.status(), 400);
+
+    let response = reqwest::get(format!("{base}/metrics")).await.unwrap();
+    assert!(response.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/plain"));
+    let text = response.text().await.unwrap();
+    let chat = r#"route="/v1/chat/completions",provider="mock",model="m""#;
+    let unknown = r#"route="/v1/chat/completions",provider="",model="""#;
+    assert!(text.contains(&format!(
+        "llm_api_requests_total{{{chat},status=\"200\"}} 2"
+    )));
+    assert!(text.contains(&format!(
+        "llm_api_errors_total{{{unknown},status=\"400\"}} 1"
+    )));
+    assert!(!text.contains(&format!("llm_api_errors_total{{{chat}")));
+    assert!(!text.contains("nope"));
+    assert!(text.contains(&format!(
+        "llm_api_request_duration_seconds_count{{{chat}}} 2"
+    )));
+    assert!(text.contains(r#"llm_api_tokens_total{provider="mock",model="m",type="prompt"} 10"#));
+    assert!(text.contains(r#"llm_api_tokens_total{provider="mock",model="m",type="completion"} 4"#));
+    assert!(!text.contains("/metrics"));
+}
+
+#[tokio::test]
+async fn metrics_fold_models_beyond_the_label_limit_into_other() {
+    let base = serve(MockLLM::new().with_fallback(MockResponse::text("ok"))).await;
+    for i in 0..65 {
+        let body = json!({
+            "model": format!("mock:m{i}"),
+            "messages": [{ "role": "user", "content": "hi" }]
+        });
+        assert_eq!(post(&base, body).await.status(), 200);
+    }
+    assert_eq!(post(&base, hello()).await.status(), 200);
+
+    let text = reqwest::get(format!("{base}/metrics"))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    let route = r#"route="/v1/chat/completions",provider="mock""#;
+    assert!(text.contains(&format!(
+        "llm_api_requests_total{{{route},model=\"m63\",status=\"200\"}} 1"
+    )));
+    assert!(!text.contains("m64"));
+    assert!(text.contains(&format!(
+        "llm_api_requests_total{{{route},model=\"other\",status=\"200\"}} 2"
+    )));
+}
+
+#[tokio::test]
+async fn chain_steps_apply_transform_pipelines() {
+    let mock = MockLLM::new();
+    mock.push_text("<think>reasoning</think>```json\n{\"city\": \"Paris\"}\n```");
+    mock.push_text("Done.");
+    let base = serve(mock.clone()).await;
+
+    let body = json!({
+        "steps": [
+            {
+                "provider_id": "mock",
+                "id": "extract",
+                "template": "Where?",
+                "response_transform": ["strip_think", "extract_json", { "type": "json_path", "path": "$.city" }]
+            },
+            {
+                "provider_id": "mock",
+                "id": "answer",
+                "template": "City: {{extract}}",
+                "response_transform": { "type": "regex", "pattern": "(\\w+)\\." }
+            }
+        ]
+    });
+    let response: Value = post(&base, body).await.json().await.unwrap();
+    assert_eq!(response["choices"][0]["message"]["content"], "Done");
+    mock.assert_last_user_message_contains("City: Paris");
+}
+
+#[tokio::test]
+async fn legacy_extract_json_returns_the_fenced_document() {
+    let mock = MockLLM::new();
+    mock.push_text("Sure:\n```json\n{\"ok\": true}\n```");
+    mock.push_text("checked");
+    let base = serve(mock.clone()).await;
+
+    let body = json!({
+        "model": "mock:m",
+        "messages": [{ "role": "user", "content": "hi" }],
+        "response_transform": "extract_json",
+        "steps": [{ "provider_id": "mock", "id": "check", "template": "Check {{initial}}" }]
+    });
+    let response: Value = post(&base, body).await.json().await.unwrap();
+    assert_eq!(response["choices"][0]["message"]["content"], "checked");
+    mock.assert_last_user_message_contains("Check {\"ok\": true}");
+}
+
+#[tokio::test]
+async fn chain_transforms_reject_unknown_names_and_failed_extractions() {
+    let mock = MockLLM::new().with_fallback(MockResponse::text("no json"));
+    let base = serve(mock).await;
+    let step = |transform: Value| json!({ "steps": [{ "provider_id": "mock", "id": "a", "template": "t", "response_transform": transform }] });
+
+    assert_eq!(post(&base, step(json!("reverse"))).await.status(), 422);
+    assert_eq!(post(&base, step(json!("extract_json"))).await.status(), 500);
+}
+
+fn summarize_chain() -> ChainDefinition {
+    serde_yaml::from_str(
+        r#"
+id: summarize
+description: Summary, then a title
+steps:
+  - id: summary
+    provider_id: mock
+    template: "Summarize in {{language}}: {{input}}"
+    response_transform: trim
+  - id: title
+    provider_id: mock
+    template: "Title for: {{summary}}"
+output: summary
+"#,
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn named_chains_run_by_id() {
+    let mock = MockLLM::new();
+    mock.push_text("  A short summary.  ");
+    mock.push_text("A title");
+    let registry = LLMRegistryBuilder::new()
+        .register("mock", Box::new(mock.clone()))
+        .build();
+    let chains = ChainStore::new(vec![summarize_chain()]).unwrap();
+    let app = Server::new(registry).with_chains(chains).router();
+    let base = format!("http://{}", spawn_app(app).await);
+    let client = reqwest::Client::new();
+
+    let list: Value = client
+        .get(format!("{base}/v1/chains"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(list["data"][0]["id"], "summarize");
+    assert_eq!(list["data"][0]["steps"], json!(["summary", "title"]));
+
+    let response: Value = client
+        .post(format!("{base}/v1/chains/summarize"))
+        .json(&json!({ "input": "a long text", "variables": { "language": "French" } }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(response["output"], "A short summary.");
+    assert_eq!(response["steps"]["title"], "A title");
+    let MockCall::Chat { messages, .. } = &mock.calls()[0] else {
+        panic!("expected a chat call");
+    };
+    assert_eq!(messages[0].content, "Summarize in French: a long text");
+
+    let missing = client
+        .post(format!("{base}/v1/chains/summarize"))
+        .json(&json!({ "input": "text" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), 400);
+    let unknown = client
+        .post(format!("{base}/v1/chains/other"))
+        .json(&json!({}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(unknown.status(), 404);
+}
+
+#[tokio::test]
+async fn named_chains_respect_key_allowlists() {
+    let registry = LLMRegistryBuilder::new()
+        .register("mock", Box::new(MockLLM::new()))
+        .build();
+    let keys = vec![ApiKeyConfig::new("other", "key-other").allow("openai")];
+    let chains = ChainStore::new(vec![summarize_chain()]).unwrap();
+    let app = Server::new(registry)
+        .with_chains(chains)
+        .with_key_store(Arc::new(KeyStore::new(keys).unwrap()))
+        .router();
+    let base = format!("http://{}", spawn_app(app).await);
+    let client = reqwest::Client::new();
+
+    let list: Value = client
+        .get(format!("{base}/v1/chains"))
+        .bearer_auth("key-other")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(list["data"], json!([]));
+    let run = client
+        .post(format!("{base}/v1/chains/summarize"))
+        .bearer_auth("key-other")
+        .json(&json!({ "input": "text", "variables": { "language": "French" } }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(run.status(), 403);
+}
+
+#[test]
+fn chain_store_validates_definitions() {
+    let mut duplicate_step = summarize_chain();
+    duplicate_step.steps[1].id = "summary".to_string();
+    assert!(ChainStore::new(vec![duplicate_step]).is_err());
+
+    let mut unknown_output = summarize_chain();
+    unknown_output.output = Some("missing".to_string());
+    assert!(ChainStore::new(vec![unknown_output]).is_err());
+
+    for template in ["{{ input | bogus }}", "Summarize: {{ input"] {
+        let mut malformed = summarize_chain();
+        malformed.steps[0].template = template.to_string();
+        assert!(ChainStore::new(vec![malformed]).is_err(), "{template}");
+    }
+
+    assert!(ChainStore::new(vec![summarize_chain(), summarize_chain()]).is_err());
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("chains.json");
+    let file = json!({ "chains": [summarize_chain()] });
+    std::fs::write(&path, file.to_string()).unwrap();
+    let store = ChainStore::from_file(&path).unwrap();
+    assert_eq!(store.get("summarize").unwrap().output_step(), "summary");
+}
+
+async fn submit_job(base: &str, key: &str, body: Value) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("{base}/v1/jobs"))
+        .bearer_auth(key)
+        .json(&body)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn get_job(base: &str, key: &str, id: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .get(format!("{base}/v1/jobs/{id}"))
+        .bearer_auth(key)
+        .send()
+        .await
+        .unwrap()
+}
+
+/// Polls the job until it is no longer queued or running.
+async fn wait_for_job(base: &str, key: &str, id: &str) -> Value {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        let job: Value = get_job(base, key, id).await.json().await.unwrap();
+        if job["status"] != "queued" && job["status"] != "running" {
+            return job;
+        }
+        assert!(Instant::now() < deadline, "job did not finish: {job}");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
+
+#[tokio::test]
+async fn jobs_run_named_chains_with_step_progress() {
+    let mock = MockLLM::new().with_latency(Duration::from_millis(200));
+    mock.push_text("  A short summary.  ");
+    mock.push_text("A title");
+    let registry = LLMRegistryBuilder::new()
+        .register("mock", Box::new(mock))
+        .build();
+    let chains = ChainStore::new(vec![summarize_chain()]).unwrap();
+    let app = Server::new(registry)
+        .with_chains(chains)
+        .with_jobs(JobConfig::new())
+        .router();
+    let base = format!("http://{}", spawn_app(app).await);
+
+    let submitted = submit_job(
+        &base,
+        "unused",
+        json!({ "chain": "summarize", "input": "a long text", "variables": { "language": "French" } }),
+    )
+    .await;
+    assert_eq!(submitted.status(), 202);
+    let job: Value = submitted.json().await.unwrap();
+    let id = job["id"].as_str().unwrap();
+    assert_eq!(job["object"], "job");
+    assert_eq!(job["steps"][0]["id"], "summary");
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        let job: Value = get_job(&base, "unused", id).await.json().await.unwrap();
+        if job["steps"][0]["status"] == "completed" {
+            assert_eq!(job["status"], "running");
+            assert_eq!(job["steps"][0]["output"], "A short summary.");
+            assert_eq!(job["steps"][1]["status"], "running");
+            break;
+        }
+        assert!(
+            Instant::now() < deadline,
+            "first step did not complete: {job}"
+        );
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    let job = wait_for_job(&base, "unused", id).await;
+    assert_eq!(job["status"], "succeeded");
+    assert_eq!(job["steps"][1]["output"], "A title");
+    assert_eq!(job["result"]["output"], "A short summary.");
+    assert_eq!(job["result"]["steps"]["title"], "A title");
+    assert!(job["finished_at"].as_u64().is_some());
+}
+
+#[tokio::test]
+async fn jobs_run_chat_requests() {
+    let mock = MockLLM::new().with_fallback(MockResponse::text("hello"));
+    let registry = LLMRegistryBuilder::new()
+        .register("mock", Box::new(mock.clone()))
+        .build();
+    let app = Server::new(registry).with_jobs(JobConfig::new()).router();
+    let base = format!("http://{}", spawn_app(app).await);
+
+    let job: Value = submit_job(&base, "unused", hello())
+        .await
+        .json()
+        .await
+        .unwrap();
+    let job = wait_for_job(&base, "unused", job["id"].as_str().unwrap()).await;
+    assert_eq!(job["status"], "succeeded");
+    assert_eq!(job["result"]["choices"][0]["message"]["content"], "hello");
+    assert_eq!(job["steps"], json!([]));
+
+    let mut failing = hello();
+    failing["model"] = json!("unknown:m");
+    let job: Value = submit_job(&base, "unused", failing)
+        .await
+        .json()
+        .await
+        .unwrap();
+    let job = wait_for_job(&base, "unused", job["id"].as_str().unwrap()).await;
+    assert_eq!(job["status"], "failed");
+    assert_eq!(job["error"]["status"], 400);
+
+    let mut streaming = hello();
+    streaming["stream"] = json!(true);
+    assert_eq!(submit_job(&base, "unused", streaming).await.status(), 400);
+    let unknown = submit_job(&base, "unused", json!({ "chain": "other" })).await;
+    assert_eq!(unknown.status(), 404);
+    assert_eq!(get_job(&base, "unused", "job_missing").await.status(), 404);
+
+    let disabled = serve(mock).await;
+    assert_eq!(submit_job(&disabled, "unused", hello()).await.status(), 404);
+}
+
+#[tokio::test]
+async fn jobs_check_key_allowlists_and_limits_on_submit() {
+    let mock = MockLLM::new().with_fallback(MockResponse::text("hello"));
+    let keys = vec![ApiKeyConfig::new("team", "key-team")
+        .allow("mock:m")
+        .requests_per_minute(1)];
+    let registry = LLMRegistryBuilder::new()
+        .register("mock", Box::new(mock))
+        .build();
+    let app = Server::new(registry)
+        .with_jobs(JobConfig::new())
+        .with_key_store(Arc::new(KeyStore::new(keys).unwrap()))
+        .router();
+    let base = format!("http://{}", spawn_app(app).await);
+
+    let other_model =
+        json!({ "model": "mock:other", "messages": [{ "role": "user", "content": "hi" }] });
+    assert_eq!(
+        submit_job(&base, "key-team", other_model).await.status(),
+        403
+    );
+    let job: Value = submit_job(&base, "key-team", hello())
+        .await
+        .json()
+        .await
+        .unwrap();
+    let job = wait_for_job(&base, "key-team", job["id"].as_str().unwrap()).await;
+    assert_eq!(job["status"], "succeeded");
+    assert_eq!(submit_job(&base, "key-team", hello()).await.status(), 429);
+}
+
+#[tokio::test]
+async fn jobs_are_bounded_cancellable_and_private() {
+    let mock = MockLLM::new()
+        .with_fallback(MockResponse::text("slow"))
+        .with_latency(Duration::from_secs(10));
+    let keys = vec![
+        ApiKeyConfig::new("team", "key-team"),
+        ApiKeyConfig::new("other", "key-other"),
+    ];
+    let jobs = JobConfig::new().workers(1).max_pending(2);
+    let registry = LLMRegistryBuilder::new()
+        .register("mock", Box::new(mock))
+        .build();
+    let app = Server::new(registry)
+        .with_jobs(jobs)
+        .with_key_store(Arc::new(KeyStore::new(keys).unwrap()))
+        .router();
+    let base = format!("http://{}", spawn_app(app).await);
+    let client = reqwest::Client::new();
+
+    let first: Value = submit_job(&base, "key-team", hello())
+        .await
+        .json()
+        .await
+        .unwrap();
+    let second: Value = submit_job(&base, "key-team", hello())
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(first["owner"], "team");
+    assert_eq!(submit_job(&base, "key-team", hello()).await.status(), 503);
+
+    let first_id = first["id"].as_str().unwrap();
+    let second_id = second["id"].as_str().unwrap();
+    assert_eq!(get_job(&base, "key-other", first_id).await.status(), 404);
+    let second: Value = get_job(&base, "key-team", second_id)
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(second["status"], "queued", "one worker runs the first job");
+
+    for id in [first_id, second_id] {
+        let cancelled: Value = client
+            .delete(format!("{base}/v1/jobs/{id}"))
+            .bearer_auth("key-team")
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(cancelled["status"], "cancelled");
+    }
+    let job: Value = get_job(&base, "key-team", first_id)
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(job["status"], "cancelled");
+    assert_eq!(submit_job(&base, "key-team", hello()).await.status(), 202);
+
+    let removed = client
+        .delete(format!("{base}/v1/jobs/{first_id}"))
+        .bearer_auth("key-team")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(removed.status(), 200);
+    assert_eq!(get_job(&base, "key-team", first_id).await.status(), 404);
+}
+
+type Socket =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// Connects to a session; the error is the HTTP status of a refused upgrade.
+async fn connect_session(url: &str) -> Result<(Socket, Value), u16> {
+    use tokio_tungstenite::tungstenite::Error;
+
+    match tokio_tungstenite::connect_async(url).await {
+        Ok((mut socket, _)) => {
+            let hello = next_event(&mut socket).await;
+            Ok((socket, hello))
+        }
+        Err(Error::Http(response)) => Err(response.status().as_u16()),
+        Err(err) => panic!("websocket connection failed: {err}"),
+    }
+}
+
+/// Reconnects once the previous connection of the session has closed.
+async fn resume_session(url: &str) -> (Socket, Value) {
+    let deadline = Instant::now() + Duration::from_secs(2);
+    loop {
+        match connect_session(url).await {
+            Ok(connected) => return connected,
+            Err(409) if Instant::now() < deadline => {
+                tokio::time::sleep(Duration::from_millis(20)).await
+            }
+            Err(status) => panic!("session was not resumed: {status}"),
+        }
+    }
+}
+
+async fn next_event(socket: &mut Socket) -> Value {
+    use futures::StreamExt;
+    use tokio_tungstenite::tungstenite::Message;
+
+    loop {
+        match socket.next().await.unwrap().unwrap() {
+            Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+            Message::Close(_) => panic!("session closed"),
+            _ => continue,
+        }
+    }
+}
+
+async fn send_event(socket: &mut Socket, event: Value) {
+    use futures::SinkExt;
+    use tokio_tungstenite::tungstenite::Message;
+
+    socket.send(Message::Text(event.to_string())).await.unwrap();
+}
+
+/// Events of a turn, up to its `done` chunk.
+async fn read_turn(socket: &mut Socket) -> Vec<Value> {
+    let mut events = Vec::new();
+    loop {
+        let event = next_event(socket).await;
+        let done = event["chunk"].get("done").is_some() || event["type"] == "error";
+        events.push(event);
+        if done {
+            return events;
+        }
+    }
+}
+
+#[tokio::test]
+async fn sessions_stream_chunks_and_remember_the_conversation() {
+    let mock = MockLLM::new();
+    mock.push_text("Hello!");
+    mock.push_text("Still here");
+    let registry = LLMRegistryBuilder::new()
+        .register("mock", Box::new(mock.clone()))
+        .build();
+    let app = Server::new(registry)
+        .with_sessions(SessionConfig::new())
+        .router();
+    let base = format!("ws://{}", spawn_app(app).await);
+
+    let (mut socket, hello) = connect_session(&format!("{base}/v1/sessions?model=mock:m"))
+        .await
+        .unwrap();
+    assert_eq!(hello["type"], "session");
+    assert_eq!(hello["resumed"], false);
+    assert!(hello["id"].as_str().unwrap().starts_with("sess_"));
+
+    send_event(&mut socket, json!({ "type": "message", "content": "hi" })).await;
+    let turn = read_turn(&mut socket).await;
+    assert_eq!(
+        turn[0],
+        json!({ "type": "chunk", "chunk": { "text": "Hello!" } })
+    );
+
+    send_event(
+        &mut socket,
+        json!({ "type": "message", "content": "still there?" }),
+    )
+    .await;
+    let turn = read_turn(&mut socket).await;
+    assert_eq!(turn[0]["chunk"]["text"], "Still here");
+    let MockCall::ChatStream { messages, .. } = &mock.calls()[1] else {
+        panic!("expected a streamed call");
+    };
+    let contents: Vec<_> = messages.iter().map(|m| m.content.as_str()).collect();
+    assert_eq!(contents, ["hi", "Hello!", "still there?"]);
+
+    send_event(&mut socket, json!({ "type": "unknown" })).await;
+    assert_eq!(next_event(&mut socket).await["type"], "error");
+    let missing = connect_session(&format!("{base}/v1/sessions/sess_missing")).await;
+    assert_eq!(missing.err(), Some(404));
+    let disabled = serve(mock).await.replace("http://", "ws://");
+    let refused = connect_session(&format!("{disabled}/v1/sessions?model=mock:m")).await;
+    assert_eq!(refused.err(), Some(404));
+}
+
+#[tokio::test]
+async fn sessions_record_streamed_usage_against_the_key() {
+    let usage = Usage {
+        prompt_tokens: 5,
+        completion_tokens: 2,
+        total_tokens: 7,
+        completion_tokens_details: None,
+        prompt_tokens_details: None,
+    };
+    let mock = MockLLM::new().with_fallback(MockResponse::text("ok").with_usage(usage));
+    let registry = LLMRegistryBuilder::new()
+        .register("mock", Box::new(mock))
+        .build();
+    let store = Arc::new(KeyStore::new(vec![ApiKeyConfig::new("team", "key-team")]).unwrap());
+    let app = Server::new(registry)
+        .with_sessions(SessionConfig::new())
+        .with_key_store(store.clone())
+        .router();
+    let addr = spawn_app(app).await;
+
+    let url = format!("ws://{addr}/v1/sessions?model=mock:m&api_key=key-team");
+    let (mut socket, _) = connect_session(&url).await.unwrap();
+    send_event(&mut socket, json!({ "type": "message", "content": "hi" })).await;
+    let turn = read_turn(&mut socket).await;
+    assert!(turn
+        .iter()
+        .any(|event| event["chunk"]["usage"]["total_tokens"] == 7));
+    assert_eq!(store.usage()["team"].total_tokens, 7);
+}
+
+#[tokio::test]
+async fn sessions_take_client_tool_results_and_resume_by_id() {
+    let mock = MockLLM::new();
+    let call = ToolCall {
+        id: "call_1".to_string(),
+        call_type: "function".to_string(),
+        function: FunctionCall {
+            name: "weather".to_string(),
+            arguments: r#"{"city":"Paris"}"#.to_string(),
+        },
+    };
+    mock.push_stream(vec![
+        StreamChunk::ToolUseComplete {
+            index: 0,
+            tool_call: call,
+        },
+        StreamChunk::Done {
+            stop_reason: "tool_use".to_string(),
+        },
+    ]);
+    mock.push_text("It is sunny in Paris");
+    let keys = vec![
+        ApiKeyConfig::new("team", "key-team"),
+        ApiKeyConfig::new("other", "key-other"),
+    ];
+    let registry = LLMRegistryBuilder::new()
+        .register("mock", Box::new(mock.clone()))
+        .build();
+    let app = Server::new(registry)
+        .with_sessions(SessionConfig::new())
+        .with_key_store(Arc::new(KeyStore::new(keys).unwrap()))
+        .router();
+    let base = format!("ws://{}", spawn_app(app).await);
+
+    let url = format!("{base}/v1/sessions?model=mock:m&api_key=key-team");
+    let (mut socket, hello) = connect_session(&url).await.unwrap();
+    let id = hello["id"].as_str().unwrap().to_string();
+    let tool = json!({ "type": "function", "function": { "name": "weather", "parameters": {} } });
+    send_event(
+        &mut socket,
+        json!({ "type": "message", "content": "weather in Paris?", "tools": [tool] }),
+    )
+    .await;
+    let turn = read_turn(&mut socket).await;
+    assert_eq!(
+        turn[0]["chunk"]["tool_use_complete"]["tool_call"]["id"],
+        "call_1"
+    );
+    let MockCall::ChatStream { tools, .. } = &mock.calls()[0] else {
+        panic!("expected a streamed call");
+    };
+    assert_eq!(tools.as_ref().unwrap()[0].function.name, "weather");
+
+    let resume = format!("{base}/v1/sessions/{id}?api_key=key-team");
+    assert_eq!(connect_session(&resume).await.err(), Some(409));
+    drop(socket);
+
+    let other = format!("{base}/v1/sessions/{id}?api_key=key-other");
+    assert_eq!(connect_session(&other).await.err(), Some(404));
+    let (mut socket, hello) = resume_session(&resume).await;
+    assert_eq!(hello["resumed"], true);
+    assert_eq!(hello["pending_tool_calls"][0]["id"], "call_1");
+    assert_eq!(hello["history"][0]["content"], "weather in Paris?");
+    assert_eq!(hello["history"][1]["tool_calls"][0]["id"], "call_1");
+
+    send_event(
+        &mut socket,
+        json!({ "type": "message", "content": "hello?" }),
+    )
+    .await;
+    assert_eq!(next_event(&mut socket).await["type"], "error");
+    send_event(
+        &mut socket,
+        json!({ "type": "tool_result", "tool_call_id": "call_1", "content": "sunny" }),
+    )
+    .await;
+    let turn = read_turn(&mut socket).await;
+    assert_eq!(turn[0]["chunk"]["text"], "It is sunny in Paris");
+
+    let MockCall::ChatStream { messages, .. } = &mock.calls()[1] else {
+        panic!("expected a streamed call");
+    };
+    let MessageType::ToolResult(results) = &messages.last().unwrap().message_type else {
+        panic!("expected the tool result last");
+    };
+    assert_eq!(results[0].id, "call_1");
+    assert_eq!(results[0].function.name, "weather");
+    assert_eq!(results[0].function.arguments, "sunny");
+}
+
+#[tokio::test]
+async fn sessions_keep_tool_calls_pending_when_the_key_is_rate_limited() {
+    let mock = MockLLM::new();
+    mock.push_stream(vec![
+        StreamChunk::ToolUseComplete {
+            index: 0,
+            tool_call: ToolCall {
+                id: "call_1".to_string(),
+                call_type: "function".to_string(),
+                function: FunctionCall {
+                    name: "weather".to_string(),
+                    arguments: "{}".to_string(),
+                },
+            },
+        },
+        StreamChunk::Done {
+            stop_reason: "tool_use".to_string(),
+        },
+    ]);
+    let keys = vec![ApiKeyConfig::new("team", "key-team").requests_per_minute(1)];
+    let registry = LLMRegistryBuilder::new()
+        .register("mock", Box::new(mock.clone()))
+        .build();
+    let app = Server::new(registry)
+        .with_sessions(SessionConfig::new())
+        .with_key_store(Arc::new(KeyStore::new(keys).unwrap()))
+        .router();
+    let base = format!("ws://{}", spawn_app(app).await);
+
+    let url = format!("{base}/v1/sessions?model=mock:m&api_key=key-team");
+    let (mut socket, hello) = connect_session(&url).await.unwrap();
+    let id = hello["id"].as_str().unwrap().to_string();
+    send_event(
+        &mut socket,
+        json!({ "type": "message", "content": "weather?" }),
+    )
+    .await;
+    read_turn(&mut socket).await;
+
+    send_event(
+        &mut socket,
+        json!({ "type": "tool_result", "tool_call_id": "call_1", "content": "sunny" }),
+    )
+    .await;
+    let refused = next_event(&mut socket).await;
+    assert_eq!(refused["type"], "error");
+    assert!(refused["message"].as_str().unwrap().contains("rate limit"));
+    send_event(
+        &mut socket,
+        json!({ "type": "message", "content": "hello?" }),
+    )
+    .await;
+    let waiting = next_event(&mut socket).await;
+    assert_eq!(waiting["message"], "Tool calls are waiting for a result");
+    assert_eq!(mock.call_count(), 1);
+    drop(socket);
+
+    let resume = format!("{base}/v1/sessions/{id}?api_key=key-team");
+    let (_, hello) = resume_session(&resume).await;
+    assert_eq!(hello["pending_tool_calls"][0]["id"], "call_1");
+}
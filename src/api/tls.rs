@@ -0,0 +1,129 @@
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::Router;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto::Builder;
+use hyper_util::service::TowerToHyperService;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tokio_rustls::rustls::crypto::{ring, CryptoProvider};
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::TlsAcceptor;
+
+use crate::error::LLMError;
+
+/// PEM files the server reads its certificate chain and private key from
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    /// Certificate chain, leaf first
+    pub cert_path: PathBuf,
+    /// PKCS#8, PKCS#1 or SEC1 private key
+    pub key_path: PathBuf,
+}
+
+impl TlsConfig {
+    /// Config reading the certificate chain and private key from the given PEM
+    /// files; they are only read when the server starts
+    pub fn new(cert_path: impl Into<PathBuf>, key_path: impl Into<PathBuf>) -> Self {
+        Self {
+            cert_path: cert_path.into(),
+            key_path: key_path.into(),
+        }
+    }
+
+    /// Loads the files, failing when either is missing or invalid
+    pub(super) fn acceptor(&self) -> Result<TlsAcceptor, LLMError> {
+        let certs = CertificateDer::pem_file_iter(&self.cert_path)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| pem_error(&self.cert_path, e))?;
+        if certs.is_empty() {
+            return Err(LLMError::InvalidRequest(format!(
+                "No certificate found in {}",
+                self.cert_path.display()
+            )));
+        }
+        let key = PrivateKeyDer::from_pem_file(&self.key_path)
+            .map_err(|e| pem_error(&self.key_path, e))?;
+
+        // Use the process default when the application installed one.
+        let provider = CryptoProvider::get_default()
+            .cloned()
+            .unwrap_or_else(|| Arc::new(ring::default_provider()));
+        let mut config = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+            .map_err(|e| LLMError::InvalidRequest(format!("Invalid TLS configuration: {e}")))?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+}
+
+fn pem_error(path: &Path, err: impl std::fmt::Display) -> LLMError {
+    LLMError::InvalidRequest(format!("Could not read {}: {err}", path.display()))
+}
+
+/// Serves `app` over TLS until `signal` resolves, then waits for open connections
+/// to finish their in-flight requests.
+pub(super) async fn serve(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    app: Router,
+    signal: impl Future<Output = ()> + Send + 'static,
+) {
+    let (shutdown_tx, shutdown_rx) = watch::channel(());
+    let (closed_tx, closed_rx) = watch::channel(());
+    tokio::pin!(signal);
+
+    loop {
+        let (tcp, remote) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    // Usually out of file descriptors; give connections time to close.
+                    log::warn!("Could not accept connection: {err}");
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            },
+            _ = &mut signal => break,
+        };
+
+        let acceptor = acceptor.clone();
+        let service = TowerToHyperService::new(app.clone());
+        let mut shutdown_rx = shutdown_rx.clone();
+        let closed_rx = closed_rx.clone();
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(tcp).await {
+                Ok(stream) => stream,
+                Err(err) => {
+                    log::debug!("TLS handshake with {remote} failed: {err}");
+                    return;
+                }
+            };
+            let builder = Builder::new(TokioExecutor::new());
+            let conn = builder.serve_connection_with_upgrades(TokioIo::new(stream), service);
+            tokio::pin!(conn);
+            let result = tokio::select! {
+                result = conn.as_mut() => result,
+                _ = shutdown_rx.changed() => {
+                    conn.as_mut().graceful_shutdown();
+                    conn.await
+                }
+            };
+            if let Err(err) = result {
+                log::debug!("Connection with {remote} closed: {err}");
+            }
+            drop(closed_rx);
+        });
+    }
+
+    drop(listener);
+    drop(closed_rx);
+    let _ = shutdown_tx.send(());
+    closed_tx.closed().await;
+}
@@ -1,4 +1,11 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::chain::TransformPipeline;
+use crate::chat::{FunctionTool, StreamChunk, StructuredOutputFormat, Tool, Usage};
+use crate::ToolCall;
 
 /// Request payload for chat completion API endpoint
 #[derive(Deserialize)]
@@ -10,19 +17,42 @@ pub struct ChatRequest {
     /// Optional chain steps for multi-step processing
     #[serde(default)]
     pub steps: Vec<ChainStepRequest>,
-    /// Optional response transformation function
+    /// Transforms applied to the output of the initial step, see [`TransformPipeline`]
     #[serde(default)]
-    pub response_transform: Option<String>,
+    pub response_transform: Option<TransformPipeline>,
     /// Optional temperature parameter
     #[serde(default)]
     pub temperature: Option<f32>,
     /// Optional max tokens parameter
     #[serde(default)]
     pub max_tokens: Option<u32>,
+    /// Stream the response as server-sent events
+    #[serde(default)]
+    pub stream: bool,
+    /// Optional streaming options
+    #[serde(default)]
+    pub stream_options: Option<StreamOptions>,
+    /// Tools the model may call, replacing the tools configured on the provider
+    #[serde(default)]
+    pub tools: Option<Vec<ApiTool>>,
+    /// "none" disables tools; other values let the model decide
+    #[serde(default)]
+    pub tool_choice: Option<Value>,
+    /// Optional structured output format
+    #[serde(default)]
+    pub response_format: Option<ResponseFormat>,
 }
 
-/// Chain step configuration for multi-step processing
+/// Options for streamed responses
 #[derive(Deserialize)]
+pub struct StreamOptions {
+    /// Send a final chunk with token usage (defaults to false)
+    #[serde(default)]
+    pub include_usage: Option<bool>,
+}
+
+/// Chain step configuration for multi-step processing
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChainStepRequest {
     /// Provider ID for this step
     pub provider_id: String,
@@ -31,23 +61,158 @@ pub struct ChainStepRequest {
     /// Template with variable substitution
     pub template: String,
     /// Optional temperature parameter
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
     /// Optional max tokens parameter
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub max_tokens: Option<u32>,
-    /// Optional response transformation function
-    #[serde(default)]
-    pub response_transform: Option<String>,
+    /// Transforms applied to the step output, e.g. `["strip_think", "extract_json"]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_transform: Option<TransformPipeline>,
 }
 
 /// Single message in a chat conversation
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Clone)]
 pub struct Message {
-    /// Role of the message sender ("user" or "assistant")
+    /// Role of the message sender ("system", "user", "assistant" or "tool")
     pub role: String,
-    /// Content of the message
-    pub content: String,
+    /// Content of the message, absent on assistant messages that only call tools
+    #[serde(default)]
+    pub content: Option<MessageContent>,
+    /// Tool calls requested by an assistant message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    /// Tool call a "tool" message answers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    /// Optional name of the participant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl Message {
+    /// Assistant message with text content.
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self {
+            role: "assistant".to_string(),
+            content: Some(MessageContent::Text(content.into())),
+            tool_calls: None,
+            tool_call_id: None,
+            name: None,
+        }
+    }
+
+    /// Text content of the message, with the text parts joined by newlines.
+    pub fn text(&self) -> String {
+        self.content
+            .as_ref()
+            .map(MessageContent::text)
+            .unwrap_or_default()
+    }
+}
+
+/// Message content, either a plain string or a list of parts
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(untagged)]
+pub enum MessageContent {
+    /// Plain text
+    Text(String),
+    /// Text and image parts
+    Parts(Vec<ContentPart>),
+}
+
+impl MessageContent {
+    /// Text of the content, with the text parts joined by newlines.
+    pub fn text(&self) -> String {
+        match self {
+            MessageContent::Text(text) => text.clone(),
+            MessageContent::Parts(parts) => parts
+                .iter()
+                .filter_map(|part| match part {
+                    ContentPart::Text { text } => Some(text.as_str()),
+                    ContentPart::ImageUrl { .. } => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+/// Part of a multimodal message
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    /// Text part
+    Text { text: String },
+    /// Image given by URL or as a base64 `data:` URL
+    ImageUrl { image_url: ImageUrl },
+}
+
+/// Image reference of an image part
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ImageUrl {
+    /// `https://` or `data:image/...;base64,...` URL
+    pub url: String,
+    /// Requested detail level, ignored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Tool definition in a chat request
+#[derive(Deserialize)]
+pub struct ApiTool {
+    /// Tool type, only "function" is supported
+    #[serde(rename = "type", default = "default_tool_type")]
+    pub tool_type: String,
+    /// Function definition
+    pub function: ApiFunction,
+}
+
+/// Function definition of a tool
+#[derive(Deserialize)]
+pub struct ApiFunction {
+    /// Name of the function
+    pub name: String,
+    /// Human-readable description
+    #[serde(default)]
+    pub description: String,
+    /// JSON Schema describing the parameters
+    #[serde(default = "default_parameters")]
+    pub parameters: Value,
+}
+
+fn default_tool_type() -> String {
+    "function".to_string()
+}
+
+fn default_parameters() -> Value {
+    json!({ "type": "object", "properties": {} })
+}
+
+impl From<ApiTool> for Tool {
+    fn from(tool: ApiTool) -> Self {
+        Tool {
+            tool_type: tool.tool_type,
+            function: FunctionTool {
+                name: tool.function.name,
+                description: tool.function.description,
+                parameters: tool.function.parameters,
+            },
+            cache_control: None,
+        }
+    }
+}
+
+/// Requested format of the response
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Free text, the default
+    Text,
+    /// Any JSON object
+    JsonObject,
+    /// JSON matching a schema
+    JsonSchema { json_schema: StructuredOutputFormat },
 }
 
 /// Response payload from chat completion API endpoint
@@ -63,6 +228,9 @@ pub struct ChatResponse {
     pub model: String,
     /// List of completion choices generated
     pub choices: Vec<Choice>,
+    /// Token usage, when the provider reports it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
 }
 
 /// Single completion choice in a chat response
@@ -75,3 +243,245 @@ pub struct Choice {
     /// Reason why the model stopped generating
     pub finish_reason: String,
 }
+
+/// Chunk of a streamed chat completion
+#[derive(Serialize)]
+pub struct ChatCompletionChunk {
+    /// Identifier shared by all chunks of a completion
+    pub id: String,
+    /// Object type identifier ("chat.completion.chunk")
+    pub object: String,
+    /// Unix timestamp when the completion started
+    pub created: u64,
+    /// Name of the model generating the completion
+    pub model: String,
+    /// Choice deltas, empty in the final usage chunk
+    pub choices: Vec<ChunkChoice>,
+    /// Token usage, only set in the final usage chunk
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+}
+
+/// Single choice delta in a streamed chunk
+#[derive(Serialize)]
+pub struct ChunkChoice {
+    /// Index of this choice in the list
+    pub index: usize,
+    /// Incremental message content
+    pub delta: ChunkDelta,
+    /// Reason why the model stopped generating, set in the last choice chunk
+    pub finish_reason: Option<String>,
+}
+
+/// Incremental message content
+#[derive(Serialize, Default)]
+pub struct ChunkDelta {
+    /// Role of the sender, set in the first chunk
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    /// Text content delta
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// Tool call deltas
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCallDelta>>,
+}
+
+/// Incremental tool call; `id`, `type` and the function name are only sent once
+#[derive(Serialize)]
+pub struct ToolCallDelta {
+    /// Position of the tool call in the message
+    pub index: usize,
+    /// Tool call identifier
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Tool call type ("function")
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub call_type: Option<String>,
+    /// Function name and arguments fragment
+    pub function: FunctionCallDelta,
+}
+
+/// Function part of a tool call delta
+#[derive(Serialize)]
+pub struct FunctionCallDelta {
+    /// Function name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Fragment of the JSON-encoded arguments
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<String>,
+}
+
+/// Model list returned by `/v1/models`
+#[derive(Serialize)]
+pub struct ModelList {
+    /// Object type identifier ("list")
+    pub object: String,
+    /// Models of every provider
+    pub data: Vec<ModelObject>,
+}
+
+/// Model available through the server
+#[derive(Serialize)]
+pub struct ModelObject {
+    /// Model identifier in format "provider:model_name"
+    pub id: String,
+    /// Object type identifier ("model")
+    pub object: String,
+    /// Unix timestamp when the model was created, 0 when unknown
+    pub created: i64,
+    /// Provider ID serving the model
+    pub owned_by: String,
+}
+
+/// Request payload for the embeddings endpoint
+#[derive(Deserialize)]
+pub struct EmbeddingRequest {
+    /// Model identifier in format "provider:model_name"
+    pub model: String,
+    /// Text or texts to embed
+    pub input: EmbeddingInput,
+    /// "float" (default) or "base64"
+    #[serde(default)]
+    pub encoding_format: Option<String>,
+}
+
+/// Input of an embeddings request
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum EmbeddingInput {
+    /// A single text
+    Single(String),
+    /// Several texts
+    Batch(Vec<String>),
+}
+
+/// Response payload of the embeddings endpoint
+#[derive(Serialize)]
+pub struct EmbeddingResponse {
+    /// Object type identifier ("list")
+    pub object: String,
+    /// One embedding per input, in input order
+    pub data: Vec<EmbeddingData>,
+    /// Name of the model that computed the embeddings
+    pub model: String,
+    /// Token usage; embedding providers do not report it yet, so it is omitted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<EmbeddingUsage>,
+}
+
+/// Single embedding
+#[derive(Serialize)]
+pub struct EmbeddingData {
+    /// Object type identifier ("embedding")
+    pub object: String,
+    /// Position of the input
+    pub index: usize,
+    /// Vector as a list of floats, or base64 of little-endian `f32`s
+    pub embedding: Value,
+}
+
+/// Token usage of an embeddings request
+#[derive(Serialize)]
+pub struct EmbeddingUsage {
+    pub prompt_tokens: u32,
+    pub total_tokens: u32,
+}
+
+/// Request payload of `/v1/chains/{id}`
+#[derive(Deserialize, Default)]
+pub struct RunChainRequest {
+    /// Text available to the templates as `{{input}}`
+    #[serde(default)]
+    pub input: Option<String>,
+    /// Further template variables; JSON values can be read into and looped over
+    #[serde(default)]
+    pub variables: HashMap<String, Value>,
+}
+
+/// Response payload of `/v1/chains/{id}`
+#[derive(Serialize)]
+pub struct RunChainResponse {
+    /// Chain id
+    pub id: String,
+    /// Output of the chain's output step
+    pub output: String,
+    /// Output of every step that ran, by step id
+    pub steps: HashMap<String, String>,
+}
+
+/// Response payload of `/v1/chains`
+#[derive(Serialize)]
+pub struct ChainList {
+    pub object: String,
+    pub data: Vec<ChainSummary>,
+}
+
+/// Named chain listed by `/v1/chains`
+#[derive(Serialize)]
+pub struct ChainSummary {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Step ids in order
+    pub steps: Vec<String>,
+    /// Provider ids the chain uses
+    pub providers: Vec<String>,
+}
+
+/// Request payload of `/v1/jobs`: a named chain or a chat completion request
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum JobRequest {
+    /// Runs the chain like `/v1/chains/{id}`
+    Chain {
+        chain: String,
+        #[serde(flatten)]
+        request: RunChainRequest,
+    },
+    /// Runs the request like `/v1/chat/completions`; streaming is not supported
+    Chat(Box<ChatRequest>),
+}
+
+/// Message a client sends on a `/v1/sessions` WebSocket
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SessionClientEvent {
+    /// User message starting a turn; `tools` replace the tools offered so far
+    Message {
+        content: String,
+        #[serde(default)]
+        tools: Option<Vec<ApiTool>>,
+    },
+    /// Result of a tool call; the next turn starts once every call has one
+    ToolResult {
+        tool_call_id: String,
+        content: String,
+    },
+}
+
+/// Message the server sends on a `/v1/sessions` WebSocket
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SessionEvent {
+    /// First message of every connection
+    Session {
+        id: String,
+        model: String,
+        resumed: bool,
+        /// Remembered conversation, when resuming
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        history: Vec<Message>,
+        /// Tool calls still waiting for a result
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        pending_tool_calls: Vec<ToolCall>,
+    },
+    /// Piece of the assistant turn, ending with a `done` chunk
+    Chunk {
+        chunk: StreamChunk,
+    },
+    Error {
+        message: String,
+    },
+}
@@ -180,6 +180,8 @@ struct AnthropicCompleteResponse {
 /// Usage information from Anthropic API response.
 #[derive(Deserialize, Debug)]
 struct AnthropicUsage {
+    /// Missing from the usage of streamed `message_delta` events
+    #[serde(default)]
     input_tokens: u32,
     output_tokens: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -213,6 +215,17 @@ struct AnthropicStreamResponse {
     content_block: Option<AnthropicStreamContentBlock>,
     /// Delta for content_block_delta and message_delta events
     delta: Option<AnthropicDelta>,
+    /// Message for message_start events
+    message: Option<AnthropicStreamMessage>,
+    /// Cumulative usage for message_delta events
+    usage: Option<AnthropicUsage>,
+}
+
+/// Message within an Anthropic streaming message_start event.
+#[derive(Deserialize, Debug)]
+struct AnthropicStreamMessage {
+    /// Input tokens, and the output tokens generated so far
+    usage: Option<AnthropicUsage>,
 }
 
 /// Content block within an Anthropic streaming content_block_start event.
@@ -323,24 +336,42 @@ impl ChatResponse for AnthropicCompleteResponse {
     }
 
     fn usage(&self) -> Option<Usage> {
-        self.usage.as_ref().map(|anthropic_usage| {
-            let cached_tokens = anthropic_usage.cache_creation_input_tokens.unwrap_or(0)
-                + anthropic_usage.cache_read_input_tokens.unwrap_or(0);
-            Usage {
-                prompt_tokens: anthropic_usage.input_tokens,
-                completion_tokens: anthropic_usage.output_tokens,
-                total_tokens: anthropic_usage.input_tokens + anthropic_usage.output_tokens,
-                completion_tokens_details: None,
-                prompt_tokens_details: if cached_tokens > 0 {
-                    Some(crate::chat::PromptTokensDetails {
-                        cached_tokens: Some(cached_tokens),
-                        audio_tokens: None,
-                    })
-                } else {
-                    None
-                },
-            }
-        })
+        self.usage.as_ref().map(AnthropicUsage::to_usage)
+    }
+}
+
+impl AnthropicUsage {
+    fn to_usage(&self) -> Usage {
+        let cached_tokens = self.cache_creation_input_tokens.unwrap_or(0)
+            + self.cache_read_input_tokens.unwrap_or(0);
+        Usage {
+            prompt_tokens: self.input_tokens,
+            completion_tokens: self.output_tokens,
+            total_tokens: self.input_tokens + self.output_tokens,
+            completion_tokens_details: None,
+            prompt_tokens_details: if cached_tokens > 0 {
+                Some(crate::chat::PromptTokensDetails {
+                    cached_tokens: Some(cached_tokens),
+                    audio_tokens: None,
+                })
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Takes over the counts of a later usage report, which are cumulative.
+    fn update(&mut self, later: AnthropicUsage) {
+        if later.input_tokens > 0 {
+            self.input_tokens = later.input_tokens;
+        }
+        self.output_tokens = later.output_tokens;
+        if later.cache_creation_input_tokens.is_some() {
+            self.cache_creation_input_tokens = later.cache_creation_input_tokens;
+        }
+        if later.cache_read_input_tokens.is_some() {
+            self.cache_read_input_tokens = later.cache_read_input_tokens;
+        }
     }
 }
 
@@ -961,8 +992,12 @@ fn create_anthropic_tool_stream(
     let stream = response
         .bytes_stream()
         .scan(
-            (String::new(), Vec::new(), HashMap::new()),
-            move |(buffer, utf8_buffer, tool_states), chunk| {
+            (
+                String::new(),
+                Vec::new(),
+                AnthropicToolStreamState::default(),
+            ),
+            move |(buffer, utf8_buffer, stream_state), chunk| {
                 let result = match chunk {
                     Ok(bytes) => {
                         utf8_buffer.extend_from_slice(&bytes);
@@ -989,8 +1024,16 @@ fn create_anthropic_tool_stream(
                             let event = buffer[..pos + 2].to_string();
                             buffer.drain(..pos + 2);
 
-                            match parse_anthropic_sse_chunk_with_tools(&event, tool_states) {
-                                Ok(Some(chunk)) => results.push(Ok(chunk)),
+                            match parse_anthropic_sse_chunk_with_tools(&event, stream_state) {
+                                Ok(Some(chunk)) => {
+                                    // Usage is complete once the stop reason arrives.
+                                    if let StreamChunk::Done { .. } = chunk {
+                                        if let Some(usage) = stream_state.usage.take() {
+                                            results.push(Ok(StreamChunk::Usage(usage.to_usage())));
+                                        }
+                                    }
+                                    results.push(Ok(chunk));
+                                }
                                 Ok(None) => {}
                                 Err(e) => results.push(Err(e)),
                             }
@@ -1155,6 +1198,15 @@ struct ToolUseState {
     json_buffer: String,
 }
 
+/// State of an Anthropic tool stream kept between SSE events
+#[derive(Debug, Default)]
+struct AnthropicToolStreamState {
+    /// Tool use blocks being streamed, by index
+    tools: HashMap<usize, ToolUseState>,
+    /// Usage reported by `message_start`, updated by `message_delta`
+    usage: Option<AnthropicUsage>,
+}
+
 /// Parses Anthropic SSE chunks with tool use support.
 ///
 /// This parser handles all Anthropic streaming event types including:
@@ -1163,10 +1215,12 @@ struct ToolUseState {
 /// - `content_block_stop`
 /// - `message_delta` with `stop_reason`
 ///
+/// The usage of `message_start` and `message_delta` is kept in the state.
+///
 /// # Arguments
 ///
 /// * `chunk` - The raw SSE chunk text
-/// * `tool_states` - Mutable reference to a map tracking tool use state by index
+/// * `stream_state` - Tool use state by index and the usage reported so far
 ///
 /// # Returns
 ///
@@ -1175,8 +1229,9 @@ struct ToolUseState {
 /// * `Err(LLMError)` - If parsing fails
 fn parse_anthropic_sse_chunk_with_tools(
     chunk: &str,
-    tool_states: &mut HashMap<usize, ToolUseState>,
+    stream_state: &mut AnthropicToolStreamState,
 ) -> Result<Option<StreamChunk>, LLMError> {
+    let tool_states = &mut stream_state.tools;
     for line in chunk.lines() {
         let line = line.trim();
         if let Some(data) = line.strip_prefix("data: ") {
@@ -1258,7 +1313,18 @@ fn parse_anthropic_sse_chunk_with_tools(
                                 }
                             }
                         }
+                        "message_start" => {
+                            if let Some(usage) = response.message.and_then(|m| m.usage) {
+                                stream_state.usage = Some(usage);
+                            }
+                        }
                         "message_delta" => {
+                            if let Some(usage) = response.usage {
+                                match &mut stream_state.usage {
+                                    Some(current) => current.update(usage),
+                                    None => stream_state.usage = Some(usage),
+                                }
+                            }
                             if let Some(delta) = response.delta {
                                 if let Some(stop_reason) = delta.stop_reason {
                                     return Ok(Some(StreamChunk::Done { stop_reason }));
@@ -1286,8 +1352,8 @@ mod tests {
 data: {"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "Hello"}}
 
 "#;
-        let mut tool_states = HashMap::new();
-        let result = parse_anthropic_sse_chunk_with_tools(chunk, &mut tool_states).unwrap();
+        let mut state = AnthropicToolStreamState::default();
+        let result = parse_anthropic_sse_chunk_with_tools(chunk, &mut state).unwrap();
 
         match result {
             Some(StreamChunk::Text(text)) => assert_eq!(text, "Hello"),
@@ -1301,8 +1367,8 @@ data: {"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta"
 data: {"type": "content_block_start", "index": 1, "content_block": {"type": "tool_use", "id": "toolu_01ABC", "name": "get_weather", "input": {}}}
 
 "#;
-        let mut tool_states = HashMap::new();
-        let result = parse_anthropic_sse_chunk_with_tools(chunk, &mut tool_states).unwrap();
+        let mut state = AnthropicToolStreamState::default();
+        let result = parse_anthropic_sse_chunk_with_tools(chunk, &mut state).unwrap();
 
         match result {
             Some(StreamChunk::ToolUseStart { index, id, name }) => {
@@ -1314,9 +1380,9 @@ data: {"type": "content_block_start", "index": 1, "content_block": {"type": "too
         }
 
         // Verify state was stored
-        assert!(tool_states.contains_key(&1));
-        assert_eq!(tool_states[&1].id, "toolu_01ABC");
-        assert_eq!(tool_states[&1].name, "get_weather");
+        assert!(state.tools.contains_key(&1));
+        assert_eq!(state.tools[&1].id, "toolu_01ABC");
+        assert_eq!(state.tools[&1].name, "get_weather");
     }
 
     #[test]
@@ -1325,9 +1391,9 @@ data: {"type": "content_block_start", "index": 1, "content_block": {"type": "too
 data: {"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "{\"location\":"}}
 
 "#;
-        let mut tool_states = HashMap::new();
+        let mut state = AnthropicToolStreamState::default();
         // Pre-populate state as if tool_use_start was already processed
-        tool_states.insert(
+        state.tools.insert(
             1,
             ToolUseState {
                 id: "toolu_01ABC".to_string(),
@@ -1336,7 +1402,7 @@ data: {"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_
             },
         );
 
-        let result = parse_anthropic_sse_chunk_with_tools(chunk, &mut tool_states).unwrap();
+        let result = parse_anthropic_sse_chunk_with_tools(chunk, &mut state).unwrap();
 
         match result {
             Some(StreamChunk::ToolUseInputDelta {
@@ -1350,7 +1416,7 @@ data: {"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_
         }
 
         // Verify JSON was accumulated
-        assert_eq!(tool_states[&1].json_buffer, "{\"location\":");
+        assert_eq!(state.tools[&1].json_buffer, "{\"location\":");
     }
 
     #[test]
@@ -1359,9 +1425,9 @@ data: {"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_
 data: {"type": "content_block_stop", "index": 1}
 
 "#;
-        let mut tool_states = HashMap::new();
+        let mut state = AnthropicToolStreamState::default();
         // Pre-populate state with accumulated JSON
-        tool_states.insert(
+        state.tools.insert(
             1,
             ToolUseState {
                 id: "toolu_01ABC".to_string(),
@@ -1370,7 +1436,7 @@ data: {"type": "content_block_stop", "index": 1}
             },
         );
 
-        let result = parse_anthropic_sse_chunk_with_tools(chunk, &mut tool_states).unwrap();
+        let result = parse_anthropic_sse_chunk_with_tools(chunk, &mut state).unwrap();
 
         match result {
             Some(StreamChunk::ToolUseComplete { index, tool_call }) => {
@@ -1383,7 +1449,7 @@ data: {"type": "content_block_stop", "index": 1}
         }
 
         // Verify state was removed
-        assert!(!tool_states.contains_key(&1));
+        assert!(!state.tools.contains_key(&1));
     }
 
     #[test]
@@ -1395,9 +1461,9 @@ data: {"type": "content_block_stop", "index": 1}
 data: {"type": "content_block_stop", "index": 1}
 
 "#;
-        let mut tool_states = HashMap::new();
+        let mut state = AnthropicToolStreamState::default();
         // Pre-populate state with EMPTY json_buffer (no input_json_delta events received)
-        tool_states.insert(
+        state.tools.insert(
             1,
             ToolUseState {
                 id: "toolu_01XYZ".to_string(),
@@ -1406,7 +1472,7 @@ data: {"type": "content_block_stop", "index": 1}
             },
         );
 
-        let result = parse_anthropic_sse_chunk_with_tools(chunk, &mut tool_states).unwrap();
+        let result = parse_anthropic_sse_chunk_with_tools(chunk, &mut state).unwrap();
 
         match result {
             Some(StreamChunk::ToolUseComplete { index, tool_call }) => {
@@ -1423,7 +1489,7 @@ data: {"type": "content_block_stop", "index": 1}
         }
 
         // Verify state was removed
-        assert!(!tool_states.contains_key(&1));
+        assert!(!state.tools.contains_key(&1));
     }
 
     #[test]
@@ -1432,8 +1498,8 @@ data: {"type": "content_block_stop", "index": 1}
 data: {"type": "message_delta", "delta": {"stop_reason": "tool_use"}}
 
 "#;
-        let mut tool_states = HashMap::new();
-        let result = parse_anthropic_sse_chunk_with_tools(chunk, &mut tool_states).unwrap();
+        let mut state = AnthropicToolStreamState::default();
+        let result = parse_anthropic_sse_chunk_with_tools(chunk, &mut state).unwrap();
 
         match result {
             Some(StreamChunk::Done { stop_reason }) => {
@@ -1449,8 +1515,8 @@ data: {"type": "message_delta", "delta": {"stop_reason": "tool_use"}}
 data: {"type": "message_delta", "delta": {"stop_reason": "end_turn"}}
 
 "#;
-        let mut tool_states = HashMap::new();
-        let result = parse_anthropic_sse_chunk_with_tools(chunk, &mut tool_states).unwrap();
+        let mut state = AnthropicToolStreamState::default();
+        let result = parse_anthropic_sse_chunk_with_tools(chunk, &mut state).unwrap();
 
         match result {
             Some(StreamChunk::Done { stop_reason }) => {
@@ -1462,14 +1528,14 @@ data: {"type": "message_delta", "delta": {"stop_reason": "end_turn"}}
 
     #[test]
     fn test_parse_stream_full_tool_use_sequence() {
-        let mut tool_states = HashMap::new();
+        let mut state = AnthropicToolStreamState::default();
 
         // 1. Tool use start
         let start_chunk = r#"event: content_block_start
 data: {"type": "content_block_start", "index": 1, "content_block": {"type": "tool_use", "id": "toolu_01ABC", "name": "get_weather", "input": {}}}
 
 "#;
-        let result = parse_anthropic_sse_chunk_with_tools(start_chunk, &mut tool_states).unwrap();
+        let result = parse_anthropic_sse_chunk_with_tools(start_chunk, &mut state).unwrap();
         assert!(matches!(result, Some(StreamChunk::ToolUseStart { .. })));
 
         // 2. Input JSON deltas
@@ -1477,23 +1543,23 @@ data: {"type": "content_block_start", "index": 1, "content_block": {"type": "too
 data: {"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "{\"loc"}}
 
 "#;
-        let _ = parse_anthropic_sse_chunk_with_tools(delta1, &mut tool_states).unwrap();
+        let _ = parse_anthropic_sse_chunk_with_tools(delta1, &mut state).unwrap();
 
         let delta2 = r#"event: content_block_delta
 data: {"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "ation\": \"Paris\"}"}}
 
 "#;
-        let _ = parse_anthropic_sse_chunk_with_tools(delta2, &mut tool_states).unwrap();
+        let _ = parse_anthropic_sse_chunk_with_tools(delta2, &mut state).unwrap();
 
         // Verify accumulated JSON
-        assert_eq!(tool_states[&1].json_buffer, "{\"location\": \"Paris\"}");
+        assert_eq!(state.tools[&1].json_buffer, "{\"location\": \"Paris\"}");
 
         // 3. Content block stop
         let stop_chunk = r#"event: content_block_stop
 data: {"type": "content_block_stop", "index": 1}
 
 "#;
-        let result = parse_anthropic_sse_chunk_with_tools(stop_chunk, &mut tool_states).unwrap();
+        let result = parse_anthropic_sse_chunk_with_tools(stop_chunk, &mut state).unwrap();
 
         match result {
             Some(StreamChunk::ToolUseComplete { tool_call, .. }) => {
@@ -1507,7 +1573,7 @@ data: {"type": "content_block_stop", "index": 1}
 data: {"type": "message_delta", "delta": {"stop_reason": "tool_use"}}
 
 "#;
-        let result = parse_anthropic_sse_chunk_with_tools(done_chunk, &mut tool_states).unwrap();
+        let result = parse_anthropic_sse_chunk_with_tools(done_chunk, &mut state).unwrap();
         assert!(matches!(
             result,
             Some(StreamChunk::Done {
@@ -1518,14 +1584,14 @@ data: {"type": "message_delta", "delta": {"stop_reason": "tool_use"}}
 
     #[test]
     fn test_parse_stream_mixed_text_and_tool() {
-        let mut tool_states = HashMap::new();
+        let mut state = AnthropicToolStreamState::default();
 
         // Text delta first
         let text_chunk = r#"event: content_block_delta
 data: {"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "I'll check the weather"}}
 
 "#;
-        let result = parse_anthropic_sse_chunk_with_tools(text_chunk, &mut tool_states).unwrap();
+        let result = parse_anthropic_sse_chunk_with_tools(text_chunk, &mut state).unwrap();
         assert!(matches!(result, Some(StreamChunk::Text(t)) if t == "I'll check the weather"));
 
         // Then tool use
@@ -1533,7 +1599,7 @@ data: {"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta"
 data: {"type": "content_block_start", "index": 1, "content_block": {"type": "tool_use", "id": "toolu_01XYZ", "name": "weather", "input": {}}}
 
 "#;
-        let result = parse_anthropic_sse_chunk_with_tools(tool_start, &mut tool_states).unwrap();
+        let result = parse_anthropic_sse_chunk_with_tools(tool_start, &mut state).unwrap();
         assert!(
             matches!(result, Some(StreamChunk::ToolUseStart { name, .. }) if name == "weather")
         );
@@ -1545,19 +1611,74 @@ data: {"type": "content_block_start", "index": 1, "content_block": {"type": "too
 data: {"type": "message_start", "message": {"id": "msg_123", "type": "message", "role": "assistant"}}
 
 "#;
-        let mut tool_states = HashMap::new();
-        let result = parse_anthropic_sse_chunk_with_tools(chunk, &mut tool_states).unwrap();
+        let mut state = AnthropicToolStreamState::default();
+        let result = parse_anthropic_sse_chunk_with_tools(chunk, &mut state).unwrap();
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_parse_stream_keeps_usage_until_done() {
+        let start = r#"event: message_start
+data: {"type": "message_start", "message": {"id": "msg_123", "usage": {"input_tokens": 25, "output_tokens": 1, "cache_read_input_tokens": 5}}}
+
+"#;
+        let delta = r#"event: message_delta
+data: {"type": "message_delta", "delta": {"stop_reason": "end_turn"}, "usage": {"output_tokens": 15}}
+
+"#;
+        let mut state = AnthropicToolStreamState::default();
+        assert!(parse_anthropic_sse_chunk_with_tools(start, &mut state)
+            .unwrap()
+            .is_none());
+        let result = parse_anthropic_sse_chunk_with_tools(delta, &mut state).unwrap();
+
+        assert!(matches!(result, Some(StreamChunk::Done { .. })));
+        let usage = state.usage.unwrap().to_usage();
+        assert_eq!(
+            (
+                usage.prompt_tokens,
+                usage.completion_tokens,
+                usage.total_tokens
+            ),
+            (25, 15, 40)
+        );
+        assert_eq!(usage.prompt_tokens_details.unwrap().cached_tokens, Some(5));
+    }
+
+    #[tokio::test]
+    async fn test_tool_stream_sends_usage_before_done() {
+        use futures::StreamExt;
+
+        let body = concat!(
+            "event: message_start\n",
+            "data: {\"type\": \"message_start\", \"message\": {\"usage\": {\"input_tokens\": 25, \"output_tokens\": 1}}}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\": \"content_block_delta\", \"index\": 0, \"delta\": {\"type\": \"text_delta\", \"text\": \"Hi\"}}\n\n",
+            "event: message_delta\n",
+            "data: {\"type\": \"message_delta\", \"delta\": {\"stop_reason\": \"end_turn\"}, \"usage\": {\"output_tokens\": 15}}\n\n",
+            "event: message_stop\n",
+            "data: {\"type\": \"message_stop\"}\n\n",
+        );
+        let response = http::Response::new(reqwest::Body::from(body));
+        let chunks: Vec<StreamChunk> = create_anthropic_tool_stream(response.into())
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        assert_eq!(chunks.len(), 3);
+        assert!(matches!(&chunks[0], StreamChunk::Text(text) if text == "Hi"));
+        assert!(matches!(&chunks[1], StreamChunk::Usage(usage) if usage.total_tokens == 40));
+        assert!(matches!(&chunks[2], StreamChunk::Done { .. }));
+    }
+
     #[test]
     fn test_parse_stream_ignores_ping() {
         let chunk = r#"event: ping
 data: {"type": "ping"}
 
 "#;
-        let mut tool_states = HashMap::new();
-        let result = parse_anthropic_sse_chunk_with_tools(chunk, &mut tool_states).unwrap();
+        let mut state = AnthropicToolStreamState::default();
+        let result = parse_anthropic_sse_chunk_with_tools(chunk, &mut state).unwrap();
         assert!(result.is_none());
     }
 
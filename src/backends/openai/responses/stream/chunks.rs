@@ -4,7 +4,7 @@ use std::pin::Pin;
 use bytes::Bytes;
 use futures::stream::{Stream, StreamExt};
 
-use crate::chat::StreamChunk;
+use crate::chat::{StreamChunk, Usage};
 use crate::error::LLMError;
 use crate::{FunctionCall, ToolCall};
 
@@ -95,8 +95,8 @@ impl ResponsesStreamChunkParser {
                 item_id,
                 output_index,
             } => self.handle_item_done(item_id, output_index),
-            ResponsesEvent::ResponseCompleted { .. } => {
-                self.handle_response_completed();
+            ResponsesEvent::ResponseCompleted { usage } => {
+                self.handle_response_completed(usage);
             }
         }
     }
@@ -154,8 +154,11 @@ impl ResponsesStreamChunkParser {
         }
     }
 
-    fn handle_response_completed(&mut self) {
+    fn handle_response_completed(&mut self, usage: Option<Usage>) {
         self.flush_tool_states();
+        if let Some(usage) = usage {
+            self.results.push(Ok(StreamChunk::Usage(usage)));
+        }
         let stop_reason = if self.saw_tool_call {
             "tool_use"
         } else {
@@ -18,9 +18,10 @@ async fn responses_stream_chunks_emits_text_and_done() {
         results.push(result.unwrap());
     }
 
-    assert_eq!(results.len(), 2);
+    assert_eq!(results.len(), 3);
     assert!(matches!(&results[0], StreamChunk::Text(text) if text == "Hello"));
-    assert!(matches!(&results[1], StreamChunk::Done { stop_reason } if stop_reason == "end_turn"));
+    assert!(matches!(&results[1], StreamChunk::Usage(usage) if usage.total_tokens == 2));
+    assert!(matches!(&results[2], StreamChunk::Done { stop_reason } if stop_reason == "end_turn"));
 }
 
 #[tokio::test]
@@ -53,7 +54,8 @@ async fn responses_stream_chunks_emits_tool_use_flow() {
     assert!(
         matches!(&results[2], StreamChunk::ToolUseComplete { tool_call, .. } if tool_call.function.arguments == "{\"city\":\"Paris\"}")
     );
-    assert!(matches!(&results[3], StreamChunk::Done { stop_reason } if stop_reason == "tool_use"));
+    assert!(matches!(&results[3], StreamChunk::Usage(_)));
+    assert!(matches!(&results[4], StreamChunk::Done { stop_reason } if stop_reason == "tool_use"));
 }
 
 fn create_mock_response(chunks: Vec<Result<Bytes, reqwest::Error>>) -> reqwest::Response {
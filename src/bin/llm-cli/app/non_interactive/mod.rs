@@ -7,7 +7,7 @@ mod tooling;
 use crate::args::CliArgs;
 use crate::config::AppConfig;
 use crate::provider::{ProviderFactory, ProviderRegistry};
-use crate::tools::{PtySessionManager, ToolContext, ToolRegistry};
+use crate::tools::{connect_mcp_servers, PtySessionManager, ToolContext, ToolRegistry};
 
 pub async fn run_non_interactive(
     args: &CliArgs,
@@ -24,8 +24,9 @@ pub async fn run_non_interactive(
     let diff_tracker = crate::tools::create_tracker();
 
     // Create tool registry with PTY support and diff tracking
-    let tool_registry =
+    let mut tool_registry =
         ToolRegistry::from_config_with_pty(&config.tools, pty_manager, diff_tracker);
+    tool_registry.add_mcp_tools(connect_mcp_servers(&config.tools.mcp_servers).await);
 
     let working_dir = std::env::current_dir()
         .map(|p| p.to_string_lossy().to_string())
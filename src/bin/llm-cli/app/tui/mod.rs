@@ -9,7 +9,7 @@ use crate::runtime::{controller::AppController, init_terminal, restore_terminal,
 use crate::runtime::{AppState, StreamManager};
 use crate::skills::SkillCatalog;
 use crate::terminal::TerminalCapabilities;
-use crate::tools::{PtySessionManager, ToolContext, ToolRegistry};
+use crate::tools::{connect_mcp_servers, PtySessionManager, ToolContext, ToolRegistry};
 
 use launch::apply_launch_options;
 
@@ -57,7 +57,7 @@ async fn run_inner(
     ctx: TuiContext,
     terminal: &mut crate::runtime::AppTerminal,
 ) -> anyhow::Result<()> {
-    let bundle = build_controller(ctx)?;
+    let bundle = build_controller(ctx).await?;
     let mut controller = bundle.controller;
     load_conversations(&mut controller)?;
     apply_launch_options(&mut controller, &bundle.options)?;
@@ -71,7 +71,7 @@ struct ControllerBundle {
     options: TuiOptions,
 }
 
-fn build_controller(ctx: TuiContext) -> anyhow::Result<ControllerBundle> {
+async fn build_controller(ctx: TuiContext) -> anyhow::Result<ControllerBundle> {
     let store =
         crate::persistence::JsonConversationStore::new(ctx.paths.data_dir.join("conversations"));
 
@@ -86,6 +86,7 @@ fn build_controller(ctx: TuiContext) -> anyhow::Result<ControllerBundle> {
         ToolRegistry::from_config_with_pty(&ctx.config.tools, pty_manager, diff_tracker);
     // Load user-defined tools from config
     tool_registry.load_user_tools(&ctx.paths.user_tools_file());
+    tool_registry.add_mcp_tools(connect_mcp_servers(&ctx.config.tools.mcp_servers).await);
 
     let working_dir = std::env::current_dir()
         .map(|p| p.to_string_lossy().to_string())
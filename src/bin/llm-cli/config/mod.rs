@@ -8,6 +8,6 @@ pub use load::load_config;
 pub use paths::ConfigPaths;
 pub use save::save_config;
 pub use types::{
-    AppConfig, LoggingConfig, ModelConfig, NavigationMode, PricingConfig, ProviderConfig,
-    ToolExecutionMode, ToolsConfig, TrimStrategy,
+    AppConfig, LoggingConfig, McpServerConfig, ModelConfig, NavigationMode, PricingConfig,
+    ProviderConfig, ToolExecutionMode, ToolsConfig, TrimStrategy,
 };
@@ -18,5 +18,5 @@ pub use chat::{ChatConfig, TrimStrategy};
 pub use logging::LoggingConfig;
 pub use provider::{ModelConfig, PricingConfig, ProviderConfig};
 pub use storage::StorageConfig;
-pub use tools::{ToolExecutionMode, ToolsConfig};
+pub use tools::{McpServerConfig, ToolExecutionMode, ToolsConfig};
 pub use ui::{NavigationMode, UiConfig};
@@ -9,6 +9,8 @@ pub struct ToolsConfig {
     pub enabled: Vec<String>,
     pub allowed_paths: Vec<String>,
     pub timeout_ms: u64,
+    /// MCP servers whose tools are offered next to the builtin ones.
+    pub mcp_servers: Vec<McpServerConfig>,
 }
 
 impl Default for ToolsConfig {
@@ -18,10 +20,21 @@ impl Default for ToolsConfig {
             enabled: Vec::new(),
             allowed_paths: Vec::new(),
             timeout_ms: DEFAULT_TOOL_TIMEOUT_MS,
+            mcp_servers: Vec::new(),
         }
     }
 }
 
+/// An MCP server, spawned with `command` or reached at `url`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct McpServerConfig {
+    pub name: String,
+    pub command: Option<String>,
+    pub args: Vec<String>,
+    pub url: Option<String>,
+}
+
 #[derive(Debug, Clone, Copy, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ToolExecutionMode {
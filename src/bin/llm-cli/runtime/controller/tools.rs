@@ -92,9 +92,11 @@ impl AppController {
                 )));
                 return false;
             }
-            // Reload registry (will remove the tool)
+            // Reload registry (will remove the tool), keeping the MCP tools
+            let mcp_tools = self.tool_registry.mcp_tools().to_vec();
             self.tool_registry = crate::tools::ToolRegistry::from_config(&self.state.config.tools);
             self.tool_registry.load_user_tools(&path);
+            self.tool_registry.add_mcp_tools(mcp_tools);
             self.push_notice(format!("Tool '{}' removed", name));
             true
         } else {
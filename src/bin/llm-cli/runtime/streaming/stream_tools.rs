@@ -82,6 +82,15 @@ impl ToolStreamState {
                 self.handle_tool_complete(tool_call, ctx).await;
                 Ok(true)
             }
+            StreamChunk::Usage(usage) => {
+                let event = StreamEvent::Usage {
+                    conversation_id: ctx.request.conversation_id,
+                    message_id: ctx.request.message_id,
+                    usage,
+                };
+                let _ = ctx.sender.send(AppEvent::Stream(event)).await;
+                Ok(true)
+            }
             StreamChunk::Done { .. } => Ok(false),
         }
     }
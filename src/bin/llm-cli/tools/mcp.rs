@@ -0,0 +1,83 @@
+//! Tools served by MCP servers.
+
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use llm::builder::FunctionBuilder;
+use llm::mcp::{McpClient, McpTool};
+
+use crate::config::McpServerConfig;
+
+use super::error::ToolError;
+
+/// A tool of a connected MCP server.
+#[derive(Clone)]
+pub struct McpToolEntry {
+    pub server: String,
+    pub tool: McpTool,
+    client: Arc<McpClient>,
+}
+
+impl McpToolEntry {
+    pub fn name(&self) -> &str {
+        &self.tool.name
+    }
+
+    pub fn function_builder(&self) -> FunctionBuilder {
+        FunctionBuilder::new(self.tool.name.clone())
+            .description(self.tool.description.clone().unwrap_or_default())
+            .json_schema(self.tool.input_schema.clone())
+    }
+
+    /// Calls the tool on its server; a result flagged as an error is returned to
+    /// the model as a failed execution.
+    pub fn execute(&self, args: Value) -> Result<String, ToolError> {
+        let result = tokio::task::block_in_place(|| {
+            tokio::runtime::Handle::current().block_on(self.client.call_tool(&self.tool.name, args))
+        })
+        .map_err(|err| ToolError::Execution(format!("{}: {err}", self.server)))?;
+        if result.is_error {
+            return Err(ToolError::Execution(result.text()));
+        }
+        Ok(match result.structured_content {
+            Some(value) => value.to_string(),
+            None => result.text(),
+        })
+    }
+}
+
+/// Connects to every configured server and lists its tools. Servers that cannot
+/// be reached are skipped with a warning.
+pub async fn connect_mcp_servers(servers: &[McpServerConfig]) -> Vec<McpToolEntry> {
+    let mut entries = Vec::new();
+    for server in servers {
+        match connect(server).await {
+            Ok(tools) => entries.extend(tools),
+            Err(err) => log::warn!("MCP server '{}' unavailable: {err}", server.name),
+        }
+    }
+    entries
+}
+
+async fn connect(server: &McpServerConfig) -> Result<Vec<McpToolEntry>, llm::error::LLMError> {
+    let client = match (&server.command, &server.url) {
+        (Some(command), _) => McpClient::stdio(command, &server.args).await?,
+        (None, Some(url)) => McpClient::http(url.clone()).await?,
+        (None, None) => {
+            return Err(llm::error::LLMError::InvalidRequest(
+                "MCP server needs a command or a url".to_string(),
+            ))
+        }
+    };
+    let client = Arc::new(client);
+    let tools = client.list_tools().await?;
+    Ok(tools
+        .into_iter()
+        .map(|tool| McpToolEntry {
+            server: server.name.clone(),
+            tool,
+            client: client.clone(),
+        })
+        .collect())
+}
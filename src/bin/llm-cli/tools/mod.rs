@@ -12,6 +12,7 @@ mod definition;
 pub mod diff_tracker;
 mod error;
 pub mod handlers;
+mod mcp;
 pub mod parallel;
 pub mod pty;
 mod registry;
@@ -26,6 +27,7 @@ pub use handlers::{
     file_read_tool, ls_tool, patch_tool, plan_tool, rollback_tool, search_tool, shell_tool,
     shell_write_tool,
 };
+pub use mcp::{connect_mcp_servers, McpToolEntry};
 pub use parallel::{is_mutating_tool, ParallelConfig, ParallelExecutor};
 pub use pty::PtySessionManager;
 pub use registry::ToolRegistry;
@@ -13,12 +13,14 @@ use super::context::ToolContext;
 use super::definition::ToolDefinition;
 use super::diff_tracker::DiffTracker;
 use super::error::ToolError;
+use super::mcp::McpToolEntry;
 use super::pty::PtySessionManager;
 use super::user_tools::UserToolsConfig;
 
 #[derive(Clone)]
 pub struct ToolRegistry {
     tools: Vec<ToolDefinition>,
+    mcp_tools: Vec<McpToolEntry>,
 }
 
 impl ToolRegistry {
@@ -28,7 +30,10 @@ impl ToolRegistry {
         if !config.enabled.is_empty() {
             tools.retain(|tool| config.enabled.iter().any(|name| name == tool.name));
         }
-        Self { tools }
+        Self {
+            tools,
+            mcp_tools: Vec::new(),
+        }
     }
 
     /// Create a registry with all tools including PTY-based shell tools and diff tracking.
@@ -41,7 +46,10 @@ impl ToolRegistry {
         if !config.enabled.is_empty() {
             tools.retain(|tool| config.enabled.iter().any(|name| name == tool.name));
         }
-        Self { tools }
+        Self {
+            tools,
+            mcp_tools: Vec::new(),
+        }
     }
 
     /// Load and add user-defined tools from a YAML file
@@ -56,21 +64,45 @@ impl ToolRegistry {
         }
     }
 
+    /// Add tools of MCP servers; a name already taken by another tool is skipped
+    pub fn add_mcp_tools(&mut self, tools: Vec<McpToolEntry>) {
+        for tool in tools {
+            if self.has_tool(tool.name()) {
+                log::warn!(
+                    "skipping MCP tool '{}' of '{}': name already in use",
+                    tool.name(),
+                    tool.server
+                );
+                continue;
+            }
+            self.mcp_tools.push(tool);
+        }
+    }
+
+    /// Tools added from MCP servers
+    pub fn mcp_tools(&self) -> &[McpToolEntry] {
+        &self.mcp_tools
+    }
+
     /// Get the list of tool names
     pub fn tool_names(&self) -> Vec<&str> {
-        self.tools.iter().map(|t| t.name).collect()
+        self.tools
+            .iter()
+            .map(|t| t.name)
+            .chain(self.mcp_tools.iter().map(McpToolEntry::name))
+            .collect()
     }
 
     /// Check if a tool exists
-    #[allow(dead_code)]
     pub fn has_tool(&self, name: &str) -> bool {
-        self.tools.iter().any(|t| t.name == name)
+        self.tools.iter().any(|t| t.name == name) || self.mcp_tools.iter().any(|t| t.name() == name)
     }
 
     pub fn function_builders(&self) -> Vec<FunctionBuilder> {
         self.tools
             .iter()
             .map(|tool| tool.function_builder())
+            .chain(self.mcp_tools.iter().map(McpToolEntry::function_builder))
             .collect()
     }
 
@@ -80,15 +112,22 @@ impl ToolRegistry {
         args_json: &str,
         context: &ToolContext,
     ) -> Result<String, ToolError> {
-        let tool = self
-            .tools
-            .iter()
-            .find(|tool| tool.name == name)
-            .ok_or_else(|| ToolError::NotFound(name.to_string()))?;
+        type Run<'a> = Box<dyn FnOnce(Value) -> Result<String, ToolError> + 'a>;
+        let run: Run<'_> = match self.tools.iter().find(|tool| tool.name == name) {
+            Some(tool) => Box::new(move |args| (tool.executor)(context, args)),
+            None => {
+                let tool = self
+                    .mcp_tools
+                    .iter()
+                    .find(|tool| tool.name() == name)
+                    .ok_or_else(|| ToolError::NotFound(name.to_string()))?;
+                Box::new(move |args| tool.execute(args))
+            }
+        };
         let args = parse_args(args_json)?;
         validate_allowed_paths(&args, context)?;
         let start = Instant::now();
-        let result = (tool.executor)(context, args);
+        let result = run(args);
         enforce_timeout(start, context.timeout_ms)?;
         result
     }
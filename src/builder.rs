@@ -22,6 +22,12 @@ mod memory;
 #[path = "builder/resilience.rs"]
 mod resilience;
 
+#[path = "builder/cache.rs"]
+mod cache;
+
+#[path = "builder/middleware.rs"]
+mod middleware;
+
 #[path = "builder/search.rs"]
 mod search;
 
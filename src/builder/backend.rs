@@ -20,6 +20,21 @@ pub enum LLMBackend {
     AwsBedrock,
 }
 
+impl LLMBackend {
+    /// Whether providers of this backend enforce a schema set with
+    /// [`LLMBuilder::schema`](super::LLMBuilder::schema) natively.
+    #[cfg(feature = "api")]
+    pub(crate) fn supports_structured_output(&self) -> bool {
+        !matches!(
+            self,
+            LLMBackend::Anthropic
+                | LLMBackend::DeepSeek
+                | LLMBackend::Phind
+                | LLMBackend::ElevenLabs
+        )
+    }
+}
+
 impl std::str::FromStr for LLMBackend {
     type Err = LLMError;
 
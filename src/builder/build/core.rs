@@ -19,9 +19,17 @@ impl BuilderState {
             .take()
             .ok_or_else(|| LLMError::InvalidRequest("No backend specified".to_string()))?;
 
-        let provider = backends::build_backend(&mut self, backend, tools, tool_choice)?;
-        let provider = wrappers::wrap_with_validator(&mut self, provider)?;
+        let cache_scope = helpers::cache_scope(&self, &backend, tools.as_deref());
+        let model = self.model.clone();
+        let declared_tools = tools.clone();
+        let provider = backends::build_backend(&mut self, backend.clone(), tools, tool_choice)?;
+        let provider = wrappers::wrap_with_middlewares(&mut self, provider, &backend, model);
+        let provider =
+            wrappers::wrap_with_tool_validation(&mut self, provider, declared_tools.clone());
+        let provider = wrappers::wrap_with_validator(&mut self, provider, declared_tools)?;
         let provider = wrappers::wrap_with_resilience(&mut self, provider);
+        let provider = wrappers::wrap_with_semantic_cache(&mut self, provider, &cache_scope);
+        let provider = wrappers::wrap_with_cache(&mut self, provider, cache_scope);
         let provider = wrappers::wrap_with_memory(&mut self, provider);
         Ok(provider)
     }
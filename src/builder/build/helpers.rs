@@ -1,6 +1,7 @@
 use secrecy::ExposeSecret;
 
 use crate::{
+    builder::LLMBackend,
     chat::{Tool, ToolChoice},
     error::LLMError,
 };
@@ -21,6 +22,34 @@ pub(super) fn log_builder_state(state: &BuilderState) {
     );
 }
 
+/// Fingerprints the settings that influence generation so cached responses are never
+/// shared between differently configured providers.
+pub(super) fn cache_scope(
+    state: &BuilderState,
+    backend: &LLMBackend,
+    tools: Option<&[Tool]>,
+) -> String {
+    serde_json::json!({
+        "backend": format!("{backend:?}"),
+        "base_url": state.base_url,
+        "model": state.model,
+        "system": state.system,
+        "max_tokens": state.max_tokens,
+        "temperature": state.temperature,
+        "top_p": state.top_p,
+        "top_k": state.top_k,
+        "reasoning": state.reasoning,
+        "reasoning_effort": state.reasoning_effort,
+        "reasoning_budget_tokens": state.reasoning_budget_tokens,
+        "json_schema": state.json_schema,
+        "tools": tools,
+        "tool_choice": state.tool_choice,
+        "embedding_dimensions": state.embedding_dimensions,
+        "extra_body": state.extra_body,
+    })
+    .to_string()
+}
+
 pub(super) fn validate_tool_config(
     state: &BuilderState,
 ) -> Result<(Option<Vec<Tool>>, Option<ToolChoice>), LLMError> {
@@ -1,20 +1,60 @@
 use std::sync::Arc;
-
-use tokio::sync::RwLock;
+use std::time::Duration;
 
 use crate::{
+    builder::LLMBackend,
+    cached_llm::{CacheConfig, CachedLLM, SemanticCacheLLM},
+    chat::Tool,
     error::LLMError,
     memory::ChatWithMemoryConfig,
+    middleware::MiddlewareLLM,
     resilient_llm::{ResilienceConfig, ResilientLLM},
+    tool_validation::ToolCallValidatedLLM,
     validated_llm::ValidatedLLM,
     LLMProvider,
 };
 
 use super::super::state::BuilderState;
 
+pub(super) fn wrap_with_middlewares(
+    state: &mut BuilderState,
+    provider: Box<dyn LLMProvider>,
+    backend: &LLMBackend,
+    model: Option<String>,
+) -> Box<dyn LLMProvider> {
+    if state.middlewares.is_empty() {
+        return provider;
+    }
+
+    let name = format!("{backend:?}").to_lowercase();
+    let wrapped = std::mem::take(&mut state.middlewares).into_iter().fold(
+        MiddlewareLLM::new(provider, name, model),
+        |llm, middleware| llm.with_middleware(middleware),
+    );
+    Box::new(wrapped)
+}
+
+pub(super) fn wrap_with_tool_validation(
+    state: &mut BuilderState,
+    provider: Box<dyn LLMProvider>,
+    tools: Option<Vec<Tool>>,
+) -> Box<dyn LLMProvider> {
+    if !state.tool_validation_enable.unwrap_or(false) {
+        return provider;
+    }
+
+    let mut wrapped = ToolCallValidatedLLM::new(provider)
+        .with_repair_attempts(state.tool_repair_attempts.unwrap_or(0));
+    if let Some(tools) = tools {
+        wrapped = wrapped.with_tools(tools);
+    }
+    Box::new(wrapped)
+}
+
 pub(super) fn wrap_with_validator(
     state: &mut BuilderState,
     provider: Box<dyn LLMProvider>,
+    tools: Option<Vec<Tool>>,
 ) -> Result<Box<dyn LLMProvider>, LLMError> {
     let Some(validator) = state.validator.take() else {
         return Ok(provider);
@@ -24,11 +64,14 @@ pub(super) fn wrap_with_validator(
             "validator_attempts must be greater than 0".to_string(),
         ));
     }
-    Ok(Box::new(ValidatedLLM::new(
-        provider,
-        validator,
-        state.validator_attempts,
-    )))
+    let mut wrapped = ValidatedLLM::with_validator(provider, validator, state.validator_attempts);
+    if let Some(tools) = tools {
+        wrapped = wrapped.with_tools(tools);
+    }
+    if let Some(hook) = state.validator_on_failure.take() {
+        wrapped = wrapped.on_failure(hook);
+    }
+    Ok(Box::new(wrapped))
 }
 
 pub(super) fn wrap_with_resilience(
@@ -55,6 +98,41 @@ pub(super) fn wrap_with_resilience(
     Box::new(ResilientLLM::new(provider, cfg))
 }
 
+pub(super) fn wrap_with_cache(
+    state: &mut BuilderState,
+    provider: Box<dyn LLMProvider>,
+    scope: String,
+) -> Box<dyn LLMProvider> {
+    if !state.cache_enable.unwrap_or(false) {
+        return provider;
+    }
+
+    let mut cfg = CacheConfig::defaults();
+    if let Some(capacity) = state.cache_capacity {
+        cfg.capacity = capacity;
+    }
+    if let Some(ttl) = state.cache_ttl_seconds {
+        cfg.ttl = Some(Duration::from_secs(ttl));
+    }
+    cfg.persist_dir = state.cache_dir.take();
+    cfg.scope = scope;
+    Box::new(CachedLLM::new(provider, cfg))
+}
+
+pub(super) fn wrap_with_semantic_cache(
+    state: &mut BuilderState,
+    provider: Box<dyn LLMProvider>,
+    scope: &str,
+) -> Box<dyn LLMProvider> {
+    let Some(mut cfg) = state.semantic_cache.take() else {
+        return provider;
+    };
+    if cfg.scope.is_empty() {
+        cfg.scope = scope.to_string();
+    }
+    Box::new(SemanticCacheLLM::new(provider, cfg))
+}
+
 pub(super) fn wrap_with_memory(
     state: &mut BuilderState,
     provider: Box<dyn LLMProvider>,
@@ -63,8 +141,7 @@ pub(super) fn wrap_with_memory(
         return provider;
     };
 
-    let memory_arc = Arc::new(RwLock::new(memory));
     let provider_arc = Arc::from(provider);
-    let config = ChatWithMemoryConfig::new(provider_arc, memory_arc);
+    let config = ChatWithMemoryConfig::new(provider_arc, memory);
     Box::new(crate::memory::ChatWithMemory::with_config(config))
 }
@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+
+use crate::cached_llm::SemanticCacheConfig;
+
+use super::llm_builder::LLMBuilder;
+
+impl LLMBuilder {
+    /// Enable the response cache wrapper.
+    pub fn cache(mut self, enable: bool) -> Self {
+        self.state.cache_enable = Some(enable);
+        self
+    }
+
+    /// Sets the maximum number of cached entries kept in memory.
+    pub fn cache_capacity(mut self, capacity: usize) -> Self {
+        self.state.cache_capacity = Some(capacity);
+        self
+    }
+
+    /// Sets how long cached entries stay valid, in seconds.
+    pub fn cache_ttl_seconds(mut self, ttl_seconds: u64) -> Self {
+        self.state.cache_ttl_seconds = Some(ttl_seconds);
+        self
+    }
+
+    /// Persists cached entries as JSON files in the given directory.
+    pub fn cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.state.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Enable the semantic cache, serving responses of similar prompts.
+    ///
+    /// When the config has no scope, the provider settings (system prompt, model...) are used.
+    pub fn semantic_cache(mut self, config: SemanticCacheConfig) -> Self {
+        self.state.semantic_cache = Some(config);
+        self
+    }
+}
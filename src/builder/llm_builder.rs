@@ -7,6 +7,10 @@ use crate::chat::ReasoningEffort;
 use super::{backend::LLMBackend, state::BuilderState};
 
 /// Builder for configuring and instantiating LLM providers.
+///
+/// Cloning a builder is cheap and lets one configuration serve as a template for
+/// several providers; clones share the memory set with [`LLMBuilder::memory`].
+#[derive(Clone)]
 pub struct LLMBuilder {
     pub(super) state: BuilderState,
 }
@@ -31,6 +35,21 @@ impl LLMBuilder {
         self
     }
 
+    /// Whether the configured backend enforces [`schema`](Self::schema) natively.
+    #[cfg(feature = "api")]
+    pub(crate) fn supports_structured_output(&self) -> bool {
+        self.state
+            .backend
+            .as_ref()
+            .is_some_and(LLMBackend::supports_structured_output)
+    }
+
+    /// Removes the memory set with [`memory`](Self::memory), returning whether there was one.
+    #[cfg(feature = "api")]
+    pub(crate) fn take_memory(&mut self) -> bool {
+        self.state.memory.take().is_some()
+    }
+
     /// Sets the API key for authentication.
     pub fn api_key(mut self, key: impl Into<String>) -> Self {
         self.state.api_key = Some(SecretString::new(key.into()));
@@ -1,3 +1,7 @@
+use std::sync::Arc;
+
+use tokio::sync::RwLock;
+
 use crate::memory::{MemoryProvider, SlidingWindowMemory, TrimStrategy};
 
 use super::llm_builder::LLMBuilder;
@@ -5,19 +9,19 @@ use super::llm_builder::LLMBuilder;
 impl LLMBuilder {
     /// Sets a custom memory provider for conversation history.
     pub fn memory(mut self, memory: impl MemoryProvider + 'static) -> Self {
-        self.state.memory = Some(Box::new(memory));
+        self.set_memory(memory);
         self
     }
 
     /// Sets a sliding window memory provider.
     pub fn sliding_memory(mut self, memory: SlidingWindowMemory) -> Self {
-        self.state.memory = Some(Box::new(memory));
+        self.set_memory(memory);
         self
     }
 
     /// Sets up a sliding window memory with the specified window size.
     pub fn sliding_window_memory(mut self, window_size: usize) -> Self {
-        self.state.memory = Some(Box::new(SlidingWindowMemory::new(window_size)));
+        self.set_memory(SlidingWindowMemory::new(window_size));
         self
     }
 
@@ -27,10 +31,11 @@ impl LLMBuilder {
         window_size: usize,
         strategy: TrimStrategy,
     ) -> Self {
-        self.state.memory = Some(Box::new(SlidingWindowMemory::with_strategy(
-            window_size,
-            strategy,
-        )));
+        self.set_memory(SlidingWindowMemory::with_strategy(window_size, strategy));
         self
     }
+
+    fn set_memory(&mut self, memory: impl MemoryProvider + 'static) {
+        self.state.memory = Some(Arc::new(RwLock::new(Box::new(memory))));
+    }
 }
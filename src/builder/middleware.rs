@@ -0,0 +1,31 @@
+use std::sync::Arc;
+
+use crate::{middleware::LLMMiddleware, usage_tracker::UsageTracker};
+
+use super::llm_builder::LLMBuilder;
+
+impl LLMBuilder {
+    /// Adds a middleware invoked around every call sent to the backend.
+    ///
+    /// Middlewares wrap the backend directly, so they observe each actual request
+    /// (including retries) but not responses served from a cache.
+    pub fn middleware(mut self, middleware: impl LLMMiddleware + 'static) -> Self {
+        self.state.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Emits a `tracing` span with GenAI semantic-convention attributes for every call.
+    #[cfg(feature = "tracing")]
+    pub fn tracing(self, enable: bool) -> Self {
+        if !enable {
+            return self;
+        }
+        self.middleware(crate::middleware::TracingMiddleware::new())
+    }
+
+    /// Records token usage and cost of every call into the given tracker,
+    /// rejecting calls once its budget is exhausted.
+    pub fn usage_tracker(self, tracker: UsageTracker) -> Self {
+        self.middleware(tracker)
+    }
+}
@@ -1,15 +1,19 @@
+use std::sync::Arc;
+
 use secrecy::SecretString;
+use tokio::sync::RwLock;
 
 use crate::{
     chat::{StructuredOutputFormat, Tool, ToolChoice},
     memory::MemoryProvider,
+    validated_llm::{ValidationFailureHook, Validator},
 };
 
-use super::{backend::LLMBackend, validation::ValidatorFn};
+use super::backend::LLMBackend;
 
 const DEFAULT_VALIDATOR_ATTEMPTS: usize = 3;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub(crate) struct BuilderState {
     pub(crate) backend: Option<LLMBackend>,
     pub(crate) api_key: Option<SecretString>,
@@ -23,8 +27,11 @@ pub(crate) struct BuilderState {
     pub(crate) top_k: Option<u32>,
     pub(crate) embedding_encoding_format: Option<String>,
     pub(crate) embedding_dimensions: Option<u32>,
-    pub(crate) validator: Option<Box<ValidatorFn>>,
+    pub(crate) validator: Option<Validator>,
+    pub(crate) validator_on_failure: Option<std::sync::Arc<ValidationFailureHook>>,
     pub(crate) validator_attempts: usize,
+    pub(crate) tool_validation_enable: Option<bool>,
+    pub(crate) tool_repair_attempts: Option<usize>,
     pub(crate) tools: Option<Vec<Tool>>,
     pub(crate) tool_choice: Option<ToolChoice>,
     pub(crate) enable_parallel_tool_use: Option<bool>,
@@ -43,7 +50,8 @@ pub(crate) struct BuilderState {
     pub(crate) xai_search_max_results: Option<u32>,
     pub(crate) xai_search_from_date: Option<String>,
     pub(crate) xai_search_to_date: Option<String>,
-    pub(crate) memory: Option<Box<dyn MemoryProvider>>,
+    /// Shared by cloned builders.
+    pub(crate) memory: Option<Arc<RwLock<Box<dyn MemoryProvider>>>>,
     pub(crate) openai_enable_web_search: Option<bool>,
     pub(crate) openai_web_search_context_size: Option<String>,
     pub(crate) openai_web_search_user_location_type: Option<String>,
@@ -55,6 +63,12 @@ pub(crate) struct BuilderState {
     pub(crate) resilient_base_delay_ms: Option<u64>,
     pub(crate) resilient_max_delay_ms: Option<u64>,
     pub(crate) resilient_jitter: Option<bool>,
+    pub(crate) cache_enable: Option<bool>,
+    pub(crate) cache_capacity: Option<usize>,
+    pub(crate) cache_ttl_seconds: Option<u64>,
+    pub(crate) cache_dir: Option<std::path::PathBuf>,
+    pub(crate) semantic_cache: Option<crate::cached_llm::SemanticCacheConfig>,
+    pub(crate) middlewares: Vec<std::sync::Arc<dyn crate::middleware::LLMMiddleware>>,
     #[cfg(feature = "google")]
     pub(crate) google_service_tier: Option<crate::backends::google::GoogleServiceTier>,
 }
@@ -162,7 +162,7 @@ impl FunctionBuilder {
     }
 
     /// Builds the function tool.
-    fn build(self) -> Tool {
+    pub(crate) fn build(self) -> Tool {
         let FunctionBuilder {
             name,
             description,
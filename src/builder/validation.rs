@@ -1,4 +1,7 @@
+use std::sync::Arc;
+
 use super::llm_builder::LLMBuilder;
+use crate::validated_llm::{ValidationFailure, Validator};
 
 /// A function type for validating LLM provider outputs.
 pub type ValidatorFn = dyn Fn(&str) -> Result<(), String> + Send + Sync + 'static;
@@ -9,7 +12,22 @@ impl LLMBuilder {
     where
         F: Fn(&str) -> Result<(), String> + Send + Sync + 'static,
     {
-        self.state.validator = Some(Box::new(f));
+        self.state.validator = Some(Validator::text(f));
+        self
+    }
+
+    /// Sets a composable validator (JSON schema, regex, length, custom checks).
+    pub fn validation(mut self, validator: Validator) -> Self {
+        self.state.validator = Some(validator);
+        self
+    }
+
+    /// Calls `hook` with every response rejected by the validator.
+    pub fn validator_on_failure<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&ValidationFailure) + Send + Sync + 'static,
+    {
+        self.state.validator_on_failure = Some(Arc::new(hook));
         self
     }
 
@@ -18,4 +36,17 @@ impl LLMBuilder {
         self.state.validator_attempts = attempts;
         self
     }
+
+    /// Validates tool-call arguments against the declared tool schemas.
+    pub fn validate_tool_calls(mut self, enable: bool) -> Self {
+        self.state.tool_validation_enable = Some(enable);
+        self
+    }
+
+    /// Number of times the model is asked to fix invalid tool calls (enables validation).
+    pub fn tool_call_repair_attempts(mut self, attempts: usize) -> Self {
+        self.state.tool_validation_enable = Some(true);
+        self.state.tool_repair_attempts = Some(attempts);
+        self
+    }
 }
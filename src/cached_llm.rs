@@ -0,0 +1,29 @@
+#[path = "cached_llm/config.rs"]
+mod config;
+
+#[path = "cached_llm/key.rs"]
+pub(crate) mod key;
+
+#[path = "cached_llm/store.rs"]
+mod store;
+
+#[path = "cached_llm/wrapper.rs"]
+mod wrapper;
+
+#[path = "cached_llm/chat.rs"]
+mod chat;
+
+#[path = "cached_llm/other.rs"]
+mod other;
+
+#[path = "cached_llm/semantic.rs"]
+mod semantic;
+
+pub use config::CacheConfig;
+pub use semantic::{SemanticCacheConfig, SemanticCacheLLM, SemanticCacheMetrics};
+pub use store::{CacheStats, CachedChatResponse};
+pub use wrapper::CachedLLM;
+
+#[cfg(test)]
+#[path = "cached_llm/tests.rs"]
+mod tests;
@@ -0,0 +1,55 @@
+use std::pin::Pin;
+
+use async_trait::async_trait;
+use futures::stream::Stream;
+
+use crate::{
+    chat::{ChatMessage, ChatProvider, ChatResponse, StreamChunk, StreamResponse, Tool},
+    error::LLMError,
+};
+
+use super::key::chat_key;
+use super::store::{CachedChatResponse, CachedValue};
+use super::wrapper::CachedLLM;
+
+#[async_trait]
+impl ChatProvider for CachedLLM {
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let key = chat_key(self.store.scope(), messages, tools);
+        if let Some(CachedValue::Chat(cached)) = self.store.get(&key).await {
+            return Ok(Box::new(cached));
+        }
+
+        let response = self.inner.chat_with_tools(messages, tools).await?;
+        let cached = CachedChatResponse::from_response(response.as_ref());
+        self.store.put(key, CachedValue::Chat(cached)).await;
+        Ok(response)
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError> {
+        self.inner.chat_stream(messages).await
+    }
+
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>, LLMError>
+    {
+        self.inner.chat_stream_struct(messages).await
+    }
+
+    async fn chat_stream_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk, LLMError>> + Send>>, LLMError> {
+        self.inner.chat_stream_with_tools(messages, tools).await
+    }
+}
@@ -0,0 +1,30 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Configuration for the response cache.
+#[derive(Clone, Debug)]
+pub struct CacheConfig {
+    /// Maximum number of entries kept in memory before least recently used ones are evicted
+    pub capacity: usize,
+    /// Time after which an entry is considered stale, `None` keeps entries forever
+    pub ttl: Option<Duration>,
+    /// Directory where entries are persisted, in a subdirectory per scope; `None`
+    /// keeps the cache in memory only
+    pub persist_dir: Option<PathBuf>,
+    /// Fingerprint of the generation settings (backend, model, temperature...) mixed into every key
+    pub scope: String,
+}
+
+const DEFAULT_CAPACITY: usize = 1_000;
+
+impl CacheConfig {
+    /// Creates a default in-memory configuration without expiry.
+    pub fn defaults() -> Self {
+        Self {
+            capacity: DEFAULT_CAPACITY,
+            ttl: None,
+            persist_dir: None,
+            scope: String::new(),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use serde_json::{json, Value};
+
+use crate::{
+    chat::{ChatMessage, ChatRole, MessageType, Tool},
+    completion::CompletionRequest,
+};
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Builds the cache key of a chat request.
+pub(crate) fn chat_key(scope: &str, messages: &[ChatMessage], tools: Option<&[Tool]>) -> String {
+    let messages: Vec<Value> = messages.iter().map(canonical_message).collect();
+    let tools = tools
+        .map(|tools| serde_json::to_value(tools).unwrap_or(Value::Null))
+        .unwrap_or(Value::Null);
+    digest(&json!({
+        "kind": "chat",
+        "scope": scope,
+        "messages": messages,
+        "tools": tools,
+    }))
+}
+
+/// Builds the cache key of a completion request.
+pub(crate) fn completion_key(scope: &str, req: &CompletionRequest) -> String {
+    digest(&json!({
+        "kind": "completion",
+        "scope": scope,
+        "prompt": req.prompt,
+        "max_tokens": req.max_tokens,
+        "temperature": req.temperature,
+    }))
+}
+
+/// Builds the cache key of a single embedding input.
+pub(crate) fn embedding_key(scope: &str, input: &str) -> String {
+    digest(&json!({
+        "kind": "embedding",
+        "scope": scope,
+        "input": input,
+    }))
+}
+
+/// Hashes raw bytes with 64-bit FNV-1a, which is stable across builds and platforms.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Serializes a JSON value (object keys are sorted) and hashes it into a hex key.
+pub(crate) fn digest(value: &Value) -> String {
+    let canonical = value.to_string();
+    // Hash twice with different seeds to make accidental collisions negligible.
+    let salted = [b"llm-cache:".as_slice(), canonical.as_bytes()].concat();
+    format!(
+        "{:016x}{:016x}",
+        fnv1a(canonical.as_bytes()),
+        fnv1a(&salted)
+    )
+}
+
+pub(crate) fn canonical_message(msg: &ChatMessage) -> Value {
+    let role = match msg.role {
+        ChatRole::User => "user",
+        ChatRole::Assistant => "assistant",
+    };
+    let payload = match &msg.message_type {
+        MessageType::Text => json!({ "type": "text" }),
+        MessageType::Image((mime, bytes)) => json!({
+            "type": "image",
+            "mime": mime.mime_type(),
+            "data": format!("{:016x}", fnv1a(bytes)),
+        }),
+        MessageType::Pdf(bytes) => json!({
+            "type": "pdf",
+            "data": format!("{:016x}", fnv1a(bytes)),
+        }),
+        MessageType::Audio(bytes) => json!({
+            "type": "audio",
+            "data": format!("{:016x}", fnv1a(bytes)),
+        }),
+        MessageType::ImageURL(url) => json!({ "type": "image_url", "url": url }),
+        MessageType::ToolUse(calls) => json!({ "type": "tool_use", "calls": calls }),
+        MessageType::ToolResult(calls) => json!({ "type": "tool_result", "calls": calls }),
+    };
+    json!({
+        "role": role,
+        "content": msg.content,
+        "payload": payload,
+    })
+}
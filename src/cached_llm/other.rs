@@ -0,0 +1,91 @@
+use async_trait::async_trait;
+
+use crate::{
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    models::{ModelListRequest, ModelListResponse, ModelsProvider},
+    stt::SpeechToTextProvider,
+    tts::TextToSpeechProvider,
+};
+
+use super::key::{completion_key, embedding_key};
+use super::store::CachedValue;
+use super::wrapper::CachedLLM;
+
+#[async_trait]
+impl CompletionProvider for CachedLLM {
+    async fn complete(&self, req: &CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        let key = completion_key(self.store.scope(), req);
+        if let Some(CachedValue::Completion { text }) = self.store.get(&key).await {
+            return Ok(CompletionResponse { text });
+        }
+
+        let response = self.inner.complete(req).await?;
+        let value = CachedValue::Completion {
+            text: response.text.clone(),
+        };
+        self.store.put(key, value).await;
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for CachedLLM {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        let mut vectors: Vec<Option<Vec<f32>>> = Vec::with_capacity(input.len());
+        let mut misses = Vec::new();
+        for (idx, text) in input.iter().enumerate() {
+            let key = embedding_key(self.store.scope(), text);
+            match self.store.get(&key).await {
+                Some(CachedValue::Embedding { vector }) => vectors.push(Some(vector)),
+                _ => {
+                    vectors.push(None);
+                    misses.push((idx, key));
+                }
+            }
+        }
+
+        if !misses.is_empty() {
+            let batch = misses.iter().map(|(idx, _)| input[*idx].clone()).collect();
+            let fresh = self.inner.embed(batch).await?;
+            if fresh.len() != misses.len() {
+                return Err(LLMError::ProviderError(format!(
+                    "expected {} embeddings, provider returned {}",
+                    misses.len(),
+                    fresh.len()
+                )));
+            }
+            for ((idx, key), vector) in misses.into_iter().zip(fresh) {
+                vectors[idx] = Some(vector.clone());
+                self.store.put(key, CachedValue::Embedding { vector }).await;
+            }
+        }
+
+        Ok(vectors.into_iter().flatten().collect())
+    }
+}
+
+#[async_trait]
+impl SpeechToTextProvider for CachedLLM {
+    async fn transcribe(&self, audio: Vec<u8>) -> Result<String, LLMError> {
+        self.inner.transcribe(audio).await
+    }
+}
+
+#[async_trait]
+impl TextToSpeechProvider for CachedLLM {
+    async fn speech(&self, text: &str) -> Result<Vec<u8>, LLMError> {
+        self.inner.speech(text).await
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for CachedLLM {
+    async fn list_models(
+        &self,
+        request: Option<&ModelListRequest>,
+    ) -> Result<Box<dyn ModelListResponse>, LLMError> {
+        self.inner.list_models(request).await
+    }
+}
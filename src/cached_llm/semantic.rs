@@ -0,0 +1,291 @@
+use std::collections::{HashMap, VecDeque};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use futures::stream::Stream;
+
+use crate::{
+    chat::{
+        ChatMessage, ChatProvider, ChatResponse, ChatRole, MessageType, StreamChunk,
+        StreamResponse, Tool,
+    },
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    models::{ModelListRequest, ModelListResponse, ModelsProvider},
+    stt::SpeechToTextProvider,
+    tts::TextToSpeechProvider,
+    LLMProvider,
+};
+
+use super::key::chat_key;
+use super::store::{CacheStats, CachedChatResponse};
+
+const DEFAULT_THRESHOLD: f32 = 0.95;
+const DEFAULT_MAX_ENTRIES: usize = 1_000;
+
+/// Shared hit/miss counters of a semantic cache.
+///
+/// The handle is cheap to clone, so it can be kept by the caller before the
+/// cache is boxed behind `dyn LLMProvider`.
+#[derive(Clone, Debug, Default)]
+pub struct SemanticCacheMetrics {
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+    entries: Arc<AtomicUsize>,
+}
+
+impl SemanticCacheMetrics {
+    /// Returns the current counter values.
+    pub fn snapshot(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Configuration for the semantic cache.
+#[derive(Clone)]
+pub struct SemanticCacheConfig {
+    /// Provider used to embed the last user message
+    pub embedder: Arc<dyn EmbeddingProvider + Send + Sync>,
+    /// Minimum cosine similarity for a cached response to be served
+    pub threshold: f32,
+    /// Maximum number of entries kept per scope, oldest entries are dropped first
+    pub max_entries: usize,
+    /// Fingerprint of the system prompt, model and settings of the wrapped provider
+    pub scope: String,
+    /// Counters updated on every lookup
+    pub metrics: SemanticCacheMetrics,
+}
+
+impl SemanticCacheConfig {
+    /// Creates a configuration with the default threshold and size.
+    pub fn new(embedder: Arc<dyn EmbeddingProvider + Send + Sync>) -> Self {
+        Self {
+            embedder,
+            threshold: DEFAULT_THRESHOLD,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            scope: String::new(),
+            metrics: SemanticCacheMetrics::default(),
+        }
+    }
+
+    /// Sets the similarity threshold.
+    pub fn threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Sets the maximum number of entries kept per scope.
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Sets the scope fingerprint, typically the system prompt and model name.
+    pub fn scope(mut self, scope: impl Into<String>) -> Self {
+        self.scope = scope.into();
+        self
+    }
+
+    /// Returns a handle on the hit/miss counters.
+    pub fn metrics(&self) -> SemanticCacheMetrics {
+        self.metrics.clone()
+    }
+}
+
+struct SemanticEntry {
+    embedding: Vec<f32>,
+    response: CachedChatResponse,
+}
+
+/// Chat wrapper returning cached responses for semantically similar prompts.
+///
+/// The last user message is embedded and compared to previous prompts sharing the
+/// same scope (configured scope, earlier conversation turns and tools). Other
+/// capabilities are forwarded to the inner provider unchanged.
+pub struct SemanticCacheLLM {
+    inner: Box<dyn LLMProvider>,
+    cfg: SemanticCacheConfig,
+    entries: Mutex<HashMap<String, VecDeque<SemanticEntry>>>,
+}
+
+impl SemanticCacheLLM {
+    /// Creates a new semantic cache around an existing provider.
+    pub fn new(inner: Box<dyn LLMProvider>, cfg: SemanticCacheConfig) -> Self {
+        Self {
+            inner,
+            cfg,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns hit/miss counters and the number of cached entries.
+    pub fn stats(&self) -> CacheStats {
+        self.cfg.metrics.snapshot()
+    }
+
+    fn lookup(&self, scope: &str, embedding: &[f32]) -> Option<CachedChatResponse> {
+        let entries = self.entries.lock().expect("semantic cache lock");
+        let (score, entry) = entries
+            .get(scope)?
+            .iter()
+            .map(|entry| (cosine_similarity(&entry.embedding, embedding), entry))
+            .max_by(|a, b| a.0.total_cmp(&b.0))?;
+        if score < self.cfg.threshold {
+            return None;
+        }
+        log::debug!("semantic cache hit with similarity {score:.4}");
+        Some(entry.response.clone())
+    }
+
+    fn insert(&self, scope: String, entry: SemanticEntry) {
+        let mut entries = self.entries.lock().expect("semantic cache lock");
+        let bucket = entries.entry(scope).or_default();
+        bucket.push_back(entry);
+        while bucket.len() > self.cfg.max_entries.max(1) {
+            bucket.pop_front();
+        }
+        let total = entries.values().map(VecDeque::len).sum();
+        self.cfg.metrics.entries.store(total, Ordering::Relaxed);
+    }
+
+    async fn embed_prompt(&self, prompt: &str) -> Result<Vec<f32>, LLMError> {
+        self.cfg
+            .embedder
+            .embed(vec![prompt.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| LLMError::ProviderError("embedder returned no vectors".to_string()))
+    }
+}
+
+/// Splits a conversation into the prompt to embed and the context it is scoped to.
+fn split_prompt(messages: &[ChatMessage]) -> Option<(&str, &[ChatMessage])> {
+    let (last, history) = messages.split_last()?;
+    match (&last.role, &last.message_type) {
+        (ChatRole::User, MessageType::Text) => Some((last.content.as_str(), history)),
+        _ => None,
+    }
+}
+
+/// Cosine similarity between two vectors, `0.0` when either is empty or zero.
+pub(crate) fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let (dot, norm_a, norm_b) = a
+        .iter()
+        .zip(b)
+        .fold((0.0f32, 0.0f32, 0.0f32), |(dot, na, nb), (x, y)| {
+            (dot + x * y, na + x * x, nb + y * y)
+        });
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+#[async_trait]
+impl ChatProvider for SemanticCacheLLM {
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let Some((prompt, history)) = split_prompt(messages) else {
+            return self.inner.chat_with_tools(messages, tools).await;
+        };
+
+        let scope = chat_key(&self.cfg.scope, history, tools);
+        let embedding = match self.embed_prompt(prompt).await {
+            Ok(embedding) => embedding,
+            Err(err) => {
+                log::warn!("semantic cache skipped, embedding failed: {err}");
+                return self.inner.chat_with_tools(messages, tools).await;
+            }
+        };
+        if let Some(cached) = self.lookup(&scope, &embedding) {
+            self.cfg.metrics.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(Box::new(cached));
+        }
+        self.cfg.metrics.misses.fetch_add(1, Ordering::Relaxed);
+
+        let response = self.inner.chat_with_tools(messages, tools).await?;
+        let entry = SemanticEntry {
+            embedding,
+            response: CachedChatResponse::from_response(response.as_ref()),
+        };
+        self.insert(scope, entry);
+        Ok(response)
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError> {
+        self.inner.chat_stream(messages).await
+    }
+
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>, LLMError>
+    {
+        self.inner.chat_stream_struct(messages).await
+    }
+
+    async fn chat_stream_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk, LLMError>> + Send>>, LLMError> {
+        self.inner.chat_stream_with_tools(messages, tools).await
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for SemanticCacheLLM {
+    async fn complete(&self, req: &CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        self.inner.complete(req).await
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for SemanticCacheLLM {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        self.inner.embed(input).await
+    }
+}
+
+#[async_trait]
+impl SpeechToTextProvider for SemanticCacheLLM {
+    async fn transcribe(&self, audio: Vec<u8>) -> Result<String, LLMError> {
+        self.inner.transcribe(audio).await
+    }
+}
+
+#[async_trait]
+impl TextToSpeechProvider for SemanticCacheLLM {
+    async fn speech(&self, text: &str) -> Result<Vec<u8>, LLMError> {
+        self.inner.speech(text).await
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for SemanticCacheLLM {
+    async fn list_models(
+        &self,
+        request: Option<&ModelListRequest>,
+    ) -> Result<Box<dyn ModelListResponse>, LLMError> {
+        self.inner.list_models(request).await
+    }
+}
+
+impl LLMProvider for SemanticCacheLLM {}
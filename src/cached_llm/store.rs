@@ -0,0 +1,284 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    chat::{ChatResponse, Usage},
+    ToolCall,
+};
+
+use super::config::CacheConfig;
+use super::key::fnv1a;
+
+/// Chat response stored in the cache and replayed on hits.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CachedChatResponse {
+    /// Text content of the response
+    pub text: Option<String>,
+    /// Tool calls requested by the model
+    pub tool_calls: Option<Vec<ToolCall>>,
+    /// Reasoning content, if the provider returned any
+    pub thinking: Option<String>,
+    /// Usage reported by the provider for the original request
+    pub usage: Option<Usage>,
+}
+
+impl CachedChatResponse {
+    /// Captures the content of any chat response.
+    pub fn from_response(response: &dyn ChatResponse) -> Self {
+        Self {
+            text: response.text(),
+            tool_calls: response.tool_calls(),
+            thinking: response.thinking(),
+            usage: response.usage(),
+        }
+    }
+}
+
+impl ChatResponse for CachedChatResponse {
+    fn text(&self) -> Option<String> {
+        self.text.clone()
+    }
+
+    fn tool_calls(&self) -> Option<Vec<ToolCall>> {
+        self.tool_calls.clone()
+    }
+
+    fn thinking(&self) -> Option<String> {
+        self.thinking.clone()
+    }
+
+    fn usage(&self) -> Option<Usage> {
+        self.usage.clone()
+    }
+}
+
+impl std::fmt::Display for CachedChatResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.text, &self.tool_calls) {
+            (Some(text), _) => write!(f, "{text}"),
+            (None, Some(calls)) => {
+                for call in calls {
+                    writeln!(f, "{call}")?;
+                }
+                Ok(())
+            }
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Counters describing cache effectiveness.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Number of lookups served from the cache
+    pub hits: u64,
+    /// Number of lookups forwarded to the inner provider
+    pub misses: u64,
+    /// Number of entries currently held in memory
+    pub entries: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum CachedValue {
+    Chat(CachedChatResponse),
+    Completion { text: String },
+    Embedding { vector: Vec<f32> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    value: CachedValue,
+    expires_at_ms: Option<u64>,
+}
+
+impl Entry {
+    fn is_expired(&self, now_ms: u64) -> bool {
+        self.expires_at_ms.is_some_and(|at| at <= now_ms)
+    }
+}
+
+#[derive(Default)]
+struct LruState {
+    entries: HashMap<String, (Entry, u64)>,
+    order: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl LruState {
+    fn get(&mut self, key: &str, now_ms: u64) -> Option<CachedValue> {
+        let (entry, tick) = self.entries.get(key)?;
+        let old_tick = *tick;
+        if entry.is_expired(now_ms) {
+            self.remove(key);
+            return None;
+        }
+        let value = entry.value.clone();
+        self.touch(key, old_tick);
+        Some(value)
+    }
+
+    fn insert(&mut self, key: String, entry: Entry, capacity: usize) {
+        self.remove(&key);
+        while self.entries.len() >= capacity.max(1) {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+        self.tick += 1;
+        self.order.insert(self.tick, key.clone());
+        self.entries.insert(key, (entry, self.tick));
+    }
+
+    fn touch(&mut self, key: &str, old_tick: u64) {
+        self.order.remove(&old_tick);
+        self.tick += 1;
+        self.order.insert(self.tick, key.to_string());
+        if let Some((_, tick)) = self.entries.get_mut(key) {
+            *tick = self.tick;
+        }
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some((_, tick)) = self.entries.remove(key) {
+            self.order.remove(&tick);
+        }
+    }
+}
+
+/// In-memory LRU with optional TTL and on-disk persistence.
+pub(crate) struct CacheStore {
+    cfg: CacheConfig,
+    state: Mutex<LruState>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CacheStore {
+    pub(crate) fn new(cfg: CacheConfig) -> Self {
+        Self {
+            cfg,
+            state: Mutex::new(LruState::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn scope(&self) -> &str {
+        &self.cfg.scope
+    }
+
+    pub(crate) async fn get(&self, key: &str) -> Option<CachedValue> {
+        let now = now_ms();
+        let cached = self.state.lock().expect("cache lock").get(key, now);
+        let value = match cached {
+            Some(value) => Some(value),
+            None => self.load_from_disk(key, now).await,
+        };
+        let counter = if value.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    pub(crate) async fn put(&self, key: String, value: CachedValue) {
+        let entry = Entry {
+            value,
+            expires_at_ms: self
+                .cfg
+                .ttl
+                .map(|ttl| now_ms().saturating_add(ttl.as_millis() as u64)),
+        };
+        self.persist(&key, &entry).await;
+        self.state
+            .lock()
+            .expect("cache lock")
+            .insert(key, entry, self.cfg.capacity);
+    }
+
+    /// Drops the entries in memory and the persisted entries of this scope;
+    /// other files of the persist directory are kept.
+    pub(crate) async fn clear(&self) {
+        *self.state.lock().expect("cache lock") = LruState::default();
+        let Some(dir) = self.scope_dir() else {
+            return;
+        };
+        match tokio::fs::remove_dir_all(&dir).await {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                log::warn!("failed to clear cache directory {}: {err}", dir.display());
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.state.lock().expect("cache lock").entries.len(),
+        }
+    }
+
+    /// Subdirectory of the persist directory holding the entries of this scope.
+    fn scope_dir(&self) -> Option<PathBuf> {
+        let scope = format!("scope-{:016x}", fnv1a(self.cfg.scope.as_bytes()));
+        self.cfg.persist_dir.as_ref().map(|dir| dir.join(scope))
+    }
+
+    fn entry_path(&self, key: &str) -> Option<PathBuf> {
+        self.scope_dir().map(|dir| dir.join(format!("{key}.json")))
+    }
+
+    async fn load_from_disk(&self, key: &str, now: u64) -> Option<CachedValue> {
+        let path = self.entry_path(key)?;
+        let raw = tokio::fs::read(&path).await.ok()?;
+        let entry: Entry = match serde_json::from_slice(&raw) {
+            Ok(entry) => entry,
+            Err(err) => {
+                log::warn!("ignoring corrupt cache entry {}: {err}", path.display());
+                return None;
+            }
+        };
+        if entry.is_expired(now) {
+            let _ = tokio::fs::remove_file(&path).await;
+            return None;
+        }
+        let value = entry.value.clone();
+        self.state
+            .lock()
+            .expect("cache lock")
+            .insert(key.to_string(), entry, self.cfg.capacity);
+        Some(value)
+    }
+
+    async fn persist(&self, key: &str, entry: &Entry) {
+        let (Some(dir), Some(path)) = (self.scope_dir(), self.entry_path(key)) else {
+            return;
+        };
+        let result = async {
+            tokio::fs::create_dir_all(&dir).await?;
+            let raw = serde_json::to_vec(entry).map_err(std::io::Error::other)?;
+            tokio::fs::write(&path, raw).await
+        }
+        .await;
+        if let Err(err) = result {
+            log::warn!("failed to persist cache entry {}: {err}", path.display());
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
@@ -0,0 +1,293 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::{
+    chat::{ChatMessage, ChatProvider, ChatResponse, Tool},
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    models::ModelsProvider,
+    stt::SpeechToTextProvider,
+    tts::TextToSpeechProvider,
+    LLMProvider,
+};
+
+use super::{CacheConfig, CachedLLM, SemanticCacheConfig, SemanticCacheLLM};
+
+#[derive(Clone, Default)]
+struct CountingProvider {
+    chat_calls: Arc<AtomicUsize>,
+    embedded: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl ChatProvider for CountingProvider {
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        _tools: Option<&[Tool]>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let call = self.chat_calls.fetch_add(1, Ordering::SeqCst);
+        let last = messages
+            .last()
+            .map(|m| m.content.clone())
+            .unwrap_or_default();
+        Ok(Box::new(CompletionResponse {
+            text: format!("{last}#{call}"),
+        }))
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for CountingProvider {
+    async fn complete(&self, req: &CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        self.chat_calls.fetch_add(1, Ordering::SeqCst);
+        Ok(CompletionResponse {
+            text: req.prompt.to_uppercase(),
+        })
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for CountingProvider {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        self.embedded.lock().unwrap().extend(input.iter().cloned());
+        Ok(input.iter().map(|s| vec![s.len() as f32]).collect())
+    }
+}
+
+#[async_trait]
+impl SpeechToTextProvider for CountingProvider {
+    async fn transcribe(&self, _audio: Vec<u8>) -> Result<String, LLMError> {
+        Err(LLMError::ProviderError("unsupported".to_string()))
+    }
+}
+
+impl TextToSpeechProvider for CountingProvider {}
+impl ModelsProvider for CountingProvider {}
+impl LLMProvider for CountingProvider {}
+
+fn cached(provider: &CountingProvider, cfg: CacheConfig) -> CachedLLM {
+    CachedLLM::new(Box::new(provider.clone()), cfg)
+}
+
+fn user(text: &str) -> Vec<ChatMessage> {
+    vec![ChatMessage::user().content(text).build()]
+}
+
+#[tokio::test]
+async fn identical_chat_requests_hit_cache() {
+    let provider = CountingProvider::default();
+    let llm = cached(&provider, CacheConfig::defaults());
+
+    let first = llm.chat(&user("hello")).await.unwrap();
+    let second = llm.chat(&user("hello")).await.unwrap();
+    let other = llm.chat(&user("bye")).await.unwrap();
+
+    assert_eq!(first.text(), second.text());
+    assert_ne!(first.text(), other.text());
+    assert_eq!(provider.chat_calls.load(Ordering::SeqCst), 2);
+    let stats = llm.stats();
+    assert_eq!((stats.hits, stats.misses, stats.entries), (1, 2, 2));
+}
+
+#[tokio::test]
+async fn scope_separates_entries() {
+    let provider = CountingProvider::default();
+    let store_a = CacheConfig {
+        scope: "a".to_string(),
+        ..CacheConfig::defaults()
+    };
+    let llm = cached(&provider, store_a);
+    llm.complete(&CompletionRequest::new("x")).await.unwrap();
+    llm.complete(&CompletionRequest::new("x")).await.unwrap();
+    assert_eq!(provider.chat_calls.load(Ordering::SeqCst), 1);
+
+    let store_b = CacheConfig {
+        scope: "b".to_string(),
+        ..CacheConfig::defaults()
+    };
+    let llm = cached(&provider, store_b);
+    llm.complete(&CompletionRequest::new("x")).await.unwrap();
+    assert_eq!(provider.chat_calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn embed_only_sends_misses() {
+    let provider = CountingProvider::default();
+    let llm = cached(&provider, CacheConfig::defaults());
+
+    llm.embed(vec!["a".into(), "bb".into()]).await.unwrap();
+    let vectors = llm
+        .embed(vec!["bb".into(), "ccc".into(), "a".into()])
+        .await
+        .unwrap();
+
+    assert_eq!(vectors, vec![vec![2.0], vec![3.0], vec![1.0]]);
+    assert_eq!(*provider.embedded.lock().unwrap(), vec!["a", "bb", "ccc"]);
+}
+
+#[tokio::test]
+async fn lru_evicts_least_recently_used() {
+    let provider = CountingProvider::default();
+    let cfg = CacheConfig {
+        capacity: 2,
+        ..CacheConfig::defaults()
+    };
+    let llm = cached(&provider, cfg);
+
+    llm.chat(&user("a")).await.unwrap();
+    llm.chat(&user("b")).await.unwrap();
+    llm.chat(&user("a")).await.unwrap();
+    llm.chat(&user("c")).await.unwrap();
+    llm.chat(&user("a")).await.unwrap();
+    llm.chat(&user("b")).await.unwrap();
+
+    assert_eq!(provider.chat_calls.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn expired_entries_are_refetched() {
+    let provider = CountingProvider::default();
+    let cfg = CacheConfig {
+        ttl: Some(Duration::from_millis(20)),
+        ..CacheConfig::defaults()
+    };
+    let llm = cached(&provider, cfg);
+
+    llm.chat(&user("a")).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(40)).await;
+    llm.chat(&user("a")).await.unwrap();
+
+    assert_eq!(provider.chat_calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn persisted_entries_survive_new_wrapper() {
+    let dir = tempfile::tempdir().unwrap();
+    let provider = CountingProvider::default();
+    let cfg = CacheConfig {
+        persist_dir: Some(dir.path().to_path_buf()),
+        ..CacheConfig::defaults()
+    };
+
+    let first = cached(&provider, cfg.clone())
+        .chat(&user("a"))
+        .await
+        .unwrap();
+    let second = cached(&provider, cfg).chat(&user("a")).await.unwrap();
+
+    assert_eq!(first.text(), second.text());
+    assert_eq!(provider.chat_calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn clear_only_removes_the_entries_of_its_scope() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "keep").unwrap();
+    let provider = CountingProvider::default();
+    let scoped = |scope: &str| CacheConfig {
+        persist_dir: Some(dir.path().to_path_buf()),
+        scope: scope.to_string(),
+        ..CacheConfig::defaults()
+    };
+    let gpt = cached(&provider, scoped("gpt"));
+    gpt.chat(&user("a")).await.unwrap();
+    cached(&provider, scoped("claude"))
+        .chat(&user("a"))
+        .await
+        .unwrap();
+
+    gpt.clear().await;
+    assert!(dir.path().join("notes.txt").exists());
+    cached(&provider, scoped("claude"))
+        .chat(&user("a"))
+        .await
+        .unwrap();
+    assert_eq!(provider.chat_calls.load(Ordering::SeqCst), 2);
+    gpt.chat(&user("a")).await.unwrap();
+    assert_eq!(provider.chat_calls.load(Ordering::SeqCst), 3);
+}
+
+/// Embeds text as `[len, 1]`, so texts of close lengths are similar.
+struct LengthEmbedder;
+
+#[async_trait]
+impl EmbeddingProvider for LengthEmbedder {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        Ok(input.iter().map(|s| vec![s.len() as f32, 1.0]).collect())
+    }
+}
+
+fn semantic(provider: &CountingProvider) -> SemanticCacheLLM {
+    let cfg = SemanticCacheConfig::new(Arc::new(LengthEmbedder)).threshold(0.99);
+    SemanticCacheLLM::new(Box::new(provider.clone()), cfg)
+}
+
+#[tokio::test]
+async fn semantic_cache_serves_similar_prompts() {
+    let provider = CountingProvider::default();
+    let llm = semantic(&provider);
+
+    let first = llm.chat(&user("hello")).await.unwrap();
+    let similar = llm.chat(&user("hello!")).await.unwrap();
+    llm.chat(&user("hi")).await.unwrap();
+
+    assert_eq!(first.text(), similar.text());
+    assert_eq!(provider.chat_calls.load(Ordering::SeqCst), 2);
+    let stats = llm.stats();
+    assert_eq!((stats.hits, stats.misses, stats.entries), (1, 2, 2));
+}
+
+#[tokio::test]
+async fn semantic_cache_is_scoped_by_history_and_tools() {
+    let provider = CountingProvider::default();
+    let llm = semantic(&provider);
+
+    llm.chat(&user("hello")).await.unwrap();
+    let mut with_history = vec![ChatMessage::assistant().content("earlier").build()];
+    with_history.extend(user("hello"));
+    llm.chat(&with_history).await.unwrap();
+
+    let tool = Tool {
+        tool_type: "function".to_string(),
+        function: crate::chat::FunctionTool {
+            name: "noop".to_string(),
+            description: String::new(),
+            parameters: serde_json::json!({"type": "object"}),
+        },
+        cache_control: None,
+    };
+    llm.chat_with_tools(&user("hello"), Some(&[tool]))
+        .await
+        .unwrap();
+
+    assert_eq!(provider.chat_calls.load(Ordering::SeqCst), 3);
+}
+
+struct FailingEmbedder;
+
+#[async_trait]
+impl EmbeddingProvider for FailingEmbedder {
+    async fn embed(&self, _input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        Err(LLMError::HttpError("embedder down".to_string()))
+    }
+}
+
+#[tokio::test]
+async fn semantic_cache_falls_through_when_embedding_fails() {
+    let provider = CountingProvider::default();
+    let cfg = SemanticCacheConfig::new(Arc::new(FailingEmbedder));
+    let llm = SemanticCacheLLM::new(Box::new(provider.clone()), cfg);
+
+    llm.chat(&user("hello")).await.unwrap();
+    llm.chat(&user("hello")).await.unwrap();
+
+    assert_eq!(provider.chat_calls.load(Ordering::SeqCst), 2);
+    assert_eq!(llm.stats().entries, 0);
+}
@@ -0,0 +1,34 @@
+use crate::LLMProvider;
+
+use super::config::CacheConfig;
+use super::store::{CacheStats, CacheStore};
+
+/// Caching wrapper that serves identical chat, completion and embedding requests from a cache.
+///
+/// Streaming, speech and model listing calls are always forwarded to the inner provider.
+pub struct CachedLLM {
+    pub(super) inner: Box<dyn LLMProvider>,
+    pub(super) store: CacheStore,
+}
+
+impl CachedLLM {
+    /// Creates a new caching wrapper around an existing provider.
+    pub fn new(inner: Box<dyn LLMProvider>, cfg: CacheConfig) -> Self {
+        Self {
+            inner,
+            store: CacheStore::new(cfg),
+        }
+    }
+
+    /// Returns hit/miss counters and the number of entries held in memory.
+    pub fn stats(&self) -> CacheStats {
+        self.store.stats()
+    }
+
+    /// Drops every cached entry, including the ones persisted for this scope.
+    pub async fn clear(&self) {
+        self.store.clear().await;
+    }
+}
+
+impl LLMProvider for CachedLLM {}
@@ -1,12 +1,17 @@
 mod multi;
+mod template;
+mod transform;
 
 use crate::{error::LLMError, LLMProvider};
+use serde_json::Value;
 use std::collections::HashMap;
 
 pub use multi::{
-    LLMRegistry, LLMRegistryBuilder, MultiChainStep, MultiChainStepBuilder, MultiChainStepMode,
-    MultiPromptChain,
+    DagChain, DagRunResult, LLMRegistry, LLMRegistryBuilder, MultiChainStep, MultiChainStepBuilder,
+    MultiChainStepMode, MultiPromptChain, StepCondition, StepOutcome, StepStatus,
 };
+pub use template::PromptTemplate;
+pub use transform::{CaptureGroup, Transform, TransformPipeline};
 
 /// Execution mode for a chain step
 #[derive(Debug, Clone)]
@@ -22,7 +27,7 @@ pub enum ChainStepMode {
 pub struct ChainStep {
     /// Unique identifier for this step
     pub id: String,
-    /// Prompt template with {{variable}} placeholders
+    /// Prompt template with {{variable}} placeholders, see [`PromptTemplate`]
     pub template: String,
     /// Execution mode (chat or completion)
     pub mode: ChainStepMode,
@@ -106,6 +111,7 @@ pub struct PromptChain<'a> {
     llm: &'a dyn LLMProvider,
     steps: Vec<ChainStep>,
     memory: HashMap<String, String>,
+    strict: bool,
 }
 
 impl<'a> PromptChain<'a> {
@@ -115,9 +121,17 @@ impl<'a> PromptChain<'a> {
             llm,
             steps: Vec::new(),
             memory: HashMap::new(),
+            strict: false,
         }
     }
 
+    /// Fails the run when a template references an undefined variable or has
+    /// malformed tags, instead of leaving them in the prompt as written
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
     /// Adds a step to the chain
     pub fn step(mut self, step: ChainStep) -> Self {
         self.steps.push(step);
@@ -125,9 +139,20 @@ impl<'a> PromptChain<'a> {
     }
 
     /// Executes all steps in the chain and returns the results
-    pub async fn run(mut self) -> Result<HashMap<String, String>, LLMError> {
+    pub async fn run(self) -> Result<HashMap<String, String>, LLMError> {
+        self.run_with_inputs(HashMap::new()).await
+    }
+
+    /// Executes all steps with input variables available to every template; step
+    /// outputs shadow inputs of the same name
+    pub async fn run_with_inputs(
+        mut self,
+        inputs: HashMap<String, Value>,
+    ) -> Result<HashMap<String, String>, LLMError> {
         for step in &self.steps {
-            let prompt = self.apply_template(&step.template);
+            let vars = template::context(&self.memory, &inputs);
+            let prompt = PromptTemplate::parse_with(step.template.as_str(), self.strict)?
+                .render_with(&vars, self.strict)?;
 
             let response_text = match step.mode {
                 ChainStepMode::Chat => {
@@ -153,14 +178,4 @@ impl<'a> PromptChain<'a> {
 
         Ok(self.memory)
     }
-
-    /// Replaces {{variable}} placeholders in template with values from memory
-    fn apply_template(&self, input: &str) -> String {
-        let mut result = input.to_string();
-        for (k, v) in &self.memory {
-            let pattern = format!("{{{{{k}}}}}");
-            result = result.replace(&pattern, v);
-        }
-        result
-    }
 }
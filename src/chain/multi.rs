@@ -7,6 +7,18 @@ mod step;
 #[path = "multi/chain.rs"]
 mod chain;
 
+#[path = "multi/condition.rs"]
+mod condition;
+
+#[path = "multi/graph.rs"]
+mod graph;
+
 pub use chain::MultiPromptChain;
+pub use condition::StepCondition;
+pub use graph::{DagChain, DagRunResult, StepOutcome, StepStatus};
 pub use registry::{LLMRegistry, LLMRegistryBuilder};
 pub use step::{MultiChainStep, MultiChainStepBuilder, MultiChainStepMode};
+
+#[cfg(test)]
+#[path = "multi/tests.rs"]
+mod tests;
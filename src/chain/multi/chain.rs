@@ -1,20 +1,31 @@
 use std::collections::HashMap;
 
+use serde_json::Value;
+
 use crate::{
-    chat::{ChatMessage, ChatRole, MessageType},
+    chat::{ChatMessage, ChatRole, MessageType, Usage},
     completion::CompletionRequest,
     error::LLMError,
-    LLMProvider,
+    tool_runtime::ToolRuntime,
+    validated_llm::ValidationFailure,
+    FunctionCall, LLMProvider, ToolCall,
 };
 
+use super::super::{template, PromptTemplate};
+use super::graph::DagChain;
 use super::registry::LLMRegistry;
-use super::step::{MultiChainStep, MultiChainStepMode, ResponseTransform};
+use super::step::{MultiChainStep, MultiChainStepMode, TryResponseTransform};
+
+/// Called with the id and output of each step once it completes.
+pub type StepObserver = Box<dyn Fn(&str, &str) + Send + Sync>;
 
 /// The multi-backend chain.
 pub struct MultiPromptChain<'a> {
     registry: &'a LLMRegistry,
     steps: Vec<MultiChainStep>,
     memory: HashMap<String, String>,
+    strict: bool,
+    observer: Option<StepObserver>,
 }
 
 impl<'a> MultiPromptChain<'a> {
@@ -23,67 +34,221 @@ impl<'a> MultiPromptChain<'a> {
             registry,
             steps: vec![],
             memory: HashMap::new(),
+            strict: false,
+            observer: None,
         }
     }
 
+    /// Fails a step whose template references an undefined variable, and the whole
+    /// run before any step when a template has malformed tags, instead of leaving
+    /// them in the prompt as written.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Calls `observer` with the id and output of each step as it completes, e.g. to
+    /// report progress. Not used once the chain is turned into a DAG.
+    pub fn on_step<F>(mut self, observer: F) -> Self
+    where
+        F: Fn(&str, &str) + Send + Sync + 'static,
+    {
+        self.observer = Some(Box::new(observer));
+        self
+    }
+
     /// Adds a step.
     pub fn step(mut self, step: MultiChainStep) -> Self {
         self.steps.push(step);
         self
     }
 
+    /// Seeds template variables available to every step as `{{name}}`.
+    pub fn inputs(mut self, inputs: HashMap<String, String>) -> Self {
+        self.memory.extend(inputs);
+        self
+    }
+
     /// Adds multiple steps at once.
     pub fn chain(mut self, steps: Vec<MultiChainStep>) -> Self {
         self.steps.extend(steps);
         self
     }
 
-    /// Executes all steps.
-    pub async fn run(mut self) -> Result<HashMap<String, String>, LLMError> {
+    /// Executes all steps; steps whose condition is false produce no output.
+    pub async fn run(self) -> Result<HashMap<String, String>, LLMError> {
+        self.run_with_inputs(HashMap::new()).await
+    }
+
+    /// Executes all steps with JSON input variables, such as lists to loop over.
+    /// Step outputs and [`inputs`](Self::inputs) shadow inputs of the same name.
+    pub async fn run_with_inputs(
+        mut self,
+        inputs: HashMap<String, Value>,
+    ) -> Result<HashMap<String, String>, LLMError> {
+        if self.strict {
+            check_templates(&self.steps)?;
+        }
         for step in &self.steps {
-            let response = self.run_step(step).await?;
+            if !condition_holds(step, &self.memory) {
+                continue;
+            }
+            let prompt_text = render_prompt(step, &self.memory, &inputs, self.strict)?;
+            let (response, _) = execute_step(self.registry, step, prompt_text).await?;
+            if let Some(observer) = &self.observer {
+                observer(&step.id, &response);
+            }
             self.memory.insert(step.id.clone(), response);
         }
         Ok(self.memory)
     }
 
-    async fn run_step(&self, step: &MultiChainStep) -> Result<String, LLMError> {
-        let prompt_text = self.replace_template(&step.template);
-        let llm = self.provider(step)?;
-        let response = match step.mode {
-            MultiChainStepMode::Chat => run_chat(llm, prompt_text).await?,
-            MultiChainStepMode::Completion => run_completion(llm, step, prompt_text).await?,
-            MultiChainStepMode::SpeechToText => llm.transcribe_file(&prompt_text).await?,
-        };
-        Ok(apply_transform(response, step.response_transform.as_ref()))
+    /// Turns the chain into a graph where independent steps run concurrently.
+    ///
+    /// A step depends on every step whose id it references as `{{id}}` in its
+    /// template, plus the ones declared with
+    /// [`MultiChainStepBuilder::depends_on`](super::MultiChainStepBuilder::depends_on).
+    /// Fails on duplicate ids, unknown dependencies and cycles.
+    pub fn into_dag(self) -> Result<DagChain<'a>, LLMError> {
+        DagChain::new(self.registry, self.steps, self.memory, self.strict)
     }
+}
 
-    fn provider(&self, step: &MultiChainStep) -> Result<&dyn LLMProvider, LLMError> {
-        self.registry.get(&step.provider_id).ok_or_else(|| {
-            LLMError::InvalidRequest(format!(
-                "No provider with id '{}' found in registry",
-                step.provider_id
-            ))
-        })
+/// Fails on the first step template with malformed tags.
+pub(super) fn check_templates(steps: &[MultiChainStep]) -> Result<(), LLMError> {
+    for step in steps {
+        PromptTemplate::parse(step.template.source())
+            .map_err(|err| LLMError::InvalidRequest(format!("step '{}': {err}", step.id)))?;
     }
+    Ok(())
+}
+
+pub(super) fn render_prompt(
+    step: &MultiChainStep,
+    outputs: &HashMap<String, String>,
+    inputs: &HashMap<String, Value>,
+    strict: bool,
+) -> Result<String, LLMError> {
+    let vars = template::context(outputs, inputs);
+    step.template
+        .render_with(&vars, strict)
+        .map_err(|err| LLMError::InvalidRequest(format!("step '{}': {err}", step.id)))
+}
+
+pub(super) fn condition_holds(step: &MultiChainStep, outputs: &HashMap<String, String>) -> bool {
+    step.condition
+        .as_ref()
+        .is_none_or(|condition| condition.evaluate(outputs))
+}
 
-    fn replace_template(&self, input: &str) -> String {
-        let mut out = input.to_string();
-        for (k, v) in &self.memory {
-            let pattern = format!("{{{{{k}}}}}");
-            out = out.replace(&pattern, v);
+/// Runs a step, re-running it with feedback until its validator passes when it
+/// has one.
+pub(super) async fn execute_step(
+    registry: &LLMRegistry,
+    step: &MultiChainStep,
+    prompt_text: String,
+) -> Result<(String, Option<Usage>), LLMError> {
+    let Some(refinement) = &step.refinement else {
+        return run_once(registry, step, prompt_text).await;
+    };
+
+    let mut prompt = prompt_text.clone();
+    let mut usage = None;
+    let mut failures = Vec::new();
+    for attempt in 1..=refinement.max_attempts {
+        let (output, attempt_usage) = run_once(registry, step, prompt).await?;
+        usage = add_usage(usage, attempt_usage.as_ref());
+        match refinement.validator.validate_text(&output) {
+            Ok(()) => return Ok((output, usage)),
+            Err(reason) => {
+                log::debug!("step '{}' attempt {attempt} rejected: {reason}", step.id);
+                prompt = format!(
+                    "{prompt_text}\n\nYour previous answer was:\n{output}\n\nIt was rejected because: {reason}\nPlease try again and produce a valid response."
+                );
+                failures.push(ValidationFailure {
+                    attempt,
+                    reason,
+                    output,
+                });
+            }
         }
-        out
     }
+    Err(LLMError::ValidationFailed { failures })
 }
 
-async fn run_chat(llm: &dyn LLMProvider, prompt_text: String) -> Result<String, LLMError> {
+/// Adds `usage` to a running total.
+pub(super) fn add_usage(total: Option<Usage>, usage: Option<&Usage>) -> Option<Usage> {
+    let Some(usage) = usage else {
+        return total;
+    };
+    Some(match total {
+        None => usage.clone(),
+        Some(mut total) => {
+            total.prompt_tokens += usage.prompt_tokens;
+            total.completion_tokens += usage.completion_tokens;
+            total.total_tokens += usage.total_tokens;
+            total
+        }
+    })
+}
+
+/// Runs a step once and applies its response transform.
+async fn run_once(
+    registry: &LLMRegistry,
+    step: &MultiChainStep,
+    prompt_text: String,
+) -> Result<(String, Option<Usage>), LLMError> {
+    let (response, usage) = match (&step.mode, &step.tools) {
+        (MultiChainStepMode::Tool, Some(tools)) => match &step.tool_name {
+            Some(name) => (call_tool(tools, name, prompt_text).await?, None),
+            None => run_tools(provider(registry, step)?, tools, prompt_text).await?,
+        },
+        (mode, _) => {
+            let llm = provider(registry, step)?;
+            match mode {
+                MultiChainStepMode::Completion => {
+                    (run_completion(llm, step, prompt_text).await?, None)
+                }
+                MultiChainStepMode::SpeechToText => {
+                    (llm.transcribe_file(&prompt_text).await?, None)
+                }
+                MultiChainStepMode::Classify => run_classify(llm, step, prompt_text).await?,
+                MultiChainStepMode::Embedding => (run_embedding(llm, prompt_text).await?, None),
+                MultiChainStepMode::Chat | MultiChainStepMode::Tool => {
+                    run_chat(llm, prompt_text).await?
+                }
+            }
+        }
+    };
+    Ok((
+        apply_transform(response, step.response_transform.as_ref())?,
+        usage,
+    ))
+}
+
+fn provider<'r>(
+    registry: &'r LLMRegistry,
+    step: &MultiChainStep,
+) -> Result<&'r dyn LLMProvider, LLMError> {
+    registry.get(&step.provider_id).ok_or_else(|| {
+        LLMError::InvalidRequest(format!(
+            "No provider with id '{}' found in registry",
+            step.provider_id
+        ))
+    })
+}
+
+async fn run_chat(
+    llm: &dyn LLMProvider,
+    prompt_text: String,
+) -> Result<(String, Option<Usage>), LLMError> {
     let messages = vec![ChatMessage {
         role: ChatRole::User,
         message_type: MessageType::Text,
         content: prompt_text,
     }];
-    Ok(llm.chat(&messages).await?.text().unwrap_or_default())
+    let response = llm.chat(&messages).await?;
+    Ok((response.text().unwrap_or_default(), response.usage()))
 }
 
 async fn run_completion(
@@ -98,9 +263,108 @@ async fn run_completion(
     Ok(response.text.to_string())
 }
 
-fn apply_transform(response: String, transform: Option<&ResponseTransform>) -> String {
+async fn run_classify(
+    llm: &dyn LLMProvider,
+    step: &MultiChainStep,
+    prompt_text: String,
+) -> Result<(String, Option<Usage>), LLMError> {
+    let prompt = format!(
+        "{prompt_text}\n\nAnswer with exactly one of the following labels and nothing else: {}",
+        step.labels.join(", ")
+    );
+    let (answer, usage) = run_chat(llm, prompt).await?;
+    let label = pick_label(&step.labels, &answer).ok_or_else(|| LLMError::ResponseFormatError {
+        message: format!("step '{}' answered with an unknown label", step.id),
+        raw_response: answer.clone(),
+    })?;
+    Ok((label.to_string(), usage))
+}
+
+/// Matches the answer exactly first, then by the first label it mentions as a
+/// whole word, then by the first label found anywhere in it.
+fn pick_label<'l>(labels: &'l [String], answer: &str) -> Option<&'l str> {
+    let answer = answer
+        .trim()
+        .trim_matches(|c: char| c == '.' || c == '"' || c == '`');
+    let lowered = answer.to_lowercase();
+    let first_mentioned = |find: fn(&str, &str) -> Option<usize>| {
+        labels
+            .iter()
+            .filter_map(|label| Some((find(&lowered, &label.to_lowercase())?, label)))
+            .min_by_key(|(position, _)| *position)
+            .map(|(_, label)| label)
+    };
+    labels
+        .iter()
+        .find(|label| label.eq_ignore_ascii_case(answer))
+        .or_else(|| first_mentioned(find_word))
+        .or_else(|| first_mentioned(|text, label| text.find(label)))
+        .map(String::as_str)
+}
+
+/// Position of `word` in `text` where it is not part of a longer word.
+fn find_word(text: &str, word: &str) -> Option<usize> {
+    let is_word = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+    text.match_indices(word)
+        .map(|(position, _)| position)
+        .find(|&position| {
+            !is_word(text[..position].chars().next_back())
+                && !is_word(text[position + word.len()..].chars().next())
+        })
+}
+
+async fn run_embedding(llm: &dyn LLMProvider, prompt_text: String) -> Result<String, LLMError> {
+    let embedding = llm
+        .embed(vec![prompt_text])
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| LLMError::ProviderError("no embedding returned".to_string()))?;
+    Ok(serde_json::to_string(&embedding)?)
+}
+
+async fn run_tools(
+    llm: &dyn LLMProvider,
+    tools: &ToolRuntime,
+    prompt_text: String,
+) -> Result<(String, Option<Usage>), LLMError> {
+    let messages = vec![ChatMessage::user().content(prompt_text).build()];
+    let outcome = tools.run(llm, messages).await?;
+    Ok((
+        outcome.response.text().unwrap_or_default(),
+        outcome.response.usage(),
+    ))
+}
+
+/// Calls a tool directly with the rendered template as its arguments.
+async fn call_tool(tools: &ToolRuntime, name: &str, arguments: String) -> Result<String, LLMError> {
+    let call = ToolCall {
+        id: format!("call_{name}"),
+        call_type: "function".to_string(),
+        function: FunctionCall {
+            name: name.to_string(),
+            arguments,
+        },
+    };
+    let execution = tools.execute(&call).await;
+    if execution.is_error {
+        return Err(LLMError::ToolConfigError(format!(
+            "tool '{name}' failed: {}",
+            execution.output
+        )));
+    }
+    Ok(match execution.output {
+        Value::String(text) => text,
+        other => other.to_string(),
+    })
+}
+
+fn apply_transform(
+    response: String,
+    transform: Option<&TryResponseTransform>,
+) -> Result<String, LLMError> {
     match transform {
         Some(transform) => transform(response),
-        None => response,
+        None => Ok(response),
     }
 }
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::{error::LLMError, validated_llm::strip_code_fence};
+
+/// Guard deciding whether a step runs, evaluated against the outputs of earlier steps.
+///
+/// A check reading a step without output (not run yet, skipped or failed) is
+/// false. The referenced steps become dependencies of the guarded step in DAG
+/// chains.
+#[derive(Debug, Clone)]
+pub enum StepCondition {
+    /// The output of `step` matches `pattern`.
+    Matches {
+        step: String,
+        pattern: Regex,
+    },
+    /// The output of `step` is JSON whose value at `pointer` equals `expected`.
+    JsonField {
+        step: String,
+        pointer: String,
+        expected: Value,
+    },
+    /// The output of `step` equals `label`, ignoring case and surrounding whitespace.
+    /// Meant for [`MultiChainStepMode::Classify`](super::MultiChainStepMode::Classify) steps.
+    Label {
+        step: String,
+        label: String,
+    },
+    /// The inner condition does not hold.
+    Not(Box<StepCondition>),
+    /// Every condition holds.
+    All(Vec<StepCondition>),
+    /// At least one condition holds.
+    Any(Vec<StepCondition>),
+}
+
+impl StepCondition {
+    /// Runs the step when the output of `step` matches `pattern`.
+    pub fn regex(step: impl Into<String>, pattern: &str) -> Result<Self, LLMError> {
+        let pattern = Regex::new(pattern)
+            .map_err(|e| LLMError::InvalidRequest(format!("invalid condition regex: {e}")))?;
+        Ok(Self::Matches {
+            step: step.into(),
+            pattern,
+        })
+    }
+
+    /// Runs the step when the JSON output of `step` has `expected` at `field`.
+    ///
+    /// `field` is a JSON pointer (`/verdict/ok`) or a dotted path (`verdict.ok`).
+    /// A surrounding Markdown code fence in the output is ignored.
+    pub fn json_field(step: impl Into<String>, field: &str, expected: impl Into<Value>) -> Self {
+        let pointer = if field.starts_with('/') || field.is_empty() {
+            field.to_string()
+        } else {
+            format!("/{}", field.replace('.', "/"))
+        };
+        Self::JsonField {
+            step: step.into(),
+            pointer,
+            expected: expected.into(),
+        }
+    }
+
+    /// Runs the step when the classifier step `step` picked `label`.
+    pub fn label(step: impl Into<String>, label: impl Into<String>) -> Self {
+        Self::Label {
+            step: step.into(),
+            label: label.into(),
+        }
+    }
+
+    /// Runs the step when both conditions hold.
+    pub fn and(self, other: StepCondition) -> Self {
+        match self {
+            Self::All(mut all) => {
+                all.push(other);
+                Self::All(all)
+            }
+            first => Self::All(vec![first, other]),
+        }
+    }
+
+    /// Runs the step when either condition holds.
+    pub fn or(self, other: StepCondition) -> Self {
+        match self {
+            Self::Any(mut any) => {
+                any.push(other);
+                Self::Any(any)
+            }
+            first => Self::Any(vec![first, other]),
+        }
+    }
+
+    /// Ids of the steps the condition reads.
+    pub(super) fn steps(&self) -> Vec<&str> {
+        match self {
+            Self::Matches { step, .. }
+            | Self::JsonField { step, .. }
+            | Self::Label { step, .. } => {
+                vec![step.as_str()]
+            }
+            Self::Not(inner) => inner.steps(),
+            Self::All(all) | Self::Any(all) => all.iter().flat_map(Self::steps).collect(),
+        }
+    }
+
+    pub(super) fn evaluate(&self, outputs: &HashMap<String, String>) -> bool {
+        match self {
+            Self::Matches { step, pattern } => outputs
+                .get(step)
+                .is_some_and(|output| pattern.is_match(output)),
+            Self::JsonField {
+                step,
+                pointer,
+                expected,
+            } => outputs
+                .get(step)
+                .and_then(|output| serde_json::from_str::<Value>(strip_code_fence(output)).ok())
+                .is_some_and(|value| value.pointer(pointer) == Some(expected)),
+            Self::Label { step, label } => outputs
+                .get(step)
+                .is_some_and(|output| output.trim().eq_ignore_ascii_case(label.trim())),
+            Self::Not(inner) => !inner.evaluate(outputs),
+            Self::All(all) => all.iter().all(|c| c.evaluate(outputs)),
+            Self::Any(any) => any.iter().any(|c| c.evaluate(outputs)),
+        }
+    }
+}
+
+/// Runs the step when the condition does not hold, e.g.
+/// `!StepCondition::label("topic", "spam")`.
+impl std::ops::Not for StepCondition {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self::Not(Box::new(self))
+    }
+}
@@ -0,0 +1,361 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use futures::stream::{FuturesUnordered, StreamExt};
+use serde::Serialize;
+use serde_json::Value;
+use tokio::time::Instant;
+
+use crate::{chat::Usage, error::LLMError};
+
+use super::chain::{add_usage, check_templates, condition_holds, execute_step, render_prompt};
+use super::registry::LLMRegistry;
+use super::step::MultiChainStep;
+
+/// Outcome of a step in a DAG run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    /// Ran and produced an output.
+    Succeeded,
+    /// Ran and returned an error.
+    Failed,
+    /// Not run because a dependency failed or was skipped.
+    Skipped,
+    /// Not run because its condition was false; dependents still run without its
+    /// output.
+    Bypassed,
+}
+
+/// Result, timing and usage of a single step.
+#[derive(Debug, Clone, Serialize)]
+pub struct StepOutcome {
+    /// Id of the step.
+    pub id: String,
+    /// Provider of the step, or the tool name for a step calling a tool directly.
+    pub provider_id: String,
+    /// How the step ended.
+    pub status: StepStatus,
+    /// Output of a succeeded step.
+    pub output: Option<String>,
+    /// Why the step failed or was skipped.
+    pub error: Option<String>,
+    /// Time between the start of the run and the start of the step.
+    pub started_after: Duration,
+    /// Time the step ran; zero for steps that did not run.
+    pub duration: Duration,
+    /// Token usage reported by the provider of the step.
+    pub usage: Option<Usage>,
+}
+
+/// Results of a DAG run, in step declaration order.
+#[derive(Debug, Clone, Serialize)]
+pub struct DagRunResult {
+    /// Outcome of every step, in declaration order.
+    pub steps: Vec<StepOutcome>,
+    /// Time the whole run took.
+    pub duration: Duration,
+}
+
+impl DagRunResult {
+    /// Whether every step succeeded or was bypassed by its condition.
+    pub fn is_success(&self) -> bool {
+        self.steps
+            .iter()
+            .all(|s| matches!(s.status, StepStatus::Succeeded | StepStatus::Bypassed))
+    }
+
+    /// Outcome of the step with id `id`.
+    pub fn get(&self, id: &str) -> Option<&StepOutcome> {
+        self.steps.iter().find(|s| s.id == id)
+    }
+
+    /// Outputs of the successful steps, keyed by step id.
+    pub fn outputs(&self) -> HashMap<String, String> {
+        self.steps
+            .iter()
+            .filter_map(|s| Some((s.id.clone(), s.output.clone()?)))
+            .collect()
+    }
+
+    /// Token usage summed over the steps that reported it.
+    pub fn total_usage(&self) -> Option<Usage> {
+        self.steps
+            .iter()
+            .fold(None, |total, s| add_usage(total, s.usage.as_ref()))
+    }
+
+    /// Converts failures into an error, keeping the outputs otherwise.
+    pub fn into_outputs(self) -> Result<HashMap<String, String>, LLMError> {
+        match self.steps.iter().find(|s| s.status == StepStatus::Failed) {
+            Some(failed) => Err(LLMError::Generic(format!(
+                "step '{}' failed: {}",
+                failed.id,
+                failed.error.as_deref().unwrap_or_default()
+            ))),
+            None => Ok(self.outputs()),
+        }
+    }
+}
+
+/// A validated chain graph, created with [`super::MultiPromptChain::into_dag`].
+///
+/// Steps start as soon as all their dependencies finished, so independent branches
+/// run concurrently. A failing step does not stop the run; the steps depending on it
+/// are skipped. Steps whose condition is false are bypassed.
+pub struct DagChain<'a> {
+    registry: &'a LLMRegistry,
+    steps: Vec<MultiChainStep>,
+    dependencies: Vec<Vec<usize>>,
+    outputs: HashMap<String, String>,
+    strict: bool,
+    max_concurrency: usize,
+}
+
+impl<'a> DagChain<'a> {
+    pub(super) fn new(
+        registry: &'a LLMRegistry,
+        steps: Vec<MultiChainStep>,
+        outputs: HashMap<String, String>,
+        strict: bool,
+    ) -> Result<Self, LLMError> {
+        if strict {
+            check_templates(&steps)?;
+        }
+        let dependencies = resolve_dependencies(&steps)?;
+        detect_cycle(&steps, &dependencies)?;
+        Ok(Self {
+            registry,
+            steps,
+            dependencies,
+            outputs,
+            strict,
+            max_concurrency: usize::MAX,
+        })
+    }
+
+    /// Limits how many steps run at the same time.
+    pub fn max_concurrency(mut self, max: usize) -> Self {
+        self.max_concurrency = max.max(1);
+        self
+    }
+
+    /// Ids of the steps each step depends on.
+    pub fn dependencies(&self) -> HashMap<&str, Vec<&str>> {
+        self.steps
+            .iter()
+            .zip(&self.dependencies)
+            .map(|(step, deps)| {
+                let deps = deps.iter().map(|&d| self.steps[d].id.as_str()).collect();
+                (step.id.as_str(), deps)
+            })
+            .collect()
+    }
+
+    /// Runs the graph without input variables.
+    pub async fn run(self) -> DagRunResult {
+        self.run_with_inputs(HashMap::new()).await
+    }
+
+    /// Runs the graph with JSON input variables available to every template.
+    pub async fn run_with_inputs(self, inputs: HashMap<String, Value>) -> DagRunResult {
+        let started = Instant::now();
+        let mut outcomes: Vec<Option<StepOutcome>> = vec![None; self.steps.len()];
+        let mut memory = self.outputs.clone();
+        let mut launched = vec![false; self.steps.len()];
+        let mut running = FuturesUnordered::new();
+
+        loop {
+            self.skip_blocked(&mut outcomes);
+            let ready: Vec<usize> = (0..self.steps.len())
+                .filter(|&i| !launched[i] && outcomes[i].is_none())
+                .filter(|&i| self.dependencies[i].iter().all(|&d| finished(&outcomes[d])))
+                .collect();
+            let (ready, bypassed): (Vec<usize>, Vec<usize>) = ready
+                .into_iter()
+                .partition(|&i| condition_holds(&self.steps[i], &memory));
+            if !bypassed.is_empty() {
+                for index in bypassed {
+                    outcomes[index] = Some(self.not_run(index, StepStatus::Bypassed, None));
+                }
+                continue;
+            }
+            for index in ready {
+                if running.len() >= self.max_concurrency {
+                    break;
+                }
+                launched[index] = true;
+                let step = &self.steps[index];
+                let prompt = render_prompt(step, &memory, &inputs, self.strict);
+                let registry = self.registry;
+                running.push(async move {
+                    let step_started = Instant::now();
+                    let result = match prompt {
+                        Ok(prompt) => execute_step(registry, step, prompt).await,
+                        Err(err) => Err(err),
+                    };
+                    (index, step_started, result)
+                });
+            }
+
+            let Some((index, step_started, result)) = running.next().await else {
+                break;
+            };
+            let step = &self.steps[index];
+            let mut outcome = StepOutcome {
+                id: step.id.clone(),
+                provider_id: step.reported_provider().to_string(),
+                status: StepStatus::Succeeded,
+                output: None,
+                error: None,
+                started_after: step_started.duration_since(started),
+                duration: step_started.elapsed(),
+                usage: None,
+            };
+            match result {
+                Ok((output, usage)) => {
+                    memory.insert(step.id.clone(), output.clone());
+                    outcome.output = Some(output);
+                    outcome.usage = usage;
+                }
+                Err(err) => {
+                    outcome.status = StepStatus::Failed;
+                    outcome.error = Some(err.to_string());
+                }
+            }
+            outcomes[index] = Some(outcome);
+        }
+
+        DagRunResult {
+            steps: outcomes.into_iter().flatten().collect(),
+            duration: started.elapsed(),
+        }
+    }
+
+    /// Marks steps whose dependencies failed or were skipped, transitively.
+    fn skip_blocked(&self, outcomes: &mut [Option<StepOutcome>]) {
+        loop {
+            let blocked: Vec<(usize, String)> = (0..self.steps.len())
+                .filter(|&i| outcomes[i].is_none())
+                .filter_map(|i| {
+                    self.dependencies[i]
+                        .iter()
+                        .find_map(|&d| match &outcomes[d] {
+                            Some(o) if !finished(&outcomes[d]) => Some((i, o.id.clone())),
+                            _ => None,
+                        })
+                })
+                .collect();
+            if blocked.is_empty() {
+                return;
+            }
+            for (index, dependency) in blocked {
+                let error = format!("dependency '{dependency}' did not succeed");
+                outcomes[index] = Some(self.not_run(index, StepStatus::Skipped, Some(error)));
+            }
+        }
+    }
+
+    fn not_run(&self, index: usize, status: StepStatus, error: Option<String>) -> StepOutcome {
+        let step = &self.steps[index];
+        StepOutcome {
+            id: step.id.clone(),
+            provider_id: step.reported_provider().to_string(),
+            status,
+            output: None,
+            error,
+            started_after: Duration::ZERO,
+            duration: Duration::ZERO,
+            usage: None,
+        }
+    }
+}
+
+/// Whether a dependency allows its dependents to run.
+fn finished(outcome: &Option<StepOutcome>) -> bool {
+    matches!(
+        outcome,
+        Some(o) if matches!(o.status, StepStatus::Succeeded | StepStatus::Bypassed)
+    )
+}
+
+/// Maps template references, condition inputs and declared dependencies to step
+/// indices.
+fn resolve_dependencies(steps: &[MultiChainStep]) -> Result<Vec<Vec<usize>>, LLMError> {
+    let mut index = HashMap::new();
+    for (i, step) in steps.iter().enumerate() {
+        if index.insert(step.id.as_str(), i).is_some() {
+            return Err(LLMError::InvalidRequest(format!(
+                "duplicate step id '{}'",
+                step.id
+            )));
+        }
+    }
+
+    steps
+        .iter()
+        .map(|step| {
+            let mut deps = HashSet::new();
+            for path in step.template.variables() {
+                let root = path.split('.').next().unwrap_or(path);
+                if let Some(&dep) = index.get(path).or_else(|| index.get(root)) {
+                    deps.insert(dep);
+                }
+            }
+            let condition_steps = step.condition.iter().flat_map(|c| c.steps());
+            for declared in step
+                .depends_on
+                .iter()
+                .map(String::as_str)
+                .chain(condition_steps)
+            {
+                let dep = index.get(declared).ok_or_else(|| {
+                    LLMError::InvalidRequest(format!(
+                        "step '{}' depends on unknown step '{declared}'",
+                        step.id
+                    ))
+                })?;
+                deps.insert(*dep);
+            }
+            let mut deps: Vec<usize> = deps.into_iter().collect();
+            deps.sort_unstable();
+            Ok(deps)
+        })
+        .collect()
+}
+
+/// Kahn's algorithm; the steps left over form at least one cycle.
+fn detect_cycle(steps: &[MultiChainStep], dependencies: &[Vec<usize>]) -> Result<(), LLMError> {
+    let mut remaining: Vec<usize> = dependencies.iter().map(Vec::len).collect();
+    let mut done = vec![false; steps.len()];
+    let mut progressed = true;
+    while progressed {
+        progressed = false;
+        for i in 0..steps.len() {
+            if !done[i] && remaining[i] == 0 {
+                done[i] = true;
+                progressed = true;
+                for (j, deps) in dependencies.iter().enumerate() {
+                    if deps.contains(&i) {
+                        remaining[j] -= 1;
+                    }
+                }
+            }
+        }
+    }
+
+    let cyclic: Vec<&str> = steps
+        .iter()
+        .zip(&done)
+        .filter(|(_, done)| !**done)
+        .map(|(step, _)| step.id.as_str())
+        .collect();
+    if cyclic.is_empty() {
+        Ok(())
+    } else {
+        Err(LLMError::InvalidRequest(format!(
+            "chain steps form a dependency cycle: {}",
+            cyclic.join(", ")
+        )))
+    }
+}
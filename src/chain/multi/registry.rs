@@ -97,6 +97,11 @@ impl ProviderId {
         }
         Ok(Self(id))
     }
+
+    /// The id as registered.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
 }
 
 impl Borrow<str> for ProviderId {
@@ -1,25 +1,48 @@
-use crate::error::LLMError;
+use crate::{error::LLMError, tool_runtime::ToolRuntime, validated_llm::Validator};
 
-/// Response transformation function.
-pub type ResponseTransform = Box<dyn Fn(String) -> String + Send + Sync>;
+use super::super::PromptTemplate;
+use super::condition::StepCondition;
 
-/// Execution mode for a step: Chat, Completion, or SpeechToText.
+/// Response transformation function; an error fails the step.
+pub type TryResponseTransform = Box<dyn Fn(String) -> Result<String, LLMError> + Send + Sync>;
+
+/// Execution mode for a step.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum MultiChainStepMode {
     Chat,
     Completion,
     SpeechToText,
+    /// Asks the provider to pick one of the step labels; the output is the label.
+    Classify,
+    /// Embeds the rendered template; the output is the vector as a JSON array.
+    Embedding,
+    /// Runs the step tools: a direct call of one tool with the rendered template as
+    /// JSON arguments, or a tool-calling loop on the provider.
+    Tool,
+}
+
+/// Re-runs a step until its output passes a validator.
+pub(crate) struct Refinement {
+    pub(crate) validator: Validator,
+    pub(crate) max_attempts: usize,
 }
 
 /// Multi-backend chain step.
 pub struct MultiChainStep {
     pub(crate) provider_id: String,
     pub(crate) id: String,
-    pub(crate) template: String,
+    pub(crate) template: PromptTemplate,
     pub(crate) mode: MultiChainStepMode,
     pub(crate) temperature: Option<f32>,
     pub(crate) max_tokens: Option<u32>,
-    pub(crate) response_transform: Option<ResponseTransform>,
+    pub(crate) response_transform: Option<TryResponseTransform>,
+    pub(crate) depends_on: Vec<String>,
+    pub(crate) condition: Option<StepCondition>,
+    pub(crate) labels: Vec<String>,
+    pub(crate) refinement: Option<Refinement>,
+    pub(crate) tools: Option<ToolRuntime>,
+    pub(crate) tool_name: Option<String>,
 }
 
 /// Builder for MultiChainStep (Stripe-style).
@@ -31,7 +54,13 @@ pub struct MultiChainStepBuilder {
     temperature: Option<f32>,
     top_p: Option<f32>,
     max_tokens: Option<u32>,
-    response_transform: Option<ResponseTransform>,
+    response_transform: Option<TryResponseTransform>,
+    depends_on: Vec<String>,
+    condition: Option<StepCondition>,
+    labels: Vec<String>,
+    refinement: Option<Refinement>,
+    tools: Option<ToolRuntime>,
+    tool_name: Option<String>,
 }
 
 impl MultiChainStepBuilder {
@@ -45,6 +74,12 @@ impl MultiChainStepBuilder {
             top_p: None,
             max_tokens: None,
             response_transform: None,
+            depends_on: Vec::new(),
+            condition: None,
+            labels: Vec::new(),
+            refinement: None,
+            tools: None,
+            tool_name: None,
         }
     }
 
@@ -60,7 +95,7 @@ impl MultiChainStepBuilder {
         self
     }
 
-    /// The prompt or template (e.g. "2 * 4 = ?").
+    /// The prompt or template (e.g. "2 * 4 = ?"), see [`PromptTemplate`] for the syntax.
     pub fn template(mut self, tmpl: impl Into<String>) -> Self {
         self.template = Some(tmpl.into());
         self
@@ -81,9 +116,66 @@ impl MultiChainStepBuilder {
         self
     }
 
+    /// Declares a dependency on another step, in addition to the ones referenced
+    /// in the template. Only used by DAG chains.
+    pub fn depends_on(mut self, step_id: impl Into<String>) -> Self {
+        self.depends_on.push(step_id.into());
+        self
+    }
+
+    /// Runs the step only when `condition` holds; otherwise it is skipped and
+    /// produces no output.
+    pub fn when(mut self, condition: StepCondition) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    /// Labels a [`MultiChainStepMode::Classify`] step chooses from.
+    pub fn labels<I, S>(mut self, labels: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.labels = labels.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Re-runs the step with the rejection reason as feedback until `validator`
+    /// accepts the output, at most `max_attempts` times.
+    pub fn repeat_until(mut self, validator: Validator, max_attempts: usize) -> Self {
+        self.refinement = Some(Refinement {
+            validator,
+            max_attempts,
+        });
+        self
+    }
+
+    /// Tools available to a [`MultiChainStepMode::Tool`] step.
+    pub fn tools(mut self, runtime: ToolRuntime) -> Self {
+        self.tools = Some(runtime);
+        self
+    }
+
+    /// Makes a [`MultiChainStepMode::Tool`] step call `name` directly, with the
+    /// rendered template as JSON arguments, instead of asking a provider.
+    pub fn call_tool(mut self, name: impl Into<String>) -> Self {
+        self.tool_name = Some(name.into());
+        self
+    }
+
     pub fn response_transform<F>(mut self, func: F) -> Self
     where
         F: Fn(String) -> String + Send + Sync + 'static,
+    {
+        self.response_transform = Some(Box::new(move |response| Ok(func(response))));
+        self
+    }
+
+    /// Like [`response_transform`](Self::response_transform), but an error fails the
+    /// step, e.g. when the expected JSON is missing from the output.
+    pub fn try_response_transform<F>(mut self, func: F) -> Self
+    where
+        F: Fn(String) -> Result<String, LLMError> + Send + Sync + 'static,
     {
         self.response_transform = Some(Box::new(func));
         self
@@ -91,9 +183,30 @@ impl MultiChainStepBuilder {
 
     /// Builds the step.
     pub fn build(self) -> Result<MultiChainStep, LLMError> {
-        let provider_id = require_field(self.provider_id, "provider_id")?;
         let id = require_field(self.id, "step id")?;
-        let template = require_field(self.template, "template")?;
+        let template = PromptTemplate::parse_lenient(require_field(self.template, "template")?);
+        let provider_id = match (&self.mode, &self.tool_name) {
+            (MultiChainStepMode::Tool, Some(_)) => self.provider_id.unwrap_or_default(),
+            _ => require_field(self.provider_id, "provider_id")?,
+        };
+        match self.mode {
+            MultiChainStepMode::Classify if self.labels.is_empty() => {
+                return Err(LLMError::InvalidRequest(format!(
+                    "classify step '{id}' has no labels"
+                )));
+            }
+            MultiChainStepMode::Tool if self.tools.is_none() => {
+                return Err(LLMError::InvalidRequest(format!(
+                    "tool step '{id}' has no tools"
+                )));
+            }
+            _ => {}
+        }
+        if matches!(&self.refinement, Some(r) if r.max_attempts == 0) {
+            return Err(LLMError::InvalidRequest(format!(
+                "step '{id}' must allow at least one attempt"
+            )));
+        }
 
         Ok(MultiChainStep {
             provider_id,
@@ -103,10 +216,27 @@ impl MultiChainStepBuilder {
             temperature: self.temperature,
             max_tokens: self.max_tokens,
             response_transform: self.response_transform,
+            depends_on: self.depends_on,
+            condition: self.condition,
+            labels: self.labels,
+            refinement: self.refinement,
+            tools: self.tools,
+            tool_name: self.tool_name,
         })
     }
 }
 
+impl MultiChainStep {
+    /// Provider reported for the step; the tool name for a step calling a tool
+    /// directly.
+    pub(crate) fn reported_provider(&self) -> &str {
+        match (&self.mode, &self.tool_name) {
+            (MultiChainStepMode::Tool, Some(name)) => name,
+            _ => &self.provider_id,
+        }
+    }
+}
+
 fn require_field(value: Option<String>, name: &str) -> Result<String, LLMError> {
     value.ok_or_else(|| LLMError::InvalidRequest(format!("No {name} set")))
 }
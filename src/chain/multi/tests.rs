@@ -0,0 +1,580 @@
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::{
+    builder::FunctionBuilder,
+    chain::{
+        LLMRegistryBuilder, MultiChainStepBuilder, MultiChainStepMode, StepCondition, StepStatus,
+    },
+    chat::Usage,
+    error::LLMError,
+    testing::{MockLLM, MockResponse},
+    tool_runtime::ToolRuntime,
+    validated_llm::Validator,
+    FunctionCall, ToolCall,
+};
+
+use super::{LLMRegistry, MultiChainStep, MultiPromptChain};
+
+fn step(id: &str, provider: &str, template: &str) -> MultiChainStepBuilder {
+    MultiChainStepBuilder::new(MultiChainStepMode::Chat)
+        .id(id)
+        .provider_id(provider)
+        .template(template)
+}
+
+fn build(builder: MultiChainStepBuilder) -> MultiChainStep {
+    builder.build().unwrap()
+}
+
+fn usage(prompt: u32, completion: u32) -> Usage {
+    Usage {
+        prompt_tokens: prompt,
+        completion_tokens: completion,
+        total_tokens: prompt + completion,
+        completion_tokens_details: None,
+        prompt_tokens_details: None,
+    }
+}
+
+/// Used with paused time, where only this latency moves the clock.
+fn slow_mock(text: &str) -> MockLLM {
+    MockLLM::new()
+        .with_latency(Duration::from_millis(100))
+        .with_fallback(MockResponse::text(text))
+}
+
+fn registry(providers: Vec<(&str, MockLLM)>) -> LLMRegistry {
+    providers
+        .into_iter()
+        .fold(LLMRegistryBuilder::new(), |builder, (id, mock)| {
+            builder.register(id, Box::new(mock))
+        })
+        .build()
+}
+
+#[tokio::test(start_paused = true)]
+async fn independent_steps_run_concurrently() {
+    let registry = registry(vec![
+        ("a", slow_mock("from a")),
+        ("b", slow_mock("from b")),
+        (
+            "c",
+            MockLLM::new().with_fallback(MockResponse::text("merged")),
+        ),
+    ]);
+    let result = MultiPromptChain::new(&registry)
+        .step(build(step("left", "a", "left")))
+        .step(build(step("right", "b", "right")))
+        .step(build(step("merge", "c", "{{left}} + {{right}}")))
+        .into_dag()
+        .unwrap()
+        .run()
+        .await;
+
+    assert!(result.is_success());
+    assert!(result.duration < Duration::from_millis(200));
+    let merge = result.get("merge").unwrap();
+    assert!(merge.started_after >= Duration::from_millis(100));
+    assert_eq!(merge.output.as_deref(), Some("merged"));
+    assert_eq!(result.outputs()["left"], "from a");
+}
+
+#[tokio::test]
+async fn dependency_outputs_are_rendered_into_templates() {
+    let upstream = MockLLM::new().with_fallback(MockResponse::text("42"));
+    let downstream = MockLLM::new().with_fallback(MockResponse::text("ok"));
+    let probe = downstream.clone();
+    let registry = registry(vec![("up", upstream), ("down", downstream)]);
+
+    let result = MultiPromptChain::new(&registry)
+        .step(build(step("answer", "down", "value is {{value}}")))
+        .step(build(step("value", "up", "compute")))
+        .into_dag()
+        .unwrap()
+        .run()
+        .await;
+
+    assert!(result.is_success());
+    probe.assert_last_user_message_contains("value is 42");
+    assert_eq!(result.steps[0].id, "answer");
+}
+
+#[tokio::test(start_paused = true)]
+async fn explicit_dependencies_order_steps() {
+    let registry = registry(vec![
+        ("slow", slow_mock("first")),
+        (
+            "fast",
+            MockLLM::new().with_fallback(MockResponse::text("second")),
+        ),
+    ]);
+    let dag = MultiPromptChain::new(&registry)
+        .step(build(step("first", "slow", "go")))
+        .step(build(step("second", "fast", "then").depends_on("first")))
+        .into_dag()
+        .unwrap();
+    assert_eq!(dag.dependencies()["second"], vec!["first"]);
+
+    let result = dag.run().await;
+    let second = result.get("second").unwrap();
+    assert!(second.started_after >= Duration::from_millis(100));
+}
+
+#[test]
+fn cycles_are_rejected() {
+    let registry = registry(vec![("a", MockLLM::new())]);
+    let err = MultiPromptChain::new(&registry)
+        .step(build(step("x", "a", "{{y}}")))
+        .step(build(step("y", "a", "{{x}}")))
+        .step(build(step("z", "a", "standalone")))
+        .into_dag()
+        .err()
+        .unwrap();
+    assert!(matches!(err, LLMError::InvalidRequest(ref msg) if msg.contains("x, y")));
+}
+
+#[test]
+fn unknown_and_duplicate_steps_are_rejected() {
+    let registry = registry(vec![("a", MockLLM::new())]);
+    let unknown = MultiPromptChain::new(&registry)
+        .step(build(step("x", "a", "go").depends_on("missing")))
+        .into_dag();
+    assert!(unknown.is_err());
+
+    let duplicate = MultiPromptChain::new(&registry)
+        .step(build(step("x", "a", "go")))
+        .step(build(step("x", "a", "again")))
+        .into_dag();
+    assert!(duplicate.is_err());
+}
+
+#[tokio::test]
+async fn failures_skip_dependents_only() {
+    let failing = MockLLM::new();
+    failing.push_error(LLMError::ProviderError("boom".into()));
+    let registry = registry(vec![
+        ("bad", failing),
+        (
+            "good",
+            MockLLM::new().with_fallback(MockResponse::text("fine")),
+        ),
+    ]);
+
+    let result = MultiPromptChain::new(&registry)
+        .step(build(step("broken", "bad", "go")))
+        .step(build(step("child", "good", "{{broken}}")))
+        .step(build(step("grandchild", "good", "{{child}}")))
+        .step(build(step("other", "good", "independent")))
+        .into_dag()
+        .unwrap()
+        .run()
+        .await;
+
+    assert!(!result.is_success());
+    assert_eq!(result.get("broken").unwrap().status, StepStatus::Failed);
+    assert!(result
+        .get("broken")
+        .unwrap()
+        .error
+        .as_deref()
+        .unwrap()
+        .contains("boom"));
+    assert_eq!(result.get("child").unwrap().status, StepStatus::Skipped);
+    assert_eq!(
+        result.get("grandchild").unwrap().status,
+        StepStatus::Skipped
+    );
+    assert_eq!(result.get("other").unwrap().status, StepStatus::Succeeded);
+    assert!(result.into_outputs().is_err());
+}
+
+#[tokio::test]
+async fn usage_is_reported_per_step_and_in_total() {
+    let registry = registry(vec![
+        (
+            "a",
+            MockLLM::new().with_fallback(MockResponse::text("x").with_usage(usage(10, 5))),
+        ),
+        (
+            "b",
+            MockLLM::new().with_fallback(MockResponse::text("y").with_usage(usage(3, 2))),
+        ),
+    ]);
+
+    let result = MultiPromptChain::new(&registry)
+        .step(build(step("one", "a", "go")))
+        .step(build(step("two", "b", "{{one}}")))
+        .into_dag()
+        .unwrap()
+        .max_concurrency(1)
+        .run()
+        .await;
+
+    assert_eq!(
+        result
+            .get("one")
+            .unwrap()
+            .usage
+            .as_ref()
+            .unwrap()
+            .total_tokens,
+        15
+    );
+    let total = result.total_usage().unwrap();
+    assert_eq!(total.prompt_tokens, 13);
+    assert_eq!(total.total_tokens, 20);
+}
+
+fn text_mock(text: &str) -> MockLLM {
+    MockLLM::new().with_fallback(MockResponse::text(text))
+}
+
+#[tokio::test]
+async fn classifier_routes_to_matching_branch() {
+    let classifier = MockLLM::new();
+    classifier.push_text("Label: Billing.");
+    let probe = classifier.clone();
+    let registry = registry(vec![
+        ("classifier", classifier),
+        ("worker", text_mock("handled")),
+    ]);
+
+    let outputs = MultiPromptChain::new(&registry)
+        .step(build(
+            MultiChainStepBuilder::new(MultiChainStepMode::Classify)
+                .id("topic")
+                .provider_id("classifier")
+                .template("Classify: my invoice is wrong")
+                .labels(["billing", "technical"]),
+        ))
+        .step(build(
+            step("billing", "worker", "refund").when(StepCondition::label("topic", "billing")),
+        ))
+        .step(build(
+            step("technical", "worker", "debug").when(StepCondition::label("topic", "technical")),
+        ))
+        .run()
+        .await
+        .unwrap();
+
+    probe.assert_last_user_message_contains("billing, technical");
+    assert_eq!(outputs["topic"], "billing");
+    assert_eq!(outputs["billing"], "handled");
+    assert!(!outputs.contains_key("technical"));
+}
+
+#[tokio::test]
+async fn classifier_prefers_labels_mentioned_as_whole_words() {
+    let classifier = MockLLM::new();
+    classifier.push_text("Not sure, but yes.");
+    let registry = registry(vec![("classifier", classifier)]);
+
+    let outputs = MultiPromptChain::new(&registry)
+        .step(build(
+            MultiChainStepBuilder::new(MultiChainStepMode::Classify)
+                .id("answer")
+                .provider_id("classifier")
+                .template("Is it done?")
+                .labels(["yes", "no"]),
+        ))
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(outputs["answer"], "yes");
+}
+
+#[tokio::test]
+async fn regex_and_json_conditions_gate_steps() {
+    let registry = registry(vec![
+        (
+            "judge",
+            text_mock("```json\n{\"verdict\": {\"ok\": false}}\n```"),
+        ),
+        ("worker", text_mock("done")),
+    ]);
+
+    let outputs = MultiPromptChain::new(&registry)
+        .step(build(step("review", "judge", "review")))
+        .step(build(step("fix", "worker", "fix it").when(
+            StepCondition::json_field("review", "verdict.ok", false),
+        )))
+        .step(build(step("ship", "worker", "ship it").when(
+            StepCondition::regex("review", r#""ok":\s*true"#).unwrap(),
+        )))
+        .step(build(
+            step("notify", "worker", "notify").when(
+                (!StepCondition::json_field("review", "/verdict/ok", true))
+                    .and(!StepCondition::label("missing", "x")),
+            ),
+        ))
+        .run()
+        .await
+        .unwrap();
+
+    assert!(outputs.contains_key("fix"));
+    assert!(!outputs.contains_key("ship"));
+    assert!(outputs.contains_key("notify"));
+}
+
+#[tokio::test]
+async fn bypassed_steps_do_not_block_dependents_in_dag() {
+    let registry = registry(vec![("a", text_mock("no"))]);
+
+    let result = MultiPromptChain::new(&registry)
+        .step(build(step("gate", "a", "go")))
+        .step(build(
+            step("branch", "a", "branch").when(StepCondition::regex("gate", "^yes").unwrap()),
+        ))
+        .step(build(step("after", "a", "{{branch}}")))
+        .into_dag()
+        .unwrap()
+        .run()
+        .await;
+
+    assert!(result.is_success());
+    assert_eq!(result.get("branch").unwrap().status, StepStatus::Bypassed);
+    assert_eq!(result.get("after").unwrap().status, StepStatus::Succeeded);
+}
+
+#[tokio::test]
+async fn repeat_until_refines_with_feedback() {
+    let writer = MockLLM::new();
+    writer.push_text("far too long answer");
+    writer.push_text("short");
+    let probe = writer.clone();
+    let registry = registry(vec![("writer", writer)]);
+
+    let outputs = MultiPromptChain::new(&registry)
+        .step(build(
+            step("summary", "writer", "summarize").repeat_until(Validator::max_length(10), 3),
+        ))
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(outputs["summary"], "short");
+    assert_eq!(probe.call_count(), 2);
+    probe.assert_last_user_message_contains("far too long answer");
+}
+
+#[tokio::test]
+async fn repeat_until_fails_after_max_attempts() {
+    let registry = registry(vec![("writer", text_mock("never valid"))]);
+
+    let err = MultiPromptChain::new(&registry)
+        .step(build(
+            step("summary", "writer", "summarize")
+                .repeat_until(Validator::regex("^ok$").unwrap(), 2),
+        ))
+        .run()
+        .await
+        .unwrap_err();
+
+    match err {
+        LLMError::ValidationFailed { failures } => assert_eq!(failures.len(), 2),
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+fn calculator() -> ToolRuntime {
+    ToolRuntime::new().register(FunctionBuilder::new("add"), |args| async move {
+        let sum = args["a"].as_i64().unwrap_or(0) + args["b"].as_i64().unwrap_or(0);
+        Ok(json!(sum))
+    })
+}
+
+#[tokio::test]
+async fn tool_steps_call_tools_directly_or_through_a_provider() {
+    let agent = MockLLM::new();
+    agent.push_tool_calls(vec![ToolCall {
+        id: "call_1".to_string(),
+        call_type: "function".to_string(),
+        function: FunctionCall {
+            name: "add".to_string(),
+            arguments: r#"{"a": 1, "b": 2}"#.to_string(),
+        },
+    }]);
+    agent.push_text("the sum is 3");
+    let registry = registry(vec![("agent", agent)]);
+
+    let mut inputs = std::collections::HashMap::new();
+    inputs.insert("x".to_string(), "40".to_string());
+    let outputs = MultiPromptChain::new(&registry)
+        .inputs(inputs)
+        .step(build(
+            MultiChainStepBuilder::new(MultiChainStepMode::Tool)
+                .id("direct")
+                .template(r#"{"a": {{x}}, "b": 2}"#)
+                .tools(calculator())
+                .call_tool("add"),
+        ))
+        .step(build(
+            MultiChainStepBuilder::new(MultiChainStepMode::Tool)
+                .id("looped")
+                .provider_id("agent")
+                .template("add 1 and 2")
+                .tools(calculator()),
+        ))
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(outputs["direct"], "42");
+    assert_eq!(outputs["looped"], "the sum is 3");
+}
+
+#[tokio::test]
+async fn direct_tool_steps_report_the_tool_name() {
+    let result = MultiPromptChain::new(&registry(Vec::new()))
+        .step(build(
+            MultiChainStepBuilder::new(MultiChainStepMode::Tool)
+                .id("direct")
+                .template(r#"{"a": 1, "b": 2}"#)
+                .tools(calculator())
+                .call_tool("add"),
+        ))
+        .into_dag()
+        .unwrap()
+        .run()
+        .await;
+
+    let direct = result.get("direct").unwrap();
+    assert_eq!(direct.status, StepStatus::Succeeded);
+    assert_eq!(direct.provider_id, "add");
+}
+
+#[tokio::test]
+async fn embedding_steps_output_json_vectors() {
+    let embedder = MockLLM::new();
+    embedder.push_embeddings(Ok(vec![vec![0.5, 1.0]]));
+    let registry = registry(vec![("embedder", embedder)]);
+
+    let outputs = MultiPromptChain::new(&registry)
+        .step(build(
+            MultiChainStepBuilder::new(MultiChainStepMode::Embedding)
+                .id("vector")
+                .provider_id("embedder")
+                .template("hello"),
+        ))
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(outputs["vector"], "[0.5,1.0]");
+}
+
+#[test]
+fn step_kinds_validate_their_configuration() {
+    let classify = MultiChainStepBuilder::new(MultiChainStepMode::Classify)
+        .id("c")
+        .provider_id("a")
+        .template("t")
+        .build();
+    assert!(classify.is_err());
+
+    let tool = MultiChainStepBuilder::new(MultiChainStepMode::Tool)
+        .id("t")
+        .template("{}")
+        .call_tool("add")
+        .build();
+    assert!(tool.is_err());
+
+    let registry = registry(vec![("a", MockLLM::new())]);
+    let unknown = MultiPromptChain::new(&registry)
+        .step(build(
+            step("x", "a", "go").when(StepCondition::label("nowhere", "x")),
+        ))
+        .into_dag();
+    assert!(unknown.is_err());
+}
+
+#[tokio::test]
+async fn run_inputs_feed_template_loops() {
+    let mock = text_mock("ok");
+    let probe = mock.clone();
+    let registry = registry(vec![("a", mock)]);
+
+    let mut inputs = std::collections::HashMap::new();
+    inputs.insert("topics".to_string(), json!(["rust", "go"]));
+    MultiPromptChain::new(&registry)
+        .step(build(step(
+            "list",
+            "a",
+            "Compare:{% for t in topics %} {{ loop.index }}={{ t }}{% endfor %}",
+        )))
+        .run_with_inputs(inputs)
+        .await
+        .unwrap();
+
+    probe.assert_last_user_message_contains("Compare: 1=rust 2=go");
+}
+
+#[tokio::test]
+async fn strict_chains_fail_on_undefined_variables() {
+    let registry = registry(vec![("a", text_mock("ok"))]);
+
+    let err = MultiPromptChain::new(&registry)
+        .strict(true)
+        .step(build(step("x", "a", "use {{ missing }}")))
+        .run()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("'missing' is not defined"));
+
+    let result = MultiPromptChain::new(&registry)
+        .strict(true)
+        .step(build(step("x", "a", "use {{ missing }}")))
+        .step(build(step("y", "a", "independent")))
+        .into_dag()
+        .unwrap()
+        .run()
+        .await;
+    assert_eq!(result.get("x").unwrap().status, StepStatus::Failed);
+    assert_eq!(result.get("y").unwrap().status, StepStatus::Succeeded);
+}
+
+#[tokio::test]
+async fn malformed_templates_fail_strict_chains_only() {
+    let mock = text_mock("ok");
+    let probe = mock.clone();
+    let registry = registry(vec![("a", mock)]);
+
+    let strict = MultiPromptChain::new(&registry)
+        .strict(true)
+        .step(build(step("first", "a", "fine")))
+        .step(build(step("x", "a", "{{ unclosed")))
+        .run()
+        .await;
+    assert!(strict.unwrap_err().to_string().contains("step 'x'"));
+    assert!(probe.last_messages().is_none(), "no step ran");
+
+    MultiPromptChain::new(&registry)
+        .step(build(step("x", "a", "{{ unclosed")))
+        .run()
+        .await
+        .unwrap();
+    probe.assert_last_user_message_contains("{{ unclosed");
+}
+
+#[tokio::test]
+async fn on_step_reports_each_completed_step() {
+    let mock = MockLLM::new();
+    mock.push_text("one");
+    mock.push_text("two");
+    let registry = registry(vec![("m", mock)]);
+    let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorder = seen.clone();
+
+    MultiPromptChain::new(&registry)
+        .step(build(step("first", "m", "a")))
+        .step(build(step("second", "m", "{{first}}")))
+        .on_step(move |id, output| recorder.lock().unwrap().push(format!("{id}={output}")))
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(*seen.lock().unwrap(), ["first=one", "second=two"]);
+}
@@ -0,0 +1,144 @@
+#[path = "template/parse.rs"]
+mod parse;
+
+#[path = "template/render.rs"]
+mod render;
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::error::LLMError;
+
+use parse::Node;
+use render::Scope;
+
+/// A parsed prompt template.
+///
+/// - `{{ name }}` inserts a variable; `{{ name.field.0 }}` reads into JSON values,
+///   including step outputs holding JSON text.
+/// - Filters are chained with `|`: `json` (JSON-encodes the value, e.g. to embed text
+///   in a JSON prompt), `trim`, `truncate(n)` (first `n` characters) and
+///   `default("text")` for undefined or null variables.
+/// - `{% for item in list %}...{% endfor %}` repeats its body for each element of a
+///   list variable, with `loop.index`, `loop.first` and `loop.last` available.
+/// - `\{{` and `\{%` produce literal `{{` and `{%`.
+///
+/// Rendering is a single pass, so inserted values are never interpreted as template
+/// syntax. Undefined variables and malformed tags are an error in strict mode and
+/// are left in the output as written otherwise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptTemplate {
+    source: String,
+    nodes: Vec<Node>,
+}
+
+impl PromptTemplate {
+    /// Parses a template, failing on unclosed tags, unknown filters and unbalanced loops.
+    pub fn parse(source: impl Into<String>) -> Result<Self, LLMError> {
+        let source = source.into();
+        let nodes = parse::parse(&source, true)?;
+        Ok(Self { source, nodes })
+    }
+
+    /// Parses a template, keeping malformed tags such as `{{}}`, an unclosed `{{`
+    /// or an unknown `{% tag %}` as literal text.
+    pub fn parse_lenient(source: impl Into<String>) -> Self {
+        let source = source.into();
+        let nodes =
+            parse::parse(&source, false).unwrap_or_else(|_| vec![Node::Text(source.clone())]);
+        Self { source, nodes }
+    }
+
+    /// Parses strictly with [`parse`](Self::parse) when `strict` is set, leniently
+    /// with [`parse_lenient`](Self::parse_lenient) otherwise.
+    pub(crate) fn parse_with(source: impl Into<String>, strict: bool) -> Result<Self, LLMError> {
+        if strict {
+            Self::parse(source)
+        } else {
+            Ok(Self::parse_lenient(source))
+        }
+    }
+
+    /// Escapes `text` so it renders literally when embedded in a template.
+    pub fn escape(text: &str) -> String {
+        text.replace("{{", "\\{{").replace("{%", "\\{%")
+    }
+
+    /// The template text.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Renders the template, leaving undefined variables as written.
+    pub fn render(&self, vars: &HashMap<String, Value>) -> Result<String, LLMError> {
+        self.render_with(vars, false)
+    }
+
+    /// Renders the template, failing on the first undefined variable.
+    pub fn render_strict(&self, vars: &HashMap<String, Value>) -> Result<String, LLMError> {
+        self.render_with(vars, true)
+    }
+
+    pub(crate) fn render_with(
+        &self,
+        vars: &HashMap<String, Value>,
+        strict: bool,
+    ) -> Result<String, LLMError> {
+        let mut out = String::with_capacity(self.source.len());
+        Scope {
+            vars,
+            locals: Vec::new(),
+            strict,
+        }
+        .render(&self.nodes, &mut out)?;
+        Ok(out)
+    }
+
+    /// Variable paths read from the caller's context, excluding loop items.
+    pub fn variables(&self) -> Vec<&str> {
+        let mut found = Vec::new();
+        collect_variables(&self.nodes, &mut Vec::new(), &mut found);
+        found
+    }
+}
+
+fn collect_variables<'n>(nodes: &'n [Node], locals: &mut Vec<&'n str>, found: &mut Vec<&'n str>) {
+    for node in nodes {
+        match node {
+            Node::Text(_) => {}
+            Node::Var(expr) => add_variable(&expr.path, locals, found),
+            Node::For { item, list, body } => {
+                add_variable(list, locals, found);
+                locals.extend([item.as_str(), "loop"]);
+                collect_variables(body, locals, found);
+                locals.truncate(locals.len() - 2);
+            }
+        }
+    }
+}
+
+fn add_variable<'n>(path: &'n str, locals: &[&str], found: &mut Vec<&'n str>) {
+    let root = path.split('.').next().unwrap_or(path);
+    if !locals.contains(&path) && !locals.contains(&root) && !found.contains(&path) {
+        found.push(path);
+    }
+}
+
+/// Template context from string variables (step outputs) and JSON inputs; strings win.
+pub(crate) fn context(
+    outputs: &HashMap<String, String>,
+    inputs: &HashMap<String, Value>,
+) -> HashMap<String, Value> {
+    let mut vars = inputs.clone();
+    vars.extend(
+        outputs
+            .iter()
+            .map(|(key, value)| (key.clone(), Value::String(value.clone()))),
+    );
+    vars
+}
+
+#[cfg(test)]
+#[path = "template/tests.rs"]
+mod tests;
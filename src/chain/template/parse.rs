@@ -0,0 +1,224 @@
+use crate::error::LLMError;
+
+/// A parsed template fragment.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Node {
+    Text(String),
+    Var(Expr),
+    For {
+        item: String,
+        list: String,
+        body: Vec<Node>,
+    },
+}
+
+/// A `{{ path | filter | ... }}` expression.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Expr {
+    /// Original text, rendered back as-is for undefined variables in lenient mode.
+    pub(super) source: String,
+    pub(super) path: String,
+    pub(super) filters: Vec<Filter>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Filter {
+    Json,
+    Trim,
+    Truncate(usize),
+    Default(String),
+}
+
+/// Parses `source`. Malformed tags are an error in strict mode and are kept as
+/// literal text otherwise.
+pub(super) fn parse(source: &str, strict: bool) -> Result<Vec<Node>, LLMError> {
+    let mut parser = Parser {
+        rest: source,
+        strict,
+        depth: 0,
+    };
+    let (nodes, _) = parser.nodes()?;
+    Ok(nodes)
+}
+
+struct Parser<'s> {
+    rest: &'s str,
+    strict: bool,
+    /// Number of enclosing loops
+    depth: usize,
+}
+
+enum Tag {
+    Node(Node),
+    EndFor,
+}
+
+impl<'s> Parser<'s> {
+    /// Parses nodes until the end of input or an `{% endfor %}`; the flag tells
+    /// which one ended them.
+    fn nodes(&mut self) -> Result<(Vec<Node>, bool), LLMError> {
+        let mut nodes = Vec::new();
+        let mut text = String::new();
+        loop {
+            let Some(pos) = self.rest.find(['{', '\\']) else {
+                text.push_str(self.rest);
+                self.rest = "";
+                break;
+            };
+            text.push_str(&self.rest[..pos]);
+            let tail = &self.rest[pos..];
+
+            if let Some(escaped) = ["\\{{", "\\{%"].iter().find(|e| tail.starts_with(**e)) {
+                text.push_str(&escaped[1..]);
+                self.rest = &tail[escaped.len()..];
+            } else if tail.starts_with("{{") || tail.starts_with("{%") {
+                let (open, inner) = tail.split_at(2);
+                match self.tag(tail, inner) {
+                    Ok(Tag::Node(node)) => {
+                        push_text(&mut nodes, &mut text);
+                        nodes.push(node);
+                    }
+                    Ok(Tag::EndFor) => {
+                        push_text(&mut nodes, &mut text);
+                        return Ok((nodes, true));
+                    }
+                    Err(err) if self.strict => return Err(err),
+                    Err(_) => {
+                        text.push_str(open);
+                        self.rest = inner;
+                    }
+                }
+            } else {
+                text.push_str(&tail[..1]);
+                self.rest = &tail[1..];
+            }
+        }
+        push_text(&mut nodes, &mut text);
+        Ok((nodes, false))
+    }
+
+    /// Parses the tag starting `tail`, where `inner` follows its opening delimiter.
+    fn tag(&mut self, tail: &'s str, inner: &'s str) -> Result<Tag, LLMError> {
+        if tail.starts_with("{{") {
+            let (body, rest) = closing(inner, "{{", "}}")?;
+            let expr = expr(&tail[..body.len() + 4], body)?;
+            self.rest = rest;
+            return Ok(Tag::Node(Node::Var(expr)));
+        }
+        let (body, rest) = closing(inner, "{%", "%}")?;
+        let tag = body.trim();
+        if tag == "endfor" {
+            if self.depth == 0 {
+                return Err(syntax_error("unexpected '{% endfor %}'".to_string()));
+            }
+            self.rest = rest;
+            return Ok(Tag::EndFor);
+        }
+        self.rest = rest;
+        self.for_loop(tag).map(Tag::Node)
+    }
+
+    fn for_loop(&mut self, tag: &str) -> Result<Node, LLMError> {
+        let words: Vec<&str> = tag.split_whitespace().collect();
+        let [keyword, item, "in", list] = words.as_slice() else {
+            return Err(syntax_error(format!("unknown tag '{{% {tag} %}}'")));
+        };
+        if *keyword != "for" {
+            return Err(syntax_error(format!("unknown tag '{{% {tag} %}}'")));
+        }
+        self.depth += 1;
+        let body = self.nodes();
+        self.depth -= 1;
+        match body? {
+            (body, true) => Ok(Node::For {
+                item: item.to_string(),
+                list: list.to_string(),
+                body,
+            }),
+            (_, false) => Err(syntax_error(format!(
+                "'{{% {tag} %}}' is missing '{{% endfor %}}'"
+            ))),
+        }
+    }
+}
+
+fn push_text(nodes: &mut Vec<Node>, text: &mut String) {
+    if !text.is_empty() {
+        nodes.push(Node::Text(std::mem::take(text)));
+    }
+}
+
+/// Splits `inner` at the `close` delimiter into the tag body and the remaining input.
+fn closing<'s>(inner: &'s str, open: &str, close: &str) -> Result<(&'s str, &'s str), LLMError> {
+    let end = inner
+        .find(close)
+        .ok_or_else(|| syntax_error(format!("unclosed '{open}'")))?;
+    Ok((&inner[..end], &inner[end + close.len()..]))
+}
+
+fn expr(source: &str, body: &str) -> Result<Expr, LLMError> {
+    let mut parts = split_unquoted(body, '|').into_iter();
+    let path = parts.next().unwrap_or_default().trim().to_string();
+    if path.is_empty() {
+        return Err(syntax_error(format!("empty expression '{source}'")));
+    }
+    let filters = parts.map(|f| filter(f.trim())).collect::<Result<_, _>>()?;
+    Ok(Expr {
+        source: source.to_string(),
+        path,
+        filters,
+    })
+}
+
+fn filter(spec: &str) -> Result<Filter, LLMError> {
+    let (name, arg) = match spec.split_once('(') {
+        Some((name, rest)) => {
+            let arg = rest
+                .strip_suffix(')')
+                .ok_or_else(|| syntax_error(format!("unclosed arguments in filter '{spec}'")))?;
+            (name.trim(), Some(arg.trim()))
+        }
+        None => (spec, None),
+    };
+    match (name, arg) {
+        ("json", None) => Ok(Filter::Json),
+        ("trim", None) => Ok(Filter::Trim),
+        ("truncate", Some(arg)) => arg
+            .parse()
+            .map(Filter::Truncate)
+            .map_err(|_| syntax_error(format!("truncate expects a length, got '{arg}'"))),
+        ("default", Some(arg)) => Ok(Filter::Default(unquote(arg).to_string())),
+        _ => Err(syntax_error(format!("unknown filter '{spec}'"))),
+    }
+}
+
+fn unquote(arg: &str) -> &str {
+    ['"', '\'']
+        .iter()
+        .find_map(|q| arg.strip_prefix(*q)?.strip_suffix(*q))
+        .unwrap_or(arg)
+}
+
+/// Splits on `separator` outside of single or double quotes.
+fn split_unquoted(input: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in input.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, _) if c == separator => {
+                parts.push(&input[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&input[start..]);
+    parts
+}
+
+fn syntax_error(message: String) -> LLMError {
+    LLMError::InvalidRequest(format!("invalid template: {message}"))
+}
@@ -0,0 +1,137 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::error::LLMError;
+
+use super::parse::{Expr, Filter, Node};
+
+/// Variables visible while rendering: the caller's context plus loop items.
+pub(super) struct Scope<'v> {
+    pub(super) vars: &'v HashMap<String, Value>,
+    pub(super) locals: Vec<(String, Value)>,
+    pub(super) strict: bool,
+}
+
+impl Scope<'_> {
+    pub(super) fn render(&mut self, nodes: &[Node], out: &mut String) -> Result<(), LLMError> {
+        for node in nodes {
+            match node {
+                Node::Text(text) => out.push_str(text),
+                Node::Var(expr) => self.render_expr(expr, out)?,
+                Node::For { item, list, body } => {
+                    let items = match self.lookup(list) {
+                        Some(value) => as_list(&value).ok_or_else(|| {
+                            LLMError::InvalidRequest(format!(
+                                "template variable '{list}' is not a list"
+                            ))
+                        })?,
+                        None if self.strict => return Err(undefined(list)),
+                        None => Vec::new(),
+                    };
+                    let count = items.len();
+                    for (index, value) in items.into_iter().enumerate() {
+                        let meta = serde_json::json!({
+                            "index": index + 1,
+                            "first": index == 0,
+                            "last": index + 1 == count,
+                        });
+                        self.locals.push(("loop".to_string(), meta));
+                        self.locals.push((item.clone(), value));
+                        let result = self.render(body, out);
+                        self.locals.truncate(self.locals.len() - 2);
+                        result?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn render_expr(&self, expr: &Expr, out: &mut String) -> Result<(), LLMError> {
+        let mut value = self.lookup(&expr.path);
+        for filter in &expr.filters {
+            value = match (filter, value) {
+                (Filter::Default(fallback), None | Some(Value::Null)) => {
+                    Some(Value::String(fallback.clone()))
+                }
+                (Filter::Default(_), value) => value,
+                (_, None) => None,
+                (Filter::Json, Some(value)) => Some(Value::String(value.to_string())),
+                (Filter::Trim, Some(value)) => {
+                    Some(Value::String(to_text(&value).trim().to_string()))
+                }
+                (Filter::Truncate(max), Some(value)) => {
+                    Some(Value::String(to_text(&value).chars().take(*max).collect()))
+                }
+            };
+        }
+        match value {
+            Some(value) => out.push_str(&to_text(&value)),
+            None if self.strict => return Err(undefined(&expr.path)),
+            None => out.push_str(&expr.source),
+        }
+        Ok(())
+    }
+
+    /// Resolves `path`, first as a whole key, then as `name.field.0` segments.
+    fn lookup(&self, path: &str) -> Option<Value> {
+        if let Some(value) = self.get(path) {
+            return Some(value.clone());
+        }
+        let mut segments = path.split('.');
+        let root = self.get(segments.next()?)?;
+        let mut current = Cow::Borrowed(root);
+        for segment in segments {
+            let parsed;
+            let container = match current.as_ref() {
+                Value::String(text) => {
+                    parsed = serde_json::from_str::<Value>(text).ok()?;
+                    &parsed
+                }
+                other => other,
+            };
+            let next = match container {
+                Value::Object(map) => map.get(segment)?,
+                Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+            current = Cow::Owned(next.clone());
+        }
+        Some(current.into_owned())
+    }
+
+    fn get(&self, name: &str) -> Option<&Value> {
+        self.locals
+            .iter()
+            .rev()
+            .find(|(local, _)| local == name)
+            .map(|(_, value)| value)
+            .or_else(|| self.vars.get(name))
+    }
+}
+
+/// Arrays, or strings holding a JSON array (such as step outputs).
+fn as_list(value: &Value) -> Option<Vec<Value>> {
+    match value {
+        Value::Array(items) => Some(items.clone()),
+        Value::String(text) => match serde_json::from_str(text.trim()) {
+            Ok(Value::Array(items)) => Some(items),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn to_text(value: &Value) -> Cow<'_, str> {
+    match value {
+        Value::Null => Cow::Borrowed(""),
+        Value::String(text) => Cow::Borrowed(text),
+        other => Cow::Owned(other.to_string()),
+    }
+}
+
+fn undefined(name: &str) -> LLMError {
+    LLMError::InvalidRequest(format!("template variable '{name}' is not defined"))
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+use super::PromptTemplate;
+
+fn vars(value: Value) -> HashMap<String, Value> {
+    serde_json::from_value(value).unwrap()
+}
+
+fn render(template: &str, value: Value) -> String {
+    PromptTemplate::parse(template)
+        .unwrap()
+        .render(&vars(value))
+        .unwrap()
+}
+
+#[test]
+fn substitutes_in_a_single_pass() {
+    let out = render("{{a}} and {{ b }}", json!({ "a": "{{b}}", "b": "second" }));
+    assert_eq!(out, "{{b}} and second");
+}
+
+#[test]
+fn lenient_mode_keeps_undefined_placeholders() {
+    assert_eq!(
+        render("hi {{ who | trim }}", json!({})),
+        "hi {{ who | trim }}"
+    );
+}
+
+#[test]
+fn strict_mode_rejects_undefined_variables() {
+    let template = PromptTemplate::parse("hi {{who}}").unwrap();
+    let err = template.render_strict(&HashMap::new()).unwrap_err();
+    assert!(err.to_string().contains("'who' is not defined"));
+    assert_eq!(
+        template
+            .render_strict(&vars(json!({ "who": "there" })))
+            .unwrap(),
+        "hi there"
+    );
+}
+
+#[test]
+fn applies_filters_in_order() {
+    let out = render(
+        r#"{"text": {{ text | trim | json }}, "short": "{{ text | trim | truncate(3) }}", "tone": "{{ tone | default("neutral") }}"}"#,
+        json!({ "text": "  say \"hi\"\n " }),
+    );
+    assert_eq!(
+        out,
+        r#"{"text": "say \"hi\"", "short": "say", "tone": "neutral"}"#
+    );
+}
+
+#[test]
+fn default_handles_quoted_pipes_and_null() {
+    assert_eq!(
+        render("{{ x | default('a|b') }}", json!({ "x": null })),
+        "a|b"
+    );
+    assert_eq!(render("{{ x | default(y) }}", json!({ "x": 3 })), "3");
+}
+
+#[test]
+fn loops_over_lists_and_json_text() {
+    let out = render(
+        "{% for item in items %}{{ loop.index }}. {{ item.name }}\n{% endfor %}done",
+        json!({ "items": [{ "name": "a" }, { "name": "b" }] }),
+    );
+    assert_eq!(out, "1. a\n2. b\ndone");
+
+    let out = render(
+        "{% for tag in tags %}{{ tag }}{% endfor %}",
+        json!({ "tags": "[\"x\", \"y\"]" }),
+    );
+    assert_eq!(out, "xy");
+}
+
+#[test]
+fn reads_nested_fields_of_json_outputs() {
+    let out = render(
+        "{{ review.verdict.ok }} {{ review.items.1 }}",
+        json!({ "review": "{\"verdict\": {\"ok\": true}, \"items\": [1, 2]}" }),
+    );
+    assert_eq!(out, "true 2");
+}
+
+#[test]
+fn escaped_delimiters_render_literally() {
+    assert_eq!(render("\\{{ x }} {{ x }}", json!({ "x": 1 })), "{{ x }} 1");
+    let text = "code: {{ not_a_var }} and {% raw";
+    assert_eq!(render(&PromptTemplate::escape(text), json!({})), text);
+}
+
+#[test]
+fn rejects_malformed_templates() {
+    for source in [
+        "{{ open",
+        "{{ }}",
+        "{{ x | shout }}",
+        "{{ x | truncate(many) }}",
+        "{% for x in xs %}no end",
+        "{% endfor %}",
+        "{% if x %}",
+    ] {
+        assert!(PromptTemplate::parse(source).is_err(), "{source}");
+    }
+}
+
+#[test]
+fn lenient_parse_keeps_malformed_tags_as_text() {
+    let template = PromptTemplate::parse_lenient(
+        "{{}} {{ x | shout }} {% if x %}{% endfor %} {{ x }} {% for y in ys %}{{ y }} {{ open",
+    );
+    assert_eq!(
+        template.render(&vars(json!({ "x": 1, "y": 2 }))).unwrap(),
+        "{{}} {{ x | shout }} {% if x %}{% endfor %} 1 {% for y in ys %}2 {{ open"
+    );
+    assert_eq!(
+        PromptTemplate::parse_lenient("{% for x in xs %}{{ x }}{% endfor %}"),
+        PromptTemplate::parse("{% for x in xs %}{{ x }}{% endfor %}").unwrap()
+    );
+}
+
+#[test]
+fn lists_free_variables() {
+    let template = PromptTemplate::parse(
+        "{{a}} {% for x in b.items %}{{ x }}{{ loop.index }}{{ c }}{% endfor %}{{a}}",
+    )
+    .unwrap();
+    assert_eq!(template.variables(), vec!["a", "b.items", "c"]);
+}
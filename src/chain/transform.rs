@@ -0,0 +1,319 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::LLMError;
+use crate::validated_llm::strip_code_fence;
+
+/// One operation of a [`TransformPipeline`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Transform {
+    /// Trims surrounding whitespace.
+    Trim,
+    /// Removes `<think>...</think>` blocks, keeping the answer.
+    StripThink,
+    /// Keeps the text inside `<think>` tags; empty when there is none.
+    ExtractThink,
+    /// Body of the first fenced code block, optionally of the given language.
+    CodeFence {
+        #[serde(default)]
+        language: Option<String>,
+    },
+    /// The JSON document of the text: a fenced block, or the text from the first
+    /// `{` or `[` to the last `}` or `]`.
+    Json,
+    /// Value at `path` in the JSON text, e.g. `$.items[0].name` or `items.0.name`;
+    /// strings are returned unquoted.
+    JsonPath { path: String },
+    /// Capture group of the first match of `pattern`: group 1 when the pattern has
+    /// groups, else the whole match.
+    Regex {
+        pattern: String,
+        #[serde(default)]
+        group: Option<CaptureGroup>,
+    },
+}
+
+/// Capture group selected by a [`Transform::Regex`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CaptureGroup {
+    /// Group by position; 0 is the whole match.
+    Index(usize),
+    /// Group by name, as in `(?P<name>...)`.
+    Name(String),
+}
+
+impl Transform {
+    /// Transform named `name`, including the legacy `trim_whitespace` and
+    /// `extract_json` names.
+    pub fn named(name: &str) -> Result<Self, LLMError> {
+        Ok(match name {
+            "trim" | "trim_whitespace" => Transform::Trim,
+            "strip_think" => Transform::StripThink,
+            "extract_think" => Transform::ExtractThink,
+            "code_fence" => Transform::CodeFence { language: None },
+            "json" | "extract_json" => Transform::Json,
+            other => {
+                return Err(LLMError::InvalidRequest(format!(
+                    "Unknown response transform: {other}"
+                )))
+            }
+        })
+    }
+}
+
+/// Transforms applied in order to a step output.
+///
+/// Deserializes from a transform name (`"strip_think"`), a transform object
+/// (`{"type": "json_path", "path": "$.answer"}`) or a list of either. Regex patterns
+/// are compiled when the pipeline is built, so invalid ones are rejected up front.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(try_from = "PipelineDef", into = "Vec<Transform>")]
+pub struct TransformPipeline {
+    transforms: Vec<Transform>,
+    regexes: Vec<Option<Regex>>,
+}
+
+impl PartialEq for TransformPipeline {
+    fn eq(&self, other: &Self) -> bool {
+        self.transforms == other.transforms
+    }
+}
+
+impl TransformPipeline {
+    /// Builds a pipeline, failing on invalid regex patterns.
+    pub fn new(transforms: Vec<Transform>) -> Result<Self, LLMError> {
+        let regexes = transforms
+            .iter()
+            .map(|transform| match transform {
+                Transform::Regex { pattern, .. } => Regex::new(pattern).map(Some).map_err(|e| {
+                    LLMError::InvalidRequest(format!("Invalid transform pattern '{pattern}': {e}"))
+                }),
+                _ => Ok(None),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            transforms,
+            regexes,
+        })
+    }
+
+    /// Transforms of the pipeline, in the order they run.
+    pub fn transforms(&self) -> &[Transform] {
+        &self.transforms
+    }
+
+    /// Whether the pipeline returns the text unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.transforms.is_empty()
+    }
+
+    /// Runs every transform on `text`; fails when one finds nothing to extract.
+    pub fn apply(&self, text: &str) -> Result<String, LLMError> {
+        let mut current = text.to_string();
+        for (transform, regex) in self.transforms.iter().zip(&self.regexes) {
+            current = match (transform, regex) {
+                (Transform::Trim, _) => current.trim().to_string(),
+                (Transform::StripThink, _) => strip_think(&current),
+                (Transform::ExtractThink, _) => extract_think(&current),
+                (Transform::CodeFence { language }, _) => code_fence(&current, language.as_deref())
+                    .ok_or_else(|| failed("no matching code block"))?
+                    .to_string(),
+                (Transform::Json, _) => extract_json(&current)?,
+                (Transform::JsonPath { path }, _) => json_path(&current, path)?,
+                (Transform::Regex { group, .. }, Some(regex)) => {
+                    capture(regex, &current, group.as_ref())?
+                }
+                (Transform::Regex { .. }, None) => unreachable!("regex compiled in new"),
+            };
+        }
+        Ok(current)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TransformDef {
+    Name(String),
+    Transform(Transform),
+}
+
+impl TryFrom<TransformDef> for Transform {
+    type Error = LLMError;
+
+    fn try_from(def: TransformDef) -> Result<Self, Self::Error> {
+        match def {
+            TransformDef::Name(name) => Transform::named(&name),
+            TransformDef::Transform(transform) => Ok(transform),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PipelineDef {
+    One(TransformDef),
+    Many(Vec<TransformDef>),
+}
+
+impl TryFrom<PipelineDef> for TransformPipeline {
+    type Error = LLMError;
+
+    fn try_from(def: PipelineDef) -> Result<Self, Self::Error> {
+        let defs = match def {
+            PipelineDef::One(def) => vec![def],
+            PipelineDef::Many(defs) => defs,
+        };
+        let transforms = defs
+            .into_iter()
+            .map(Transform::try_from)
+            .collect::<Result<_, _>>()?;
+        TransformPipeline::new(transforms)
+    }
+}
+
+impl From<TransformPipeline> for Vec<Transform> {
+    fn from(pipeline: TransformPipeline) -> Self {
+        pipeline.transforms
+    }
+}
+
+fn failed(reason: impl std::fmt::Display) -> LLMError {
+    LLMError::Generic(format!("Response transform failed: {reason}"))
+}
+
+fn strip_think(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("<think>") {
+        out.push_str(&rest[..start]);
+        match rest[start..].find("</think>") {
+            Some(end) => rest = &rest[start + end + "</think>".len()..],
+            None => {
+                rest = "";
+                break;
+            }
+        }
+    }
+    out.push_str(rest);
+    out.trim().to_string()
+}
+
+fn extract_think(text: &str) -> String {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("<think>") {
+        let inner = &rest[start + "<think>".len()..];
+        let end = inner.find("</think>").unwrap_or(inner.len());
+        parts.push(inner[..end].trim());
+        rest = &inner[end..];
+    }
+    parts.join("\n")
+}
+
+/// Body of the first ``` block whose info string starts with `language`, if given.
+fn code_fence<'a>(text: &'a str, language: Option<&str>) -> Option<&'a str> {
+    let mut rest = text;
+    while let Some(start) = rest.find("```") {
+        let after = &rest[start + 3..];
+        let (info, body) = after.split_once('\n')?;
+        let end = body.find("```")?;
+        let info = info.trim();
+        let matches = language.is_none_or(|lang| {
+            info.split_whitespace()
+                .next()
+                .is_some_and(|first| first.eq_ignore_ascii_case(lang))
+        });
+        if matches {
+            return Some(body[..end].trim());
+        }
+        rest = &body[end + 3..];
+    }
+    None
+}
+
+fn extract_json(text: &str) -> Result<String, LLMError> {
+    let candidate = match code_fence(text, None) {
+        Some(body) => body,
+        None => {
+            let start = text
+                .find(['{', '['])
+                .ok_or_else(|| failed("no JSON in the response"))?;
+            let end = text
+                .rfind(['}', ']'])
+                .filter(|end| *end > start)
+                .ok_or_else(|| failed("no JSON in the response"))?;
+            &text[start..=end]
+        }
+    };
+    serde_json::from_str::<Value>(candidate).map_err(|e| failed(format!("invalid JSON: {e}")))?;
+    Ok(candidate.to_string())
+}
+
+fn json_path(text: &str, path: &str) -> Result<String, LLMError> {
+    let mut value: Value = serde_json::from_str(strip_code_fence(text))
+        .map_err(|e| failed(format!("invalid JSON: {e}")))?;
+    for segment in path_segments(path) {
+        let next = match &mut value {
+            Value::Object(map) => map.remove(&segment),
+            Value::Array(items) => segment
+                .parse::<usize>()
+                .ok()
+                .filter(|index| *index < items.len())
+                .map(|index| items.swap_remove(index)),
+            _ => None,
+        };
+        value = next.ok_or_else(|| failed(format!("'{path}' not found")))?;
+    }
+    Ok(match value {
+        Value::String(text) => text,
+        other => other.to_string(),
+    })
+}
+
+/// `$.a.b[0]["c d"]` and `a.b.0` both give `a`, `b`, `0`, ...
+fn path_segments(path: &str) -> Vec<String> {
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let mut segments = Vec::new();
+    for part in path.split('.').filter(|part| !part.is_empty()) {
+        let mut rest = part;
+        if let Some(open) = rest.find('[') {
+            if open > 0 {
+                segments.push(rest[..open].to_string());
+            }
+            rest = &rest[open..];
+            while let Some(inner) = rest.strip_prefix('[') {
+                let Some(close) = inner.find(']') else {
+                    break;
+                };
+                let key = inner[..close].trim_matches(|c| c == '"' || c == '\'');
+                segments.push(key.to_string());
+                rest = &inner[close + 1..];
+            }
+        } else {
+            segments.push(rest.to_string());
+        }
+    }
+    segments
+}
+
+fn capture(regex: &Regex, text: &str, group: Option<&CaptureGroup>) -> Result<String, LLMError> {
+    let captures = regex
+        .captures(text)
+        .ok_or_else(|| failed(format!("'{}' did not match", regex.as_str())))?;
+    let matched = match group {
+        Some(CaptureGroup::Index(index)) => captures.get(*index),
+        Some(CaptureGroup::Name(name)) => captures.name(name),
+        None if captures.len() > 1 => captures.get(1),
+        None => captures.get(0),
+    };
+    matched
+        .map(|m| m.as_str().to_string())
+        .ok_or_else(|| failed(format!("'{}' captured nothing", regex.as_str())))
+}
+
+#[cfg(test)]
+#[path = "transform/tests.rs"]
+mod tests;
@@ -0,0 +1,105 @@
+use super::*;
+
+fn pipeline(json: &str) -> TransformPipeline {
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn extract_json_reads_fenced_blocks() {
+    let pipeline = pipeline(r#""extract_json""#);
+    let text = "Here you go:\n```json\n{\"answer\": 42}\n```\nAnything else?";
+    assert_eq!(pipeline.apply(text).unwrap(), "{\"answer\": 42}");
+}
+
+#[test]
+fn extract_json_reads_unfenced_documents() {
+    let pipeline = pipeline(r#""json""#);
+    assert_eq!(
+        pipeline.apply("Result: [1, 2, 3] done").unwrap(),
+        "[1, 2, 3]"
+    );
+    assert!(pipeline.apply("no json here").is_err());
+    assert!(pipeline.apply("broken {\"a\": }").is_err());
+}
+
+#[test]
+fn json_path_reads_nested_values() {
+    let text = r#"{"items": [{"name": "first"}, {"name": "second", "tags": ["a"]}]}"#;
+    let name = TransformPipeline::new(vec![Transform::JsonPath {
+        path: "$.items[1].name".to_string(),
+    }])
+    .unwrap();
+    assert_eq!(name.apply(text).unwrap(), "second");
+
+    let tags = TransformPipeline::new(vec![Transform::JsonPath {
+        path: "items.1.tags".to_string(),
+    }])
+    .unwrap();
+    assert_eq!(tags.apply(text).unwrap(), r#"["a"]"#);
+
+    let missing = TransformPipeline::new(vec![Transform::JsonPath {
+        path: "$.items[5]".to_string(),
+    }])
+    .unwrap();
+    assert!(missing.apply(text).is_err());
+}
+
+#[test]
+fn regex_captures_groups() {
+    let first = pipeline(r#"{"type": "regex", "pattern": "score: (\\d+)"}"#);
+    assert_eq!(first.apply("final score: 87 points").unwrap(), "87");
+
+    let named =
+        pipeline(r#"{"type": "regex", "pattern": "(?P<a>\\w+)-(?P<b>\\w+)", "group": "b"}"#);
+    assert_eq!(named.apply("left-right").unwrap(), "right");
+
+    let whole = pipeline(r#"{"type": "regex", "pattern": "\\d+", "group": 0}"#);
+    assert_eq!(whole.apply("abc 123").unwrap(), "123");
+    assert!(whole.apply("abc").is_err());
+}
+
+#[test]
+fn rejects_invalid_patterns_and_names() {
+    assert!(
+        serde_json::from_str::<TransformPipeline>(r#"{"type": "regex", "pattern": "("}"#).is_err()
+    );
+    assert!(serde_json::from_str::<TransformPipeline>(r#""reverse""#).is_err());
+}
+
+#[test]
+fn think_tags_are_stripped_or_extracted() {
+    let text = "<think>\nplan it\n</think>\nThe answer.";
+    assert_eq!(
+        pipeline(r#""strip_think""#).apply(text).unwrap(),
+        "The answer."
+    );
+    assert_eq!(
+        pipeline(r#""extract_think""#).apply(text).unwrap(),
+        "plan it"
+    );
+    assert_eq!(pipeline(r#""extract_think""#).apply("plain").unwrap(), "");
+}
+
+#[test]
+fn code_fence_selects_language() {
+    let text = "```text\nnotes\n```\n```python\nprint(1)\n```";
+    let python = pipeline(r#"{"type": "code_fence", "language": "python"}"#);
+    assert_eq!(python.apply(text).unwrap(), "print(1)");
+    assert_eq!(pipeline(r#""code_fence""#).apply(text).unwrap(), "notes");
+    assert!(python.apply("no fences").is_err());
+}
+
+#[test]
+fn pipelines_run_in_order_and_round_trip() {
+    let pipeline = pipeline(
+        r#"["strip_think", "extract_json", {"type": "json_path", "path": "$.answer"}, "trim"]"#,
+    );
+    let text = "<think>hmm</think>```json\n{\"answer\": \"  yes  \"}\n```";
+    assert_eq!(pipeline.apply(text).unwrap(), "yes");
+
+    let json = serde_json::to_string(&pipeline).unwrap();
+    assert_eq!(
+        serde_json::from_str::<TransformPipeline>(&json).unwrap(),
+        pipeline
+    );
+}
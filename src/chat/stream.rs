@@ -33,7 +33,8 @@ pub struct StreamDelta {
 }
 
 /// A streaming chunk that can be either text or a tool call event.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum StreamChunk {
     /// Text content delta
     Text(String),
@@ -64,6 +65,10 @@ pub enum StreamChunk {
         tool_call: ToolCall,
     },
 
+    /// Token usage of the response, reported before [`StreamChunk::Done`] by
+    /// providers that know it
+    Usage(Usage),
+
     /// Stream ended with stop reason
     Done {
         /// The reason the stream stopped (e.g., "end_turn", "tool_use")
@@ -39,7 +39,7 @@ pub trait ChatProvider: Sync + Send {
         &self,
         _input: String,
     ) -> Result<Box<dyn ChatResponse>, LLMError> {
-        Err(LLMError::Generic(
+        Err(LLMError::Unsupported(
             "Web search not supported for this provider".to_string(),
         ))
     }
@@ -48,7 +48,7 @@ pub trait ChatProvider: Sync + Send {
         &self,
         _messages: &[ChatMessage],
     ) -> Result<Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError> {
-        Err(LLMError::Generic(
+        Err(LLMError::Unsupported(
             "Streaming not supported for this provider".to_string(),
         ))
     }
@@ -58,7 +58,7 @@ pub trait ChatProvider: Sync + Send {
         _messages: &[ChatMessage],
     ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>, LLMError>
     {
-        Err(LLMError::Generic(
+        Err(LLMError::Unsupported(
             "Structured streaming not supported for this provider".to_string(),
         ))
     }
@@ -68,7 +68,7 @@ pub trait ChatProvider: Sync + Send {
         _messages: &[ChatMessage],
         _tools: Option<&[Tool]>,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk, LLMError>> + Send>>, LLMError> {
-        Err(LLMError::Generic(
+        Err(LLMError::Unsupported(
             "Streaming with tools not supported for this provider".to_string(),
         ))
     }
@@ -2,6 +2,7 @@ use thiserror::Error;
 
 /// Error types that can occur when interacting with LLM providers.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum LLMError {
     /// HTTP request/response errors
     #[error("HTTP error: {0}")]
@@ -33,6 +34,31 @@ pub enum LLMError {
     /// Retry attempts exceeded
     #[error("Retry attempts exceeded after {attempts} tries: {last_error}")]
     RetryExceeded { attempts: usize, last_error: String },
+    /// Tool call arguments that do not match the tool's declared schema
+    #[error(
+        "Invalid arguments for tool '{tool}' (call {call_id}): {}",
+        crate::json_schema::join_violations(.violations)
+    )]
+    InvalidToolArguments {
+        tool: String,
+        call_id: String,
+        violations: Vec<crate::json_schema::SchemaViolation>,
+    },
+    /// Every validation attempt was rejected
+    #[error(
+        "Validation error after {} attempts: {}",
+        .failures.len(),
+        .failures.last().map_or("", |f| f.reason.as_str())
+    )]
+    ValidationFailed {
+        failures: Vec<crate::validated_llm::ValidationFailure>,
+    },
+    /// Usage budget exhausted
+    #[error("Budget exceeded: {0}")]
+    BudgetExceeded(String),
+    /// Operation the provider does not implement, such as streaming
+    #[error("Unsupported: {0}")]
+    Unsupported(String),
 }
 
 /// Converts reqwest HTTP errors into LlmErrors
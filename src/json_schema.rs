@@ -0,0 +1,16 @@
+//! Minimal JSON Schema validation.
+//!
+//! Covers the subset of draft-07 that tool definitions and structured output schemas
+//! use in practice: `type`, `enum`, `const`, object and array keywords, string and
+//! numeric bounds, `pattern`, the `allOf`/`anyOf`/`oneOf`/`not` combinators and
+//! local `$ref`s. Unknown keywords (such as `format`) are ignored.
+
+#[path = "json_schema/validate.rs"]
+mod validate;
+
+pub(crate) use validate::join_violations;
+pub use validate::{validate, Schema, SchemaViolation};
+
+#[cfg(test)]
+#[path = "json_schema/tests.rs"]
+mod tests;
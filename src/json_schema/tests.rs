@@ -0,0 +1,99 @@
+use serde_json::json;
+
+use super::{validate, Schema};
+
+fn paths(schema: serde_json::Value, instance: serde_json::Value) -> Vec<String> {
+    validate(&schema, &instance)
+        .err()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|v| format!("{v}"))
+        .collect()
+}
+
+#[test]
+fn accepts_matching_object() {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "city": { "type": "string", "minLength": 2 },
+            "days": { "type": "integer", "minimum": 1, "maximum": 7 },
+            "units": { "enum": ["metric", "imperial"] }
+        },
+        "required": ["city"],
+        "additionalProperties": false
+    });
+    assert!(validate(
+        &schema,
+        &json!({ "city": "Paris", "days": 3, "units": "metric" })
+    )
+    .is_ok());
+}
+
+#[test]
+fn reports_every_violation_with_its_path() {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "city": { "type": "string" },
+            "days": { "type": "integer", "maximum": 7 },
+            "tags": { "type": "array", "items": { "type": "string", "pattern": "^[a-z]+$" } }
+        },
+        "required": ["city", "days"],
+        "additionalProperties": false
+    });
+    let errors = paths(
+        schema,
+        json!({ "days": 9.5, "tags": ["ok", "NO"], "extra": true }),
+    );
+    assert_eq!(
+        errors,
+        vec![
+            "$: missing required property 'city'",
+            "$.days: expected integer, got number",
+            "$: unexpected property 'extra'",
+            "$.tags[1]: must match pattern '^[a-z]+$'",
+        ]
+    );
+}
+
+#[test]
+fn resolves_refs_and_combinators() {
+    let schema = json!({
+        "$defs": { "point": { "type": "object", "required": ["x"] } },
+        "type": "object",
+        "properties": {
+            "at": { "$ref": "#/$defs/point" },
+            "id": { "anyOf": [{ "type": "string" }, { "type": "integer" }] },
+            "note": { "type": ["string", "null"] }
+        }
+    });
+    assert!(validate(&schema, &json!({ "at": { "x": 1 }, "id": 3, "note": null })).is_ok());
+    assert_eq!(
+        paths(schema, json!({ "at": {}, "id": true })),
+        vec![
+            "$.at: missing required property 'x'",
+            "$.id: does not match any of the allowed schemas",
+        ]
+    );
+}
+
+#[test]
+fn compiled_schema_checks_patterns_of_every_subschema() {
+    let schema = Schema::new(json!({
+        "type": "object",
+        "patternProperties": { "^x_": { "type": "string", "pattern": "^[0-9]+$" } },
+        "properties": { "bad": { "type": "string", "pattern": "(" } }
+    }));
+
+    assert!(schema.validate(&json!({ "x_id": "42" })).is_ok());
+    let violations = schema
+        .validate(&json!({ "x_id": "abc", "bad": "x" }))
+        .unwrap_err();
+    let messages: Vec<_> = violations.iter().map(|v| v.to_string()).collect();
+    assert_eq!(messages.len(), 2);
+    assert!(messages.contains(&"$.x_id: must match pattern '^[0-9]+$'".to_string()));
+    assert!(messages
+        .iter()
+        .any(|m| m.starts_with("$.bad: invalid pattern '('")));
+}
@@ -0,0 +1,449 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+const MAX_DEPTH: usize = 64;
+
+/// A single place where a value does not match its schema.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaViolation {
+    /// Location of the offending value, e.g. `$.items[2].name`.
+    pub path: String,
+    /// Human readable reason.
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+pub(crate) fn join_violations(violations: &[SchemaViolation]) -> String {
+    violations
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Validates `instance` against `schema`, returning every violation found.
+///
+/// Compiles the regular expressions of the schema on every call; use [`Schema`] to
+/// validate many values against the same schema.
+pub fn validate(schema: &Value, instance: &Value) -> Result<(), Vec<SchemaViolation>> {
+    run(schema, &compile_patterns(schema), instance)
+}
+
+/// A schema whose `pattern` and `patternProperties` expressions are compiled once.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    root: Value,
+    patterns: Patterns,
+}
+
+impl Schema {
+    /// Compiles the expressions of `schema`; invalid ones are reported by
+    /// [`Schema::validate`].
+    pub fn new(schema: Value) -> Self {
+        let patterns = compile_patterns(&schema);
+        Self {
+            root: schema,
+            patterns,
+        }
+    }
+
+    /// Validates `instance`, returning every violation found.
+    pub fn validate(&self, instance: &Value) -> Result<(), Vec<SchemaViolation>> {
+        run(&self.root, &self.patterns, instance)
+    }
+
+    /// The schema document.
+    pub fn as_value(&self) -> &Value {
+        &self.root
+    }
+}
+
+/// Compiled expressions by source; invalid ones keep their error message.
+type Patterns = HashMap<String, Result<Regex, String>>;
+
+fn compile_patterns(schema: &Value) -> Patterns {
+    fn collect(value: &Value, patterns: &mut Patterns) {
+        match value {
+            Value::Object(obj) => {
+                if let Some(pattern) = obj.get("pattern").and_then(Value::as_str) {
+                    add(pattern, patterns);
+                }
+                if let Some(keys) = obj.get("patternProperties").and_then(Value::as_object) {
+                    keys.keys().for_each(|pattern| add(pattern, patterns));
+                }
+                obj.values().for_each(|value| collect(value, patterns));
+            }
+            Value::Array(items) => items.iter().for_each(|value| collect(value, patterns)),
+            _ => {}
+        }
+    }
+    fn add(pattern: &str, patterns: &mut Patterns) {
+        if !patterns.contains_key(pattern) {
+            let compiled = Regex::new(pattern).map_err(|e| e.to_string());
+            patterns.insert(pattern.to_string(), compiled);
+        }
+    }
+
+    let mut patterns = Patterns::new();
+    collect(schema, &mut patterns);
+    patterns
+}
+
+fn run(schema: &Value, patterns: &Patterns, instance: &Value) -> Result<(), Vec<SchemaViolation>> {
+    let mut validator = Validator {
+        root: schema,
+        patterns,
+        violations: Vec::new(),
+    };
+    validator.check(schema, instance, "$", 0);
+    if validator.violations.is_empty() {
+        Ok(())
+    } else {
+        Err(validator.violations)
+    }
+}
+
+struct Validator<'a> {
+    root: &'a Value,
+    patterns: &'a Patterns,
+    violations: Vec<SchemaViolation>,
+}
+
+impl<'a> Validator<'a> {
+    fn fail(&mut self, path: &str, message: impl Into<String>) {
+        self.violations.push(SchemaViolation {
+            path: path.to_string(),
+            message: message.into(),
+        });
+    }
+
+    fn matches(&self, schema: &'a Value, instance: &Value, depth: usize) -> bool {
+        let mut nested = Validator {
+            root: self.root,
+            patterns: self.patterns,
+            violations: Vec::new(),
+        };
+        nested.check(schema, instance, "$", depth);
+        nested.violations.is_empty()
+    }
+
+    fn check(&mut self, schema: &'a Value, instance: &Value, path: &str, depth: usize) {
+        if depth > MAX_DEPTH {
+            self.fail(path, "schema nesting too deep");
+            return;
+        }
+        let schema = match schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => return self.fail(path, "no value is allowed here"),
+            Value::Object(schema) => schema,
+            _ => return,
+        };
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            match self.resolve(reference) {
+                Some(target) => self.check(target, instance, path, depth + 1),
+                None => self.fail(path, format!("unresolvable $ref '{reference}'")),
+            }
+        }
+
+        if let Some(expected) = schema.get("type") {
+            if !type_matches(expected, instance) {
+                return self.fail(
+                    path,
+                    format!(
+                        "expected {}, got {}",
+                        describe_type(expected),
+                        type_name(instance)
+                    ),
+                );
+            }
+        }
+        if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+            if !options.contains(instance) {
+                self.fail(
+                    path,
+                    format!("must be one of {}", Value::Array(options.clone())),
+                );
+            }
+        }
+        if let Some(expected) = schema.get("const") {
+            if expected != instance {
+                self.fail(path, format!("must be {expected}"));
+            }
+        }
+
+        match instance {
+            Value::String(s) => self.check_string(schema, s, path),
+            Value::Number(_) => self.check_number(schema, instance, path),
+            Value::Object(obj) => self.check_object(schema, obj, path, depth),
+            Value::Array(items) => self.check_array(schema, items, path, depth),
+            _ => {}
+        }
+
+        self.check_combinators(schema, instance, path, depth);
+    }
+
+    fn pattern(&self, pattern: &str) -> Result<&'a Regex, &'a str> {
+        match self.patterns.get(pattern) {
+            Some(Ok(re)) => Ok(re),
+            Some(Err(e)) => Err(e),
+            None => Err("not compiled"),
+        }
+    }
+
+    fn resolve(&self, reference: &str) -> Option<&'a Value> {
+        let pointer = reference.strip_prefix('#')?;
+        if pointer.is_empty() {
+            return Some(self.root);
+        }
+        self.root.pointer(pointer)
+    }
+
+    fn check_string(&mut self, schema: &Map<String, Value>, s: &str, path: &str) {
+        let len = s.chars().count() as u64;
+        if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+            if len < min {
+                self.fail(path, format!("must be at least {min} characters long"));
+            }
+        }
+        if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+            if len > max {
+                self.fail(path, format!("must be at most {max} characters long"));
+            }
+        }
+        if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+            match self.pattern(pattern) {
+                Ok(re) if !re.is_match(s) => {
+                    self.fail(path, format!("must match pattern '{pattern}'"))
+                }
+                Ok(_) => {}
+                Err(e) => self.fail(path, format!("invalid pattern '{pattern}': {e}")),
+            }
+        }
+    }
+
+    fn check_number(&mut self, schema: &Map<String, Value>, value: &Value, path: &str) {
+        let Some(n) = value.as_f64() else {
+            return;
+        };
+        let bound = |key: &str| schema.get(key).and_then(Value::as_f64);
+        if let Some(min) = bound("minimum") {
+            if n < min {
+                self.fail(path, format!("must be >= {min}"));
+            }
+        }
+        if let Some(max) = bound("maximum") {
+            if n > max {
+                self.fail(path, format!("must be <= {max}"));
+            }
+        }
+        if let Some(min) = bound("exclusiveMinimum") {
+            if n <= min {
+                self.fail(path, format!("must be > {min}"));
+            }
+        }
+        if let Some(max) = bound("exclusiveMaximum") {
+            if n >= max {
+                self.fail(path, format!("must be < {max}"));
+            }
+        }
+        if let Some(step) = bound("multipleOf").filter(|s| *s > 0.0) {
+            let ratio = n / step;
+            if (ratio - ratio.round()).abs() > 1e-9 {
+                self.fail(path, format!("must be a multiple of {step}"));
+            }
+        }
+    }
+
+    fn check_object(
+        &mut self,
+        schema: &'a Map<String, Value>,
+        obj: &Map<String, Value>,
+        path: &str,
+        depth: usize,
+    ) {
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !obj.contains_key(name) {
+                    self.fail(path, format!("missing required property '{name}'"));
+                }
+            }
+        }
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let patterns = schema.get("patternProperties").and_then(Value::as_object);
+        let additional = schema.get("additionalProperties");
+
+        for (key, value) in obj {
+            let child = format!("{path}.{key}");
+            let mut known = false;
+            if let Some(sub) = properties.and_then(|p| p.get(key)) {
+                known = true;
+                self.check(sub, value, &child, depth + 1);
+            }
+            for (pattern, sub) in patterns.into_iter().flatten() {
+                if self.pattern(pattern).is_ok_and(|re| re.is_match(key)) {
+                    known = true;
+                    self.check(sub, value, &child, depth + 1);
+                }
+            }
+            match additional {
+                Some(Value::Bool(false)) if !known => {
+                    self.fail(path, format!("unexpected property '{key}'"))
+                }
+                Some(sub @ Value::Object(_)) if !known => self.check(sub, value, &child, depth + 1),
+                _ => {}
+            }
+        }
+
+        let count = obj.len() as u64;
+        if let Some(min) = schema.get("minProperties").and_then(Value::as_u64) {
+            if count < min {
+                self.fail(path, format!("must have at least {min} properties"));
+            }
+        }
+        if let Some(max) = schema.get("maxProperties").and_then(Value::as_u64) {
+            if count > max {
+                self.fail(path, format!("must have at most {max} properties"));
+            }
+        }
+    }
+
+    fn check_array(
+        &mut self,
+        schema: &'a Map<String, Value>,
+        items: &[Value],
+        path: &str,
+        depth: usize,
+    ) {
+        match schema.get("items") {
+            Some(Value::Array(tuple)) => {
+                for (i, (sub, item)) in tuple.iter().zip(items).enumerate() {
+                    self.check(sub, item, &format!("{path}[{i}]"), depth + 1);
+                }
+            }
+            Some(sub) => {
+                for (i, item) in items.iter().enumerate() {
+                    self.check(sub, item, &format!("{path}[{i}]"), depth + 1);
+                }
+            }
+            None => {}
+        }
+
+        let count = items.len() as u64;
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+            if count < min {
+                self.fail(path, format!("must have at least {min} items"));
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+            if count > max {
+                self.fail(path, format!("must have at most {max} items"));
+            }
+        }
+        if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+            let duplicated = items
+                .iter()
+                .enumerate()
+                .any(|(i, item)| items[..i].contains(item));
+            if duplicated {
+                self.fail(path, "items must be unique");
+            }
+        }
+    }
+
+    fn check_combinators(
+        &mut self,
+        schema: &'a Map<String, Value>,
+        instance: &Value,
+        path: &str,
+        depth: usize,
+    ) {
+        if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+            for sub in all {
+                self.check(sub, instance, path, depth + 1);
+            }
+        }
+        if let Some(any) = schema.get("anyOf").and_then(Value::as_array) {
+            if !any.iter().any(|sub| self.matches(sub, instance, depth + 1)) {
+                self.fail(path, "does not match any of the allowed schemas");
+            }
+        }
+        if let Some(one) = schema.get("oneOf").and_then(Value::as_array) {
+            let matched = one
+                .iter()
+                .filter(|sub| self.matches(sub, instance, depth + 1))
+                .count();
+            if matched != 1 {
+                self.fail(
+                    path,
+                    format!("must match exactly one allowed schema, matched {matched}"),
+                );
+            }
+        }
+        if let Some(not) = schema.get("not") {
+            if self.matches(not, instance, depth + 1) {
+                self.fail(path, "matches a disallowed schema");
+            }
+        }
+    }
+}
+
+fn type_matches(expected: &Value, instance: &Value) -> bool {
+    match expected {
+        Value::String(name) => is_type(name, instance),
+        Value::Array(names) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .any(|name| is_type(name, instance)),
+        _ => true,
+    }
+}
+
+fn is_type(name: &str, instance: &Value) -> bool {
+    match name {
+        "null" => instance.is_null(),
+        "boolean" => instance.is_boolean(),
+        "object" => instance.is_object(),
+        "array" => instance.is_array(),
+        "string" => instance.is_string(),
+        "number" => instance.is_number(),
+        "integer" => {
+            instance.is_i64()
+                || instance.is_u64()
+                || instance.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        _ => true,
+    }
+}
+
+fn describe_type(expected: &Value) -> String {
+    match expected {
+        Value::Array(names) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" or "),
+        other => other.as_str().unwrap_or("any").to_string(),
+    }
+}
+
+fn type_name(instance: &Value) -> &'static str {
+    match instance {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
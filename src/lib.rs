@@ -58,6 +58,31 @@ pub mod validated_llm;
 /// Resilience wrapper (retry/backoff) for LLM providers
 pub mod resilient_llm;
 
+/// Response caching wrapper for LLM providers
+pub mod cached_llm;
+
+/// Record/replay providers for deterministic tests
+pub mod record_replay;
+
+/// Request/response middleware hooks for LLM providers
+pub mod middleware;
+
+/// Token usage and cost accounting with optional budgets
+pub mod usage_tracker;
+
+/// Minimal JSON Schema validation used for tool arguments and structured output
+pub mod json_schema;
+
+/// Schema validation of tool calls before dispatch
+pub mod tool_validation;
+
+/// Model Context Protocol client
+#[cfg(feature = "mcp")]
+pub mod mcp;
+
+/// Automatic tool-execution loop with Rust async handlers
+pub mod tool_runtime;
+
 /// Evaluator for LLM providers
 pub mod evaluator;
 
@@ -80,6 +105,10 @@ pub mod memory;
 #[cfg(feature = "agent")]
 pub mod agent;
 
+/// Scriptable mock provider for unit tests
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
 #[cfg(feature = "api")]
 pub mod api;
 
@@ -154,3 +183,19 @@ impl std::fmt::Display for FunctionCall {
         )
     }
 }
+
+impl FunctionCall {
+    /// Deserializes the JSON arguments into a typed value.
+    ///
+    /// Empty arguments are treated as an empty object, which some providers send for
+    /// tools without parameters.
+    pub fn parse_arguments<T: serde::de::DeserializeOwned>(&self) -> Result<T, error::LLMError> {
+        let raw = match self.arguments.trim() {
+            "" => "{}",
+            raw => raw,
+        };
+        serde_json::from_str(raw).map_err(|e| {
+            error::LLMError::JsonError(format!("invalid arguments for '{}': {e}", self.name))
+        })
+    }
+}
@@ -0,0 +1,42 @@
+//! Model Context Protocol client.
+//!
+//! [`McpClient`] connects to an MCP server over stdio ([`StdioTransport`]) or
+//! streamable HTTP ([`HttpTransport`]), converts its tools into [`crate::chat::Tool`]
+//! definitions and proxies tool calls, resources and prompts. Use
+//! [`crate::tool_runtime::ToolRuntime::register_mcp`] to let a model call the
+//! server's tools with any provider.
+//!
+//! In the other direction, [`McpServer`] exposes the providers of an
+//! [`crate::chain::LLMRegistry`] and multi-provider chains as MCP tools.
+
+#[path = "mcp/protocol.rs"]
+mod protocol;
+
+#[path = "mcp/transport.rs"]
+mod transport;
+
+#[path = "mcp/stdio.rs"]
+mod stdio;
+
+#[path = "mcp/http.rs"]
+mod http;
+
+#[path = "mcp/client.rs"]
+mod client;
+
+#[path = "mcp/server.rs"]
+mod server;
+
+pub use client::McpClient;
+pub use http::HttpTransport;
+pub use protocol::{
+    CallToolResult, GetPromptResult, McpContent, McpPrompt, McpResource, McpTool, PromptArgument,
+    PromptMessage, ResourceContents, ServerInfo, PROTOCOL_VERSION,
+};
+pub use server::{ChainFactory, McpServer};
+pub use stdio::StdioTransport;
+pub use transport::McpTransport;
+
+#[cfg(test)]
+#[path = "mcp/tests.rs"]
+mod tests;
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use crate::{
+    chat::{ChatMessage, Tool},
+    error::LLMError,
+    tool_runtime::ToolRuntime,
+    ToolCall,
+};
+
+use super::http::HttpTransport;
+use super::protocol::{
+    into_result, notification, request, string_map, CallToolResult, GetPromptResult,
+    InitializeResult, McpContent, McpPrompt, McpResource, McpTool, ResourceContents, ServerInfo,
+    PROTOCOL_VERSION,
+};
+use super::stdio::StdioTransport;
+use super::transport::McpTransport;
+
+/// Client for a single MCP server.
+///
+/// ```no_run
+/// # async fn run() -> Result<(), llm::error::LLMError> {
+/// use std::sync::Arc;
+/// use llm::{mcp::McpClient, tool_runtime::ToolRuntime};
+///
+/// let client = Arc::new(McpClient::stdio("npx", ["-y", "@modelcontextprotocol/server-everything"]).await?);
+/// let runtime = ToolRuntime::new().register_mcp(client).await?;
+/// // runtime.run(llm.as_ref(), messages).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct McpClient {
+    transport: Box<dyn McpTransport>,
+    next_id: AtomicU64,
+    server_info: ServerInfo,
+    protocol_version: String,
+    capabilities: Value,
+    instructions: Option<String>,
+}
+
+impl McpClient {
+    /// Initializes a session over `transport`.
+    pub async fn connect(transport: impl McpTransport + 'static) -> Result<Self, LLMError> {
+        let mut client = Self {
+            transport: Box::new(transport),
+            next_id: AtomicU64::new(1),
+            server_info: ServerInfo::default(),
+            protocol_version: String::new(),
+            capabilities: Value::Null,
+            instructions: None,
+        };
+        let params = json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": {},
+            "clientInfo": { "name": "llm", "version": env!("CARGO_PKG_VERSION") },
+        });
+        let init: InitializeResult = client.call_method("initialize", params).await?;
+        client
+            .transport
+            .notify(notification("notifications/initialized"))
+            .await?;
+        client.server_info = init.server_info;
+        client.protocol_version = init.protocol_version;
+        client.capabilities = init.capabilities;
+        client.instructions = init.instructions;
+        Ok(client)
+    }
+
+    /// Spawns a server subprocess and connects to it over stdio.
+    pub async fn stdio<I, S>(program: impl AsRef<OsStr>, args: I) -> Result<Self, LLMError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        Self::connect(StdioTransport::spawn(program, args)?).await
+    }
+
+    /// Connects to a streamable HTTP endpoint.
+    pub async fn http(url: impl Into<String>) -> Result<Self, LLMError> {
+        Self::connect(HttpTransport::new(url)).await
+    }
+
+    /// Name and version the server reported during initialization.
+    pub fn server_info(&self) -> &ServerInfo {
+        &self.server_info
+    }
+
+    /// Protocol revision agreed with the server.
+    pub fn protocol_version(&self) -> &str {
+        &self.protocol_version
+    }
+
+    /// Raw capabilities advertised by the server.
+    pub fn capabilities(&self) -> &Value {
+        &self.capabilities
+    }
+
+    /// Usage instructions provided by the server, if any.
+    pub fn instructions(&self) -> Option<&str> {
+        self.instructions.as_deref()
+    }
+
+    /// Every tool of the server, following pagination.
+    pub async fn list_tools(&self) -> Result<Vec<McpTool>, LLMError> {
+        self.list_all("tools/list", "tools").await
+    }
+
+    /// Server tools as chat tool definitions.
+    pub async fn chat_tools(&self) -> Result<Vec<Tool>, LLMError> {
+        let tools = self.list_tools().await?;
+        Ok(tools.iter().map(McpTool::to_chat_tool).collect())
+    }
+
+    /// Calls a server tool. A tool failure is reported in the result's `is_error`,
+    /// not as an `Err`.
+    pub async fn call_tool(
+        &self,
+        name: &str,
+        arguments: Value,
+    ) -> Result<CallToolResult, LLMError> {
+        self.call_method(
+            "tools/call",
+            json!({ "name": name, "arguments": arguments }),
+        )
+        .await
+    }
+
+    /// Proxies a tool call emitted by a model.
+    pub async fn call(&self, call: &ToolCall) -> Result<CallToolResult, LLMError> {
+        let arguments: Value = call.function.parse_arguments()?;
+        self.call_tool(&call.function.name, arguments).await
+    }
+
+    /// Every resource of the server, following pagination.
+    pub async fn list_resources(&self) -> Result<Vec<McpResource>, LLMError> {
+        self.list_all("resources/list", "resources").await
+    }
+
+    /// Contents of the resource at `uri`.
+    pub async fn read_resource(&self, uri: &str) -> Result<Vec<ResourceContents>, LLMError> {
+        let result: Value = self
+            .call_method("resources/read", json!({ "uri": uri }))
+            .await?;
+        field(result, "contents")
+    }
+
+    /// Every prompt template of the server, following pagination.
+    pub async fn list_prompts(&self) -> Result<Vec<McpPrompt>, LLMError> {
+        self.list_all("prompts/list", "prompts").await
+    }
+
+    /// Renders the prompt `name` with its arguments.
+    pub async fn get_prompt(
+        &self,
+        name: &str,
+        arguments: HashMap<String, String>,
+    ) -> Result<GetPromptResult, LLMError> {
+        let params = json!({ "name": name, "arguments": string_map(arguments) });
+        self.call_method("prompts/get", params).await
+    }
+
+    /// Ends the session and stops the server process, if any.
+    pub async fn close(&self) -> Result<(), LLMError> {
+        self.transport.close().await
+    }
+
+    async fn call_method<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<T, LLMError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let response = self.transport.request(request(id, method, params)).await?;
+        let result = into_result(response)?;
+        serde_json::from_value(result).map_err(|e| LLMError::ResponseFormatError {
+            message: format!("invalid {method} result: {e}"),
+            raw_response: String::new(),
+        })
+    }
+
+    /// Follows `nextCursor` pagination and collects the `key` arrays.
+    async fn list_all<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        key: &str,
+    ) -> Result<Vec<T>, LLMError> {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let page: Value = self.call_method(method, params).await?;
+            cursor = page
+                .get("nextCursor")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            items.extend(field::<Vec<T>>(page, key)?);
+            if cursor.is_none() {
+                return Ok(items);
+            }
+        }
+    }
+}
+
+fn field<T: DeserializeOwned>(mut value: Value, key: &str) -> Result<T, LLMError> {
+    let value = match value[key].take() {
+        Value::Null => json!([]),
+        value => value,
+    };
+    serde_json::from_value(value).map_err(|e| LLMError::ResponseFormatError {
+        message: format!("invalid MCP payload: {e}"),
+        raw_response: String::new(),
+    })
+}
+
+impl GetPromptResult {
+    /// Converts the text messages of the prompt into chat messages.
+    pub fn to_chat_messages(&self) -> Vec<ChatMessage> {
+        self.messages
+            .iter()
+            .filter_map(|message| {
+                let McpContent::Text { text } = &message.content else {
+                    return None;
+                };
+                let builder = match message.role.as_str() {
+                    "assistant" => ChatMessage::assistant(),
+                    _ => ChatMessage::user(),
+                };
+                Some(builder.content(text.clone()).build())
+            })
+            .collect()
+    }
+}
+
+impl ToolRuntime {
+    /// Registers every tool of an MCP server, proxying calls to it.
+    pub async fn register_mcp(self, client: Arc<McpClient>) -> Result<Self, LLMError> {
+        let tools = client.list_tools().await?;
+        Ok(tools.into_iter().fold(self, |runtime, tool| {
+            let client = Arc::clone(&client);
+            let name = tool.name.clone();
+            runtime.register_tool(tool.to_chat_tool(), move |args| {
+                let client = Arc::clone(&client);
+                let name = name.clone();
+                async move { client.call_tool(&name, args).await?.into_value() }
+            })
+        }))
+    }
+}
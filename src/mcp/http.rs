@@ -0,0 +1,156 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use serde_json::Value;
+
+use crate::error::LLMError;
+
+use super::transport::{request_id, McpTransport};
+
+const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Streamable HTTP transport: every message is POSTed to a single endpoint and the
+/// server answers with JSON or a short-lived SSE stream.
+pub struct HttpTransport {
+    client: reqwest::Client,
+    url: String,
+    headers: Vec<(String, String)>,
+    session_id: Mutex<Option<String>>,
+}
+
+impl HttpTransport {
+    /// Transport posting to the MCP endpoint at `url`, e.g. `http://localhost:3000/mcp`.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.into(),
+            headers: Vec::new(),
+            session_id: Mutex::new(None),
+        }
+    }
+
+    /// Adds a header sent with every request.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sends `Authorization: Bearer <token>` with every request.
+    pub fn with_bearer_token(self, token: impl AsRef<str>) -> Self {
+        let value = format!("Bearer {}", token.as_ref());
+        self.with_header("Authorization", value)
+    }
+
+    /// Uses a preconfigured HTTP client (proxies, timeouts, TLS).
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Session id assigned by the server during initialization.
+    pub fn session_id(&self) -> Option<String> {
+        self.session_id
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn request_builder(&self, method: reqwest::Method) -> reqwest::RequestBuilder {
+        let mut builder = self
+            .client
+            .request(method, &self.url)
+            .header(ACCEPT, "application/json, text/event-stream");
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        if let Some(session) = self.session_id() {
+            builder = builder.header(SESSION_HEADER, session);
+        }
+        builder
+    }
+
+    async fn post(&self, message: &Value) -> Result<reqwest::Response, LLMError> {
+        let response = self
+            .request_builder(reqwest::Method::POST)
+            .header(CONTENT_TYPE, "application/json")
+            .json(message)
+            .send()
+            .await?;
+        if let Some(session) = response.headers().get(SESSION_HEADER) {
+            if let Ok(session) = session.to_str() {
+                *self.session_id.lock().unwrap_or_else(|e| e.into_inner()) =
+                    Some(session.to_string());
+            }
+        }
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(LLMError::HttpError(format!(
+                "MCP server returned {status}: {body}"
+            )));
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl McpTransport for HttpTransport {
+    async fn request(&self, message: Value) -> Result<Value, LLMError> {
+        let id = request_id(&message)?;
+        let response = self.post(&message).await?;
+        let is_sse = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+        let body = response.text().await?;
+
+        let messages = if is_sse {
+            sse_messages(&body)
+        } else {
+            match serde_json::from_str::<Value>(&body)? {
+                Value::Array(batch) => batch,
+                single => vec![single],
+            }
+        };
+        messages
+            .into_iter()
+            .find(|m| {
+                m.get("method").is_none() && m.get("id").map(Value::to_string) == Some(id.clone())
+            })
+            .ok_or_else(|| LLMError::ResponseFormatError {
+                message: format!("MCP server sent no response for request {id}"),
+                raw_response: body,
+            })
+    }
+
+    async fn notify(&self, message: Value) -> Result<(), LLMError> {
+        self.post(&message).await.map(|_| ())
+    }
+
+    async fn close(&self) -> Result<(), LLMError> {
+        if self.session_id().is_some() {
+            // Servers may not support explicit termination; ignore failures.
+            let _ = self.request_builder(reqwest::Method::DELETE).send().await;
+            *self.session_id.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the JSON payloads of the `data:` fields of an SSE body.
+fn sse_messages(body: &str) -> Vec<Value> {
+    body.replace("\r\n", "\n")
+        .split("\n\n")
+        .filter_map(|event| {
+            let data = event
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(str::trim_start)
+                .collect::<Vec<_>>()
+                .join("\n");
+            serde_json::from_str(&data).ok()
+        })
+        .collect()
+}
@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    chat::{FunctionTool, Tool},
+    error::LLMError,
+};
+
+/// MCP protocol revision sent during initialization.
+pub const PROTOCOL_VERSION: &str = "2025-03-26";
+
+/// A tool advertised by an MCP server.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpTool {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default = "empty_object_schema")]
+    pub input_schema: Value,
+}
+
+impl McpTool {
+    /// Converts the tool into a chat tool definition.
+    pub fn to_chat_tool(&self) -> Tool {
+        Tool {
+            tool_type: "function".to_string(),
+            function: FunctionTool {
+                name: self.name.clone(),
+                description: self.description.clone().unwrap_or_default(),
+                parameters: self.input_schema.clone(),
+            },
+            cache_control: None,
+        }
+    }
+}
+
+fn empty_object_schema() -> Value {
+    json!({ "type": "object", "properties": {} })
+}
+
+/// A content block returned by tools, resources and prompts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum McpContent {
+    Text {
+        text: String,
+    },
+    Image {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+    },
+    Audio {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+    },
+    Resource {
+        resource: ResourceContents,
+    },
+    #[serde(other)]
+    Unsupported,
+}
+
+/// Result of `tools/call`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallToolResult {
+    #[serde(default)]
+    pub content: Vec<McpContent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub structured_content: Option<Value>,
+    #[serde(default)]
+    pub is_error: bool,
+}
+
+impl CallToolResult {
+    /// Concatenated text blocks of the result.
+    pub fn text(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(|block| match block {
+                McpContent::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// JSON value to hand back to the model: the structured content when present,
+    /// otherwise the text. Errors reported by the tool become `Err`.
+    pub fn into_value(self) -> Result<Value, LLMError> {
+        if self.is_error {
+            return Err(LLMError::ProviderError(self.text()));
+        }
+        Ok(self
+            .structured_content
+            .clone()
+            .unwrap_or_else(|| Value::String(self.text())))
+    }
+}
+
+/// A resource advertised by an MCP server.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpResource {
+    pub uri: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+/// Contents of a resource, as text or base64 encoded blob.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceContents {
+    pub uri: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blob: Option<String>,
+}
+
+/// A prompt template advertised by an MCP server.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct McpPrompt {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub arguments: Vec<PromptArgument>,
+}
+
+/// An argument of a prompt template.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptArgument {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// A rendered prompt message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptMessage {
+    pub role: String,
+    pub content: McpContent,
+}
+
+/// Result of `prompts/get`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GetPromptResult {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub messages: Vec<PromptMessage>,
+}
+
+/// Name and version reported by the server.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServerInfo {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct InitializeResult {
+    #[serde(default)]
+    pub(super) protocol_version: String,
+    #[serde(default)]
+    pub(super) capabilities: Value,
+    #[serde(default)]
+    pub(super) server_info: ServerInfo,
+    #[serde(default)]
+    pub(super) instructions: Option<String>,
+}
+
+pub(super) fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+pub(super) fn notification(method: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method })
+}
+
+/// Extracts `result` from a JSON-RPC response, mapping `error` to [`LLMError`].
+pub(super) fn into_result(response: Value) -> Result<Value, LLMError> {
+    if let Some(error) = response.get("error") {
+        let code = error
+            .get("code")
+            .and_then(Value::as_i64)
+            .unwrap_or_default();
+        let message = error
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown error");
+        return Err(LLMError::ProviderError(format!(
+            "MCP error {code}: {message}"
+        )));
+    }
+    response
+        .get("result")
+        .cloned()
+        .ok_or_else(|| LLMError::ResponseFormatError {
+            message: "MCP response has neither result nor error".to_string(),
+            raw_response: response.to_string(),
+        })
+}
+
+/// Response to a request the server sent to us; only `ping` is supported.
+pub(super) fn reply_to_server_request(message: &Value) -> Option<Value> {
+    let id = message.get("id")?.clone();
+    let method = message.get("method")?.as_str()?;
+    Some(if method == "ping" {
+        json!({ "jsonrpc": "2.0", "id": id, "result": {} })
+    } else {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": -32601, "message": format!("method not found: {method}") }
+        })
+    })
+}
+
+pub(super) fn string_map(args: HashMap<String, String>) -> Value {
+    Value::Object(
+        args.into_iter()
+            .map(|(k, v)| (k, Value::String(v)))
+            .collect(),
+    )
+}
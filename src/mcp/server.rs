@@ -0,0 +1,421 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::Mutex;
+use tokio::task::JoinSet;
+
+use crate::{
+    chain::{LLMRegistry, MultiChainStep, MultiPromptChain},
+    chat::ChatMessage,
+    error::LLMError,
+};
+
+use super::protocol::{McpTool, ServerInfo, PROTOCOL_VERSION};
+
+/// Produces the steps of a chain for one run.
+pub type ChainFactory = dyn Fn() -> Result<Vec<MultiChainStep>, LLMError> + Send + Sync;
+
+struct ChainEntry {
+    description: String,
+    inputs: Vec<String>,
+    factory: Arc<ChainFactory>,
+}
+
+/// Exposes the providers of an [`LLMRegistry`] and named [`MultiPromptChain`]
+/// pipelines as MCP tools.
+///
+/// Every provider becomes an `ask_<id>` tool taking a `prompt` (and an optional
+/// `history`), every chain a `run_<name>` tool taking its input variables and
+/// returning the output of each step. Characters other than letters, digits, `_`
+/// and `-` become `_`, and ids that end up with the same name get a numbered suffix.
+///
+/// ```no_run
+/// # async fn run(registry: llm::chain::LLMRegistry) -> Result<(), llm::error::LLMError> {
+/// use llm::chain::{MultiChainStepBuilder, MultiChainStepMode};
+/// use llm::mcp::McpServer;
+///
+/// McpServer::new(registry)
+///     .chain("summarize", "Summarize then translate a text", &["text"], || {
+///         Ok(vec![
+///             MultiChainStepBuilder::new(MultiChainStepMode::Chat)
+///                 .provider_id("openai")
+///                 .id("summary")
+///                 .template("Summarize: {{text}}")
+///                 .build()?,
+///         ])
+///     })
+///     .serve_stdio()
+///     .await
+/// # }
+/// ```
+pub struct McpServer {
+    registry: Arc<LLMRegistry>,
+    chains: HashMap<String, ChainEntry>,
+    info: ServerInfo,
+    instructions: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct HistoryMessage {
+    role: String,
+    content: String,
+}
+
+impl McpServer {
+    /// Serves the providers of `registry`, with no chains.
+    pub fn new(registry: LLMRegistry) -> Self {
+        Self::from_shared(Arc::new(registry))
+    }
+
+    /// Serves a registry that is shared with other servers.
+    pub fn from_shared(registry: Arc<LLMRegistry>) -> Self {
+        Self {
+            registry,
+            chains: HashMap::new(),
+            info: ServerInfo {
+                name: "llm".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            instructions: None,
+        }
+    }
+
+    /// Name and version reported to clients.
+    pub fn server_info(mut self, name: impl Into<String>, version: impl Into<String>) -> Self {
+        self.info = ServerInfo {
+            name: name.into(),
+            version: version.into(),
+        };
+        self
+    }
+
+    /// Usage instructions sent to clients during initialization.
+    pub fn instructions(mut self, instructions: impl Into<String>) -> Self {
+        self.instructions = Some(instructions.into());
+        self
+    }
+
+    /// Exposes a chain as the `run_<name>` tool. `inputs` are the template variables
+    /// callers must provide; `factory` builds the steps for each run.
+    pub fn chain<F>(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        inputs: &[&str],
+        factory: F,
+    ) -> Self
+    where
+        F: Fn() -> Result<Vec<MultiChainStep>, LLMError> + Send + Sync + 'static,
+    {
+        self.chains.insert(
+            name.into(),
+            ChainEntry {
+                description: description.into(),
+                inputs: inputs.iter().map(|s| s.to_string()).collect(),
+                factory: Arc::new(factory),
+            },
+        );
+        self
+    }
+
+    /// Tools advertised to clients.
+    pub fn tools(&self) -> Vec<McpTool> {
+        let mut tools: Vec<McpTool> = self
+            .provider_ids()
+            .into_iter()
+            .map(|(tool_id, id)| McpTool {
+                name: format!("ask_{tool_id}"),
+                description: Some(format!(
+                    "Send a prompt to the '{id}' model and return its answer."
+                )),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "prompt": { "type": "string", "description": "Message to send" },
+                        "history": {
+                            "type": "array",
+                            "description": "Earlier messages of the conversation",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "role": { "type": "string", "enum": ["user", "assistant"] },
+                                    "content": { "type": "string" }
+                                },
+                                "required": ["role", "content"]
+                            }
+                        }
+                    },
+                    "required": ["prompt"]
+                }),
+            })
+            .collect();
+
+        tools.extend(self.chain_ids().into_iter().map(|(tool_id, name)| {
+            let chain = &self.chains[name];
+            let properties: Map<String, Value> = chain
+                .inputs
+                .iter()
+                .map(|input| (input.clone(), json!({ "type": "string" })))
+                .collect();
+            McpTool {
+                name: format!("run_{tool_id}"),
+                description: Some(chain.description.clone()),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": properties,
+                    "required": chain.inputs,
+                }),
+            }
+        }));
+        tools
+    }
+
+    /// Serves over the process stdin/stdout until stdin closes.
+    pub async fn serve_stdio(self) -> Result<(), LLMError> {
+        self.serve(tokio::io::stdin(), tokio::io::stdout()).await
+    }
+
+    /// Serves newline-delimited JSON-RPC over any reader/writer pair. Requests are
+    /// handled concurrently; the server returns once the reader ends and every
+    /// request is answered.
+    pub async fn serve<R, W>(self, reader: R, writer: W) -> Result<(), LLMError>
+    where
+        R: AsyncRead + Send + Unpin,
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        let server = Arc::new(self);
+        let writer = Arc::new(Mutex::new(writer));
+        let mut lines = BufReader::new(reader).lines();
+        let mut tasks = JoinSet::new();
+        while let Some(line) = lines
+            .next_line()
+            .await
+            .map_err(|e| LLMError::ProviderError(format!("failed to read MCP request: {e}")))?
+        {
+            if line.trim().is_empty() {
+                continue;
+            }
+            while tasks.try_join_next().is_some() {}
+            let server = Arc::clone(&server);
+            let writer = Arc::clone(&writer);
+            tasks.spawn(async move {
+                let Some(response) = server.handle_line(&line).await else {
+                    return;
+                };
+                let mut out = response.to_string().into_bytes();
+                out.push(b'\n');
+                let mut writer = writer.lock().await;
+                if writer
+                    .write_all(&out)
+                    .await
+                    .and(writer.flush().await)
+                    .is_err()
+                {
+                    log::warn!("failed to write MCP response");
+                }
+            });
+        }
+        while tasks.join_next().await.is_some() {}
+        Ok(())
+    }
+
+    /// Handles one JSON-RPC message, returning the response for requests.
+    pub async fn handle_line(&self, line: &str) -> Option<Value> {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(e) => {
+                return Some(error_response(
+                    Value::Null,
+                    -32700,
+                    &format!("parse error: {e}"),
+                ))
+            }
+        };
+        let id = message.get("id")?.clone();
+        let method = message
+            .get("method")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let result = match method {
+            "initialize" => Ok(self.initialize_result(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": self.tools() })),
+            "tools/call" => Ok(self.call_tool(&params).await),
+            _ => Err((-32601, format!("method not found: {method}"))),
+        };
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error_response(id, code, &message),
+        })
+    }
+
+    /// Agrees to the client's protocol version when it is the one this
+    /// server speaks and otherwise offers [`PROTOCOL_VERSION`], leaving the
+    /// client to disconnect if it cannot use that.
+    fn initialize_result(&self, params: &Value) -> Value {
+        let version = match params.get("protocolVersion").and_then(Value::as_str) {
+            Some(requested) if requested == PROTOCOL_VERSION => requested,
+            _ => PROTOCOL_VERSION,
+        };
+        let mut result = json!({
+            "protocolVersion": version,
+            "capabilities": { "tools": { "listChanged": false } },
+            "serverInfo": self.info,
+        });
+        if let Some(instructions) = &self.instructions {
+            result["instructions"] = json!(instructions);
+        }
+        result
+    }
+
+    async fn call_tool(&self, params: &Value) -> Value {
+        let name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let args = params
+            .get("arguments")
+            .cloned()
+            .unwrap_or_else(|| json!({}));
+        let outcome = match name.split_once('_') {
+            Some(("ask", id)) => self.ask(id, args).await.map(Value::String),
+            Some(("run", chain)) => self.run_chain(chain, args).await,
+            _ => Err(LLMError::InvalidRequest(format!("unknown tool '{name}'"))),
+        };
+        match outcome {
+            Ok(Value::String(text)) => json!({ "content": [{ "type": "text", "text": text }] }),
+            Ok(value) => json!({
+                "content": [{ "type": "text", "text": value.to_string() }],
+                "structuredContent": value,
+            }),
+            Err(err) => json!({
+                "content": [{ "type": "text", "text": err.to_string() }],
+                "isError": true,
+            }),
+        }
+    }
+
+    async fn ask(&self, tool_id: &str, args: Value) -> Result<String, LLMError> {
+        let provider = self
+            .provider_ids()
+            .into_iter()
+            .find(|(name, _)| name == tool_id)
+            .and_then(|(_, id)| self.registry.backends.get(id))
+            .map(|provider| provider.as_ref())
+            .ok_or_else(|| LLMError::InvalidRequest(format!("unknown provider '{tool_id}'")))?;
+        let prompt = args
+            .get("prompt")
+            .and_then(Value::as_str)
+            .ok_or_else(|| LLMError::InvalidRequest("missing 'prompt' argument".to_string()))?;
+        let history: Vec<HistoryMessage> = match args.get("history") {
+            Some(history) => serde_json::from_value(history.clone())?,
+            None => Vec::new(),
+        };
+
+        let mut messages: Vec<ChatMessage> = history
+            .into_iter()
+            .map(|m| match m.role.as_str() {
+                "assistant" => ChatMessage::assistant().content(m.content).build(),
+                _ => ChatMessage::user().content(m.content).build(),
+            })
+            .collect();
+        messages.push(ChatMessage::user().content(prompt).build());
+        let response = provider.chat(&messages).await?;
+        Ok(response.text().unwrap_or_default())
+    }
+
+    async fn run_chain(&self, tool_id: &str, args: Value) -> Result<Value, LLMError> {
+        let chain = self
+            .chain_ids()
+            .into_iter()
+            .find(|(name, _)| name == tool_id)
+            .map(|(_, name)| &self.chains[name])
+            .ok_or_else(|| LLMError::InvalidRequest(format!("unknown chain '{tool_id}'")))?;
+        let mut inputs = HashMap::new();
+        for input in &chain.inputs {
+            let value = match args.get(input) {
+                Some(Value::String(s)) => s.clone(),
+                Some(other) => other.to_string(),
+                None => {
+                    return Err(LLMError::InvalidRequest(format!(
+                        "missing '{input}' argument"
+                    )))
+                }
+            };
+            inputs.insert(input.clone(), value);
+        }
+
+        let steps = (chain.factory)()?;
+        let outputs = MultiPromptChain::new(&self.registry)
+            .inputs(inputs)
+            .chain(steps)
+            .run()
+            .await?;
+        let steps: Map<String, Value> = outputs
+            .into_iter()
+            .filter(|(key, _)| !chain.inputs.contains(key))
+            .map(|(key, value)| (key, Value::String(value)))
+            .collect();
+        Ok(Value::Object(steps))
+    }
+
+    /// Tool ids of the providers, paired with their provider id.
+    fn provider_ids(&self) -> Vec<(String, &str)> {
+        unique_ids(self.registry.backends.keys().map(|id| id.as_str()))
+    }
+
+    /// Tool ids of the chains, paired with their name.
+    fn chain_ids(&self) -> Vec<(String, &str)> {
+        unique_ids(self.chains.keys().map(String::as_str))
+    }
+}
+
+impl LLMRegistry {
+    /// Exposes every registered provider as an `ask_<id>` MCP tool over stdio.
+    /// Use [`McpServer`] directly to also expose chains.
+    pub async fn serve_mcp(self) -> Result<(), LLMError> {
+        McpServer::new(self).serve_stdio().await
+    }
+}
+
+/// Sanitized ids in sorted order. Ids that sanitize to the same name, like
+/// `critic.v2` and `critic_v2`, get a `_2`, `_3`, ... suffix in that order.
+fn unique_ids<'a>(ids: impl Iterator<Item = &'a str>) -> Vec<(String, &'a str)> {
+    let mut ids: Vec<&str> = ids.collect();
+    ids.sort_unstable();
+    let mut taken = HashSet::new();
+    ids.into_iter()
+        .map(|id| {
+            let base = sanitize(id);
+            let mut name = base.clone();
+            let mut n = 2;
+            while !taken.insert(name.clone()) {
+                name = format!("{base}_{n}");
+                n += 1;
+            }
+            (name, id)
+        })
+        .collect()
+}
+
+/// MCP tool names are limited to letters, digits, `_` and `-`.
+fn sanitize(id: &str) -> String {
+    id.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
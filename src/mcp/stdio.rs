@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{oneshot, Mutex as AsyncMutex};
+use tokio::task::JoinHandle;
+
+use crate::error::LLMError;
+
+use super::protocol::reply_to_server_request;
+use super::transport::{request_id, McpTransport};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+type Writer = Arc<AsyncMutex<Box<dyn AsyncWrite + Send + Unpin>>>;
+type Pending = Arc<Mutex<PendingRequests>>;
+
+/// Requests waiting for their response, keyed by JSON-RPC id.
+#[derive(Default)]
+struct PendingRequests {
+    senders: HashMap<String, oneshot::Sender<Value>>,
+    /// The server's output ended, so no response will ever arrive
+    closed: bool,
+}
+
+fn lock(pending: &Pending) -> MutexGuard<'_, PendingRequests> {
+    pending.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn connection_closed() -> LLMError {
+    LLMError::ProviderError("MCP server closed the connection".to_string())
+}
+
+/// Newline-delimited JSON-RPC over a pair of byte streams, usually the stdin and
+/// stdout of a server subprocess.
+pub struct StdioTransport {
+    writer: Writer,
+    pending: Pending,
+    reader: JoinHandle<()>,
+    child: Option<AsyncMutex<Child>>,
+    timeout: Duration,
+}
+
+impl StdioTransport {
+    /// Spawns `program` with `args` and talks to it over its stdin/stdout.
+    pub fn spawn<I, S>(program: impl AsRef<OsStr>, args: I) -> Result<Self, LLMError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut command = Command::new(program);
+        command.args(args);
+        Self::from_command(command)
+    }
+
+    /// Spawns a prepared command (environment, working directory...). The server's
+    /// stderr is forwarded to the `debug` log.
+    pub fn from_command(mut command: Command) -> Result<Self, LLMError> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| LLMError::ProviderError(format!("failed to start MCP server: {e}")))?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    log::debug!("mcp server: {line}");
+                }
+            });
+        }
+        let mut transport = Self::new(stdout, stdin);
+        transport.child = Some(AsyncMutex::new(child));
+        Ok(transport)
+    }
+
+    /// Uses an existing reader/writer pair, e.g. an in-process server.
+    pub fn new<R, W>(reader: R, writer: W) -> Self
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        let writer: Writer = Arc::new(AsyncMutex::new(Box::new(writer)));
+        let pending: Pending = Arc::default();
+        let reader = tokio::spawn(read_loop(reader, Arc::clone(&writer), Arc::clone(&pending)));
+        Self {
+            writer,
+            pending,
+            reader,
+            child: None,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Maximum time to wait for a response (default 60 seconds).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+async fn write_message(writer: &Writer, message: &Value) -> Result<(), LLMError> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    let mut writer = writer.lock().await;
+    writer
+        .write_all(&line)
+        .await
+        .and(writer.flush().await)
+        .map_err(|e| LLMError::ProviderError(format!("failed to write to MCP server: {e}")))
+}
+
+async fn read_loop<R>(reader: R, writer: Writer, pending: Pending)
+where
+    R: AsyncRead + Send + Unpin,
+{
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            log::debug!("ignoring non JSON-RPC output from MCP server: {line}");
+            continue;
+        };
+        if message.get("method").is_some() {
+            if let Some(reply) = reply_to_server_request(&message) {
+                if let Err(err) = write_message(&writer, &reply).await {
+                    log::warn!("{err}");
+                }
+            }
+            continue;
+        }
+        let Some(id) = message.get("id").map(Value::to_string) else {
+            continue;
+        };
+        if let Some(sender) = lock(&pending).senders.remove(&id) {
+            let _ = sender.send(message);
+        }
+    }
+    mark_closed(&pending);
+}
+
+/// Fails every request still waiting, by dropping their senders, and the later
+/// ones without sending them.
+fn mark_closed(pending: &Pending) {
+    let mut pending = lock(pending);
+    pending.closed = true;
+    pending.senders.clear();
+}
+
+#[async_trait]
+impl McpTransport for StdioTransport {
+    async fn request(&self, message: Value) -> Result<Value, LLMError> {
+        let id = request_id(&message)?;
+        let (tx, rx) = oneshot::channel();
+        {
+            let mut pending = lock(&self.pending);
+            if pending.closed {
+                return Err(connection_closed());
+            }
+            pending.senders.insert(id.clone(), tx);
+        }
+        if let Err(err) = write_message(&self.writer, &message).await {
+            lock(&self.pending).senders.remove(&id);
+            return Err(err);
+        }
+        match tokio::time::timeout(self.timeout, rx).await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(_)) => Err(connection_closed()),
+            Err(_) => {
+                lock(&self.pending).senders.remove(&id);
+                Err(LLMError::HttpError(format!(
+                    "MCP request timed out after {:?}",
+                    self.timeout
+                )))
+            }
+        }
+    }
+
+    async fn notify(&self, message: Value) -> Result<(), LLMError> {
+        write_message(&self.writer, &message).await
+    }
+
+    async fn close(&self) -> Result<(), LLMError> {
+        self.reader.abort();
+        mark_closed(&self.pending);
+        if let Some(child) = &self.child {
+            let _ = child.lock().await.kill().await;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for StdioTransport {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
@@ -0,0 +1,473 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+use crate::{chat::ChatRole, testing::MockLLM, tool_runtime::ToolRuntime, FunctionCall, ToolCall};
+
+use super::{HttpTransport, McpClient, McpContent, StdioTransport, PROTOCOL_VERSION};
+
+/// Answers a JSON-RPC request the way a small MCP server would.
+fn stub_response(request: &Value) -> Option<Value> {
+    let id = request.get("id")?.clone();
+    let params = &request["params"];
+    let result = match request["method"].as_str()? {
+        "initialize" => json!({
+            "protocolVersion": if params["protocolVersion"] == PROTOCOL_VERSION {
+                params["protocolVersion"].clone()
+            } else {
+                json!(PROTOCOL_VERSION)
+            },
+            "capabilities": { "tools": {}, "resources": {}, "prompts": {} },
+            "serverInfo": { "name": "stub", "version": "0.1.0" },
+            "instructions": "be nice"
+        }),
+        "tools/list" if params.get("cursor").is_none() => json!({
+            "tools": [{
+                "name": "add",
+                "description": "Adds two numbers",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": { "a": { "type": "number" }, "b": { "type": "number" } },
+                    "required": ["a", "b"]
+                }
+            }],
+            "nextCursor": "page-2"
+        }),
+        "tools/list" => json!({ "tools": [{ "name": "fail" }] }),
+        "tools/call" if params["name"] == "add" => {
+            let sum = params["arguments"]["a"].as_f64().unwrap_or(0.0)
+                + params["arguments"]["b"].as_f64().unwrap_or(0.0);
+            json!({ "content": [{ "type": "text", "text": sum.to_string() }] })
+        }
+        "tools/call" => json!({
+            "content": [{ "type": "text", "text": "tool exploded" }],
+            "isError": true
+        }),
+        "resources/list" => json!({
+            "resources": [{ "uri": "file:///notes.txt", "name": "notes", "mimeType": "text/plain" }]
+        }),
+        "resources/read" => json!({
+            "contents": [{ "uri": params["uri"], "text": "remember the milk" }]
+        }),
+        "prompts/list" => json!({
+            "prompts": [{ "name": "greet", "arguments": [{ "name": "who", "required": true }] }]
+        }),
+        "prompts/get" => json!({
+            "messages": [
+                { "role": "user", "content": { "type": "text", "text": format!("Say hi to {}", params["arguments"]["who"].as_str().unwrap_or("?")) } },
+                { "role": "assistant", "content": { "type": "image", "data": "AA==", "mimeType": "image/png" } }
+            ]
+        }),
+        other => {
+            return Some(json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": -32601, "message": format!("unknown method {other}") }
+            }))
+        }
+    };
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+/// Connects a client to a stub server running on the other end of an in-memory pipe.
+/// The server also emits log noise, a notification and a ping before each response.
+async fn stdio_client() -> McpClient {
+    let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+    let (server_read, mut server_write) = tokio::io::split(server_io);
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(server_read).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let request: Value = serde_json::from_str(&line).unwrap();
+            let Some(response) = stub_response(&request) else {
+                continue;
+            };
+            let noise = "starting up...\n{\"jsonrpc\":\"2.0\",\"method\":\"notifications/message\"}\n{\"jsonrpc\":\"2.0\",\"id\":\"srv-1\",\"method\":\"ping\"}\n";
+            server_write.write_all(noise.as_bytes()).await.unwrap();
+            let mut out = serde_json::to_vec(&response).unwrap();
+            out.push(b'\n');
+            server_write.write_all(&out).await.unwrap();
+        }
+    });
+    let (read, write) = tokio::io::split(client_io);
+    McpClient::connect(StdioTransport::new(read, write))
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn discovers_paginated_tools_and_proxies_calls() {
+    let client = stdio_client().await;
+    assert_eq!(client.server_info().name, "stub");
+    assert_eq!(client.protocol_version(), PROTOCOL_VERSION);
+    assert_eq!(client.instructions(), Some("be nice"));
+
+    let tools = client.chat_tools().await.unwrap();
+    assert_eq!(
+        tools
+            .iter()
+            .map(|t| t.function.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["add", "fail"]
+    );
+    assert_eq!(tools[0].function.parameters["required"], json!(["a", "b"]));
+    assert_eq!(tools[1].function.parameters["type"], "object");
+
+    let call = ToolCall {
+        id: "call_1".to_string(),
+        call_type: "function".to_string(),
+        function: FunctionCall {
+            name: "add".to_string(),
+            arguments: r#"{"a":2,"b":3}"#.to_string(),
+        },
+    };
+    let result = client.call(&call).await.unwrap();
+    assert_eq!(result.text(), "5");
+    let failed = client.call_tool("fail", json!({})).await.unwrap();
+    assert!(failed.is_error);
+    assert!(failed.into_value().is_err());
+}
+
+#[tokio::test]
+async fn exposes_resources_and_prompts() {
+    let client = stdio_client().await;
+
+    let resources = client.list_resources().await.unwrap();
+    assert_eq!(resources[0].mime_type.as_deref(), Some("text/plain"));
+    let contents = client.read_resource(&resources[0].uri).await.unwrap();
+    assert_eq!(contents[0].text.as_deref(), Some("remember the milk"));
+
+    let prompts = client.list_prompts().await.unwrap();
+    assert!(prompts[0].arguments[0].required);
+    let args = HashMap::from([("who".to_string(), "Ada".to_string())]);
+    let prompt = client.get_prompt("greet", args).await.unwrap();
+    assert!(matches!(
+        prompt.messages[1].content,
+        McpContent::Image { .. }
+    ));
+    let messages = prompt.to_chat_messages();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].role, ChatRole::User);
+    assert_eq!(messages[0].content, "Say hi to Ada");
+}
+
+#[tokio::test]
+async fn tool_runtime_proxies_model_calls_to_the_server() {
+    let client = Arc::new(stdio_client().await);
+    let runtime = ToolRuntime::new().register_mcp(client).await.unwrap();
+    assert_eq!(runtime.tools().len(), 2);
+
+    let mock = MockLLM::new();
+    mock.push_tool_calls(vec![
+        ToolCall {
+            id: "1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "add".to_string(),
+                arguments: r#"{"a":1,"b":1}"#.to_string(),
+            },
+        },
+        ToolCall {
+            id: "2".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "fail".to_string(),
+                arguments: "{}".to_string(),
+            },
+        },
+    ]);
+    mock.push_text("done");
+
+    let outcome = runtime
+        .run(
+            &mock,
+            vec![crate::chat::ChatMessage::user().content("go").build()],
+        )
+        .await
+        .unwrap();
+    assert_eq!(outcome.executions[0].output, json!("2"));
+    assert!(outcome.executions[1].is_error);
+}
+
+#[tokio::test]
+async fn closed_server_fails_pending_requests() {
+    let (client_io, server_io) = tokio::io::duplex(1024);
+    let (read, write) = tokio::io::split(client_io);
+    drop(server_io);
+    let result = McpClient::connect(StdioTransport::new(read, write)).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn requests_fail_fast_once_the_server_output_ends() {
+    // The server keeps reading its input but stops writing after initialization,
+    // like a process that closed its stdout.
+    let (client_read, server_write) = tokio::io::duplex(64 * 1024);
+    let (server_read, client_write) = tokio::io::duplex(64 * 1024);
+    tokio::spawn(async move {
+        let mut server_write = server_write;
+        let mut lines = BufReader::new(server_read).lines();
+        let line = lines.next_line().await.unwrap().unwrap();
+        let response = stub_response(&serde_json::from_str(&line).unwrap()).unwrap();
+        let mut out = serde_json::to_vec(&response).unwrap();
+        out.push(b'\n');
+        server_write.write_all(&out).await.unwrap();
+        drop(server_write);
+        while let Ok(Some(_)) = lines.next_line().await {}
+    });
+    let client = McpClient::connect(StdioTransport::new(client_read, client_write))
+        .await
+        .unwrap();
+
+    let listed = tokio::time::timeout(std::time::Duration::from_secs(5), client.list_tools())
+        .await
+        .expect("request waited for a closed server");
+    assert!(listed
+        .unwrap_err()
+        .to_string()
+        .contains("closed the connection"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn spawns_stdio_server_process() {
+    let script = r#"
+        read -r line
+        echo 'booting'
+        echo '{"jsonrpc":"2.0","id":1,"result":{"protocolVersion":"2025-03-26","capabilities":{},"serverInfo":{"name":"sh-stub"}}}'
+        read -r line
+        read -r line
+        echo '{"jsonrpc":"2.0","id":2,"result":{"tools":[{"name":"echo"}]}}'
+        read -r line
+    "#;
+    let client = McpClient::stdio("sh", ["-c", script]).await.unwrap();
+    assert_eq!(client.server_info().name, "sh-stub");
+    assert_eq!(client.list_tools().await.unwrap()[0].name, "echo");
+    client.close().await.unwrap();
+}
+
+/// Minimal streamable HTTP server: JSON responses, except `tools/call` which is
+/// answered as an SSE stream. Checks the session header after initialization.
+async fn spawn_http_stub() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut socket, _)) = listener.accept().await else {
+                return;
+            };
+            tokio::spawn(async move {
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 4096];
+                let (head, body) = loop {
+                    let n = socket.read(&mut chunk).await.unwrap();
+                    if n == 0 {
+                        return;
+                    }
+                    buf.extend_from_slice(&chunk[..n]);
+                    let text = String::from_utf8_lossy(&buf).to_string();
+                    let Some((head, body)) = text.split_once("\r\n\r\n") else {
+                        continue;
+                    };
+                    let length = head
+                        .lines()
+                        .find_map(|l| {
+                            l.to_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|v| v.trim().parse::<usize>().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if body.len() >= length {
+                        break (head.to_lowercase(), body.to_string());
+                    }
+                };
+
+                let request: Value = serde_json::from_str(&body).unwrap_or(Value::Null);
+                let initialized = request["method"] != "initialize";
+                if initialized && !head.contains("mcp-session-id: session-42") {
+                    let reply = "HTTP/1.1 400 Bad Request\r\ncontent-length: 0\r\n\r\n";
+                    socket.write_all(reply.as_bytes()).await.unwrap();
+                    return;
+                }
+                let reply = match stub_response(&request) {
+                    None => "HTTP/1.1 202 Accepted\r\ncontent-length: 0\r\n\r\n".to_string(),
+                    Some(response) if request["method"] == "tools/call" => {
+                        let body = format!(
+                            "event: message\ndata: {}\n\nevent: message\ndata: {}\n\n",
+                            json!({ "jsonrpc": "2.0", "method": "notifications/progress" }),
+                            response
+                        );
+                        format!(
+                            "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ncontent-length: {}\r\n\r\n{body}",
+                            body.len()
+                        )
+                    }
+                    Some(response) => {
+                        let body = response.to_string();
+                        format!(
+                            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\nmcp-session-id: session-42\r\ncontent-length: {}\r\n\r\n{body}",
+                            body.len()
+                        )
+                    }
+                };
+                socket.write_all(reply.as_bytes()).await.unwrap();
+            });
+        }
+    });
+    format!("http://{addr}/mcp")
+}
+
+#[tokio::test]
+async fn streamable_http_transport_keeps_session_and_reads_sse() {
+    let url = spawn_http_stub().await;
+    let client = McpClient::connect(HttpTransport::new(url)).await.unwrap();
+
+    let tools = client.list_tools().await.unwrap();
+    assert_eq!(tools.len(), 2);
+    let result = client
+        .call_tool("add", json!({ "a": 20, "b": 22 }))
+        .await
+        .unwrap();
+    assert_eq!(result.into_value().unwrap(), json!("42"));
+    client.close().await.unwrap();
+}
+
+fn served_registry_client(
+    server: super::McpServer,
+) -> impl std::future::Future<Output = McpClient> {
+    let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+    let (server_read, server_write) = tokio::io::split(server_io);
+    tokio::spawn(server.serve(server_read, server_write));
+    let (read, write) = tokio::io::split(client_io);
+    async move {
+        McpClient::connect(StdioTransport::new(read, write))
+            .await
+            .unwrap()
+    }
+}
+
+#[tokio::test]
+async fn registry_server_offers_its_own_protocol_version() {
+    let server = super::McpServer::new(crate::chain::LLMRegistryBuilder::new().build());
+    for (requested, answered) in [
+        (PROTOCOL_VERSION, PROTOCOL_VERSION),
+        ("1999-01-01", PROTOCOL_VERSION),
+    ] {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": { "protocolVersion": requested, "capabilities": {} }
+        });
+        let response = server.handle_line(&request.to_string()).await.unwrap();
+        assert_eq!(response["result"]["protocolVersion"], answered);
+    }
+    let response = server
+        .handle_line(r#"{"jsonrpc":"2.0","id":2,"method":"initialize","params":{}}"#)
+        .await
+        .unwrap();
+    assert_eq!(response["result"]["protocolVersion"], PROTOCOL_VERSION);
+}
+
+#[tokio::test]
+async fn registry_server_exposes_providers_and_chains() {
+    use crate::chain::{LLMRegistryBuilder, MultiChainStepBuilder, MultiChainStepMode};
+
+    let writer = MockLLM::new();
+    writer.push_text("a short poem");
+    writer.push_text("a haiku about rust");
+    let critic = MockLLM::new();
+    critic.push_text("4/5");
+    let registry = LLMRegistryBuilder::new()
+        .register("writer", Box::new(writer.clone()))
+        .register("critic.v2", Box::new(critic.clone()))
+        .build();
+
+    let server = super::McpServer::new(registry)
+        .instructions("ask or run")
+        .chain("review", "Write then review", &["topic"], || {
+            Ok(vec![
+                MultiChainStepBuilder::new(MultiChainStepMode::Chat)
+                    .provider_id("writer")
+                    .id("draft")
+                    .template("Write about {{topic}}")
+                    .build()?,
+                MultiChainStepBuilder::new(MultiChainStepMode::Chat)
+                    .provider_id("critic.v2")
+                    .id("score")
+                    .template("Rate: {{draft}}")
+                    .build()?,
+            ])
+        });
+    let client = served_registry_client(server).await;
+    assert_eq!(client.server_info().name, "llm");
+    assert_eq!(client.instructions(), Some("ask or run"));
+
+    let tools = client.list_tools().await.unwrap();
+    let names: Vec<_> = tools.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["ask_critic_v2", "ask_writer", "run_review"]);
+    assert_eq!(tools[2].input_schema["required"], json!(["topic"]));
+
+    let answer = client
+        .call_tool(
+            "ask_writer",
+            json!({ "prompt": "a poem", "history": [{ "role": "assistant", "content": "hi" }] }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(answer.text(), "a short poem");
+    assert_eq!(writer.last_messages().unwrap().len(), 2);
+
+    let run = client
+        .call_tool("run_review", json!({ "topic": "rust" }))
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert_eq!(
+        run,
+        json!({ "draft": "a haiku about rust", "score": "4/5" })
+    );
+    critic.assert_last_user_message_contains("Rate: a haiku about rust");
+    writer.assert_last_user_message_contains("Write about rust");
+
+    let missing = client.call_tool("run_review", json!({})).await.unwrap();
+    assert!(missing.is_error && missing.text().contains("topic"));
+    let unknown = client
+        .call_tool("ask_nobody", json!({ "prompt": "?" }))
+        .await
+        .unwrap();
+    assert!(unknown.is_error);
+}
+
+#[tokio::test]
+async fn providers_with_colliding_tool_names_stay_reachable() {
+    use crate::chain::LLMRegistryBuilder;
+
+    let dotted = MockLLM::new();
+    dotted.push_text("dotted");
+    let underscored = MockLLM::new();
+    underscored.push_text("underscored");
+    let registry = LLMRegistryBuilder::new()
+        .register("critic_v2", Box::new(underscored))
+        .register("critic.v2", Box::new(dotted))
+        .build();
+    let client = served_registry_client(super::McpServer::new(registry)).await;
+
+    let tools = client.list_tools().await.unwrap();
+    let names: Vec<_> = tools.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["ask_critic_v2", "ask_critic_v2_2"]);
+
+    for (tool, answer) in [
+        ("ask_critic_v2", "dotted"),
+        ("ask_critic_v2_2", "underscored"),
+    ] {
+        let result = client
+            .call_tool(tool, json!({ "prompt": "?" }))
+            .await
+            .unwrap();
+        assert_eq!(result.text(), answer);
+    }
+}
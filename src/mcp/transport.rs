@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::error::LLMError;
+
+/// Channel carrying JSON-RPC messages to an MCP server.
+#[async_trait]
+pub trait McpTransport: Send + Sync {
+    /// Sends a request and waits for the response carrying the same id.
+    async fn request(&self, message: Value) -> Result<Value, LLMError>;
+
+    /// Sends a notification, which has no response.
+    async fn notify(&self, message: Value) -> Result<(), LLMError>;
+
+    /// Ends the session.
+    async fn close(&self) -> Result<(), LLMError> {
+        Ok(())
+    }
+}
+
+pub(super) fn request_id(message: &Value) -> Result<String, LLMError> {
+    message
+        .get("id")
+        .map(Value::to_string)
+        .ok_or_else(|| LLMError::InvalidRequest("MCP request without id".to_string()))
+}
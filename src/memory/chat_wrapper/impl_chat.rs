@@ -1,15 +1,19 @@
+use std::pin::Pin;
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use futures::future::try_join_all;
+use futures::stream::{self, Stream, StreamExt};
 
 use crate::{
-    chat::{ChatMessage, ChatProvider, ChatResponse, ChatRole, Tool},
+    chat::{ChatMessage, ChatProvider, ChatResponse, ChatRole, StreamChunk, Tool},
     error::LLMError,
 };
 
 use super::wrapper::ChatWithMemory;
 
+type ChunkStream = Pin<Box<dyn Stream<Item = Result<StreamChunk, LLMError>> + Send>>;
+
 #[async_trait]
 impl ChatProvider for ChatWithMemory {
     async fn chat_with_tools(
@@ -17,15 +21,7 @@ impl ChatProvider for ChatWithMemory {
         messages: &[ChatMessage],
         tools: Option<&[Tool]>,
     ) -> Result<Box<dyn ChatResponse>, LLMError> {
-        let normalized = self.normalize_messages(messages).await?;
-        self.reset_cycle_counter(&normalized);
-        self.remember_messages(&normalized).await?;
-
-        let mut context = self.load_context().await?;
-        let summarized = self.maybe_summarize(&mut context).await?;
-        if summarized {
-            context.extend_from_slice(&normalized);
-        }
+        let context = self.prepare_context(messages).await?;
 
         let response = self.provider.chat_with_tools(&context, tools).await?;
         if let Some(text) = response.text() {
@@ -35,12 +31,49 @@ impl ChatProvider for ChatWithMemory {
         Ok(response)
     }
 
+    /// Streams the answer to the remembered context; the assistant turn, with its
+    /// tool calls, is remembered once the stream is read to the end. Providers
+    /// that cannot stream with tools answer in one piece.
+    async fn chat_stream_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<ChunkStream, LLMError> {
+        let context = self.prepare_context(messages).await?;
+
+        let chunks = match self.provider.chat_stream_with_tools(&context, tools).await {
+            Err(LLMError::Unsupported(_)) => {
+                let response = self.provider.chat_with_tools(&context, tools).await?;
+                response_chunks(response.as_ref())
+            }
+            chunks => chunks?,
+        };
+        Ok(self.record_stream(chunks))
+    }
+
     async fn memory_contents(&self) -> Option<Vec<ChatMessage>> {
         Some(self.memory_contents().await)
     }
 }
 
 impl ChatWithMemory {
+    /// Remembers the new messages and loads the context to send to the provider.
+    async fn prepare_context(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        let normalized = self.normalize_messages(messages).await?;
+        self.reset_cycle_counter(&normalized);
+        self.remember_messages(&normalized).await?;
+
+        let mut context = self.load_context().await?;
+        let summarized = self.maybe_summarize(&mut context).await?;
+        if summarized {
+            context.extend_from_slice(&normalized);
+        }
+        Ok(context)
+    }
+
     fn reset_cycle_counter(&self, messages: &[ChatMessage]) {
         if messages.iter().any(|m| matches!(m.role, ChatRole::User)) {
             self.cycle_counter
@@ -118,6 +151,68 @@ impl ChatWithMemory {
             }
         });
     }
+
+    fn record_stream(&self, chunks: ChunkStream) -> ChunkStream {
+        let turn = Arc::new(std::sync::Mutex::new((String::new(), Vec::new())));
+        let seen = turn.clone();
+        let recorded = chunks.inspect(move |chunk| {
+            let mut turn = seen.lock().unwrap_or_else(|e| e.into_inner());
+            match chunk {
+                Ok(StreamChunk::Text(text)) => turn.0.push_str(text),
+                Ok(StreamChunk::ToolUseComplete { tool_call, .. }) => {
+                    turn.1.push(tool_call.clone())
+                }
+                _ => {}
+            }
+        });
+
+        let memory = self.memory.clone();
+        let role = self.role.clone();
+        let persist = stream::once(async move {
+            let (text, tool_calls) =
+                std::mem::take(&mut *turn.lock().unwrap_or_else(|e| e.into_inner()));
+            let saved = if tool_calls.is_empty() {
+                if text.is_empty() {
+                    return;
+                }
+                persist_response(memory, role, text).await
+            } else {
+                let msg = ChatMessage::assistant()
+                    .tool_use(tool_calls)
+                    .content(text)
+                    .build();
+                memory.write().await.remember(&msg).await
+            };
+            if let Err(err) = saved {
+                log::warn!("Memory save error: {err}");
+            }
+        });
+        Box::pin(recorded.chain(persist.filter_map(|()| async { None })))
+    }
+}
+
+/// Replays a complete response as stream chunks.
+fn response_chunks(response: &dyn ChatResponse) -> ChunkStream {
+    let mut chunks = Vec::new();
+    if let Some(text) = response.text().filter(|text| !text.is_empty()) {
+        chunks.push(Ok(StreamChunk::Text(text)));
+    }
+    let tool_calls = response.tool_calls().unwrap_or_default();
+    let stop_reason = if tool_calls.is_empty() {
+        "end_turn"
+    } else {
+        "tool_use"
+    };
+    for (index, tool_call) in tool_calls.into_iter().enumerate() {
+        chunks.push(Ok(StreamChunk::ToolUseComplete { index, tool_call }));
+    }
+    if let Some(usage) = response.usage() {
+        chunks.push(Ok(StreamChunk::Usage(usage)));
+    }
+    chunks.push(Ok(StreamChunk::Done {
+        stop_reason: stop_reason.to_string(),
+    }));
+    Box::pin(stream::iter(chunks))
 }
 
 async fn persist_response(
@@ -4,17 +4,19 @@ use std::sync::{
 };
 
 use async_trait::async_trait;
+use futures::StreamExt;
 
 use crate::{
-    chat::{ChatMessage, ChatProvider, ChatRole, Tool},
+    chat::{ChatMessage, ChatProvider, ChatRole, MessageType, StreamChunk, Tool},
     completion::{CompletionProvider, CompletionRequest, CompletionResponse},
     embedding::EmbeddingProvider,
     error::LLMError,
     memory::{ChatWithMemory, ChatWithMemoryConfig, MemoryProvider, SlidingWindowMemory},
     models::ModelsProvider,
     stt::SpeechToTextProvider,
+    testing::MockLLM,
     tts::TextToSpeechProvider,
-    LLMProvider,
+    FunctionCall, LLMProvider, ToolCall,
 };
 
 const RESPONSE_TEXT: &str = "ok";
@@ -131,3 +133,77 @@ async fn audio_messages_are_transcribed_before_chat() {
     assert_eq!(stored_msg.content, TRANSCRIPT);
     assert!(!stored_msg.has_audio());
 }
+
+fn sliding_memory() -> Arc<tokio::sync::RwLock<Box<dyn MemoryProvider>>> {
+    Arc::new(tokio::sync::RwLock::new(Box::new(
+        SlidingWindowMemory::new(10),
+    )))
+}
+
+#[tokio::test]
+async fn streamed_turns_are_remembered_with_their_tool_calls() {
+    let mock = MockLLM::new();
+    let call = ToolCall {
+        id: "call_1".to_string(),
+        call_type: "function".to_string(),
+        function: FunctionCall {
+            name: "lookup".to_string(),
+            arguments: "{}".to_string(),
+        },
+    };
+    mock.push_stream(vec![
+        StreamChunk::Text("Looking".to_string()),
+        StreamChunk::ToolUseComplete {
+            index: 0,
+            tool_call: call.clone(),
+        },
+        StreamChunk::Done {
+            stop_reason: "tool_use".to_string(),
+        },
+    ]);
+    let memory = sliding_memory();
+    let wrapper = ChatWithMemory::new(Arc::new(mock), Arc::clone(&memory));
+
+    let question = ChatMessage::user().content("find it").build();
+    let chunks: Vec<_> = wrapper
+        .chat_stream_with_tools(&[question], None)
+        .await
+        .expect("stream")
+        .collect()
+        .await;
+    assert_eq!(chunks.len(), 3);
+
+    let stored = memory.read().await.recall("", None).await.expect("recall");
+    assert_eq!(stored.len(), 2);
+    assert_eq!(stored[0].content, "find it");
+    assert_eq!(stored[1].content, "Looking");
+    assert!(
+        matches!(&stored[1].message_type, MessageType::ToolUse(calls) if calls[0].id == "call_1")
+    );
+}
+
+#[tokio::test]
+async fn providers_without_streaming_answer_in_one_chunk() {
+    let provider = RecordingProvider::new(
+        Arc::new(Mutex::new(Vec::new())),
+        Arc::new(AtomicUsize::new(0)),
+        TRANSCRIPT,
+        RESPONSE_TEXT,
+    );
+    let memory = sliding_memory();
+    let wrapper = ChatWithMemory::new(Arc::new(provider), Arc::clone(&memory));
+
+    let question = ChatMessage::user().content("hi").build();
+    let chunks: Vec<_> = wrapper
+        .chat_stream_with_tools(&[question], None)
+        .await
+        .expect("stream")
+        .map(|chunk| chunk.expect("chunk"))
+        .collect()
+        .await;
+    assert!(matches!(&chunks[0], StreamChunk::Text(text) if text == RESPONSE_TEXT));
+    assert!(matches!(&chunks[1], StreamChunk::Done { stop_reason } if stop_reason == "end_turn"));
+
+    let stored = memory.read().await.recall("", None).await.expect("recall");
+    assert_eq!(stored[1].content, RESPONSE_TEXT);
+}
@@ -0,0 +1,25 @@
+#[path = "middleware/types.rs"]
+mod types;
+
+#[path = "middleware/wrapper.rs"]
+mod wrapper;
+
+#[path = "middleware/chat.rs"]
+mod chat;
+
+#[path = "middleware/other.rs"]
+mod other;
+
+#[cfg(feature = "tracing")]
+#[path = "middleware/tracing.rs"]
+mod tracing;
+
+pub use types::{CallContext, CallInput, CallOutcome, LLMMiddleware, Operation};
+pub use wrapper::MiddlewareLLM;
+
+#[cfg(feature = "tracing")]
+pub use self::tracing::TracingMiddleware;
+
+#[cfg(test)]
+#[path = "middleware/tests.rs"]
+mod tests;
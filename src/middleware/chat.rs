@@ -0,0 +1,92 @@
+use async_trait::async_trait;
+
+use crate::{
+    chat::{ChatMessage, ChatProvider, ChatResponse, StreamChunk, StreamResponse, Tool},
+    error::LLMError,
+};
+
+use super::types::{CallInput, Operation};
+use super::wrapper::{BoxedStream, MiddlewareLLM};
+
+fn chat_input<'a>(messages: &'a [ChatMessage], tools: Option<&'a [Tool]>) -> CallInput<'a> {
+    CallInput::Chat { messages, tools }
+}
+
+fn struct_chunks(response: &StreamResponse) -> (Vec<StreamChunk>, Option<crate::chat::Usage>) {
+    let mut chunks = Vec::new();
+    for choice in &response.choices {
+        if let Some(content) = &choice.delta.content {
+            chunks.push(StreamChunk::Text(content.clone()));
+        }
+        for (index, tool_call) in choice.delta.tool_calls.iter().flatten().enumerate() {
+            chunks.push(StreamChunk::ToolUseComplete {
+                index,
+                tool_call: tool_call.clone(),
+            });
+        }
+    }
+    (chunks, response.usage.clone())
+}
+
+fn tool_chunks(chunk: &StreamChunk) -> (Vec<StreamChunk>, Option<crate::chat::Usage>) {
+    let usage = match chunk {
+        StreamChunk::Usage(usage) => Some(usage.clone()),
+        _ => None,
+    };
+    (vec![chunk.clone()], usage)
+}
+
+#[async_trait]
+impl ChatProvider for MiddlewareLLM {
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let call = self.inner.chat_with_tools(messages, tools);
+        self.observe(
+            Operation::Chat,
+            chat_input(messages, tools),
+            call,
+            |response| {
+                let finish_reason = match response.tool_calls() {
+                    Some(calls) if !calls.is_empty() => "tool_calls",
+                    _ => "stop",
+                };
+                (response.usage(), Some(finish_reason.to_string()))
+            },
+        )
+        .await
+    }
+
+    async fn chat_stream(&self, messages: &[ChatMessage]) -> Result<BoxedStream<String>, LLMError> {
+        let call = self.inner.chat_stream(messages);
+        self.observe_stream(chat_input(messages, None), call, |text| {
+            (vec![StreamChunk::Text(text.clone())], None)
+        })
+        .await
+    }
+
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<BoxedStream<StreamResponse>, LLMError> {
+        let call = self.inner.chat_stream_struct(messages);
+        self.observe_stream(chat_input(messages, None), call, struct_chunks)
+            .await
+    }
+
+    async fn chat_stream_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<BoxedStream<StreamChunk>, LLMError> {
+        let call = self.inner.chat_stream_with_tools(messages, tools);
+        self.observe_stream(chat_input(messages, tools), call, tool_chunks)
+            .await
+    }
+
+    async fn memory_contents(&self) -> Option<Vec<ChatMessage>> {
+        self.inner.memory_contents().await
+    }
+}
@@ -0,0 +1,81 @@
+use async_trait::async_trait;
+
+use crate::{
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    models::{ModelListRequest, ModelListResponse, ModelsProvider},
+    stt::SpeechToTextProvider,
+    tts::TextToSpeechProvider,
+};
+
+use super::types::{CallInput, Operation};
+use super::wrapper::MiddlewareLLM;
+
+#[async_trait]
+impl CompletionProvider for MiddlewareLLM {
+    async fn complete(&self, req: &CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        let call = self.inner.complete(req);
+        self.observe(
+            Operation::Completion,
+            CallInput::Completion(req),
+            call,
+            |_| (None, None),
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for MiddlewareLLM {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        let call = self.inner.embed(input.clone());
+        self.observe(
+            Operation::Embedding,
+            CallInput::Embedding(&input),
+            call,
+            |_| (None, None),
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl SpeechToTextProvider for MiddlewareLLM {
+    async fn transcribe(&self, audio: Vec<u8>) -> Result<String, LLMError> {
+        let input = audio.clone();
+        let call = self.inner.transcribe(audio);
+        self.observe(
+            Operation::Transcription,
+            CallInput::Transcription(&input),
+            call,
+            |_| (None, None),
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl TextToSpeechProvider for MiddlewareLLM {
+    async fn speech(&self, text: &str) -> Result<Vec<u8>, LLMError> {
+        let call = self.inner.speech(text);
+        self.observe(Operation::Speech, CallInput::Speech(text), call, |_| {
+            (None, None)
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for MiddlewareLLM {
+    async fn list_models(
+        &self,
+        request: Option<&ModelListRequest>,
+    ) -> Result<Box<dyn ModelListResponse>, LLMError> {
+        let call = self.inner.list_models(request);
+        self.observe(Operation::ListModels, CallInput::ListModels, call, |_| {
+            (None, None)
+        })
+        .await
+    }
+}
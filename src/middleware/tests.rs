@@ -0,0 +1,166 @@
+use std::sync::{Arc, Mutex};
+
+use futures::StreamExt;
+
+use crate::{
+    chat::{ChatMessage, ChatProvider, StreamChunk, Usage},
+    error::LLMError,
+    testing::{MockLLM, MockResponse},
+    LLMProvider,
+};
+
+use super::{CallContext, CallInput, CallOutcome, LLMMiddleware, MiddlewareLLM, Operation};
+
+#[derive(Default)]
+struct Recorder {
+    events: Mutex<Vec<String>>,
+    reject: bool,
+}
+
+impl LLMMiddleware for Recorder {
+    fn before_request(&self, ctx: &CallContext, input: &CallInput<'_>) -> Result<(), LLMError> {
+        let count = match input {
+            CallInput::Chat { messages, .. } => messages.len(),
+            _ => 0,
+        };
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("before {:?} {count}", ctx.operation));
+        if self.reject {
+            return Err(LLMError::InvalidRequest("blocked".to_string()));
+        }
+        Ok(())
+    }
+
+    fn after_response(&self, _ctx: &CallContext, outcome: &CallOutcome) {
+        self.events.lock().unwrap().push(format!(
+            "after {:?} {:?} {:?}",
+            outcome.usage.as_ref().map(|u| u.total_tokens),
+            outcome.finish_reason,
+            outcome.error_type
+        ));
+    }
+
+    fn on_stream_chunk(&self, _ctx: &CallContext, chunk: &StreamChunk) {
+        if let StreamChunk::Text(text) = chunk {
+            self.events.lock().unwrap().push(format!("chunk {text}"));
+        }
+    }
+}
+
+fn usage() -> Usage {
+    Usage {
+        prompt_tokens: 3,
+        completion_tokens: 2,
+        total_tokens: 5,
+        completion_tokens_details: None,
+        prompt_tokens_details: None,
+    }
+}
+
+fn wrap(mock: &MockLLM, recorder: Arc<Recorder>) -> MiddlewareLLM {
+    MiddlewareLLM::new(Box::new(mock.clone()), "mock", Some("m1".to_string()))
+        .with_middleware(recorder)
+}
+
+#[tokio::test]
+async fn hooks_wrap_chat_calls() {
+    let mock = MockLLM::new().with_response(MockResponse::text("hi").with_usage(usage()));
+    let recorder = Arc::new(Recorder::default());
+    let llm = wrap(&mock, recorder.clone());
+
+    llm.chat(&[ChatMessage::user().content("hello").build()])
+        .await
+        .unwrap();
+    assert!(llm.chat(&[]).await.is_err());
+
+    let events = recorder.events.lock().unwrap().clone();
+    assert_eq!(
+        events,
+        vec![
+            format!("before {:?} 1", Operation::Chat),
+            "after Some(5) Some(\"stop\") None".to_string(),
+            format!("before {:?} 0", Operation::Chat),
+            "after None None Some(\"InvalidRequest\")".to_string(),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn hooks_observe_streams_until_completion() {
+    let mock = MockLLM::new().with_response(MockResponse::text("streamed"));
+    let recorder = Arc::new(Recorder::default());
+    let llm = wrap(&mock, recorder.clone());
+
+    let stream = llm.chat_stream_with_tools(&[], None).await.unwrap();
+    assert_eq!(recorder.events.lock().unwrap().len(), 1);
+    let chunks: Vec<_> = stream.collect().await;
+    assert_eq!(chunks.len(), 2);
+
+    let events = recorder.events.lock().unwrap().clone();
+    assert_eq!(events[1], "chunk streamed");
+    assert_eq!(events[2], "after None Some(\"end_turn\") None");
+}
+
+#[tokio::test]
+async fn dropped_stream_still_calls_after_hook() {
+    let mock = MockLLM::new().with_response(MockResponse::text("streamed"));
+    let recorder = Arc::new(Recorder::default());
+    let llm = wrap(&mock, recorder.clone());
+
+    let mut stream = llm.chat_stream_with_tools(&[], None).await.unwrap();
+    stream.next().await.unwrap().unwrap();
+    drop(stream);
+
+    let events = recorder.events.lock().unwrap().clone();
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[2], "after None None Some(\"StreamDropped\")");
+}
+
+#[tokio::test]
+async fn rejecting_middleware_aborts_call() {
+    let mock = MockLLM::new().with_response(MockResponse::text("never"));
+    let recorder = Arc::new(Recorder {
+        reject: true,
+        ..Recorder::default()
+    });
+    let llm: Box<dyn LLMProvider> = Box::new(wrap(&mock, recorder.clone()));
+
+    assert!(matches!(
+        llm.chat(&[]).await,
+        Err(LLMError::InvalidRequest(_))
+    ));
+    assert_eq!(mock.call_count(), 0);
+    assert_eq!(recorder.events.lock().unwrap().len(), 2);
+}
+
+struct CallIds(Arc<Mutex<Vec<u64>>>);
+
+impl LLMMiddleware for CallIds {
+    fn before_request(&self, ctx: &CallContext, _input: &CallInput<'_>) -> Result<(), LLMError> {
+        self.0.lock().unwrap().push(ctx.id);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ollama")]
+#[tokio::test]
+async fn call_ids_are_unique_across_providers_sharing_a_middleware() {
+    use crate::builder::{LLMBackend, LLMBuilder};
+
+    let ids = Arc::new(Mutex::new(Vec::new()));
+    let template = LLMBuilder::new()
+        .backend(LLMBackend::Ollama)
+        .base_url("http://127.0.0.1:9")
+        .middleware(CallIds(ids.clone()));
+    let first = template.clone().model("a").build().unwrap();
+    let second = template.model("b").build().unwrap();
+
+    let messages = [ChatMessage::user().content("hi").build()];
+    let _ = tokio::join!(first.chat(&messages), second.chat(&messages));
+
+    let ids = ids.lock().unwrap().clone();
+    assert_eq!(ids.len(), 2);
+    assert_ne!(ids[0], ids[1]);
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use tracing::{field::Empty, Span};
+
+use crate::error::LLMError;
+
+use super::types::{CallContext, CallInput, CallOutcome, LLMMiddleware};
+
+/// Middleware emitting one `tracing` span per call, annotated with the
+/// OpenTelemetry GenAI semantic-convention attributes.
+///
+/// The span is opened in `before_request` (as a child of the current span) and closed
+/// once the response or the stream has completed, or the stream was dropped.
+#[derive(Default)]
+pub struct TracingMiddleware {
+    spans: Mutex<HashMap<u64, Span>>,
+}
+
+impl TracingMiddleware {
+    /// Creates a middleware with no open spans.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl LLMMiddleware for TracingMiddleware {
+    fn before_request(&self, ctx: &CallContext, _input: &CallInput<'_>) -> Result<(), LLMError> {
+        let operation = ctx.operation.as_str();
+        let model = ctx.model.as_deref().unwrap_or_default();
+        let span = tracing::info_span!(
+            "gen_ai",
+            otel.name = %format!("{operation} {model}").trim_end(),
+            otel.kind = "client",
+            gen_ai.operation.name = operation,
+            gen_ai.provider.name = %ctx.provider,
+            gen_ai.request.model = model,
+            gen_ai.usage.input_tokens = Empty,
+            gen_ai.usage.output_tokens = Empty,
+            gen_ai.response.finish_reasons = Empty,
+            error.type = Empty,
+            duration_ms = Empty,
+        );
+        self.spans.lock().expect("span lock").insert(ctx.id, span);
+        Ok(())
+    }
+
+    fn after_response(&self, ctx: &CallContext, outcome: &CallOutcome) {
+        let Some(span) = self.spans.lock().expect("span lock").remove(&ctx.id) else {
+            return;
+        };
+        if let Some(usage) = &outcome.usage {
+            span.record("gen_ai.usage.input_tokens", usage.prompt_tokens);
+            span.record("gen_ai.usage.output_tokens", usage.completion_tokens);
+        }
+        if let Some(reason) = &outcome.finish_reason {
+            span.record("gen_ai.response.finish_reasons", reason.as_str());
+        }
+        if let Some(error_type) = outcome.error_type {
+            span.record("error.type", error_type);
+        }
+        if let Some(error) = &outcome.error {
+            span.in_scope(|| tracing::warn!(error = %error, "LLM call failed"));
+        }
+        span.record("duration_ms", outcome.duration.as_millis() as u64);
+    }
+}
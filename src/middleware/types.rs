@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    chat::{ChatMessage, StreamChunk, Tool, Usage},
+    completion::CompletionRequest,
+    error::LLMError,
+};
+
+/// Kind of provider call being observed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    /// Non-streaming chat, with or without tools
+    Chat,
+    /// Streaming chat, with or without tools
+    ChatStream,
+    /// Text completion
+    Completion,
+    /// Embedding of one or more texts
+    Embedding,
+    /// Speech-to-text transcription
+    Transcription,
+    /// Text-to-speech synthesis
+    Speech,
+    /// Listing of the available models
+    ListModels,
+}
+
+impl Operation {
+    /// Operation name following the OpenTelemetry GenAI semantic conventions
+    /// (`gen_ai.operation.name`) where one exists.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operation::Chat | Operation::ChatStream => "chat",
+            Operation::Completion => "text_completion",
+            Operation::Embedding => "embeddings",
+            Operation::Transcription => "transcription",
+            Operation::Speech => "speech",
+            Operation::ListModels => "list_models",
+        }
+    }
+}
+
+/// Metadata shared by every hook of a single call.
+#[derive(Debug, Clone)]
+pub struct CallContext {
+    /// Identifier unique within the process
+    pub id: u64,
+    /// Kind of call being made
+    pub operation: Operation,
+    /// Backend name, e.g. `openai`
+    pub provider: String,
+    /// Model configured on the provider, if known
+    pub model: Option<String>,
+    /// Time the call started, before any `before_request` hook ran
+    pub started_at: Instant,
+}
+
+/// Request payload passed to [`LLMMiddleware::before_request`].
+#[derive(Debug, Clone, Copy)]
+pub enum CallInput<'a> {
+    /// Conversation and tools of a chat call, streaming or not
+    Chat {
+        /// Messages sent to the provider
+        messages: &'a [ChatMessage],
+        /// Tools offered to the model, if any
+        tools: Option<&'a [Tool]>,
+    },
+    /// Completion request
+    Completion(&'a CompletionRequest),
+    /// Texts to embed
+    Embedding(&'a [String]),
+    /// Audio bytes to transcribe
+    Transcription(&'a [u8]),
+    /// Text to synthesize
+    Speech(&'a str),
+    /// Model listing, which carries no payload
+    ListModels,
+}
+
+/// Result summary passed to [`LLMMiddleware::after_response`].
+///
+/// For streaming calls it is produced once the stream has been fully consumed, or
+/// with an error once it is dropped before its end.
+#[derive(Debug, Clone, Default)]
+pub struct CallOutcome {
+    /// Time elapsed since the call started
+    pub duration: Duration,
+    /// Usage reported by the provider, if any
+    pub usage: Option<Usage>,
+    /// Stop reason reported by the stream, or `tool_calls`/`stop` for chat responses
+    pub finish_reason: Option<String>,
+    /// Error message when the call failed
+    pub error: Option<String>,
+    /// Low-cardinality class of the error, the [`LLMError`] variant name or
+    /// `StreamDropped` for a stream abandoned before its end
+    pub error_type: Option<&'static str>,
+    /// A `before_request` hook refused the call, which never reached the provider
+    pub rejected: bool,
+}
+
+/// Hooks invoked around every call of a [`MiddlewareLLM`](super::MiddlewareLLM).
+///
+/// All hooks have no-op defaults so implementors only override what they need.
+pub trait LLMMiddleware: Send + Sync {
+    /// Called before the request is sent. Returning an error aborts the call.
+    fn before_request(&self, _ctx: &CallContext, _input: &CallInput<'_>) -> Result<(), LLMError> {
+        Ok(())
+    }
+
+    /// Called once the call completed, successfully or not.
+    fn after_response(&self, _ctx: &CallContext, _outcome: &CallOutcome) {}
+
+    /// Called for every chunk of a streaming call. Text-only streams are reported as
+    /// [`StreamChunk::Text`].
+    fn on_stream_chunk(&self, _ctx: &CallContext, _chunk: &StreamChunk) {}
+}
@@ -0,0 +1,227 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use futures::stream::{self, Stream, StreamExt};
+
+use crate::{
+    chat::{StreamChunk, Tool, Usage},
+    error::LLMError,
+    LLMProvider,
+};
+
+use super::types::{CallContext, CallInput, CallOutcome, LLMMiddleware, Operation};
+
+pub(super) type BoxedStream<T> = Pin<Box<dyn Stream<Item = Result<T, LLMError>> + Send>>;
+
+/// Source of [`CallContext::id`], shared by every wrapper so middlewares attached to
+/// several providers never see the same id twice.
+static NEXT_CALL_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Wrapper running a chain of [`LLMMiddleware`] hooks around every provider call.
+pub struct MiddlewareLLM {
+    pub(super) inner: Box<dyn LLMProvider>,
+    middlewares: Vec<Arc<dyn LLMMiddleware>>,
+    provider: String,
+    model: Option<String>,
+}
+
+impl MiddlewareLLM {
+    /// Creates a wrapper without hooks. `provider` and `model` are reported in every
+    /// [`CallContext`].
+    pub fn new(
+        inner: Box<dyn LLMProvider>,
+        provider: impl Into<String>,
+        model: Option<String>,
+    ) -> Self {
+        Self {
+            inner,
+            middlewares: Vec::new(),
+            provider: provider.into(),
+            model,
+        }
+    }
+
+    /// Appends a middleware. Hooks run in registration order.
+    pub fn with_middleware(mut self, middleware: Arc<dyn LLMMiddleware>) -> Self {
+        self.middlewares.push(middleware);
+        self
+    }
+
+    fn start(&self, operation: Operation, input: &CallInput<'_>) -> Result<CallContext, LLMError> {
+        let ctx = CallContext {
+            id: NEXT_CALL_ID.fetch_add(1, Ordering::Relaxed),
+            operation,
+            provider: self.provider.clone(),
+            model: self.model.clone(),
+            started_at: Instant::now(),
+        };
+        for middleware in &self.middlewares {
+            if let Err(err) = middleware.before_request(&ctx, input) {
+                let mut outcome = CallOutcome {
+                    duration: ctx.started_at.elapsed(),
+                    rejected: true,
+                    ..CallOutcome::default()
+                };
+                outcome.fail(&err);
+                finish(&self.middlewares, &ctx, &outcome);
+                return Err(err);
+            }
+        }
+        Ok(ctx)
+    }
+
+    /// Runs a non-streaming call between the before/after hooks.
+    pub(super) async fn observe<T, Fut>(
+        &self,
+        operation: Operation,
+        input: CallInput<'_>,
+        call: Fut,
+        summarize: impl FnOnce(&T) -> (Option<Usage>, Option<String>),
+    ) -> Result<T, LLMError>
+    where
+        Fut: Future<Output = Result<T, LLMError>>,
+    {
+        let ctx = self.start(operation, &input)?;
+        let result = call.await;
+        let mut outcome = CallOutcome {
+            duration: ctx.started_at.elapsed(),
+            ..CallOutcome::default()
+        };
+        match &result {
+            Ok(value) => (outcome.usage, outcome.finish_reason) = summarize(value),
+            Err(err) => outcome.fail(err),
+        }
+        finish(&self.middlewares, &ctx, &outcome);
+        result
+    }
+
+    /// Runs a streaming call, reporting every chunk and calling the after hook once the
+    /// stream ends.
+    pub(super) async fn observe_stream<T, Fut>(
+        &self,
+        input: CallInput<'_>,
+        call: Fut,
+        inspect: fn(&T) -> (Vec<StreamChunk>, Option<Usage>),
+    ) -> Result<BoxedStream<T>, LLMError>
+    where
+        T: Send + 'static,
+        Fut: Future<Output = Result<BoxedStream<T>, LLMError>>,
+    {
+        let ctx = self.start(Operation::ChatStream, &input)?;
+        let inner = match call.await {
+            Ok(inner) => inner,
+            Err(err) => {
+                let mut outcome = CallOutcome {
+                    duration: ctx.started_at.elapsed(),
+                    ..CallOutcome::default()
+                };
+                outcome.fail(&err);
+                finish(&self.middlewares, &ctx, &outcome);
+                return Err(err);
+            }
+        };
+
+        let middlewares = self.middlewares.clone();
+        let outcome = Arc::new(Mutex::new(CallOutcome::default()));
+        let (tap_ctx, tap_middlewares, tap_outcome) =
+            (ctx.clone(), middlewares.clone(), outcome.clone());
+        let tapped = inner.inspect(move |item| {
+            let mut outcome = tap_outcome.lock().expect("outcome lock");
+            match item {
+                Ok(value) => {
+                    let (chunks, usage) = inspect(value);
+                    for chunk in &chunks {
+                        if let StreamChunk::Done { stop_reason } = chunk {
+                            outcome.finish_reason = Some(stop_reason.clone());
+                        }
+                        for middleware in &tap_middlewares {
+                            middleware.on_stream_chunk(&tap_ctx, chunk);
+                        }
+                    }
+                    if usage.is_some() {
+                        outcome.usage = usage;
+                    }
+                }
+                Err(err) => outcome.fail(err),
+            }
+        });
+
+        let pending = PendingStream {
+            middlewares,
+            ctx,
+            outcome,
+            completed: false,
+        };
+        let done = stream::once(async move { pending.complete() })
+            .filter_map(|_| async { None::<Result<T, LLMError>> });
+
+        Ok(Box::pin(tapped.chain(done)))
+    }
+}
+
+/// Calls the after hooks of a stream once it ends or is dropped, so a consumer
+/// stopping early still closes what `before_request` opened.
+struct PendingStream {
+    middlewares: Vec<Arc<dyn LLMMiddleware>>,
+    ctx: CallContext,
+    outcome: Arc<Mutex<CallOutcome>>,
+    completed: bool,
+}
+
+impl PendingStream {
+    fn complete(mut self) {
+        self.completed = true;
+    }
+}
+
+impl Drop for PendingStream {
+    fn drop(&mut self) {
+        let mut outcome = std::mem::take(&mut *self.outcome.lock().expect("outcome lock"));
+        outcome.duration = self.ctx.started_at.elapsed();
+        if !self.completed && outcome.error.is_none() {
+            outcome.error = Some("stream dropped before completion".to_string());
+            outcome.error_type = Some("StreamDropped");
+        }
+        finish(&self.middlewares, &self.ctx, &outcome);
+    }
+}
+
+impl CallOutcome {
+    fn fail(&mut self, err: &LLMError) {
+        self.error = Some(err.to_string());
+        self.error_type = Some(error_type(err));
+    }
+}
+
+fn error_type(err: &LLMError) -> &'static str {
+    match err {
+        LLMError::HttpError(_) => "HttpError",
+        LLMError::AuthError(_) => "AuthError",
+        LLMError::InvalidRequest(_) => "InvalidRequest",
+        LLMError::ProviderError(_) => "ProviderError",
+        LLMError::ResponseFormatError { .. } => "ResponseFormatError",
+        LLMError::Generic(_) => "Generic",
+        LLMError::JsonError(_) => "JsonError",
+        LLMError::ToolConfigError(_) => "ToolConfigError",
+        LLMError::RetryExceeded { .. } => "RetryExceeded",
+        LLMError::InvalidToolArguments { .. } => "InvalidToolArguments",
+        LLMError::ValidationFailed { .. } => "ValidationFailed",
+        LLMError::BudgetExceeded(_) => "BudgetExceeded",
+        LLMError::Unsupported(_) => "Unsupported",
+    }
+}
+
+fn finish(middlewares: &[Arc<dyn LLMMiddleware>], ctx: &CallContext, outcome: &CallOutcome) {
+    for middleware in middlewares {
+        middleware.after_response(ctx, outcome);
+    }
+}
+
+impl LLMProvider for MiddlewareLLM {
+    fn tools(&self) -> Option<&[Tool]> {
+        self.inner.tools()
+    }
+}
@@ -94,7 +94,7 @@ pub trait ModelsProvider {
         &self,
         _request: Option<&ModelListRequest>,
     ) -> Result<Box<dyn ModelListResponse>, LLMError> {
-        Err(LLMError::ProviderError(
+        Err(LLMError::Unsupported(
             "List Models not supported".to_string(),
         ))
     }
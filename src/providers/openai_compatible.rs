@@ -889,7 +889,10 @@ impl<T: OpenAIProviderConfig> ChatProvider for OpenAICompatibleProvider<T> {
             });
         }
 
-        Ok(create_openai_tool_stream(response))
+        Ok(create_openai_tool_stream(
+            response,
+            T::SUPPORTS_STREAM_OPTIONS,
+        ))
     }
 }
 
@@ -906,15 +909,34 @@ struct OpenAIToolUseState {
     started: bool,
 }
 
+/// State of an OpenAI-compatible tool stream kept between SSE events
+#[derive(Debug, Default)]
+struct OpenAIToolStreamState {
+    /// Tool calls being streamed, by index
+    tools: HashMap<usize, OpenAIToolUseState>,
+    /// Whether the request asked for a final usage chunk (`include_usage`)
+    expects_usage: bool,
+    /// Stop reason held back until the usage chunk that follows it
+    stop_reason: Option<String>,
+}
+
 /// Creates an SSE stream that parses OpenAI-compatible tool use events into ChatStreamChunk.
+///
+/// With `expects_usage`, `Done` is held back until the usage chunk sent after the
+/// finish reason, so that `Usage` comes first.
 fn create_openai_tool_stream(
     response: reqwest::Response,
+    expects_usage: bool,
 ) -> Pin<Box<dyn Stream<Item = Result<ChatStreamChunk, LLMError>> + Send>> {
+    let state = OpenAIToolStreamState {
+        expects_usage,
+        ..Default::default()
+    };
     let stream = response
         .bytes_stream()
         .scan(
-            (String::new(), HashMap::<usize, OpenAIToolUseState>::new()),
-            move |(buffer, tool_states), chunk| {
+            (String::new(), state),
+            move |(buffer, stream_state), chunk| {
                 let result = match chunk {
                     Ok(bytes) => {
                         let text = String::from_utf8_lossy(&bytes);
@@ -933,7 +955,7 @@ fn create_openai_tool_stream(
                                 continue;
                             }
 
-                            match parse_openai_sse_chunk_with_tools(event, tool_states) {
+                            match parse_openai_sse_chunk_with_tools(event, stream_state) {
                                 Ok(chunks) => results.extend(chunks.into_iter().map(Ok)),
                                 Err(e) => results.push(Err(e)),
                             }
@@ -960,11 +982,13 @@ fn create_openai_tool_stream(
 /// - `tool_calls[].function.name` - function name (first chunk only)
 /// - `tool_calls[].function.arguments` - partial JSON arguments (streamed)
 /// - `finish_reason: "tool_calls"` - signals completion
+/// - `usage` - token usage, in the last chunk when `include_usage` is set
 fn parse_openai_sse_chunk_with_tools(
     event: &str,
-    tool_states: &mut HashMap<usize, OpenAIToolUseState>,
+    stream_state: &mut OpenAIToolStreamState,
 ) -> Result<Vec<ChatStreamChunk>, LLMError> {
     let mut results = Vec::new();
+    let tool_states = &mut stream_state.tools;
 
     for line in event.lines() {
         let line = line.trim();
@@ -986,8 +1010,9 @@ fn parse_openai_sse_chunk_with_tools(
                         });
                     }
                 }
+                let stop_reason = stream_state.stop_reason.take();
                 results.push(ChatStreamChunk::Done {
-                    stop_reason: "end_turn".to_string(),
+                    stop_reason: stop_reason.unwrap_or_else(|| "end_turn".to_string()),
                 });
                 return Ok(results);
             }
@@ -1060,9 +1085,23 @@ fn parse_openai_sse_chunk_with_tools(
                             "stop" => "end_turn",
                             other => other,
                         };
-                        results.push(ChatStreamChunk::Done {
-                            stop_reason: stop_reason.to_string(),
-                        });
+                        if stream_state.expects_usage && chunk.usage.is_none() {
+                            stream_state.stop_reason = Some(stop_reason.to_string());
+                        } else {
+                            results.extend(chunk.usage.clone().map(ChatStreamChunk::Usage));
+                            results.push(ChatStreamChunk::Done {
+                                stop_reason: stop_reason.to_string(),
+                            });
+                        }
+                    }
+                }
+
+                if chunk.choices.is_empty() {
+                    if let Some(usage) = chunk.usage {
+                        results.push(ChatStreamChunk::Usage(usage));
+                        if let Some(stop_reason) = stream_state.stop_reason.take() {
+                            results.push(ChatStreamChunk::Done { stop_reason });
+                        }
                     }
                 }
             }
@@ -1076,6 +1115,7 @@ fn parse_openai_sse_chunk_with_tools(
 #[derive(Debug, Deserialize)]
 struct OpenAIToolStreamChunk {
     choices: Vec<OpenAIToolStreamChoice>,
+    usage: Option<Usage>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -1327,8 +1367,8 @@ mod tests {
     #[test]
     fn test_parse_openai_stream_text_delta() {
         let event = r#"data: {"id":"chatcmpl-123","object":"chat.completion.chunk","choices":[{"index":0,"delta":{"content":"Hello"},"finish_reason":null}]}"#;
-        let mut tool_states = HashMap::new();
-        let results = parse_openai_sse_chunk_with_tools(event, &mut tool_states).unwrap();
+        let mut state = OpenAIToolStreamState::default();
+        let results = parse_openai_sse_chunk_with_tools(event, &mut state).unwrap();
 
         assert_eq!(results.len(), 1);
         match &results[0] {
@@ -1340,8 +1380,8 @@ mod tests {
     #[test]
     fn test_parse_openai_stream_tool_call_start() {
         let event = r#"data: {"id":"chatcmpl-123","object":"chat.completion.chunk","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"id":"call_abc123","type":"function","function":{"name":"get_weather","arguments":""}}]},"finish_reason":null}]}"#;
-        let mut tool_states = HashMap::new();
-        let results = parse_openai_sse_chunk_with_tools(event, &mut tool_states).unwrap();
+        let mut state = OpenAIToolStreamState::default();
+        let results = parse_openai_sse_chunk_with_tools(event, &mut state).unwrap();
 
         assert_eq!(results.len(), 1);
         match &results[0] {
@@ -1354,17 +1394,17 @@ mod tests {
         }
 
         // Verify state was stored
-        assert!(tool_states.contains_key(&0));
-        assert_eq!(tool_states[&0].id, "call_abc123");
-        assert_eq!(tool_states[&0].name, "get_weather");
-        assert!(tool_states[&0].started);
+        assert!(state.tools.contains_key(&0));
+        assert_eq!(state.tools[&0].id, "call_abc123");
+        assert_eq!(state.tools[&0].name, "get_weather");
+        assert!(state.tools[&0].started);
     }
 
     #[test]
     fn test_parse_openai_stream_tool_call_arguments_delta() {
         // First, set up tool state as if start was already processed
-        let mut tool_states = HashMap::new();
-        tool_states.insert(
+        let mut state = OpenAIToolStreamState::default();
+        state.tools.insert(
             0,
             OpenAIToolUseState {
                 id: "call_abc123".to_string(),
@@ -1375,7 +1415,7 @@ mod tests {
         );
 
         let event = r#"data: {"id":"chatcmpl-123","object":"chat.completion.chunk","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"location\":"}}]},"finish_reason":null}]}"#;
-        let results = parse_openai_sse_chunk_with_tools(event, &mut tool_states).unwrap();
+        let results = parse_openai_sse_chunk_with_tools(event, &mut state).unwrap();
 
         assert_eq!(results.len(), 1);
         match &results[0] {
@@ -1390,13 +1430,13 @@ mod tests {
         }
 
         // Verify arguments were accumulated
-        assert_eq!(tool_states[&0].arguments_buffer, "{\"location\":");
+        assert_eq!(state.tools[&0].arguments_buffer, "{\"location\":");
     }
 
     #[test]
     fn test_parse_openai_stream_finish_reason_tool_calls() {
-        let mut tool_states = HashMap::new();
-        tool_states.insert(
+        let mut state = OpenAIToolStreamState::default();
+        state.tools.insert(
             0,
             OpenAIToolUseState {
                 id: "call_abc123".to_string(),
@@ -1407,7 +1447,7 @@ mod tests {
         );
 
         let event = r#"data: {"id":"chatcmpl-123","object":"chat.completion.chunk","choices":[{"index":0,"delta":{},"finish_reason":"tool_calls"}]}"#;
-        let results = parse_openai_sse_chunk_with_tools(event, &mut tool_states).unwrap();
+        let results = parse_openai_sse_chunk_with_tools(event, &mut state).unwrap();
 
         // Should have ToolUseComplete and Done
         assert_eq!(results.len(), 2);
@@ -1430,14 +1470,14 @@ mod tests {
         }
 
         // Verify state was cleared
-        assert!(tool_states.is_empty());
+        assert!(state.tools.is_empty());
     }
 
     #[test]
     fn test_parse_openai_stream_finish_reason_stop() {
         let event = r#"data: {"id":"chatcmpl-123","object":"chat.completion.chunk","choices":[{"index":0,"delta":{},"finish_reason":"stop"}]}"#;
-        let mut tool_states = HashMap::new();
-        let results = parse_openai_sse_chunk_with_tools(event, &mut tool_states).unwrap();
+        let mut state = OpenAIToolStreamState::default();
+        let results = parse_openai_sse_chunk_with_tools(event, &mut state).unwrap();
 
         assert_eq!(results.len(), 1);
         match &results[0] {
@@ -1451,8 +1491,8 @@ mod tests {
     #[test]
     fn test_parse_openai_stream_done_marker() {
         let event = "data: [DONE]";
-        let mut tool_states = HashMap::new();
-        let results = parse_openai_sse_chunk_with_tools(event, &mut tool_states).unwrap();
+        let mut state = OpenAIToolStreamState::default();
+        let results = parse_openai_sse_chunk_with_tools(event, &mut state).unwrap();
 
         assert_eq!(results.len(), 1);
         match &results[0] {
@@ -1465,8 +1505,8 @@ mod tests {
 
     #[test]
     fn test_parse_openai_stream_done_marker_with_pending_tool() {
-        let mut tool_states = HashMap::new();
-        tool_states.insert(
+        let mut state = OpenAIToolStreamState::default();
+        state.tools.insert(
             0,
             OpenAIToolUseState {
                 id: "call_xyz".to_string(),
@@ -1477,7 +1517,7 @@ mod tests {
         );
 
         let event = "data: [DONE]";
-        let results = parse_openai_sse_chunk_with_tools(event, &mut tool_states).unwrap();
+        let results = parse_openai_sse_chunk_with_tools(event, &mut state).unwrap();
 
         // Should emit ToolUseComplete before Done
         assert_eq!(results.len(), 2);
@@ -1488,31 +1528,63 @@ mod tests {
         assert!(matches!(&results[1], ChatStreamChunk::Done { .. }));
     }
 
+    #[test]
+    fn test_parse_openai_stream_usage_before_done() {
+        let mut state = OpenAIToolStreamState {
+            expects_usage: true,
+            ..Default::default()
+        };
+
+        let finish_event = r#"data: {"id":"chatcmpl-123","choices":[{"index":0,"delta":{},"finish_reason":"stop"}]}"#;
+        let results = parse_openai_sse_chunk_with_tools(finish_event, &mut state).unwrap();
+        assert!(results.is_empty());
+
+        let usage_event = r#"data: {"id":"chatcmpl-123","choices":[],"usage":{"prompt_tokens":9,"completion_tokens":3,"total_tokens":12}}"#;
+        let results = parse_openai_sse_chunk_with_tools(usage_event, &mut state).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(matches!(&results[0], ChatStreamChunk::Usage(usage) if usage.total_tokens == 12));
+        assert!(matches!(
+            &results[1],
+            ChatStreamChunk::Done { stop_reason } if stop_reason == "end_turn"
+        ));
+    }
+
+    #[test]
+    fn test_parse_openai_stream_usage_in_finish_chunk() {
+        let event = r#"data: {"id":"chatcmpl-123","choices":[{"index":0,"delta":{},"finish_reason":"stop"}],"usage":{"prompt_tokens":9,"completion_tokens":3,"total_tokens":12}}"#;
+        let mut state = OpenAIToolStreamState::default();
+        let results = parse_openai_sse_chunk_with_tools(event, &mut state).unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert!(matches!(&results[0], ChatStreamChunk::Usage(usage) if usage.prompt_tokens == 9));
+        assert!(matches!(&results[1], ChatStreamChunk::Done { .. }));
+    }
+
     #[test]
     fn test_parse_openai_stream_full_tool_sequence() {
-        let mut tool_states = HashMap::new();
+        let mut state = OpenAIToolStreamState::default();
 
         // 1. Tool call start with name
         let start_event = r#"data: {"id":"chatcmpl-123","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"id":"call_abc","type":"function","function":{"name":"get_weather","arguments":""}}]},"finish_reason":null}]}"#;
-        let results = parse_openai_sse_chunk_with_tools(start_event, &mut tool_states).unwrap();
+        let results = parse_openai_sse_chunk_with_tools(start_event, &mut state).unwrap();
         assert!(
             matches!(&results[0], ChatStreamChunk::ToolUseStart { name, .. } if name == "get_weather")
         );
 
         // 2. Arguments delta 1
         let delta1 = r#"data: {"id":"chatcmpl-123","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"loc"}}]},"finish_reason":null}]}"#;
-        let _ = parse_openai_sse_chunk_with_tools(delta1, &mut tool_states).unwrap();
+        let _ = parse_openai_sse_chunk_with_tools(delta1, &mut state).unwrap();
 
         // 3. Arguments delta 2
         let delta2 = r#"data: {"id":"chatcmpl-123","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"ation\":\"Tokyo\"}"}}]},"finish_reason":null}]}"#;
-        let _ = parse_openai_sse_chunk_with_tools(delta2, &mut tool_states).unwrap();
+        let _ = parse_openai_sse_chunk_with_tools(delta2, &mut state).unwrap();
 
         // Verify accumulated arguments
-        assert_eq!(tool_states[&0].arguments_buffer, "{\"location\":\"Tokyo\"}");
+        assert_eq!(state.tools[&0].arguments_buffer, "{\"location\":\"Tokyo\"}");
 
         // 4. Finish reason
         let finish_event = r#"data: {"id":"chatcmpl-123","choices":[{"index":0,"delta":{},"finish_reason":"tool_calls"}]}"#;
-        let results = parse_openai_sse_chunk_with_tools(finish_event, &mut tool_states).unwrap();
+        let results = parse_openai_sse_chunk_with_tools(finish_event, &mut state).unwrap();
 
         assert_eq!(results.len(), 2);
         match &results[0] {
@@ -1529,11 +1601,11 @@ mod tests {
 
     #[test]
     fn test_parse_openai_stream_parallel_tool_calls() {
-        let mut tool_states = HashMap::new();
+        let mut state = OpenAIToolStreamState::default();
 
         // Two tool calls in one chunk
         let event = r#"data: {"id":"chatcmpl-123","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"id":"call_1","type":"function","function":{"name":"get_weather","arguments":""}},{"index":1,"id":"call_2","type":"function","function":{"name":"get_time","arguments":""}}]},"finish_reason":null}]}"#;
-        let results = parse_openai_sse_chunk_with_tools(event, &mut tool_states).unwrap();
+        let results = parse_openai_sse_chunk_with_tools(event, &mut state).unwrap();
 
         assert_eq!(results.len(), 2);
         assert!(
@@ -1544,15 +1616,15 @@ mod tests {
         );
 
         // Verify both states exist
-        assert!(tool_states.contains_key(&0));
-        assert!(tool_states.contains_key(&1));
+        assert!(state.tools.contains_key(&0));
+        assert!(state.tools.contains_key(&1));
     }
 
     #[test]
     fn test_parse_openai_stream_ignores_empty_content() {
         let event = r#"data: {"id":"chatcmpl-123","choices":[{"index":0,"delta":{"content":""},"finish_reason":null}]}"#;
-        let mut tool_states = HashMap::new();
-        let results = parse_openai_sse_chunk_with_tools(event, &mut tool_states).unwrap();
+        let mut state = OpenAIToolStreamState::default();
+        let results = parse_openai_sse_chunk_with_tools(event, &mut state).unwrap();
 
         assert!(results.is_empty());
     }
@@ -1560,16 +1632,16 @@ mod tests {
     #[test]
     fn test_parse_vllm_stream_tool_calls() {
         // vLLM includes extra fields like reasoning_content, type, token_ids
-        let mut tool_states = HashMap::new();
+        let mut state = OpenAIToolStreamState::default();
 
         // First chunk from vLLM - just role, empty content
         let first_chunk = r#"data: {"id":"chatcmpl-be8d6d925ff14741","object":"chat.completion.chunk","created":1765374283,"model":"Qwen/Qwen2.5-Coder-7B-Instruct-AWQ","choices":[{"index":0,"delta":{"role":"assistant","content":"","reasoning_content":null},"logprobs":null,"finish_reason":null}],"prompt_token_ids":null}"#;
-        let results = parse_openai_sse_chunk_with_tools(first_chunk, &mut tool_states).unwrap();
+        let results = parse_openai_sse_chunk_with_tools(first_chunk, &mut state).unwrap();
         assert!(results.is_empty(), "First chunk should produce no results");
 
         // Second chunk - tool call start with id, type, index, function.name and function.arguments
         let tool_start = r#"data: {"id":"chatcmpl-be8d6d925ff14741","object":"chat.completion.chunk","created":1765374283,"model":"Qwen/Qwen2.5-Coder-7B-Instruct-AWQ","choices":[{"index":0,"delta":{"reasoning_content":null,"tool_calls":[{"id":"chatcmpl-tool-a331788bab1045a8","type":"function","index":0,"function":{"name":"db_list_databases","arguments":"{\"catalog\":"}}]},"logprobs":null,"finish_reason":null,"token_ids":null}]}"#;
-        let results = parse_openai_sse_chunk_with_tools(tool_start, &mut tool_states).unwrap();
+        let results = parse_openai_sse_chunk_with_tools(tool_start, &mut state).unwrap();
 
         // Should have ToolUseStart and ToolUseInputDelta
         assert!(
@@ -1585,7 +1657,7 @@ mod tests {
 
         // Arguments delta
         let args_delta = r#"data: {"id":"chatcmpl-be8d6d925ff14741","object":"chat.completion.chunk","created":1765374283,"model":"Qwen/Qwen2.5-Coder-7B-Instruct-AWQ","choices":[{"index":0,"delta":{"reasoning_content":null,"tool_calls":[{"index":0,"function":{"arguments":"\"default\"}"}}]},"logprobs":null,"finish_reason":null,"token_ids":null}]}"#;
-        let results = parse_openai_sse_chunk_with_tools(args_delta, &mut tool_states).unwrap();
+        let results = parse_openai_sse_chunk_with_tools(args_delta, &mut state).unwrap();
         assert!(
             matches!(&results[0], ChatStreamChunk::ToolUseInputDelta { partial_json, .. } if partial_json == "\"default\"}"),
             "Expected ToolUseInputDelta, got {:?}",
@@ -1594,7 +1666,7 @@ mod tests {
 
         // Finish with stop reason
         let finish = r#"data: {"id":"chatcmpl-be8d6d925ff14741","object":"chat.completion.chunk","created":1765374283,"model":"Qwen/Qwen2.5-Coder-7B-Instruct-AWQ","choices":[{"index":0,"delta":{"reasoning_content":null,"tool_calls":[{"index":0,"function":{"arguments":""}}]},"logprobs":null,"finish_reason":"stop","stop_reason":null,"token_ids":null}]}"#;
-        let results = parse_openai_sse_chunk_with_tools(finish, &mut tool_states).unwrap();
+        let results = parse_openai_sse_chunk_with_tools(finish, &mut state).unwrap();
 
         // Should have ToolUseComplete and Done
         assert!(
@@ -0,0 +1,16 @@
+#[path = "record_replay/cassette.rs"]
+mod cassette;
+
+#[path = "record_replay/recording.rs"]
+mod recording;
+
+#[path = "record_replay/replay.rs"]
+mod replay;
+
+pub use cassette::{Cassette, Interaction, RecordedRequest, RecordedResponse};
+pub use recording::RecordingLLM;
+pub use replay::ReplayLLM;
+
+#[cfg(test)]
+#[path = "record_replay/tests.rs"]
+mod tests;
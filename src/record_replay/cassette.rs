@@ -0,0 +1,140 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    cached_llm::{
+        key::{canonical_message, digest, fnv1a},
+        CachedChatResponse,
+    },
+    chat::{ChatMessage, StreamChunk, StreamResponse, Tool},
+    completion::CompletionRequest,
+    error::LLMError,
+};
+
+/// A request captured by [`RecordingLLM`](super::RecordingLLM).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecordedRequest {
+    /// Stable hash of `body`, used to match replayed requests
+    pub key: String,
+    /// Canonical JSON description of the request (operation, messages, tools...)
+    pub body: Value,
+}
+
+impl RecordedRequest {
+    fn from_body(body: Value) -> Self {
+        Self {
+            key: digest(&body),
+            body,
+        }
+    }
+
+    pub(crate) fn chat(op: &str, messages: &[ChatMessage], tools: Option<&[Tool]>) -> Self {
+        let messages: Vec<Value> = messages.iter().map(canonical_message).collect();
+        let tools = tools
+            .map(|tools| serde_json::to_value(tools).unwrap_or(Value::Null))
+            .unwrap_or(Value::Null);
+        Self::from_body(json!({ "op": op, "messages": messages, "tools": tools }))
+    }
+
+    pub(crate) fn completion(req: &CompletionRequest) -> Self {
+        Self::from_body(json!({
+            "op": "complete",
+            "prompt": req.prompt,
+            "max_tokens": req.max_tokens,
+            "temperature": req.temperature,
+        }))
+    }
+
+    pub(crate) fn embedding(input: &[String]) -> Self {
+        Self::from_body(json!({ "op": "embed", "input": input }))
+    }
+
+    pub(crate) fn transcription(audio: &[u8]) -> Self {
+        Self::from_body(json!({
+            "op": "transcribe",
+            "audio": format!("{:016x}", fnv1a(audio)),
+            "audio_len": audio.len(),
+        }))
+    }
+
+    pub(crate) fn speech(text: &str) -> Self {
+        Self::from_body(json!({ "op": "speech", "text": text }))
+    }
+}
+
+/// A response captured by [`RecordingLLM`](super::RecordingLLM).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordedResponse {
+    /// Non-streaming chat response
+    Chat(CachedChatResponse),
+    /// Chunks of `chat_stream`
+    TextStream {
+        chunks: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// Chunks of `chat_stream_struct`
+    StructStream {
+        chunks: Vec<StreamResponse>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// Chunks of `chat_stream_with_tools`
+    ToolStream {
+        chunks: Vec<StreamChunk>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// Completion text
+    Completion { text: String },
+    /// Embedding vectors, one per input
+    Embedding { vectors: Vec<Vec<f32>> },
+    /// Transcribed text
+    Transcription { text: String },
+    /// Base64 encoded audio
+    Speech { audio_base64: String },
+    /// Error returned by the provider, replayed as [`LLMError::ProviderError`]
+    Error { message: String },
+}
+
+/// A single request/response pair.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+/// Ordered list of recorded interactions, stored as pretty-printed JSON.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    /// Loads a cassette from a JSON file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LLMError> {
+        let path = path.as_ref();
+        let raw = std::fs::read(path).map_err(|e| {
+            LLMError::Generic(format!("failed to read cassette {}: {e}", path.display()))
+        })?;
+        Ok(serde_json::from_slice(&raw)?)
+    }
+
+    /// Writes the cassette to a JSON file, creating parent directories as needed.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), LLMError> {
+        let path = path.as_ref();
+        let write = || -> std::io::Result<()> {
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
+            }
+            let raw = serde_json::to_vec_pretty(self).map_err(std::io::Error::other)?;
+            std::fs::write(path, raw)
+        };
+        write().map_err(|e| {
+            LLMError::Generic(format!("failed to write cassette {}: {e}", path.display()))
+        })
+    }
+}
@@ -0,0 +1,285 @@
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::stream::{self, Stream, StreamExt};
+
+use crate::{
+    cached_llm::CachedChatResponse,
+    chat::{ChatMessage, ChatProvider, ChatResponse, StreamChunk, StreamResponse, Tool},
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    models::{ModelListRequest, ModelListResponse, ModelsProvider},
+    stt::SpeechToTextProvider,
+    tts::TextToSpeechProvider,
+    LLMProvider,
+};
+
+use super::cassette::{Cassette, Interaction, RecordedRequest, RecordedResponse};
+
+type BoxedStream<T> = Pin<Box<dyn Stream<Item = Result<T, LLMError>> + Send>>;
+
+struct Recorder {
+    cassette: Mutex<Cassette>,
+    path: Option<PathBuf>,
+    /// Interactions were recorded since the file was last written
+    dirty: AtomicBool,
+}
+
+impl Recorder {
+    fn record(&self, request: RecordedRequest, response: RecordedResponse) {
+        self.cassette
+            .lock()
+            .expect("cassette lock")
+            .interactions
+            .push(Interaction { request, response });
+        self.dirty.store(true, Ordering::Release);
+    }
+
+    /// Writes the cassette file if anything was recorded since the last write.
+    fn save(&self) -> Result<(), LLMError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if !self.dirty.swap(false, Ordering::AcqRel) {
+            return Ok(());
+        }
+        let cassette = self.cassette.lock().expect("cassette lock").clone();
+        cassette.save(path).inspect_err(|_| {
+            self.dirty.store(true, Ordering::Release);
+        })
+    }
+
+    fn record_result<T>(
+        &self,
+        request: RecordedRequest,
+        result: &Result<T, LLMError>,
+        to_response: impl FnOnce(&T) -> RecordedResponse,
+    ) {
+        let response = match result {
+            Ok(value) => to_response(value),
+            Err(err) => RecordedResponse::Error {
+                message: err.to_string(),
+            },
+        };
+        self.record(request, response);
+    }
+}
+
+/// Wrapper that forwards every call to a real provider and records the exchanges
+/// into a [`Cassette`], for later use with [`ReplayLLM`](super::ReplayLLM).
+///
+/// Streams are recorded once they have been fully consumed. A cassette file is
+/// written by [`RecordingLLM::flush`] and when the recorder is dropped.
+pub struct RecordingLLM {
+    inner: Box<dyn LLMProvider>,
+    recorder: Arc<Recorder>,
+}
+
+impl RecordingLLM {
+    /// Records in memory only, see [`RecordingLLM::cassette`].
+    pub fn new(inner: Box<dyn LLMProvider>) -> Self {
+        Self::build(inner, None)
+    }
+
+    /// Records in memory and writes the cassette file on flush and on drop.
+    pub fn to_file(inner: Box<dyn LLMProvider>, path: impl Into<PathBuf>) -> Self {
+        Self::build(inner, Some(path.into()))
+    }
+
+    fn build(inner: Box<dyn LLMProvider>, path: Option<PathBuf>) -> Self {
+        Self {
+            inner,
+            recorder: Arc::new(Recorder {
+                cassette: Mutex::new(Cassette::default()),
+                path,
+                dirty: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    /// Returns a copy of everything recorded so far.
+    pub fn cassette(&self) -> Cassette {
+        self.recorder
+            .cassette
+            .lock()
+            .expect("cassette lock")
+            .clone()
+    }
+
+    /// Writes the interactions recorded so far to the cassette file, off the
+    /// async runtime. Does nothing for in-memory recorders.
+    pub async fn flush(&self) -> Result<(), LLMError> {
+        let recorder = self.recorder.clone();
+        tokio::task::spawn_blocking(move || recorder.save())
+            .await
+            .map_err(|e| LLMError::Generic(format!("cassette write failed: {e}")))?
+    }
+
+    fn record_stream<T>(
+        &self,
+        request: RecordedRequest,
+        result: Result<BoxedStream<T>, LLMError>,
+        to_response: fn(Vec<T>, Option<String>) -> RecordedResponse,
+    ) -> Result<BoxedStream<T>, LLMError>
+    where
+        T: Clone + Send + 'static,
+    {
+        let inner = match result {
+            Ok(inner) => inner,
+            Err(err) => {
+                self.recorder.record(
+                    request,
+                    RecordedResponse::Error {
+                        message: err.to_string(),
+                    },
+                );
+                return Err(err);
+            }
+        };
+
+        let sink: Arc<Mutex<(Vec<T>, Option<String>)>> = Arc::default();
+        let tap = sink.clone();
+        let tapped = inner.inspect(move |item| {
+            let mut guard = tap.lock().expect("stream sink lock");
+            match item {
+                Ok(chunk) => guard.0.push(chunk.clone()),
+                Err(err) => guard.1 = Some(err.to_string()),
+            }
+        });
+
+        let recorder = self.recorder.clone();
+        let finish = stream::once(async move {
+            let (chunks, error) = std::mem::take(&mut *sink.lock().expect("stream sink lock"));
+            recorder.record(request, to_response(chunks, error));
+        })
+        .filter_map(|_| async { None::<Result<T, LLMError>> });
+
+        Ok(Box::pin(tapped.chain(finish)))
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if let Err(err) = self.save() {
+            log::warn!("{err}");
+        }
+    }
+}
+
+#[async_trait]
+impl ChatProvider for RecordingLLM {
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let request = RecordedRequest::chat("chat_with_tools", messages, tools);
+        let result = self.inner.chat_with_tools(messages, tools).await;
+        self.recorder.record_result(request, &result, |response| {
+            RecordedResponse::Chat(CachedChatResponse::from_response(response.as_ref()))
+        });
+        result
+    }
+
+    async fn chat_stream(&self, messages: &[ChatMessage]) -> Result<BoxedStream<String>, LLMError> {
+        let request = RecordedRequest::chat("chat_stream", messages, None);
+        let result = self.inner.chat_stream(messages).await;
+        self.record_stream(request, result, |chunks, error| {
+            RecordedResponse::TextStream { chunks, error }
+        })
+    }
+
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<BoxedStream<StreamResponse>, LLMError> {
+        let request = RecordedRequest::chat("chat_stream_struct", messages, None);
+        let result = self.inner.chat_stream_struct(messages).await;
+        self.record_stream(request, result, |chunks, error| {
+            RecordedResponse::StructStream { chunks, error }
+        })
+    }
+
+    async fn chat_stream_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<BoxedStream<StreamChunk>, LLMError> {
+        let request = RecordedRequest::chat("chat_stream_with_tools", messages, tools);
+        let result = self.inner.chat_stream_with_tools(messages, tools).await;
+        self.record_stream(request, result, |chunks, error| {
+            RecordedResponse::ToolStream { chunks, error }
+        })
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for RecordingLLM {
+    async fn complete(&self, req: &CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        let result = self.inner.complete(req).await;
+        self.recorder
+            .record_result(RecordedRequest::completion(req), &result, |response| {
+                RecordedResponse::Completion {
+                    text: response.text.clone(),
+                }
+            });
+        result
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for RecordingLLM {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        let request = RecordedRequest::embedding(&input);
+        let result = self.inner.embed(input).await;
+        self.recorder
+            .record_result(request, &result, |vectors| RecordedResponse::Embedding {
+                vectors: vectors.clone(),
+            });
+        result
+    }
+}
+
+#[async_trait]
+impl SpeechToTextProvider for RecordingLLM {
+    async fn transcribe(&self, audio: Vec<u8>) -> Result<String, LLMError> {
+        let request = RecordedRequest::transcription(&audio);
+        let result = self.inner.transcribe(audio).await;
+        self.recorder
+            .record_result(request, &result, |text| RecordedResponse::Transcription {
+                text: text.clone(),
+            });
+        result
+    }
+}
+
+#[async_trait]
+impl TextToSpeechProvider for RecordingLLM {
+    async fn speech(&self, text: &str) -> Result<Vec<u8>, LLMError> {
+        let result = self.inner.speech(text).await;
+        self.recorder
+            .record_result(RecordedRequest::speech(text), &result, |audio| {
+                RecordedResponse::Speech {
+                    audio_base64: STANDARD.encode(audio),
+                }
+            });
+        result
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for RecordingLLM {
+    async fn list_models(
+        &self,
+        request: Option<&ModelListRequest>,
+    ) -> Result<Box<dyn ModelListResponse>, LLMError> {
+        self.inner.list_models(request).await
+    }
+}
+
+impl LLMProvider for RecordingLLM {}
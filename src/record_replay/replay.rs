@@ -0,0 +1,192 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::stream::{self, Stream};
+
+use crate::{
+    chat::{ChatMessage, ChatProvider, ChatResponse, StreamChunk, StreamResponse, Tool},
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    models::ModelsProvider,
+    stt::SpeechToTextProvider,
+    tts::TextToSpeechProvider,
+    LLMProvider,
+};
+
+use super::cassette::{Cassette, RecordedRequest, RecordedResponse};
+
+type BoxedStream<T> = Pin<Box<dyn Stream<Item = Result<T, LLMError>> + Send>>;
+
+/// Provider serving the responses of a [`Cassette`] without any network access.
+///
+/// Requests are matched on their canonical content. Identical requests are served in
+/// recording order, and a request with no recorded response left returns
+/// [`LLMError::InvalidRequest`] describing the unmatched request.
+pub struct ReplayLLM {
+    pending: Mutex<HashMap<String, VecDeque<RecordedResponse>>>,
+}
+
+impl ReplayLLM {
+    /// Creates a replay provider from an in-memory cassette.
+    pub fn new(cassette: Cassette) -> Self {
+        let mut pending: HashMap<String, VecDeque<RecordedResponse>> = HashMap::new();
+        for interaction in cassette.interactions {
+            pending
+                .entry(interaction.request.key)
+                .or_default()
+                .push_back(interaction.response);
+        }
+        Self {
+            pending: Mutex::new(pending),
+        }
+    }
+
+    /// Loads the cassette from a JSON file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, LLMError> {
+        Ok(Self::new(Cassette::load(path)?))
+    }
+
+    /// Number of recorded responses that have not been served yet.
+    pub fn remaining(&self) -> usize {
+        self.pending
+            .lock()
+            .expect("replay lock")
+            .values()
+            .map(VecDeque::len)
+            .sum()
+    }
+
+    /// Panics if some recorded interactions were never requested.
+    pub fn assert_exhausted(&self) {
+        let remaining = self.remaining();
+        assert!(
+            remaining == 0,
+            "{remaining} recorded interaction(s) were never replayed"
+        );
+    }
+
+    fn take(&self, request: RecordedRequest) -> Result<RecordedResponse, LLMError> {
+        let response = self
+            .pending
+            .lock()
+            .expect("replay lock")
+            .get_mut(&request.key)
+            .and_then(VecDeque::pop_front);
+        match response {
+            Some(RecordedResponse::Error { message }) => Err(LLMError::ProviderError(message)),
+            Some(response) => Ok(response),
+            None => Err(LLMError::InvalidRequest(format!(
+                "no recorded interaction matches request {}",
+                request.body
+            ))),
+        }
+    }
+}
+
+fn unexpected(response: RecordedResponse) -> LLMError {
+    LLMError::InvalidRequest(format!(
+        "recorded response has an unexpected kind: {response:?}"
+    ))
+}
+
+fn replay_stream<T: Send + 'static>(chunks: Vec<T>, error: Option<String>) -> BoxedStream<T> {
+    let items = chunks
+        .into_iter()
+        .map(Ok)
+        .chain(error.map(|message| Err(LLMError::ProviderError(message))));
+    Box::pin(stream::iter(items))
+}
+
+#[async_trait]
+impl ChatProvider for ReplayLLM {
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        match self.take(RecordedRequest::chat("chat_with_tools", messages, tools))? {
+            RecordedResponse::Chat(response) => Ok(Box::new(response)),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn chat_stream(&self, messages: &[ChatMessage]) -> Result<BoxedStream<String>, LLMError> {
+        match self.take(RecordedRequest::chat("chat_stream", messages, None))? {
+            RecordedResponse::TextStream { chunks, error } => Ok(replay_stream(chunks, error)),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<BoxedStream<StreamResponse>, LLMError> {
+        match self.take(RecordedRequest::chat("chat_stream_struct", messages, None))? {
+            RecordedResponse::StructStream { chunks, error } => Ok(replay_stream(chunks, error)),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn chat_stream_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<BoxedStream<StreamChunk>, LLMError> {
+        let request = RecordedRequest::chat("chat_stream_with_tools", messages, tools);
+        match self.take(request)? {
+            RecordedResponse::ToolStream { chunks, error } => Ok(replay_stream(chunks, error)),
+            other => Err(unexpected(other)),
+        }
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for ReplayLLM {
+    async fn complete(&self, req: &CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        match self.take(RecordedRequest::completion(req))? {
+            RecordedResponse::Completion { text } => Ok(CompletionResponse { text }),
+            other => Err(unexpected(other)),
+        }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for ReplayLLM {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        match self.take(RecordedRequest::embedding(&input))? {
+            RecordedResponse::Embedding { vectors } => Ok(vectors),
+            other => Err(unexpected(other)),
+        }
+    }
+}
+
+#[async_trait]
+impl SpeechToTextProvider for ReplayLLM {
+    async fn transcribe(&self, audio: Vec<u8>) -> Result<String, LLMError> {
+        match self.take(RecordedRequest::transcription(&audio))? {
+            RecordedResponse::Transcription { text } => Ok(text),
+            other => Err(unexpected(other)),
+        }
+    }
+}
+
+#[async_trait]
+impl TextToSpeechProvider for ReplayLLM {
+    async fn speech(&self, text: &str) -> Result<Vec<u8>, LLMError> {
+        match self.take(RecordedRequest::speech(text))? {
+            RecordedResponse::Speech { audio_base64 } => STANDARD
+                .decode(audio_base64)
+                .map_err(|e| LLMError::Generic(format!("invalid recorded audio: {e}"))),
+            other => Err(unexpected(other)),
+        }
+    }
+}
+
+impl ModelsProvider for ReplayLLM {}
+
+impl LLMProvider for ReplayLLM {}
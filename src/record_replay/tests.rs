@@ -0,0 +1,163 @@
+use std::pin::Pin;
+
+use async_trait::async_trait;
+use futures::stream::{self, Stream, StreamExt};
+
+use crate::{
+    chat::{ChatMessage, ChatProvider, ChatResponse, StreamChunk, Tool},
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    models::ModelsProvider,
+    stt::SpeechToTextProvider,
+    tts::TextToSpeechProvider,
+    FunctionCall, LLMProvider, ToolCall,
+};
+
+use super::{Cassette, RecordingLLM, ReplayLLM};
+
+struct LiveProvider;
+
+fn weather_call() -> ToolCall {
+    ToolCall {
+        id: "call_1".to_string(),
+        call_type: "function".to_string(),
+        function: FunctionCall {
+            name: "weather".to_string(),
+            arguments: "{\"city\":\"Paris\"}".to_string(),
+        },
+    }
+}
+
+#[async_trait]
+impl ChatProvider for LiveProvider {
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        _tools: Option<&[Tool]>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        match messages.last().map(|m| m.content.as_str()) {
+            Some("fail") => Err(LLMError::ProviderError("boom".to_string())),
+            Some(text) => Ok(Box::new(CompletionResponse {
+                text: format!("echo: {text}"),
+            })),
+            None => Ok(Box::new(CompletionResponse {
+                text: String::new(),
+            })),
+        }
+    }
+
+    async fn chat_stream_with_tools(
+        &self,
+        _messages: &[ChatMessage],
+        _tools: Option<&[Tool]>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk, LLMError>> + Send>>, LLMError> {
+        let chunks = vec![
+            Ok(StreamChunk::Text("Checking".to_string())),
+            Ok(StreamChunk::ToolUseComplete {
+                index: 1,
+                tool_call: weather_call(),
+            }),
+            Ok(StreamChunk::Done {
+                stop_reason: "tool_use".to_string(),
+            }),
+        ];
+        Ok(Box::pin(stream::iter(chunks)))
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for LiveProvider {
+    async fn complete(&self, req: &CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        Ok(CompletionResponse {
+            text: req.prompt.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for LiveProvider {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        Ok(input.iter().map(|s| vec![s.len() as f32]).collect())
+    }
+}
+
+#[async_trait]
+impl SpeechToTextProvider for LiveProvider {
+    async fn transcribe(&self, _audio: Vec<u8>) -> Result<String, LLMError> {
+        Ok("transcript".to_string())
+    }
+}
+
+impl TextToSpeechProvider for LiveProvider {}
+impl ModelsProvider for LiveProvider {}
+impl LLMProvider for LiveProvider {}
+
+fn user(text: &str) -> Vec<ChatMessage> {
+    vec![ChatMessage::user().content(text).build()]
+}
+
+#[tokio::test]
+async fn replays_recorded_cassette_from_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cassettes/session.json");
+
+    let recorder = RecordingLLM::to_file(Box::new(LiveProvider), &path);
+    recorder.chat(&user("hi")).await.unwrap();
+    assert!(recorder.chat(&user("fail")).await.is_err());
+    recorder.embed(vec!["abc".to_string()]).await.unwrap();
+    let live: Vec<_> = recorder
+        .chat_stream_with_tools(&user("weather?"), None)
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(live.len(), 3);
+    assert_eq!(recorder.cassette().interactions.len(), 4);
+    assert!(!path.exists(), "the cassette is written on flush");
+    recorder.flush().await.unwrap();
+    assert_eq!(Cassette::load(&path).unwrap().interactions.len(), 4);
+
+    recorder.chat(&user("again")).await.unwrap();
+    drop(recorder);
+    assert_eq!(Cassette::load(&path).unwrap().interactions.len(), 5);
+
+    let replay = ReplayLLM::from_file(&path).unwrap();
+    let response = replay.chat(&user("hi")).await.unwrap();
+    assert_eq!(response.text().as_deref(), Some("echo: hi"));
+    match replay.chat(&user("fail")).await {
+        Err(LLMError::ProviderError(message)) => assert!(message.contains("boom")),
+        other => panic!("expected recorded error, got {other:?}"),
+    }
+    assert_eq!(
+        replay.embed(vec!["abc".to_string()]).await.unwrap(),
+        vec![vec![3.0]]
+    );
+    let replayed: Vec<_> = replay
+        .chat_stream_with_tools(&user("weather?"), None)
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert!(matches!(
+        &replayed[1],
+        StreamChunk::ToolUseComplete { tool_call, .. } if *tool_call == weather_call()
+    ));
+    replay.chat(&user("again")).await.unwrap();
+    replay.assert_exhausted();
+}
+
+#[tokio::test]
+async fn unmatched_request_fails() {
+    let recorder = RecordingLLM::new(Box::new(LiveProvider));
+    recorder.chat(&user("hi")).await.unwrap();
+    let replay = ReplayLLM::new(recorder.cassette());
+
+    replay.chat(&user("hi")).await.unwrap();
+    let err = replay.chat(&user("hi")).await.err().unwrap();
+    assert!(
+        matches!(err, LLMError::InvalidRequest(ref m) if m.contains("no recorded interaction"))
+    );
+    assert!(replay.chat(&user("other")).await.is_err());
+}
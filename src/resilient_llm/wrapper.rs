@@ -60,6 +60,10 @@ impl ResilientLLM {
             LLMError::AuthError(_) => false,
             LLMError::InvalidRequest(_) => false,
             LLMError::ToolConfigError(_) => false,
+            LLMError::BudgetExceeded(_) => false,
+            LLMError::InvalidToolArguments { .. } => false,
+            LLMError::ValidationFailed { .. } => false,
+            LLMError::Unsupported(_) => false,
         }
     }
 
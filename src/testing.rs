@@ -0,0 +1,17 @@
+//! Test doubles for code built on top of [`LLMProvider`](crate::LLMProvider).
+//!
+//! [`MockLLM`] is a scriptable provider: queue the responses, tool calls, stream
+//! chunks or errors it should return, then assert on the calls it received.
+
+#[path = "testing/script.rs"]
+mod script;
+
+#[path = "testing/mock.rs"]
+mod mock;
+
+pub use mock::MockLLM;
+pub use script::{MockCall, MockResponse};
+
+#[cfg(test)]
+#[path = "testing/tests.rs"]
+mod tests;
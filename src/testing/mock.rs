@@ -0,0 +1,405 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::stream::{self, Stream};
+
+use crate::{
+    builder::LLMBackend,
+    chat::{ChatMessage, ChatProvider, ChatResponse, StreamChunk, StreamResponse, Tool},
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    models::{
+        ModelListRequest, ModelListResponse, ModelsProvider, StandardModelEntry,
+        StandardModelListResponse, StandardModelListResponseInner,
+    },
+    stt::SpeechToTextProvider,
+    tts::TextToSpeechProvider,
+    LLMProvider,
+};
+
+use super::script::{chunk_to_struct, ChatStep, MockCall, MockResponse};
+
+const DEFAULT_EMBEDDING_DIMENSIONS: usize = 8;
+
+#[derive(Default)]
+struct MockState {
+    chat: VecDeque<ChatStep>,
+    completions: VecDeque<Result<String, LLMError>>,
+    embeddings: VecDeque<Result<Vec<Vec<f32>>, LLMError>>,
+    transcriptions: VecDeque<Result<String, LLMError>>,
+    speech: VecDeque<Result<Vec<u8>, LLMError>>,
+    models: Option<(LLMBackend, Vec<String>)>,
+    fallback: Option<MockResponse>,
+    calls: Vec<MockCall>,
+}
+
+/// Scriptable in-memory provider implementing the full [`LLMProvider`] surface.
+///
+/// Clones share the same script and call log, so a clone can be boxed and handed to
+/// the code under test while the original is kept for assertions:
+///
+/// ```
+/// use llm::testing::{MockLLM, MockResponse};
+/// use llm::chat::ChatMessage;
+///
+/// # tokio_test::block_on(async {
+/// let mock = MockLLM::new().with_response(MockResponse::text("4"));
+/// let provider: Box<dyn llm::LLMProvider> = Box::new(mock.clone());
+///
+/// let reply = provider
+///     .chat(&[ChatMessage::user().content("2+2?").build()])
+///     .await
+///     .unwrap();
+/// assert_eq!(reply.text().as_deref(), Some("4"));
+/// mock.assert_last_user_message_contains("2+2");
+/// # });
+/// ```
+///
+/// Chat turns are consumed in order by every chat method: scripted responses are
+/// split into chunks for streaming calls (their usage is streamed before the
+/// final `Done` chunk) and scripted streams are assembled for non-streaming
+/// ones. When a queue is empty, chat calls return the fallback response if one is
+/// set and an error otherwise, and `embed` returns deterministic vectors derived
+/// from the input text.
+#[derive(Clone, Default)]
+pub struct MockLLM {
+    state: Arc<Mutex<MockState>>,
+    latency: Option<Duration>,
+    tools: Option<Vec<Tool>>,
+}
+
+impl MockLLM {
+    /// Creates a mock with an empty script.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Delays every call by the given duration.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    /// Tools reported by [`LLMProvider::tools`].
+    pub fn with_tools(mut self, tools: Vec<Tool>) -> Self {
+        self.tools = Some(tools);
+        self
+    }
+
+    /// Queues a chat response.
+    pub fn with_response(self, response: MockResponse) -> Self {
+        self.push_response(response);
+        self
+    }
+
+    /// Response returned once the chat script is exhausted.
+    pub fn with_fallback(self, response: MockResponse) -> Self {
+        self.state().fallback = Some(response);
+        self
+    }
+
+    /// Models returned by `list_models`.
+    pub fn with_models(self, backend: LLMBackend, models: Vec<String>) -> Self {
+        self.state().models = Some((backend, models));
+        self
+    }
+
+    /// Queues a chat response.
+    pub fn push_response(&self, response: MockResponse) {
+        self.state().chat.push_back(ChatStep::Response(response));
+    }
+
+    /// Queues a text chat response.
+    pub fn push_text(&self, text: impl Into<String>) {
+        self.push_response(MockResponse::text(text));
+    }
+
+    /// Queues a chat response requesting tool calls.
+    pub fn push_tool_calls(&self, calls: Vec<crate::ToolCall>) {
+        self.push_response(MockResponse::tool_calls(calls));
+    }
+
+    /// Queues the exact chunks a streaming chat call should yield.
+    pub fn push_stream(&self, chunks: Vec<StreamChunk>) {
+        self.state().chat.push_back(ChatStep::Stream(chunks));
+    }
+
+    /// Queues a stream that yields `chunks` and then fails with `error`, like a
+    /// connection dropped mid-response. Non-streaming chat calls get the error.
+    pub fn push_stream_error(&self, chunks: Vec<StreamChunk>, error: LLMError) {
+        self.state()
+            .chat
+            .push_back(ChatStep::BrokenStream(chunks, error));
+    }
+
+    /// Queues an error for the next chat call.
+    pub fn push_error(&self, error: LLMError) {
+        self.state().chat.push_back(ChatStep::Error(error));
+    }
+
+    /// Queues the result of the next `complete` call.
+    pub fn push_completion(&self, result: Result<String, LLMError>) {
+        self.state().completions.push_back(result);
+    }
+
+    /// Queues the result of the next `embed` call.
+    pub fn push_embeddings(&self, result: Result<Vec<Vec<f32>>, LLMError>) {
+        self.state().embeddings.push_back(result);
+    }
+
+    /// Queues the result of the next `transcribe` call.
+    pub fn push_transcription(&self, result: Result<String, LLMError>) {
+        self.state().transcriptions.push_back(result);
+    }
+
+    /// Queues the result of the next `speech` call.
+    pub fn push_speech(&self, result: Result<Vec<u8>, LLMError>) {
+        self.state().speech.push_back(result);
+    }
+
+    /// Every call received so far, in order.
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.state().calls.clone()
+    }
+
+    /// Number of calls received so far.
+    pub fn call_count(&self) -> usize {
+        self.state().calls.len()
+    }
+
+    /// Messages of the most recent chat call.
+    pub fn last_messages(&self) -> Option<Vec<ChatMessage>> {
+        self.state()
+            .calls
+            .iter()
+            .rev()
+            .find_map(|call| call.messages().map(<[ChatMessage]>::to_vec))
+    }
+
+    /// Panics unless the last message of the most recent chat call contains `needle`.
+    pub fn assert_last_user_message_contains(&self, needle: &str) {
+        let messages = self.last_messages().expect("MockLLM received no chat call");
+        let last = messages.last().expect("last chat call had no messages");
+        assert!(
+            last.content.contains(needle),
+            "expected last message to contain {needle:?}, got {:?}",
+            last.content
+        );
+    }
+
+    /// Panics if any scripted chat turn or result was not consumed.
+    pub fn assert_script_consumed(&self) {
+        let state = self.state();
+        let left = state.chat.len()
+            + state.completions.len()
+            + state.embeddings.len()
+            + state.transcriptions.len()
+            + state.speech.len();
+        assert!(
+            left == 0,
+            "{left} scripted MockLLM result(s) were never used"
+        );
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().expect("mock state lock")
+    }
+
+    async fn enter(&self, call: MockCall) {
+        self.state().calls.push(call);
+        if let Some(latency) = self.latency {
+            tokio::time::sleep(latency).await;
+        }
+    }
+
+    fn next_chat(&self) -> ChatStep {
+        let mut state = self.state();
+        if let Some(step) = state.chat.pop_front() {
+            return step;
+        }
+        match &state.fallback {
+            Some(response) => ChatStep::Response(response.clone()),
+            None => ChatStep::Error(LLMError::InvalidRequest(
+                "MockLLM has no scripted chat response left".to_string(),
+            )),
+        }
+    }
+
+    /// Chunks of the next streaming turn and the error ending it, if any.
+    fn next_chunks(&self) -> Result<(Vec<StreamChunk>, Option<LLMError>), LLMError> {
+        match self.next_chat() {
+            ChatStep::Response(response) => Ok((response.into_chunks(), None)),
+            ChatStep::Stream(chunks) => Ok((chunks, None)),
+            ChatStep::BrokenStream(chunks, err) => Ok((chunks, Some(err))),
+            ChatStep::Error(err) => Err(err),
+        }
+    }
+}
+
+fn exhausted(what: &str) -> LLMError {
+    LLMError::InvalidRequest(format!("MockLLM has no scripted {what} left"))
+}
+
+/// Deterministic pseudo-embedding so similar tests produce stable vectors.
+fn fake_embedding(text: &str) -> Vec<f32> {
+    (0..DEFAULT_EMBEDDING_DIMENSIONS)
+        .map(|dim| {
+            let hash = text.bytes().fold(dim as u32 + 1, |acc, b| {
+                acc.wrapping_mul(31).wrapping_add(u32::from(b))
+            });
+            (hash % 1000) as f32 / 1000.0
+        })
+        .collect()
+}
+
+#[async_trait]
+impl ChatProvider for MockLLM {
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        self.enter(MockCall::Chat {
+            messages: messages.to_vec(),
+            tools: tools.map(<[Tool]>::to_vec),
+        })
+        .await;
+        match self.next_chat() {
+            ChatStep::Response(response) => Ok(Box::new(response)),
+            ChatStep::Stream(chunks) => Ok(Box::new(MockResponse::from_chunks(chunks))),
+            ChatStep::BrokenStream(_, err) | ChatStep::Error(err) => Err(err),
+        }
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError> {
+        self.enter(MockCall::ChatStream {
+            messages: messages.to_vec(),
+            tools: None,
+        })
+        .await;
+        let (chunks, error) = self.next_chunks()?;
+        let texts = chunks
+            .into_iter()
+            .filter_map(|chunk| match chunk {
+                StreamChunk::Text(text) => Some(Ok(text)),
+                _ => None,
+            })
+            .chain(error.map(Err));
+        Ok(Box::pin(stream::iter(texts.collect::<Vec<_>>())))
+    }
+
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>, LLMError>
+    {
+        self.enter(MockCall::ChatStream {
+            messages: messages.to_vec(),
+            tools: None,
+        })
+        .await;
+        let (chunks, error) = self.next_chunks()?;
+        let mut responses: Vec<_> = chunks
+            .into_iter()
+            .filter_map(chunk_to_struct)
+            .map(Ok)
+            .collect();
+        responses.extend(error.map(Err));
+        Ok(Box::pin(stream::iter(responses)))
+    }
+
+    async fn chat_stream_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk, LLMError>> + Send>>, LLMError> {
+        self.enter(MockCall::ChatStream {
+            messages: messages.to_vec(),
+            tools: tools.map(<[Tool]>::to_vec),
+        })
+        .await;
+        let (chunks, error) = self.next_chunks()?;
+        let items = chunks.into_iter().map(Ok).chain(error.map(Err));
+        Ok(Box::pin(stream::iter(items.collect::<Vec<_>>())))
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for MockLLM {
+    async fn complete(&self, req: &CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        self.enter(MockCall::Complete(req.clone())).await;
+        let next = self.state().completions.pop_front();
+        let text = next.unwrap_or_else(|| Err(exhausted("completion")))?;
+        Ok(CompletionResponse { text })
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for MockLLM {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        self.enter(MockCall::Embed(input.clone())).await;
+        let next = self.state().embeddings.pop_front();
+        match next {
+            Some(result) => result,
+            None => Ok(input.iter().map(|text| fake_embedding(text)).collect()),
+        }
+    }
+}
+
+#[async_trait]
+impl SpeechToTextProvider for MockLLM {
+    async fn transcribe(&self, audio: Vec<u8>) -> Result<String, LLMError> {
+        self.enter(MockCall::Transcribe(audio)).await;
+        let next = self.state().transcriptions.pop_front();
+        next.unwrap_or_else(|| Err(exhausted("transcription")))
+    }
+}
+
+#[async_trait]
+impl TextToSpeechProvider for MockLLM {
+    async fn speech(&self, text: &str) -> Result<Vec<u8>, LLMError> {
+        self.enter(MockCall::Speech(text.to_string())).await;
+        let next = self.state().speech.pop_front();
+        next.unwrap_or_else(|| Err(exhausted("speech")))
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for MockLLM {
+    async fn list_models(
+        &self,
+        _request: Option<&ModelListRequest>,
+    ) -> Result<Box<dyn ModelListResponse>, LLMError> {
+        self.enter(MockCall::ListModels).await;
+        let (backend, models) = self
+            .state()
+            .models
+            .clone()
+            .ok_or_else(|| exhausted("models"))?;
+        let data = models
+            .into_iter()
+            .map(|id| StandardModelEntry {
+                id,
+                created: None,
+                extra: serde_json::Value::Null,
+            })
+            .collect();
+        Ok(Box::new(StandardModelListResponse {
+            inner: StandardModelListResponseInner { data },
+            backend,
+        }))
+    }
+}
+
+impl LLMProvider for MockLLM {
+    fn tools(&self) -> Option<&[Tool]> {
+        self.tools.as_deref()
+    }
+}
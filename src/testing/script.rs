@@ -0,0 +1,198 @@
+use crate::{
+    chat::{ChatMessage, ChatResponse, StreamChunk, StreamDelta, StreamResponse, Tool, Usage},
+    completion::CompletionRequest,
+    error::LLMError,
+    ToolCall,
+};
+
+/// A scripted chat response returned by [`MockLLM`](super::MockLLM).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MockResponse {
+    /// Text content, streamed as a single text chunk
+    pub text: Option<String>,
+    /// Tool calls requested by the model, streamed after the text
+    pub tool_calls: Option<Vec<ToolCall>>,
+    /// Reasoning content; not part of streamed responses
+    pub thinking: Option<String>,
+    /// Token usage, streamed before the final `Done` chunk
+    pub usage: Option<Usage>,
+}
+
+impl MockResponse {
+    /// A plain text response.
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: Some(text.into()),
+            ..Self::default()
+        }
+    }
+
+    /// A response requesting the given tool calls.
+    pub fn tool_calls(calls: Vec<ToolCall>) -> Self {
+        Self {
+            tool_calls: Some(calls),
+            ..Self::default()
+        }
+    }
+
+    /// Attaches reasoning content.
+    pub fn with_thinking(mut self, thinking: impl Into<String>) -> Self {
+        self.thinking = Some(thinking.into());
+        self
+    }
+
+    /// Attaches usage metadata.
+    pub fn with_usage(mut self, usage: Usage) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    /// Splits the response into the chunks a streaming backend would emit.
+    pub(super) fn into_chunks(self) -> Vec<StreamChunk> {
+        let mut chunks = Vec::new();
+        if let Some(text) = self.text {
+            chunks.push(StreamChunk::Text(text));
+        }
+        let calls = self.tool_calls.unwrap_or_default();
+        let stop_reason = if calls.is_empty() {
+            "end_turn"
+        } else {
+            "tool_use"
+        };
+        for (index, call) in calls.into_iter().enumerate() {
+            chunks.push(StreamChunk::ToolUseStart {
+                index,
+                id: call.id.clone(),
+                name: call.function.name.clone(),
+            });
+            chunks.push(StreamChunk::ToolUseInputDelta {
+                index,
+                partial_json: call.function.arguments.clone(),
+            });
+            chunks.push(StreamChunk::ToolUseComplete {
+                index,
+                tool_call: call,
+            });
+        }
+        chunks.extend(self.usage.map(StreamChunk::Usage));
+        chunks.push(StreamChunk::Done {
+            stop_reason: stop_reason.to_string(),
+        });
+        chunks
+    }
+
+    /// Assembles streamed chunks back into a single response.
+    pub(super) fn from_chunks(chunks: Vec<StreamChunk>) -> Self {
+        let mut response = Self::default();
+        for chunk in chunks {
+            match chunk {
+                StreamChunk::Text(text) => response
+                    .text
+                    .get_or_insert_with(String::new)
+                    .push_str(&text),
+                StreamChunk::ToolUseComplete { tool_call, .. } => response
+                    .tool_calls
+                    .get_or_insert_with(Vec::new)
+                    .push(tool_call),
+                StreamChunk::Usage(usage) => response.usage = Some(usage),
+                _ => {}
+            }
+        }
+        response
+    }
+}
+
+impl ChatResponse for MockResponse {
+    fn text(&self) -> Option<String> {
+        self.text.clone()
+    }
+
+    fn tool_calls(&self) -> Option<Vec<ToolCall>> {
+        self.tool_calls.clone()
+    }
+
+    fn thinking(&self) -> Option<String> {
+        self.thinking.clone()
+    }
+
+    fn usage(&self) -> Option<Usage> {
+        self.usage.clone()
+    }
+}
+
+impl std::fmt::Display for MockResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text.as_deref().unwrap_or_default())
+    }
+}
+
+/// Converts a tool stream chunk to the OpenAI-style structure of `chat_stream_struct`.
+pub(super) fn chunk_to_struct(chunk: StreamChunk) -> Option<StreamResponse> {
+    let delta = match chunk {
+        StreamChunk::Text(text) => StreamDelta {
+            content: Some(text),
+            tool_calls: None,
+        },
+        StreamChunk::ToolUseComplete { tool_call, .. } => StreamDelta {
+            content: None,
+            tool_calls: Some(vec![tool_call]),
+        },
+        StreamChunk::Usage(usage) => {
+            return Some(StreamResponse {
+                choices: Vec::new(),
+                usage: Some(usage),
+            })
+        }
+        _ => return None,
+    };
+    Some(StreamResponse {
+        choices: vec![crate::chat::StreamChoice { delta }],
+        usage: None,
+    })
+}
+
+/// A scripted chat turn.
+pub(super) enum ChatStep {
+    Response(MockResponse),
+    Stream(Vec<StreamChunk>),
+    /// Chunks followed by an error ending the stream
+    BrokenStream(Vec<StreamChunk>, LLMError),
+    Error(LLMError),
+}
+
+/// A call received by [`MockLLM`](super::MockLLM).
+#[derive(Debug, Clone)]
+pub enum MockCall {
+    /// `chat`/`chat_with_tools`
+    Chat {
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<Tool>>,
+    },
+    /// Any of the streaming chat methods
+    ChatStream {
+        messages: Vec<ChatMessage>,
+        tools: Option<Vec<Tool>>,
+    },
+    /// `complete`
+    Complete(CompletionRequest),
+    /// `embed`
+    Embed(Vec<String>),
+    /// `transcribe`
+    Transcribe(Vec<u8>),
+    /// `speech`
+    Speech(String),
+    /// `list_models`
+    ListModels,
+}
+
+impl MockCall {
+    /// Messages sent with a chat call, `None` for other calls.
+    pub fn messages(&self) -> Option<&[ChatMessage]> {
+        match self {
+            MockCall::Chat { messages, .. } | MockCall::ChatStream { messages, .. } => {
+                Some(messages)
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+
+use crate::{
+    chat::{ChatMessage, ChatProvider, StreamChunk, Usage},
+    completion::{CompletionProvider, CompletionRequest},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    FunctionCall, ToolCall,
+};
+
+use super::{MockCall, MockLLM, MockResponse};
+
+fn user(text: &str) -> Vec<ChatMessage> {
+    vec![ChatMessage::user().content(text).build()]
+}
+
+fn call(name: &str) -> ToolCall {
+    ToolCall {
+        id: format!("call_{name}"),
+        call_type: "function".to_string(),
+        function: FunctionCall {
+            name: name.to_string(),
+            arguments: "{}".to_string(),
+        },
+    }
+}
+
+#[tokio::test]
+async fn serves_script_in_order_then_errors() {
+    let mock = MockLLM::new();
+    mock.push_text("first");
+    mock.push_tool_calls(vec![call("lookup")]);
+    mock.push_error(LLMError::ProviderError("rate limited".to_string()));
+
+    let first = mock.chat(&user("a")).await.unwrap();
+    assert_eq!(first.text().as_deref(), Some("first"));
+    let second = mock.chat(&user("b")).await.unwrap();
+    assert_eq!(second.tool_calls(), Some(vec![call("lookup")]));
+    assert!(matches!(
+        mock.chat(&user("c")).await,
+        Err(LLMError::ProviderError(_))
+    ));
+    assert!(matches!(
+        mock.chat(&user("d")).await,
+        Err(LLMError::InvalidRequest(_))
+    ));
+    mock.assert_script_consumed();
+    mock.assert_last_user_message_contains("d");
+    assert_eq!(mock.call_count(), 4);
+}
+
+#[tokio::test]
+async fn responses_and_streams_are_interchangeable() {
+    let mock = MockLLM::new().with_response(MockResponse::text("checking").with_thinking("hmm"));
+    mock.push_stream(vec![
+        StreamChunk::Text("Hel".to_string()),
+        StreamChunk::Text("lo".to_string()),
+    ]);
+
+    let chunks: Vec<_> = mock
+        .chat_stream_with_tools(&user("a"), None)
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert!(matches!(&chunks[0], StreamChunk::Text(t) if t == "checking"));
+    assert!(matches!(chunks.last(), Some(StreamChunk::Done { .. })));
+
+    let assembled = mock.chat(&user("b")).await.unwrap();
+    assert_eq!(assembled.text().as_deref(), Some("Hello"));
+    assert!(matches!(mock.calls()[0], MockCall::ChatStream { .. }));
+}
+
+#[tokio::test]
+async fn stream_errors_after_scripted_chunks() {
+    let mock = MockLLM::new();
+    for _ in 0..2 {
+        mock.push_stream_error(
+            vec![StreamChunk::Text("partial".to_string())],
+            LLMError::HttpError("connection reset".to_string()),
+        );
+    }
+
+    let items: Vec<_> = mock
+        .chat_stream_with_tools(&user("a"), None)
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(items.len(), 2);
+    assert!(matches!(&items[0], Ok(StreamChunk::Text(t)) if t == "partial"));
+    assert!(matches!(&items[1], Err(LLMError::HttpError(e)) if e == "connection reset"));
+
+    let err = mock.chat(&user("b")).await.err().unwrap();
+    assert!(matches!(err, LLMError::HttpError(_)));
+    mock.assert_script_consumed();
+}
+
+#[tokio::test]
+async fn struct_streams_end_with_the_response_usage() {
+    let usage = Usage {
+        prompt_tokens: 3,
+        completion_tokens: 2,
+        total_tokens: 5,
+        completion_tokens_details: None,
+        prompt_tokens_details: None,
+    };
+    let mock = MockLLM::new();
+    mock.push_response(MockResponse::text("hello").with_usage(usage.clone()));
+
+    let items: Vec<_> = mock
+        .chat_stream_struct(&user("a"))
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    let (last, content) = items.split_last().unwrap();
+    assert_eq!(
+        content[0].choices[0].delta.content.as_deref(),
+        Some("hello")
+    );
+    assert!(last.choices.is_empty());
+    assert_eq!(last.usage, Some(usage));
+}
+
+#[tokio::test]
+async fn other_capabilities_and_latency() {
+    let mock = MockLLM::new().with_latency(Duration::from_millis(20));
+    mock.push_completion(Ok("done".to_string()));
+
+    let started = Instant::now();
+    let completion = mock.complete(&CompletionRequest::new("x")).await.unwrap();
+    assert_eq!(completion.text, "done");
+    assert!(started.elapsed() >= Duration::from_millis(20));
+
+    let first = mock.embed(vec!["same".to_string()]).await.unwrap();
+    let second = mock.embed(vec!["same".to_string()]).await.unwrap();
+    assert_eq!(first, second);
+    assert_eq!(first[0].len(), 8);
+}
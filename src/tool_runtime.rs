@@ -0,0 +1,26 @@
+//! Automatic tool-execution loop.
+//!
+//! [`ToolRuntime`] keeps a set of Rust async handlers next to their tool definitions
+//! and drives the "call model, run tools, send results back" loop until the model
+//! answers without requesting tools. Tools can also be written as [`ToolFn`]
+//! implementations receiving typed arguments.
+
+#[path = "tool_runtime/types.rs"]
+mod types;
+
+#[path = "tool_runtime/runtime.rs"]
+mod runtime;
+
+#[path = "tool_runtime/stream.rs"]
+mod stream;
+
+#[path = "tool_runtime/tool_fn.rs"]
+mod tool_fn;
+
+pub use runtime::ToolRuntime;
+pub use tool_fn::{ToolArgs, ToolFn};
+pub use types::{ToolExecution, ToolHandler, ToolRunEvent, ToolRunOutcome};
+
+#[cfg(test)]
+#[path = "tool_runtime/tests.rs"]
+mod tests;
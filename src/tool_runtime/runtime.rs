@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Instant;
+
+use futures::future::{join_all, FutureExt};
+use serde_json::{json, Value};
+
+use crate::{
+    builder::FunctionBuilder,
+    chat::{ChatMessage, ChatProvider, Tool},
+    error::LLMError,
+    tool_validation::ToolSchemas,
+    ToolCall,
+};
+
+use super::types::{ToolExecution, ToolHandler, ToolRunOutcome};
+
+const DEFAULT_MAX_ITERATIONS: usize = 10;
+
+/// Registry of tools backed by Rust async handlers, driving the tool-call loop.
+///
+/// ```
+/// use llm::builder::{FunctionBuilder, ParamBuilder};
+/// use llm::tool_runtime::ToolRuntime;
+/// use serde_json::json;
+///
+/// let runtime = ToolRuntime::new().register(
+///     FunctionBuilder::new("get_weather")
+///         .description("Current weather of a city")
+///         .param(ParamBuilder::new("city").type_of("string"))
+///         .required(vec!["city".to_string()]),
+///     |args| async move {
+///         let city = args["city"].as_str().unwrap_or("somewhere").to_string();
+///         Ok(json!({ "city": city, "forecast": "sunny" }))
+///     },
+/// );
+/// assert_eq!(runtime.tools().len(), 1);
+/// // let outcome = runtime.run(llm.as_ref(), messages).await?;
+/// ```
+#[derive(Clone)]
+pub struct ToolRuntime {
+    tools: Vec<Tool>,
+    schemas: ToolSchemas,
+    handlers: HashMap<String, Arc<ToolHandler>>,
+    max_iterations: usize,
+    parallel: bool,
+    validate_arguments: bool,
+}
+
+impl Default for ToolRuntime {
+    fn default() -> Self {
+        Self {
+            tools: Vec::new(),
+            schemas: ToolSchemas::default(),
+            handlers: HashMap::new(),
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+            parallel: true,
+            validate_arguments: true,
+        }
+    }
+}
+
+impl ToolRuntime {
+    /// Creates an empty runtime running tool calls in parallel with argument validation.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a tool and the async handler executing it. Registering a name twice
+    /// replaces the previous tool.
+    pub fn register<F, Fut>(self, function: FunctionBuilder, handler: F) -> Self
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value, LLMError>> + Send + 'static,
+    {
+        self.register_tool(function.build(), handler)
+    }
+
+    /// Registers an already built tool definition and its handler.
+    pub fn register_tool<F, Fut>(mut self, tool: Tool, handler: F) -> Self
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value, LLMError>> + Send + 'static,
+    {
+        let name = tool.function.name.clone();
+        self.tools.retain(|t| t.function.name != name);
+        self.schemas.insert(&tool);
+        self.tools.push(tool);
+        let handler: Arc<ToolHandler> = Arc::new(move |args| handler(args).boxed());
+        self.handlers.insert(name, handler);
+        self
+    }
+
+    /// Maximum number of model calls before the loop gives up.
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Whether calls requested in the same model turn run concurrently (default `true`).
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Whether arguments are validated against the tool schema before the handler
+    /// runs (default `true`). Validation errors are sent back to the model.
+    pub fn validate_arguments(mut self, validate: bool) -> Self {
+        self.validate_arguments = validate;
+        self
+    }
+
+    /// Tool definitions to send to the model.
+    pub fn tools(&self) -> &[Tool] {
+        &self.tools
+    }
+
+    pub(super) fn max_iterations_value(&self) -> usize {
+        self.max_iterations
+    }
+
+    /// Runs the loop until the model answers without requesting tools.
+    ///
+    /// Tool failures, unknown tools and invalid arguments are reported back to the model
+    /// as `{"error": ...}` results; only model errors and exceeding the iteration limit
+    /// end the loop with an error.
+    pub async fn run<P>(
+        &self,
+        llm: &P,
+        messages: Vec<ChatMessage>,
+    ) -> Result<ToolRunOutcome, LLMError>
+    where
+        P: ChatProvider + ?Sized,
+    {
+        let mut messages = messages;
+        let mut executions = Vec::new();
+
+        for iteration in 1..=self.max_iterations {
+            let response = llm.chat_with_tools(&messages, Some(&self.tools)).await?;
+            let calls = response.tool_calls().unwrap_or_default();
+            if calls.is_empty() {
+                if let Some(text) = response.text() {
+                    messages.push(ChatMessage::assistant().content(text).build());
+                }
+                return Ok(ToolRunOutcome {
+                    response,
+                    messages,
+                    executions,
+                    iterations: iteration,
+                });
+            }
+
+            let text = response.text().unwrap_or_default();
+            let results = self.execute_all(&calls).await;
+            push_tool_turn(&mut messages, text, calls, &results);
+            executions.extend(results);
+        }
+
+        Err(self.iterations_exceeded())
+    }
+
+    /// Executes the given calls, concurrently when parallel execution is enabled.
+    pub async fn execute_all(&self, calls: &[ToolCall]) -> Vec<ToolExecution> {
+        if self.parallel {
+            return join_all(calls.iter().map(|call| self.execute(call))).await;
+        }
+        let mut results = Vec::with_capacity(calls.len());
+        for call in calls {
+            results.push(self.execute(call).await);
+        }
+        results
+    }
+
+    /// Executes a single call with its registered handler.
+    pub async fn execute(&self, call: &ToolCall) -> ToolExecution {
+        let started = Instant::now();
+        let result = match self.handlers.get(&call.function.name) {
+            None => Err(format!("unknown tool '{}'", call.function.name)),
+            Some(handler) => match self.parse_arguments(call) {
+                Ok(args) => handler(args).await.map_err(|e| e.to_string()),
+                Err(err) => Err(err.to_string()),
+            },
+        };
+        let (output, is_error) = match result {
+            Ok(output) => (output, false),
+            Err(message) => (json!({ "error": message }), true),
+        };
+        ToolExecution {
+            call: call.clone(),
+            output,
+            is_error,
+            duration: started.elapsed(),
+        }
+    }
+
+    fn parse_arguments(&self, call: &ToolCall) -> Result<Value, LLMError> {
+        if self.validate_arguments {
+            self.schemas.validate(call)
+        } else {
+            call.function.parse_arguments()
+        }
+    }
+
+    pub(super) fn iterations_exceeded(&self) -> LLMError {
+        LLMError::Generic(format!(
+            "tool loop did not finish within {} iterations",
+            self.max_iterations
+        ))
+    }
+}
+
+/// Appends the assistant tool use message and the matching tool results.
+pub(super) fn push_tool_turn(
+    messages: &mut Vec<ChatMessage>,
+    text: String,
+    calls: Vec<ToolCall>,
+    results: &[ToolExecution],
+) {
+    messages.push(
+        ChatMessage::assistant()
+            .tool_use(calls)
+            .content(text)
+            .build(),
+    );
+    let results = results.iter().map(ToolExecution::to_result_call).collect();
+    messages.push(ChatMessage::user().tool_result(results).build());
+}
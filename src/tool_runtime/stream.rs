@@ -0,0 +1,99 @@
+use std::pin::Pin;
+use std::sync::Arc;
+
+use futures::stream::{self, Stream, StreamExt};
+use tokio::sync::mpsc;
+
+use crate::{
+    chat::{ChatMessage, ChatProvider, StreamChunk},
+    error::LLMError,
+};
+
+use super::runtime::{push_tool_turn, ToolRuntime};
+use super::types::ToolRunEvent;
+
+const EVENT_BUFFER: usize = 64;
+
+impl ToolRuntime {
+    /// Streaming variant of [`ToolRuntime::run`] built on `chat_stream_with_tools`.
+    ///
+    /// Model chunks are forwarded as they arrive, each executed tool is reported, and
+    /// the stream ends with [`ToolRunEvent::Finished`] or an error. The loop runs on a
+    /// spawned task and stops when the returned stream is dropped, cancelling any tool
+    /// calls still in flight.
+    pub fn run_stream<P>(
+        &self,
+        llm: Arc<P>,
+        messages: Vec<ChatMessage>,
+    ) -> Pin<Box<dyn Stream<Item = Result<ToolRunEvent, LLMError>> + Send>>
+    where
+        P: ChatProvider + ?Sized + 'static,
+    {
+        let (tx, rx) = mpsc::channel(EVENT_BUFFER);
+        let runtime = self.clone();
+        tokio::spawn(async move {
+            if let Err(err) = runtime.drive_stream(llm.as_ref(), messages, &tx).await {
+                let _ = tx.send(Err(err)).await;
+            }
+        });
+        Box::pin(stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|event| (event, rx))
+        }))
+    }
+
+    async fn drive_stream<P>(
+        &self,
+        llm: &P,
+        mut messages: Vec<ChatMessage>,
+        tx: &mpsc::Sender<Result<ToolRunEvent, LLMError>>,
+    ) -> Result<(), LLMError>
+    where
+        P: ChatProvider + ?Sized,
+    {
+        for iteration in 1..=self.max_iterations_value() {
+            let mut chunks = llm
+                .chat_stream_with_tools(&messages, Some(self.tools()))
+                .await?;
+            let mut text = String::new();
+            let mut calls = Vec::new();
+            while let Some(chunk) = chunks.next().await {
+                let chunk = chunk?;
+                match &chunk {
+                    StreamChunk::Text(delta) => text.push_str(delta),
+                    StreamChunk::ToolUseComplete { tool_call, .. } => calls.push(tool_call.clone()),
+                    _ => {}
+                }
+                if tx.send(Ok(ToolRunEvent::Chunk(chunk))).await.is_err() {
+                    return Ok(());
+                }
+            }
+
+            if calls.is_empty() {
+                messages.push(ChatMessage::assistant().content(text).build());
+                let finished = ToolRunEvent::Finished {
+                    messages,
+                    iterations: iteration,
+                };
+                let _ = tx.send(Ok(finished)).await;
+                return Ok(());
+            }
+
+            let results = tokio::select! {
+                results = self.execute_all(&calls) => results,
+                () = tx.closed() => return Ok(()),
+            };
+            for result in &results {
+                if tx
+                    .send(Ok(ToolRunEvent::ToolResult(result.clone())))
+                    .await
+                    .is_err()
+                {
+                    return Ok(());
+                }
+            }
+            push_tool_turn(&mut messages, text, calls, &results);
+        }
+
+        Err(self.iterations_exceeded())
+    }
+}
@@ -0,0 +1,283 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::StreamExt;
+use serde_json::json;
+use tokio::sync::Barrier;
+
+use crate::{
+    builder::FunctionBuilder,
+    chat::{ChatMessage, MessageType},
+    error::LLMError,
+    testing::{MockCall, MockLLM, MockResponse},
+    FunctionCall, ToolCall,
+};
+
+use super::{ToolRunEvent, ToolRuntime};
+
+fn call(id: &str, name: &str, args: &str) -> ToolCall {
+    ToolCall {
+        id: id.to_string(),
+        call_type: "function".to_string(),
+        function: FunctionCall {
+            name: name.to_string(),
+            arguments: args.to_string(),
+        },
+    }
+}
+
+fn runtime() -> ToolRuntime {
+    ToolRuntime::new()
+        .register(FunctionBuilder::new("add"), |args| async move {
+            let sum = args["a"].as_i64().unwrap_or(0) + args["b"].as_i64().unwrap_or(0);
+            Ok(json!(sum))
+        })
+        .register(FunctionBuilder::new("slow"), |_| async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(json!("done"))
+        })
+        .register(FunctionBuilder::new("fail"), |_| async move {
+            Err(LLMError::ProviderError("disk full".to_string()))
+        })
+}
+
+fn user(text: &str) -> Vec<ChatMessage> {
+    vec![ChatMessage::user().content(text).build()]
+}
+
+#[tokio::test]
+async fn runs_tools_until_final_answer() {
+    let mock = MockLLM::new();
+    mock.push_tool_calls(vec![
+        call("1", "add", r#"{"a":2,"b":3}"#),
+        call("2", "fail", "{}"),
+        call("3", "missing", "{}"),
+    ]);
+    mock.push_text("the answer is 5");
+
+    let outcome = runtime().run(&mock, user("2+3?")).await.unwrap();
+
+    assert_eq!(outcome.iterations, 2);
+    assert_eq!(outcome.response.text().as_deref(), Some("the answer is 5"));
+    let outputs: Vec<_> = outcome
+        .executions
+        .iter()
+        .map(|e| (e.output.clone(), e.is_error))
+        .collect();
+    assert_eq!(outputs[0], (json!(5), false));
+    assert!(
+        outputs[1].1
+            && outputs[1].0["error"]
+                .as_str()
+                .unwrap()
+                .contains("disk full")
+    );
+    assert!(outputs[2].1);
+
+    let sent = mock.last_messages().unwrap();
+    assert!(matches!(sent[1].message_type, MessageType::ToolUse(ref c) if c.len() == 3));
+    match &sent[2].message_type {
+        MessageType::ToolResult(results) => assert_eq!(results[0].function.arguments, "5"),
+        other => panic!("expected tool results, got {other:?}"),
+    }
+    assert_eq!(outcome.messages.len(), 4);
+    match &mock.calls()[0] {
+        MockCall::Chat { tools, .. } => assert_eq!(tools.as_ref().unwrap().len(), 3),
+        other => panic!("unexpected call {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn independent_calls_run_concurrently() {
+    let mock = MockLLM::new();
+    mock.push_tool_calls(vec![call("1", "meet", ""), call("2", "meet", "")]);
+    mock.push_text("ok");
+
+    // Each call waits for the other, so running them one after the other never ends.
+    let barrier = Arc::new(Barrier::new(2));
+    let runtime = runtime().register(FunctionBuilder::new("meet"), move |_| {
+        let barrier = barrier.clone();
+        async move {
+            barrier.wait().await;
+            Ok(json!("met"))
+        }
+    });
+    let run = runtime.run(&mock, user("go"));
+    tokio::time::timeout(Duration::from_secs(5), run)
+        .await
+        .expect("tool calls ran one after the other")
+        .unwrap();
+}
+
+#[tokio::test]
+async fn stops_after_max_iterations() {
+    let mock = MockLLM::new().with_fallback(MockResponse::tool_calls(vec![call("1", "add", "{}")]));
+
+    let err = runtime()
+        .max_iterations(3)
+        .run(&mock, user("loop"))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("3 iterations"));
+    assert_eq!(mock.call_count(), 3);
+}
+
+#[tokio::test]
+async fn streams_chunks_tool_results_and_finish() {
+    let mock = MockLLM::new();
+    mock.push_tool_calls(vec![call("1", "add", r#"{"a":1,"b":1}"#)]);
+    mock.push_text("two");
+
+    let events: Vec<_> = runtime()
+        .run_stream(Arc::new(mock.clone()), user("1+1?"))
+        .map(Result::unwrap)
+        .collect()
+        .await;
+
+    let results: Vec<_> = events
+        .iter()
+        .filter_map(|e| match e {
+            ToolRunEvent::ToolResult(exec) => Some(exec.output.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(results, vec![json!(2)]);
+    match events.last() {
+        Some(ToolRunEvent::Finished {
+            messages,
+            iterations,
+        }) => {
+            assert_eq!(*iterations, 2);
+            assert_eq!(messages.last().unwrap().content, "two");
+        }
+        other => panic!("expected finish event, got {other:?}"),
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn dropping_the_stream_cancels_running_tools() {
+    let finished = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let flag = Arc::clone(&finished);
+    let runtime = ToolRuntime::new().register(FunctionBuilder::new("slow"), move |_| {
+        let flag = Arc::clone(&flag);
+        async move {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            flag.store(true, std::sync::atomic::Ordering::SeqCst);
+            Ok(json!("done"))
+        }
+    });
+    let mock = MockLLM::new();
+    mock.push_tool_calls(vec![call("1", "slow", "{}")]);
+    mock.push_text("never asked");
+
+    let mut events = runtime.run_stream(Arc::new(mock.clone()), user("go"));
+    assert!(matches!(
+        events.next().await,
+        Some(Ok(ToolRunEvent::Chunk(_)))
+    ));
+    drop(events);
+    tokio::time::sleep(Duration::from_secs(5)).await;
+
+    assert!(!finished.load(std::sync::atomic::Ordering::SeqCst));
+    assert_eq!(mock.call_count(), 1);
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct AddArgs {
+    a: i64,
+    b: Option<i64>,
+}
+
+impl super::ToolArgs for AddArgs {
+    fn parameters_schema() -> serde_json::Value {
+        json!({
+            "description": "Adds two numbers",
+            "type": "object",
+            "properties": { "a": { "type": "integer" }, "b": { "type": "integer" } },
+            "required": ["a"]
+        })
+    }
+}
+
+struct Add;
+
+#[async_trait::async_trait]
+impl super::ToolFn for Add {
+    type Args = AddArgs;
+    type Output = i64;
+
+    fn name(&self) -> &str {
+        "typed_add"
+    }
+
+    async fn call(&self, args: AddArgs) -> Result<i64, LLMError> {
+        Ok(args.a + args.b.unwrap_or(0))
+    }
+}
+
+#[tokio::test]
+async fn typed_tool_parses_arguments() {
+    use super::ToolFn;
+
+    let tool = Add.function().build();
+    assert_eq!(tool.function.description, "Adds two numbers");
+    assert_eq!(tool.function.parameters["required"], json!(["a"]));
+
+    let output = Add
+        .invoke(&call("1", "typed_add", r#"{"a":4}"#))
+        .await
+        .unwrap();
+    assert_eq!(output, json!(4));
+    let err = Add
+        .invoke(&call("1", "typed_add", r#"{"b":"x"}"#))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, LLMError::JsonError(_)));
+
+    let mock = MockLLM::new();
+    mock.push_tool_calls(vec![call("1", "typed_add", r#"{"a":1,"b":2}"#)]);
+    mock.push_text("3");
+    let outcome = ToolRuntime::new()
+        .register_fn(Add)
+        .run(&mock, user("1+2?"))
+        .await
+        .unwrap();
+    assert_eq!(outcome.executions[0].output, json!(3));
+}
+
+#[cfg(feature = "schema")]
+#[test]
+fn derived_schema_describes_nested_and_optional_fields() {
+    use super::ToolArgs;
+
+    /// Books a trip.
+    #[derive(serde::Deserialize, schemars::JsonSchema)]
+    #[allow(dead_code)]
+    struct Trip {
+        /// Destination city
+        city: String,
+        #[schemars(range(min = 1, max = 30))]
+        nights: u32,
+        traveller: Option<Traveller>,
+    }
+
+    #[derive(serde::Deserialize, schemars::JsonSchema)]
+    #[allow(dead_code)]
+    struct Traveller {
+        name: String,
+    }
+
+    let schema = Trip::parameters_schema();
+    assert!(schema.get("$schema").is_none() && schema.get("title").is_none());
+    assert_eq!(schema["description"], "Books a trip.");
+    assert_eq!(
+        schema["properties"]["city"]["description"],
+        "Destination city"
+    );
+    assert_eq!(schema["properties"]["nights"]["maximum"], 30);
+    assert_eq!(
+        schema["properties"]["traveller"]["properties"]["name"]["type"],
+        "string"
+    );
+    assert_eq!(schema["required"], json!(["city", "nights"]));
+}
@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::{builder::FunctionBuilder, error::LLMError, ToolCall};
+
+use super::runtime::ToolRuntime;
+
+/// Typed tool arguments that know their JSON schema.
+///
+/// With the `schema` feature, every type deriving `schemars::JsonSchema` and
+/// `Deserialize` implements this trait; doc comments, `Option` fields and
+/// `#[schemars(range(...))]` attributes end up in the generated schema. Without it,
+/// implement [`ToolArgs::parameters_schema`] by hand.
+pub trait ToolArgs: DeserializeOwned + Send + 'static {
+    /// JSON schema of the tool parameters (an object schema).
+    fn parameters_schema() -> Value;
+}
+
+#[cfg(feature = "schema")]
+impl<T> ToolArgs for T
+where
+    T: schemars::JsonSchema + DeserializeOwned + Send + 'static,
+{
+    fn parameters_schema() -> Value {
+        generate_schema::<T>()
+    }
+}
+
+/// Generates an inlined draft-07 schema without the `$schema` and `title` keywords,
+/// which several providers reject in tool definitions.
+#[cfg(feature = "schema")]
+fn generate_schema<T: schemars::JsonSchema>() -> Value {
+    let mut schema = schemars::generate::SchemaSettings::draft07()
+        .with(|s| s.inline_subschemas = true)
+        .into_generator()
+        .into_root_schema_for::<T>()
+        .to_value();
+    if let Some(obj) = schema.as_object_mut() {
+        obj.remove("$schema");
+        obj.remove("title");
+        obj.entry("properties")
+            .or_insert_with(|| Value::Object(Default::default()));
+    }
+    schema
+}
+
+/// A tool implemented in Rust with typed arguments and output.
+///
+/// ```ignore
+/// #[derive(Deserialize, JsonSchema)]
+/// /// Current weather of a city.
+/// struct WeatherArgs {
+///     /// City name
+///     city: String,
+///     /// Number of days to forecast
+///     #[schemars(range(min = 1, max = 7))]
+///     days: Option<u8>,
+/// }
+///
+/// struct Weather;
+///
+/// #[async_trait]
+/// impl ToolFn for Weather {
+///     type Args = WeatherArgs;
+///     type Output = serde_json::Value;
+///
+///     fn name(&self) -> &str {
+///         "get_weather"
+///     }
+///
+///     async fn call(&self, args: WeatherArgs) -> Result<Self::Output, LLMError> {
+///         Ok(json!({ "city": args.city, "forecast": "sunny" }))
+///     }
+/// }
+///
+/// let llm = LLMBuilder::new().function(Weather.function()).build()?;
+/// let runtime = ToolRuntime::new().register_fn(Weather);
+/// ```
+#[async_trait]
+pub trait ToolFn: Send + Sync + 'static {
+    /// Arguments the model must provide.
+    type Args: ToolArgs;
+    /// Result sent back to the model, serialized as JSON.
+    type Output: Serialize + Send;
+
+    /// Tool name exposed to the model.
+    fn name(&self) -> &str;
+
+    /// Tool description; defaults to the `description` of the arguments schema
+    /// (the doc comment of the arguments type when derived).
+    fn description(&self) -> String {
+        Self::Args::parameters_schema()
+            .get("description")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    /// Runs the tool.
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, LLMError>;
+
+    /// Tool definition, usable with [`crate::builder::LLMBuilder::function`].
+    fn function(&self) -> FunctionBuilder {
+        FunctionBuilder::new(self.name())
+            .description(self.description())
+            .json_schema(Self::Args::parameters_schema())
+    }
+
+    /// Parses the arguments of a call made by the model.
+    fn parse_call(&self, call: &ToolCall) -> Result<Self::Args, LLMError> {
+        call.function.parse_arguments()
+    }
+
+    /// Parses, runs and serializes a call made by the model.
+    async fn invoke(&self, call: &ToolCall) -> Result<Value, LLMError> {
+        let args = self.parse_call(call)?;
+        let output = self.call(args).await?;
+        serde_json::to_value(output).map_err(|e| LLMError::JsonError(e.to_string()))
+    }
+}
+
+impl ToolRuntime {
+    /// Registers a typed [`ToolFn`], using its generated definition.
+    pub fn register_fn<T: ToolFn>(self, tool: T) -> Self {
+        let function = tool.function();
+        let tool = Arc::new(tool);
+        self.register(function, move |args: Value| {
+            let tool = Arc::clone(&tool);
+            async move {
+                let args: T::Args = serde_json::from_value(args)
+                    .map_err(|e| LLMError::JsonError(format!("invalid arguments: {e}")))?;
+                let output = tool.call(args).await?;
+                serde_json::to_value(output).map_err(|e| LLMError::JsonError(e.to_string()))
+            }
+        })
+    }
+}
@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use serde_json::Value;
+
+use crate::{
+    chat::{ChatMessage, ChatResponse, StreamChunk},
+    error::LLMError,
+    ToolCall,
+};
+
+/// Type-erased async tool handler receiving the parsed JSON arguments.
+pub type ToolHandler = dyn Fn(Value) -> BoxFuture<'static, Result<Value, LLMError>> + Send + Sync;
+
+/// A tool call executed by the runtime.
+#[derive(Debug, Clone)]
+pub struct ToolExecution {
+    /// The call requested by the model
+    pub call: ToolCall,
+    /// Result sent back to the model, `{"error": ...}` when the handler failed
+    pub output: Value,
+    /// Whether the handler failed, the tool was unknown or the arguments were invalid
+    pub is_error: bool,
+    /// Wall time of the tool call, including argument validation
+    pub duration: Duration,
+}
+
+impl ToolExecution {
+    /// The tool result in the form expected by `ChatMessageBuilder::tool_result`.
+    pub fn to_result_call(&self) -> ToolCall {
+        let mut call = self.call.clone();
+        call.function.arguments = self.output.to_string();
+        call
+    }
+}
+
+/// Final state of a completed tool loop.
+#[derive(Debug)]
+pub struct ToolRunOutcome {
+    /// The last model response, which requested no further tool call
+    pub response: Box<dyn ChatResponse>,
+    /// The full conversation, including tool use and tool result messages
+    pub messages: Vec<ChatMessage>,
+    /// Every tool call executed, in order
+    pub executions: Vec<ToolExecution>,
+    /// Number of model calls made
+    pub iterations: usize,
+}
+
+/// Event emitted by [`ToolRuntime::run_stream`](super::ToolRuntime::run_stream).
+#[derive(Debug, Clone)]
+pub enum ToolRunEvent {
+    /// A chunk streamed by the model
+    Chunk(StreamChunk),
+    /// A tool call finished executing
+    ToolResult(ToolExecution),
+    /// The model answered without requesting tools
+    Finished {
+        /// The full conversation, including the final assistant message
+        messages: Vec<ChatMessage>,
+        /// Number of model calls made
+        iterations: usize,
+    },
+}
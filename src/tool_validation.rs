@@ -0,0 +1,25 @@
+//! Validation of tool-call arguments against the declared tool schemas.
+//!
+//! [`validate_tool_call`] checks a single call, and [`ToolCallValidatedLLM`] checks
+//! every call a provider returns, optionally re-prompting the model with the
+//! validation errors until it produces valid arguments.
+
+#[path = "tool_validation/validate.rs"]
+mod validate;
+
+#[path = "tool_validation/wrapper.rs"]
+mod wrapper;
+
+#[path = "tool_validation/chat.rs"]
+mod chat;
+
+#[path = "tool_validation/passthrough.rs"]
+mod passthrough;
+
+pub use validate::validate_tool_call;
+pub(crate) use validate::ToolSchemas;
+pub use wrapper::ToolCallValidatedLLM;
+
+#[cfg(test)]
+#[path = "tool_validation/tests.rs"]
+mod tests;
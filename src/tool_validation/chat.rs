@@ -0,0 +1,134 @@
+use std::pin::Pin;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::stream::{Stream, StreamExt};
+use serde_json::json;
+
+use crate::{
+    chat::{ChatMessage, ChatProvider, ChatResponse, StreamChunk, StreamResponse, Tool},
+    error::LLMError,
+    FunctionCall, ToolCall,
+};
+
+use super::validate::ToolSchemas;
+use super::wrapper::ToolCallValidatedLLM;
+
+const SKIPPED_CALL: &str =
+    "not executed because other calls in this turn had invalid arguments; repeat all calls";
+
+#[async_trait]
+impl ChatProvider for ToolCallValidatedLLM {
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let Some(schemas) = self.schemas_for(tools) else {
+            return self.inner.chat_with_tools(messages, tools).await;
+        };
+
+        let mut local_messages = messages.to_vec();
+        let mut remaining_attempts = self.repair_attempts;
+        loop {
+            let response = self.inner.chat_with_tools(&local_messages, tools).await?;
+            let calls = response.tool_calls().unwrap_or_default();
+            let mut errors: Vec<Option<LLMError>> = calls
+                .iter()
+                .map(|call| schemas.validate(call).err())
+                .collect();
+            if errors.iter().all(Option::is_none) {
+                return Ok(response);
+            }
+            if remaining_attempts == 0 {
+                return Err(errors.iter_mut().find_map(Option::take).unwrap());
+            }
+            remaining_attempts -= 1;
+            let text = response.text().unwrap_or_default();
+            append_repair_turn(&mut local_messages, text, calls, &errors);
+        }
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError> {
+        self.inner.chat_stream(messages).await
+    }
+
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>, LLMError>
+    {
+        self.inner.chat_stream_struct(messages).await
+    }
+
+    /// Streams are not repaired; a completed tool call with invalid arguments is
+    /// replaced by its validation error.
+    async fn chat_stream_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk, LLMError>> + Send>>, LLMError> {
+        let stream = self.inner.chat_stream_with_tools(messages, tools).await?;
+        let Some(schemas) = self.schemas_for(tools) else {
+            return Ok(stream);
+        };
+        Ok(Box::pin(stream.map(move |chunk| {
+            match chunk {
+                Ok(StreamChunk::ToolUseComplete { tool_call, index }) => schemas
+                    .validate(&tool_call)
+                    .map(|_| StreamChunk::ToolUseComplete { tool_call, index }),
+                other => other,
+            }
+        })))
+    }
+
+    async fn memory_contents(&self) -> Option<Vec<ChatMessage>> {
+        self.inner.memory_contents().await
+    }
+}
+
+impl ToolCallValidatedLLM {
+    /// Schemas of the tools passed to the call, or of the configured tools.
+    fn schemas_for(&self, tools: Option<&[Tool]>) -> Option<Arc<ToolSchemas>> {
+        match tools {
+            Some(tools) => Some(Arc::new(ToolSchemas::new(tools))),
+            None => self.tools.as_ref().map(|_| self.schemas.clone()),
+        }
+    }
+}
+
+fn append_repair_turn(
+    messages: &mut Vec<ChatMessage>,
+    text: String,
+    calls: Vec<ToolCall>,
+    errors: &[Option<LLMError>],
+) {
+    let results = calls
+        .iter()
+        .zip(errors)
+        .map(|(call, error)| {
+            let reason = match error {
+                Some(err) => format!("{err}. Fix the arguments and call the tool again."),
+                None => SKIPPED_CALL.to_string(),
+            };
+            ToolCall {
+                id: call.id.clone(),
+                call_type: call.call_type.clone(),
+                function: FunctionCall {
+                    name: call.function.name.clone(),
+                    arguments: json!({ "error": reason }).to_string(),
+                },
+            }
+        })
+        .collect();
+    messages.push(
+        ChatMessage::assistant()
+            .tool_use(calls)
+            .content(text)
+            .build(),
+    );
+    messages.push(ChatMessage::user().tool_result(results).build());
+}
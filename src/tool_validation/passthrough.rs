@@ -0,0 +1,55 @@
+use async_trait::async_trait;
+
+use crate::{
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    models::{ModelListRequest, ModelListResponse, ModelsProvider},
+    stt::SpeechToTextProvider,
+    tts::TextToSpeechProvider,
+    LLMProvider,
+};
+
+use super::wrapper::ToolCallValidatedLLM;
+
+impl LLMProvider for ToolCallValidatedLLM {}
+
+#[async_trait]
+impl CompletionProvider for ToolCallValidatedLLM {
+    async fn complete(
+        &self,
+        req: &CompletionRequest,
+    ) -> Result<CompletionResponse, crate::error::LLMError> {
+        self.inner.complete(req).await
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for ToolCallValidatedLLM {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, crate::error::LLMError> {
+        self.inner.embed(input).await
+    }
+}
+
+#[async_trait]
+impl SpeechToTextProvider for ToolCallValidatedLLM {
+    async fn transcribe(&self, audio: Vec<u8>) -> Result<String, crate::error::LLMError> {
+        self.inner.transcribe(audio).await
+    }
+}
+
+#[async_trait]
+impl TextToSpeechProvider for ToolCallValidatedLLM {
+    async fn speech(&self, text: &str) -> Result<Vec<u8>, crate::error::LLMError> {
+        self.inner.speech(text).await
+    }
+}
+
+#[async_trait]
+impl ModelsProvider for ToolCallValidatedLLM {
+    async fn list_models(
+        &self,
+        request: Option<&ModelListRequest>,
+    ) -> Result<Box<dyn ModelListResponse>, crate::error::LLMError> {
+        self.inner.list_models(request).await
+    }
+}
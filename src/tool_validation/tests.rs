@@ -0,0 +1,150 @@
+use futures::StreamExt;
+use serde_json::json;
+
+use crate::{
+    builder::{FunctionBuilder, ParamBuilder},
+    chat::{ChatMessage, ChatProvider, MessageType, StreamChunk, Tool},
+    error::LLMError,
+    testing::MockLLM,
+    FunctionCall, ToolCall,
+};
+
+use super::{validate_tool_call, ToolCallValidatedLLM};
+
+fn weather_tool() -> Tool {
+    FunctionBuilder::new("get_weather")
+        .param(ParamBuilder::new("city").type_of("string"))
+        .param(ParamBuilder::new("days").type_of("integer"))
+        .required(vec!["city".to_string()])
+        .build()
+}
+
+fn call(id: &str, args: &str) -> ToolCall {
+    ToolCall {
+        id: id.to_string(),
+        call_type: "function".to_string(),
+        function: FunctionCall {
+            name: "get_weather".to_string(),
+            arguments: args.to_string(),
+        },
+    }
+}
+
+fn user(text: &str) -> Vec<ChatMessage> {
+    vec![ChatMessage::user().content(text).build()]
+}
+
+#[test]
+fn validates_arguments_against_declared_schema() {
+    let tools = [weather_tool()];
+    let args = validate_tool_call(&tools, &call("1", r#"{"city":"Paris","days":2}"#)).unwrap();
+    assert_eq!(args["city"], "Paris");
+
+    match validate_tool_call(&tools, &call("2", r#"{"days":"two"}"#)).unwrap_err() {
+        LLMError::InvalidToolArguments {
+            tool,
+            call_id,
+            violations,
+        } => {
+            assert_eq!((tool.as_str(), call_id.as_str()), ("get_weather", "2"));
+            assert_eq!(violations.len(), 2);
+            assert_eq!(violations[1].path, "$.days");
+        }
+        other => panic!("unexpected error {other:?}"),
+    }
+    assert!(matches!(
+        validate_tool_call(&tools, &call("3", "{city")),
+        Err(LLMError::InvalidToolArguments { .. })
+    ));
+
+    let mut unknown = call("4", "{}");
+    unknown.function.name = "get_time".to_string();
+    assert!(matches!(
+        validate_tool_call(&tools, &unknown),
+        Err(LLMError::ToolConfigError(_))
+    ));
+}
+
+#[tokio::test]
+async fn repairs_invalid_calls_by_reprompting() {
+    let mock = MockLLM::new();
+    mock.push_tool_calls(vec![call("1", r#"{"city":"Paris"}"#), call("2", "{}")]);
+    mock.push_tool_calls(vec![call("3", r#"{"city":"Lyon"}"#)]);
+    let llm = ToolCallValidatedLLM::new(Box::new(mock.clone())).with_repair_attempts(1);
+
+    let response = llm
+        .chat_with_tools(&user("weather?"), Some(&[weather_tool()]))
+        .await
+        .unwrap();
+    assert_eq!(response.tool_calls().unwrap()[0].id, "3");
+
+    let sent = mock.last_messages().unwrap();
+    assert_eq!(sent.len(), 3);
+    match &sent[2].message_type {
+        MessageType::ToolResult(results) => {
+            assert!(results[0].function.arguments.contains("not executed"));
+            assert!(results[1]
+                .function
+                .arguments
+                .contains("missing required property"));
+        }
+        other => panic!("expected tool results, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn reports_structured_error_when_attempts_run_out() {
+    let mock = MockLLM::new();
+    mock.push_tool_calls(vec![call("1", r#"{"city":3}"#)]);
+    let llm = ToolCallValidatedLLM::new(Box::new(mock.clone())).with_tools(vec![weather_tool()]);
+
+    let err = llm.chat(&user("weather?")).await.err().unwrap();
+    assert!(matches!(err, LLMError::InvalidToolArguments { ref call_id, .. } if call_id == "1"));
+    assert_eq!(mock.call_count(), 1);
+}
+
+#[tokio::test]
+async fn rejects_invalid_calls_in_streams() {
+    let mock = MockLLM::new();
+    mock.push_stream(vec![
+        StreamChunk::Text("checking".to_string()),
+        StreamChunk::ToolUseComplete {
+            index: 0,
+            tool_call: call("1", r#"{"days":1}"#),
+        },
+    ]);
+    let llm = ToolCallValidatedLLM::new(Box::new(mock));
+
+    let items: Vec<_> = llm
+        .chat_stream_with_tools(&user("weather?"), Some(&[weather_tool()]))
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert!(items[0].is_ok());
+    assert!(matches!(
+        items[1],
+        Err(LLMError::InvalidToolArguments { .. })
+    ));
+}
+
+#[tokio::test]
+async fn tool_runtime_reports_validation_errors_to_the_model() {
+    let mock = MockLLM::new();
+    mock.push_tool_calls(vec![call("1", r#"{"city":["Paris"]}"#)]);
+    mock.push_text("sorry");
+    let runtime = crate::tool_runtime::ToolRuntime::new().register(
+        FunctionBuilder::new("get_weather")
+            .param(ParamBuilder::new("city").type_of("string"))
+            .required(vec!["city".to_string()]),
+        |_| async { Ok(json!("sunny")) },
+    );
+
+    let outcome = runtime.run(&mock, user("weather?")).await.unwrap();
+    let execution = &outcome.executions[0];
+    assert!(execution.is_error);
+    assert!(execution.output["error"]
+        .as_str()
+        .unwrap()
+        .contains("$.city: expected string, got array"));
+}
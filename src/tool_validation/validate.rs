@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{
+    chat::Tool,
+    error::LLMError,
+    json_schema::{Schema, SchemaViolation},
+    ToolCall,
+};
+
+/// Parses the arguments of `call` and validates them against the schema of the
+/// matching tool in `tools`.
+///
+/// Returns the parsed arguments, [`LLMError::ToolConfigError`] when no tool has the
+/// call's name, and [`LLMError::InvalidToolArguments`] when the arguments are not
+/// valid JSON or do not match the schema.
+pub fn validate_tool_call(tools: &[Tool], call: &ToolCall) -> Result<Value, LLMError> {
+    let name = &call.function.name;
+    let tool = tools
+        .iter()
+        .find(|tool| &tool.function.name == name)
+        .ok_or_else(|| unknown_tool(name))?;
+    check_arguments(&Schema::new(tool.function.parameters.clone()), call)
+}
+
+/// Parameter schemas of a set of tools, compiled once for every call checked.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ToolSchemas {
+    schemas: HashMap<String, Schema>,
+}
+
+impl ToolSchemas {
+    pub(crate) fn new(tools: &[Tool]) -> Self {
+        let mut schemas = Self::default();
+        tools.iter().for_each(|tool| schemas.insert(tool));
+        schemas
+    }
+
+    /// Adds or replaces the schema of `tool`.
+    pub(crate) fn insert(&mut self, tool: &Tool) {
+        let schema = Schema::new(tool.function.parameters.clone());
+        self.schemas.insert(tool.function.name.clone(), schema);
+    }
+
+    /// Same as [`validate_tool_call`] against the tools of this set.
+    pub(crate) fn validate(&self, call: &ToolCall) -> Result<Value, LLMError> {
+        let name = &call.function.name;
+        let schema = self.schemas.get(name).ok_or_else(|| unknown_tool(name))?;
+        check_arguments(schema, call)
+    }
+}
+
+fn unknown_tool(name: &str) -> LLMError {
+    LLMError::ToolConfigError(format!("unknown tool '{name}'"))
+}
+
+fn check_arguments(schema: &Schema, call: &ToolCall) -> Result<Value, LLMError> {
+    let invalid = |violations| LLMError::InvalidToolArguments {
+        tool: call.function.name.clone(),
+        call_id: call.id.clone(),
+        violations,
+    };
+    let args: Value = call.function.parse_arguments().map_err(|e| {
+        invalid(vec![SchemaViolation {
+            path: "$".to_string(),
+            message: match e {
+                LLMError::JsonError(msg) => msg,
+                other => other.to_string(),
+            },
+        }])
+    })?;
+    schema.validate(&args).map_err(invalid)?;
+    Ok(args)
+}
@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use crate::{chat::Tool, LLMProvider};
+
+use super::validate::ToolSchemas;
+
+/// Wrapper validating tool calls returned by the inner provider.
+///
+/// Calls are checked against the tools passed to `chat_with_tools`, falling back to
+/// the tools configured on the wrapper. Invalid calls are answered with tool results
+/// carrying the validation errors and the model is asked again, up to
+/// `repair_attempts` times; after that the first error is returned.
+pub struct ToolCallValidatedLLM {
+    pub(super) inner: Box<dyn LLMProvider>,
+    pub(super) tools: Option<Vec<Tool>>,
+    /// Compiled schemas of `tools`
+    pub(super) schemas: Arc<ToolSchemas>,
+    pub(super) repair_attempts: usize,
+}
+
+impl ToolCallValidatedLLM {
+    /// Creates a wrapper that reports invalid calls without re-prompting.
+    pub fn new(inner: Box<dyn LLMProvider>) -> Self {
+        Self {
+            inner,
+            tools: None,
+            schemas: Arc::default(),
+            repair_attempts: 0,
+        }
+    }
+
+    /// Tools to validate against when the caller does not pass any.
+    pub fn with_tools(mut self, tools: Vec<Tool>) -> Self {
+        self.schemas = Arc::new(ToolSchemas::new(&tools));
+        self.tools = Some(tools);
+        self
+    }
+
+    /// Number of times the model is asked to fix invalid calls.
+    pub fn with_repair_attempts(mut self, attempts: usize) -> Self {
+        self.repair_attempts = attempts;
+        self
+    }
+}
@@ -0,0 +1,12 @@
+#[path = "usage_tracker/pricing.rs"]
+mod pricing;
+
+#[path = "usage_tracker/tracker.rs"]
+mod tracker;
+
+pub use pricing::{ModelPricing, PricingTable};
+pub use tracker::{Budget, UsageKey, UsageTotals, UsageTracker};
+
+#[cfg(test)]
+#[path = "usage_tracker/tests.rs"]
+mod tests;
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::chat::Usage;
+
+/// Prices of a model in USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    /// Price of uncached prompt tokens
+    pub input_per_million: f64,
+    /// Price of completion tokens, reasoning tokens included
+    pub output_per_million: f64,
+    /// Price of prompt tokens served from the provider cache, defaults to the input price
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cached_input_per_million: Option<f64>,
+}
+
+impl ModelPricing {
+    /// Prices of prompt and completion tokens; cached prompt tokens cost the input price.
+    pub fn new(input_per_million: f64, output_per_million: f64) -> Self {
+        Self {
+            input_per_million,
+            output_per_million,
+            cached_input_per_million: None,
+        }
+    }
+
+    /// Sets the discounted price of cached prompt tokens.
+    pub fn with_cached_input(mut self, cached_input_per_million: f64) -> Self {
+        self.cached_input_per_million = Some(cached_input_per_million);
+        self
+    }
+
+    /// Cost in USD of the given usage.
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        let cached = usage
+            .prompt_tokens_details
+            .as_ref()
+            .and_then(|d| d.cached_tokens)
+            .unwrap_or(0)
+            .min(usage.prompt_tokens);
+        let uncached = usage.prompt_tokens - cached;
+        let cached_price = self
+            .cached_input_per_million
+            .unwrap_or(self.input_per_million);
+        (f64::from(uncached) * self.input_per_million
+            + f64::from(cached) * cached_price
+            + f64::from(usage.completion_tokens) * self.output_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// Model prices keyed by backend name (`openai`, `anthropic`...) and model id.
+///
+/// Lookups fall back to the longest registered model prefix, so `gpt-4o-mini-2024-07-18`
+/// uses the `gpt-4o-mini` entry. [`PricingTable::default`] ships approximate public
+/// list prices; override them with [`PricingTable::set`] or load a table with serde
+/// (`{"openai": {"gpt-4o": {"input_per_million": 2.5, "output_per_million": 10.0}}}`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PricingTable {
+    entries: HashMap<String, HashMap<String, ModelPricing>>,
+}
+
+impl PricingTable {
+    /// Creates a table without any price.
+    pub fn empty() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Adds or replaces the price of a model.
+    pub fn set(&mut self, backend: &str, model: &str, pricing: ModelPricing) {
+        self.entries
+            .entry(backend.to_lowercase())
+            .or_default()
+            .insert(model.to_string(), pricing);
+    }
+
+    /// Builder-style variant of [`PricingTable::set`].
+    pub fn with(mut self, backend: &str, model: &str, pricing: ModelPricing) -> Self {
+        self.set(backend, model, pricing);
+        self
+    }
+
+    /// Merges another table into this one, its prices taking precedence.
+    pub fn merge(&mut self, other: PricingTable) {
+        for (backend, models) in other.entries {
+            self.entries.entry(backend).or_default().extend(models);
+        }
+    }
+
+    /// Looks up the price of a model, falling back to the longest matching prefix.
+    pub fn get(&self, backend: &str, model: &str) -> Option<&ModelPricing> {
+        let models = self.entries.get(&backend.to_lowercase())?;
+        if let Some(pricing) = models.get(model) {
+            return Some(pricing);
+        }
+        models
+            .iter()
+            .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, pricing)| pricing)
+    }
+
+    /// Cost in USD of the given usage, `None` when the model has no price.
+    pub fn cost(&self, backend: &str, model: &str, usage: &Usage) -> Option<f64> {
+        self.get(backend, model).map(|pricing| pricing.cost(usage))
+    }
+}
+
+impl Default for PricingTable {
+    fn default() -> Self {
+        const PRICES: &[(&str, &str, f64, f64, f64)] = &[
+            ("openai", "gpt-4o", 2.50, 1.25, 10.00),
+            ("openai", "gpt-4o-mini", 0.15, 0.075, 0.60),
+            ("openai", "gpt-4.1", 2.00, 0.50, 8.00),
+            ("openai", "gpt-4.1-mini", 0.40, 0.10, 1.60),
+            ("openai", "gpt-4.1-nano", 0.10, 0.025, 0.40),
+            ("openai", "o1", 15.00, 7.50, 60.00),
+            ("openai", "o1-mini", 1.10, 0.55, 4.40),
+            ("openai", "o3", 2.00, 0.50, 8.00),
+            ("openai", "o3-mini", 1.10, 0.55, 4.40),
+            ("openai", "o4-mini", 1.10, 0.275, 4.40),
+            ("openai", "text-embedding-3-small", 0.02, 0.02, 0.0),
+            ("openai", "text-embedding-3-large", 0.13, 0.13, 0.0),
+            ("anthropic", "claude-3-5-haiku", 0.80, 0.08, 4.00),
+            ("anthropic", "claude-3-7-sonnet", 3.00, 0.30, 15.00),
+            ("anthropic", "claude-sonnet-4", 3.00, 0.30, 15.00),
+            ("anthropic", "claude-opus-4", 15.00, 1.50, 75.00),
+            ("google", "gemini-2.0-flash", 0.10, 0.025, 0.40),
+            ("google", "gemini-2.5-flash", 0.30, 0.075, 2.50),
+            ("google", "gemini-2.5-pro", 1.25, 0.31, 10.00),
+            ("deepseek", "deepseek-chat", 0.27, 0.07, 1.10),
+            ("deepseek", "deepseek-reasoner", 0.55, 0.14, 2.19),
+            ("mistral", "mistral-large", 2.00, 2.00, 6.00),
+            ("mistral", "mistral-small", 0.10, 0.10, 0.30),
+        ];
+        PRICES.iter().fold(
+            Self::empty(),
+            |table, (backend, model, input, cached, output)| {
+                table.with(
+                    backend,
+                    model,
+                    ModelPricing::new(*input, *output).with_cached_input(*cached),
+                )
+            },
+        )
+    }
+}
@@ -0,0 +1,130 @@
+use futures::StreamExt;
+
+use crate::{
+    chat::{ChatMessage, ChatProvider, PromptTokensDetails, Usage},
+    error::LLMError,
+    middleware::MiddlewareLLM,
+    testing::{MockLLM, MockResponse},
+};
+
+use super::{Budget, ModelPricing, PricingTable, UsageTracker};
+
+fn usage(prompt: u32, cached: u32, completion: u32) -> Usage {
+    Usage {
+        prompt_tokens: prompt,
+        completion_tokens: completion,
+        total_tokens: prompt + completion,
+        completion_tokens_details: None,
+        prompt_tokens_details: Some(PromptTokensDetails {
+            cached_tokens: Some(cached),
+            audio_tokens: None,
+        }),
+    }
+}
+
+fn tracked(mock: &MockLLM, model: &str, tracker: UsageTracker) -> MiddlewareLLM {
+    MiddlewareLLM::new(Box::new(mock.clone()), "openai", Some(model.to_string()))
+        .with_middleware(std::sync::Arc::new(tracker))
+}
+
+#[test]
+fn pricing_uses_longest_prefix_and_cached_rate() {
+    let table = PricingTable::empty()
+        .with("openai", "gpt-4o", ModelPricing::new(2.0, 8.0))
+        .with(
+            "openai",
+            "gpt-4o-mini",
+            ModelPricing::new(1.0, 4.0).with_cached_input(0.5),
+        );
+
+    let cost = table
+        .cost(
+            "OpenAI",
+            "gpt-4o-mini-2024-07-18",
+            &usage(1_000_000, 500_000, 1_000_000),
+        )
+        .unwrap();
+    assert!((cost - 4.75).abs() < 1e-9);
+    assert!(table.cost("openai", "o3", &usage(1, 0, 1)).is_none());
+}
+
+#[test]
+fn default_prices_keep_mini_models_apart() {
+    let table = PricingTable::default();
+    let o3 = table.get("openai", "o3-2025-04-16").unwrap();
+    let o3_mini = table.get("openai", "o3-mini-2025-01-31").unwrap();
+    assert_eq!(o3.input_per_million, 2.0);
+    assert_eq!(o3_mini.input_per_million, 1.1);
+}
+
+#[tokio::test]
+async fn accumulates_per_model_and_tag() {
+    let pricing = PricingTable::empty().with("openai", "m", ModelPricing::new(1.0, 2.0));
+    let tracker = UsageTracker::new(pricing);
+    let mock =
+        MockLLM::new().with_fallback(MockResponse::text("ok").with_usage(usage(1_000, 0, 500)));
+
+    let llm = tracked(&mock, "m", tracker.tagged("ci"));
+    llm.chat(&[ChatMessage::user().content("a").build()])
+        .await
+        .unwrap();
+    llm.chat(&[ChatMessage::user().content("b").build()])
+        .await
+        .unwrap();
+    tracked(&mock, "other", tracker.clone())
+        .chat(&[ChatMessage::user().content("c").build()])
+        .await
+        .unwrap();
+
+    let ci = tracker.total_for_tag("ci");
+    assert_eq!(
+        (ci.requests, ci.prompt_tokens, ci.completion_tokens),
+        (2, 2_000, 1_000)
+    );
+    assert!((ci.cost_usd - 0.004).abs() < 1e-9);
+    let other = tracker.total_for_model("openai", "other");
+    assert_eq!((other.requests, other.unpriced_requests), (1, 1));
+    assert_eq!(tracker.breakdown().len(), 2);
+}
+
+#[tokio::test]
+async fn records_usage_streamed_with_tools() {
+    let tracker = UsageTracker::new(PricingTable::empty());
+    let mock =
+        MockLLM::new().with_fallback(MockResponse::text("ok").with_usage(usage(1_000, 0, 500)));
+
+    let llm = tracked(&mock, "m", tracker.clone());
+    let stream = llm
+        .chat_stream_with_tools(&[ChatMessage::user().content("a").build()], None)
+        .await
+        .unwrap();
+    stream.for_each(|_| async {}).await;
+
+    let total = tracker.total_for_model("openai", "m");
+    assert_eq!(
+        (total.requests, total.prompt_tokens, total.completion_tokens),
+        (1, 1_000, 500)
+    );
+}
+
+#[tokio::test]
+async fn budget_rejects_requests_once_exceeded() {
+    let tracker = UsageTracker::with_budget(
+        PricingTable::empty(),
+        Budget {
+            max_tokens: Some(100),
+            ..Budget::default()
+        },
+    );
+    let mock = MockLLM::new().with_fallback(MockResponse::text("ok").with_usage(usage(80, 0, 40)));
+    let llm = tracked(&mock, "m", tracker.clone());
+
+    llm.chat(&[]).await.unwrap();
+    assert!(matches!(
+        llm.chat(&[]).await,
+        Err(LLMError::BudgetExceeded(_))
+    ));
+    assert_eq!(mock.call_count(), 1);
+    assert_eq!(tracker.total().requests, 1);
+    assert_eq!(tracker.total().failed_requests, 0);
+}
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    chat::Usage,
+    error::LLMError,
+    middleware::{CallContext, CallInput, CallOutcome, LLMMiddleware},
+};
+
+use super::pricing::PricingTable;
+
+const UNKNOWN_MODEL: &str = "default";
+
+/// Hard limits applied across every provider sharing a tracker.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Budget {
+    /// Maximum total cost in USD
+    pub max_cost_usd: Option<f64>,
+    /// Maximum total number of tokens (prompt and completion)
+    pub max_tokens: Option<u64>,
+}
+
+/// Aggregation key of the tracked usage.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct UsageKey {
+    /// Provider name reported by the wrapped provider (`openai`, `anthropic`...)
+    pub provider: String,
+    /// Model id, `default` when the provider was built without one
+    pub model: String,
+    /// Tag of the [`UsageTracker::tagged`] handle that recorded the usage
+    pub tag: Option<String>,
+}
+
+/// Accumulated usage and cost.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct UsageTotals {
+    /// Calls that reached the provider
+    pub requests: u64,
+    /// Calls that reached the provider and failed
+    pub failed_requests: u64,
+    /// Prompt tokens, cached ones included
+    pub prompt_tokens: u64,
+    /// Completion tokens, reasoning ones included
+    pub completion_tokens: u64,
+    /// Prompt tokens served from the provider cache
+    pub cached_tokens: u64,
+    /// Completion tokens spent on reasoning
+    pub reasoning_tokens: u64,
+    /// Cost in USD of the calls whose model has a price
+    pub cost_usd: f64,
+    /// Number of successful calls that reported usage for a model without a price
+    pub unpriced_requests: u64,
+}
+
+impl UsageTotals {
+    /// Prompt and completion tokens.
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    fn add(&mut self, other: &UsageTotals) {
+        self.requests += other.requests;
+        self.failed_requests += other.failed_requests;
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.cached_tokens += other.cached_tokens;
+        self.reasoning_tokens += other.reasoning_tokens;
+        self.cost_usd += other.cost_usd;
+        self.unpriced_requests += other.unpriced_requests;
+    }
+
+    fn record(&mut self, usage: &Usage, cost: Option<f64>) {
+        self.prompt_tokens += u64::from(usage.prompt_tokens);
+        self.completion_tokens += u64::from(usage.completion_tokens);
+        self.cached_tokens += usage
+            .prompt_tokens_details
+            .as_ref()
+            .and_then(|d| d.cached_tokens)
+            .map(u64::from)
+            .unwrap_or(0);
+        self.reasoning_tokens += usage
+            .completion_tokens_details
+            .as_ref()
+            .and_then(|d| d.reasoning_tokens)
+            .map(u64::from)
+            .unwrap_or(0);
+        match cost {
+            Some(cost) => self.cost_usd += cost,
+            None => self.unpriced_requests += 1,
+        }
+    }
+}
+
+struct Ledger {
+    pricing: PricingTable,
+    budget: Budget,
+    totals: Mutex<HashMap<UsageKey, UsageTotals>>,
+}
+
+/// Middleware accumulating token usage and cost per provider, model and tag.
+///
+/// Clones share the same ledger; [`UsageTracker::tagged`] returns a clone recording
+/// under a tag, so one tracker can account for several providers or pipelines:
+///
+/// ```
+/// use llm::builder::{LLMBackend, LLMBuilder};
+/// use llm::usage_tracker::{Budget, PricingTable, UsageTracker};
+///
+/// let tracker = UsageTracker::with_budget(
+///     PricingTable::default(),
+///     Budget {
+///         max_cost_usd: Some(5.0),
+///         ..Budget::default()
+///     },
+/// );
+/// let builder = LLMBuilder::new()
+///     .backend(LLMBackend::OpenAI)
+///     .model("gpt-4o-mini")
+///     .usage_tracker(tracker.tagged("evals"));
+/// // ... after some calls:
+/// println!("spent ${:.4}", tracker.total().cost_usd);
+/// ```
+///
+/// Once the budget is exhausted, new calls fail with [`LLMError::BudgetExceeded`]
+/// before reaching the provider; they are not counted as requests.
+#[derive(Clone)]
+pub struct UsageTracker {
+    ledger: Arc<Ledger>,
+    tag: Option<String>,
+}
+
+impl UsageTracker {
+    /// Creates a tracker without budget, pricing calls with `pricing`.
+    pub fn new(pricing: PricingTable) -> Self {
+        Self::with_budget(pricing, Budget::default())
+    }
+
+    /// Creates a tracker enforcing `budget` across every handle sharing it.
+    pub fn with_budget(pricing: PricingTable, budget: Budget) -> Self {
+        Self {
+            ledger: Arc::new(Ledger {
+                pricing,
+                budget,
+                totals: Mutex::new(HashMap::new()),
+            }),
+            tag: None,
+        }
+    }
+
+    /// Returns a handle sharing this ledger that records under `tag`.
+    pub fn tagged(&self, tag: impl Into<String>) -> Self {
+        Self {
+            ledger: self.ledger.clone(),
+            tag: Some(tag.into()),
+        }
+    }
+
+    /// Usage per provider, model and tag, sorted by key.
+    pub fn breakdown(&self) -> Vec<(UsageKey, UsageTotals)> {
+        let mut rows: Vec<_> = self.totals().iter().map(|(k, v)| (k.clone(), *v)).collect();
+        rows.sort_by(|a, b| a.0.cmp(&b.0));
+        rows
+    }
+
+    /// Usage across every key.
+    pub fn total(&self) -> UsageTotals {
+        self.sum(|_| true)
+    }
+
+    /// Usage recorded under the given tag.
+    pub fn total_for_tag(&self, tag: &str) -> UsageTotals {
+        self.sum(|key| key.tag.as_deref() == Some(tag))
+    }
+
+    /// Usage recorded for the given provider and model.
+    pub fn total_for_model(&self, provider: &str, model: &str) -> UsageTotals {
+        self.sum(|key| key.provider == provider && key.model == model)
+    }
+
+    /// Clears every recorded total.
+    pub fn reset(&self) {
+        self.totals().clear();
+    }
+
+    fn totals(&self) -> std::sync::MutexGuard<'_, HashMap<UsageKey, UsageTotals>> {
+        self.ledger.totals.lock().expect("usage ledger lock")
+    }
+
+    fn sum(&self, filter: impl Fn(&UsageKey) -> bool) -> UsageTotals {
+        let mut sum = UsageTotals::default();
+        for (key, totals) in self.totals().iter() {
+            if filter(key) {
+                sum.add(totals);
+            }
+        }
+        sum
+    }
+
+    fn check_budget(&self) -> Result<(), LLMError> {
+        let budget = self.ledger.budget;
+        let total = self.total();
+        if let Some(max) = budget.max_cost_usd.filter(|max| total.cost_usd >= *max) {
+            return Err(LLMError::BudgetExceeded(format!(
+                "spent ${:.4} of ${max:.4}",
+                total.cost_usd
+            )));
+        }
+        if let Some(max) = budget.max_tokens.filter(|max| total.total_tokens() >= *max) {
+            return Err(LLMError::BudgetExceeded(format!(
+                "used {} of {max} tokens",
+                total.total_tokens()
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl LLMMiddleware for UsageTracker {
+    fn before_request(&self, _ctx: &CallContext, _input: &CallInput<'_>) -> Result<(), LLMError> {
+        self.check_budget()
+    }
+
+    fn after_response(&self, ctx: &CallContext, outcome: &CallOutcome) {
+        if outcome.rejected {
+            return;
+        }
+        let model = ctx.model.as_deref().unwrap_or(UNKNOWN_MODEL);
+        let key = UsageKey {
+            provider: ctx.provider.clone(),
+            model: model.to_string(),
+            tag: self.tag.clone(),
+        };
+        let cost = outcome
+            .usage
+            .as_ref()
+            .and_then(|usage| self.ledger.pricing.cost(&ctx.provider, model, usage));
+
+        let mut totals = self.totals();
+        let entry = totals.entry(key).or_default();
+        entry.requests += 1;
+        if outcome.error.is_some() {
+            entry.failed_requests += 1;
+        }
+        if let Some(usage) = &outcome.usage {
+            entry.record(usage, cost);
+        }
+    }
+}
@@ -1,16 +1,25 @@
 #[path = "validated_llm/wrapper.rs"]
 mod wrapper;
 
+#[path = "validated_llm/validator.rs"]
+mod validator;
+
 #[path = "validated_llm/chat.rs"]
 mod chat;
 
+#[path = "validated_llm/stream.rs"]
+mod stream;
+
 #[path = "validated_llm/completion.rs"]
 mod completion;
 
 #[path = "validated_llm/passthrough.rs"]
 mod passthrough;
 
-#[path = "validated_llm/helpers.rs"]
-mod helpers;
+pub(crate) use validator::strip_code_fence;
+pub use validator::{ValidationFailure, ValidationInput, Validator};
+pub use wrapper::{ValidatedLLM, ValidationFailureHook};
 
-pub use wrapper::ValidatedLLM;
+#[cfg(test)]
+#[path = "validated_llm/tests.rs"]
+mod tests;
@@ -1,11 +1,19 @@
+use std::pin::Pin;
+
 use async_trait::async_trait;
+use futures::stream::Stream;
+use serde_json::json;
 
 use crate::{
-    chat::{ChatMessage, ChatProvider, ChatResponse, ChatRole, MessageType, Tool},
+    chat::{
+        ChatMessage, ChatProvider, ChatResponse, ChatRole, MessageType, StreamChunk,
+        StreamResponse, Tool,
+    },
     error::LLMError,
+    FunctionCall, ToolCall,
 };
 
-use super::helpers::decrement_attempts;
+use super::stream::{validate_stream, Assembled};
 use super::wrapper::ValidatedLLM;
 
 #[async_trait]
@@ -16,20 +24,85 @@ impl ChatProvider for ValidatedLLM {
         tools: Option<&[Tool]>,
     ) -> Result<Box<dyn ChatResponse>, LLMError> {
         let mut local_messages = messages.to_vec();
-        let mut remaining_attempts = self.attempts();
+        let mut failures = Vec::new();
 
-        loop {
+        for attempt in 1..=self.attempts() {
             let response = self.inner().chat_with_tools(&local_messages, tools).await?;
             let text = response.text().unwrap_or_default();
+            let tool_calls = response.tool_calls().unwrap_or_default();
 
-            match (self.validator())(&text) {
+            match self.checks.check(&text, &tool_calls, tools) {
                 Ok(()) => return Ok(response),
                 Err(err) => {
-                    remaining_attempts = decrement_attempts(remaining_attempts, &err)?;
-                    append_validation_feedback(&mut local_messages, &err);
+                    if tool_calls.is_empty() {
+                        append_validation_feedback(&mut local_messages, &err);
+                    } else {
+                        append_tool_feedback(&mut local_messages, &text, tool_calls, &err);
+                    }
+                    failures.push(self.checks.report(attempt, err, text));
                 }
             }
         }
+
+        Err(LLMError::ValidationFailed { failures })
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<String, LLMError>> + Send>>, LLMError> {
+        let stream = self.inner().chat_stream(messages).await?;
+        Ok(validate_stream(
+            stream,
+            self.checks.clone(),
+            None,
+            |text, acc: &mut Assembled| acc.text.push_str(text),
+        ))
+    }
+
+    async fn chat_stream_struct(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamResponse, LLMError>> + Send>>, LLMError>
+    {
+        let stream = self.inner().chat_stream_struct(messages).await?;
+        Ok(validate_stream(
+            stream,
+            self.checks.clone(),
+            None,
+            |chunk: &StreamResponse, acc| {
+                for choice in &chunk.choices {
+                    if let Some(content) = &choice.delta.content {
+                        acc.text.push_str(content);
+                    }
+                }
+            },
+        ))
+    }
+
+    async fn chat_stream_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk, LLMError>> + Send>>, LLMError> {
+        let stream = self.inner().chat_stream_with_tools(messages, tools).await?;
+        let tools = tools.map(<[Tool]>::to_vec);
+        Ok(validate_stream(
+            stream,
+            self.checks.clone(),
+            tools,
+            |chunk, acc| match chunk {
+                StreamChunk::Text(text) => acc.text.push_str(text),
+                StreamChunk::ToolUseComplete { tool_call, .. } => {
+                    acc.tool_calls.push(tool_call.clone())
+                }
+                _ => {}
+            },
+        ))
+    }
+
+    async fn memory_contents(&self) -> Option<Vec<ChatMessage>> {
+        self.inner().memory_contents().await
     }
 }
 
@@ -42,3 +115,33 @@ fn append_validation_feedback(messages: &mut Vec<ChatMessage>, err: &str) {
         ),
     });
 }
+
+/// Answers every requested call with the validation error so the model can retry.
+fn append_tool_feedback(
+    messages: &mut Vec<ChatMessage>,
+    text: &str,
+    calls: Vec<ToolCall>,
+    err: &str,
+) {
+    let results = calls
+        .iter()
+        .map(|call| {
+            let error = format!("Invalid tool calls: {err}. Fix them and try again.");
+            ToolCall {
+                id: call.id.clone(),
+                call_type: call.call_type.clone(),
+                function: FunctionCall {
+                    name: call.function.name.clone(),
+                    arguments: json!({ "error": error }).to_string(),
+                },
+            }
+        })
+        .collect();
+    messages.push(
+        ChatMessage::assistant()
+            .tool_use(calls)
+            .content(text)
+            .build(),
+    );
+    messages.push(ChatMessage::user().tool_result(results).build());
+}
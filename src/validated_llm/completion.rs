@@ -5,20 +5,19 @@ use crate::{
     error::LLMError,
 };
 
-use super::{helpers::decrement_attempts, wrapper::ValidatedLLM};
+use super::wrapper::ValidatedLLM;
 
 #[async_trait]
 impl CompletionProvider for ValidatedLLM {
     async fn complete(&self, req: &CompletionRequest) -> Result<CompletionResponse, LLMError> {
-        let mut remaining_attempts = self.attempts();
-        loop {
+        let mut failures = Vec::new();
+        for attempt in 1..=self.attempts() {
             let response = self.inner().complete(req).await?;
-            match (self.validator())(&response.text) {
+            match self.checks.check(&response.text, &[], None) {
                 Ok(()) => return Ok(response),
-                Err(err) => {
-                    remaining_attempts = decrement_attempts(remaining_attempts, &err)?;
-                }
+                Err(err) => failures.push(self.checks.report(attempt, err, response.text)),
             }
         }
+        Err(LLMError::ValidationFailed { failures })
     }
 }
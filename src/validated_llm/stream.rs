@@ -0,0 +1,56 @@
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use futures::stream::{self, Stream, StreamExt};
+
+use crate::{chat::Tool, error::LLMError, ToolCall};
+
+use super::wrapper::Checks;
+
+/// Text and tool calls collected from a stream.
+#[derive(Default)]
+pub(super) struct Assembled {
+    pub(super) text: String,
+    pub(super) tool_calls: Vec<ToolCall>,
+    failed: bool,
+}
+
+/// Forwards `stream` unchanged and validates what it assembled once it ends,
+/// appending a [`LLMError::ValidationFailed`] item when the result is invalid.
+pub(super) fn validate_stream<T, F>(
+    stream: Pin<Box<dyn Stream<Item = Result<T, LLMError>> + Send>>,
+    checks: Checks,
+    tools: Option<Vec<Tool>>,
+    collect: F,
+) -> Pin<Box<dyn Stream<Item = Result<T, LLMError>> + Send>>
+where
+    T: Send + 'static,
+    F: Fn(&T, &mut Assembled) + Send + 'static,
+{
+    let assembled = Arc::new(Mutex::new(Assembled::default()));
+    let sink = Arc::clone(&assembled);
+    let forwarded = stream.inspect(move |item| {
+        let mut acc = sink.lock().unwrap();
+        match item {
+            Ok(item) => collect(item, &mut acc),
+            Err(_) => acc.failed = true,
+        }
+    });
+
+    let verdict = stream::once(async move {
+        let acc = std::mem::take(&mut *assembled.lock().unwrap());
+        if acc.failed {
+            return None;
+        }
+        let reason = checks
+            .check(&acc.text, &acc.tool_calls, tools.as_deref())
+            .err()?;
+        let failure = checks.report(1, reason, acc.text);
+        Some(Err(LLMError::ValidationFailed {
+            failures: vec![failure],
+        }))
+    })
+    .filter_map(|item| async move { item });
+
+    Box::pin(forwarded.chain(verdict))
+}
@@ -0,0 +1,179 @@
+use std::sync::{Arc, Mutex};
+
+use futures::StreamExt;
+use serde_json::json;
+
+use crate::{
+    builder::{FunctionBuilder, ParamBuilder},
+    chat::{ChatMessage, ChatProvider, MessageType, StreamChunk, StructuredOutputFormat},
+    completion::{CompletionProvider, CompletionRequest},
+    error::LLMError,
+    testing::MockLLM,
+    FunctionCall, ToolCall,
+};
+
+use super::{ValidatedLLM, ValidationFailure, Validator};
+
+fn person_format() -> StructuredOutputFormat {
+    serde_json::from_value(json!({
+        "name": "person",
+        "schema": {
+            "type": "object",
+            "properties": { "name": { "type": "string" }, "age": { "type": "integer" } },
+            "required": ["name", "age"]
+        }
+    }))
+    .unwrap()
+}
+
+fn user(text: &str) -> Vec<ChatMessage> {
+    vec![ChatMessage::user().content(text).build()]
+}
+
+#[test]
+fn builtin_validators_compose() {
+    let json = Validator::json_schema(person_format());
+    assert!(json
+        .validate_text("```json\n{\"name\":\"Ada\",\"age\":36}\n```")
+        .is_ok());
+    let err = json.validate_text(r#"{"name":"Ada"}"#).unwrap_err();
+    assert!(err.contains("schema 'person'") && err.contains("'age'"));
+    assert!(json
+        .validate_text("Ada, 36")
+        .unwrap_err()
+        .contains("not valid JSON"));
+
+    let short_digits = Validator::regex(r"^\d+$")
+        .unwrap()
+        .and(Validator::max_length(3));
+    assert!(short_digits.validate_text("123").is_ok());
+    assert!(short_digits.validate_text("1234").is_err());
+    assert!(short_digits.validate_text("abc").is_err());
+
+    let either = Validator::regex("^yes$")
+        .unwrap()
+        .or(Validator::regex("^no$").unwrap());
+    assert!(either.validate_text("no").is_ok());
+    assert!(either
+        .validate_text("maybe")
+        .unwrap_err()
+        .contains("alternatively"));
+    assert!(Validator::regex("(").is_err());
+}
+
+#[tokio::test]
+async fn retries_with_feedback_and_reports_failures() {
+    let mock = MockLLM::new();
+    mock.push_text("not json");
+    mock.push_text(r#"{"name":"Ada"}"#);
+    mock.push_text(r#"{"name":"Ada","age":36}"#);
+    let seen: Arc<Mutex<Vec<ValidationFailure>>> = Arc::default();
+    let sink = Arc::clone(&seen);
+    let llm = ValidatedLLM::with_validator(
+        Box::new(mock.clone()),
+        Validator::json_schema(person_format()),
+        3,
+    )
+    .on_failure(Arc::new(move |f: &ValidationFailure| {
+        sink.lock().unwrap().push(f.clone())
+    }));
+
+    let response = llm.chat(&user("who?")).await.unwrap();
+    assert_eq!(response.text().unwrap(), r#"{"name":"Ada","age":36}"#);
+    let seen = seen.lock().unwrap();
+    assert_eq!(
+        seen.iter().map(|f| f.attempt).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert_eq!(seen[0].output, "not json");
+    mock.assert_last_user_message_contains("missing required property 'age'");
+}
+
+#[tokio::test]
+async fn exhausted_attempts_return_every_failure() {
+    let mock = MockLLM::new().with_fallback(crate::testing::MockResponse::text("too long"));
+    mock.push_completion(Ok("long".to_string()));
+    mock.push_completion(Ok("longer".to_string()));
+    let llm = ValidatedLLM::with_validator(Box::new(mock), Validator::max_length(3), 2);
+
+    match llm.chat(&user("hi")).await.err().unwrap() {
+        LLMError::ValidationFailed { failures } => {
+            assert_eq!(failures.len(), 2);
+            assert!(failures[1].reason.contains("maximum is 3"));
+        }
+        other => panic!("unexpected error {other:?}"),
+    }
+
+    let completion = llm.complete(&CompletionRequest::new("hi")).await;
+    assert!(matches!(completion, Err(LLMError::ValidationFailed { .. })));
+}
+
+#[tokio::test]
+async fn validates_tool_calls_against_request_tools() {
+    let tool = FunctionBuilder::new("lookup")
+        .param(ParamBuilder::new("id").type_of("integer"))
+        .required(vec!["id".to_string()])
+        .build();
+    let call = |args: &str| ToolCall {
+        id: "call_1".to_string(),
+        call_type: "function".to_string(),
+        function: FunctionCall {
+            name: "lookup".to_string(),
+            arguments: args.to_string(),
+        },
+    };
+    let mock = MockLLM::new();
+    mock.push_tool_calls(vec![call(r#"{"id":"seven"}"#)]);
+    mock.push_tool_calls(vec![call(r#"{"id":7}"#)]);
+    let llm = ValidatedLLM::with_validator(
+        Box::new(mock.clone()),
+        Validator::json_schema(person_format()),
+        2,
+    );
+
+    let response = llm
+        .chat_with_tools(&user("look up 7"), Some(&[tool]))
+        .await
+        .unwrap();
+    assert_eq!(
+        response.tool_calls().unwrap()[0].function.arguments,
+        r#"{"id":7}"#
+    );
+    let sent = mock.last_messages().unwrap();
+    assert!(matches!(sent[1].message_type, MessageType::ToolUse(_)));
+    match &sent[2].message_type {
+        MessageType::ToolResult(results) => {
+            assert!(results[0].function.arguments.contains("expected integer"))
+        }
+        other => panic!("expected tool results, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn validates_assembled_streams() {
+    let mock = MockLLM::new();
+    mock.push_stream(vec![
+        StreamChunk::Text("{\"name\":".to_string()),
+        StreamChunk::Text("\"Ada\"}".to_string()),
+        StreamChunk::Done {
+            stop_reason: "end_turn".to_string(),
+        },
+    ]);
+    let llm =
+        ValidatedLLM::with_validator(Box::new(mock), Validator::json_schema(person_format()), 3);
+
+    let items: Vec<_> = llm
+        .chat_stream_with_tools(&user("who?"), None)
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(items.len(), 4);
+    assert!(items[..3].iter().all(Result::is_ok));
+    match &items[3] {
+        Err(LLMError::ValidationFailed { failures }) => {
+            assert_eq!(failures[0].output, r#"{"name":"Ada"}"#)
+        }
+        other => panic!("expected validation error, got {other:?}"),
+    }
+}
@@ -0,0 +1,164 @@
+use std::sync::Arc;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{chat::StructuredOutputFormat, error::LLMError, json_schema, ToolCall};
+
+/// What a [`Validator`] checks: the assembled text of a response and its tool calls.
+#[derive(Debug, Clone, Copy)]
+pub struct ValidationInput<'a> {
+    /// Text of the response, empty when it has none
+    pub text: &'a str,
+    /// Tool calls requested by the response
+    pub tool_calls: &'a [ToolCall],
+}
+
+type CheckFn = dyn Fn(&ValidationInput<'_>) -> Result<(), String> + Send + Sync;
+
+/// Composable response validator.
+///
+/// Text validators ([`Validator::text`], [`Validator::json_schema`],
+/// [`Validator::regex`], [`Validator::max_length`]) accept responses that request
+/// tool calls without looking at their text, which is usually a preamble to the
+/// calls rather than the answer; their arguments are checked against the tool
+/// schemas instead. Use [`Validator::new`] to also check the text of such responses.
+///
+/// ```
+/// use llm::validated_llm::Validator;
+///
+/// let validator = Validator::max_length(200)
+///     .and(Validator::regex(r"^\d+$").unwrap().or(Validator::regex("^none$").unwrap()));
+/// assert!(validator.validate_text("42").is_ok());
+/// assert!(validator.validate_text("forty-two").is_err());
+/// ```
+#[derive(Clone)]
+pub struct Validator {
+    check: Arc<CheckFn>,
+}
+
+impl Validator {
+    /// Validator with access to both the text and the tool calls of a response.
+    pub fn new<F>(check: F) -> Self
+    where
+        F: Fn(&ValidationInput<'_>) -> Result<(), String> + Send + Sync + 'static,
+    {
+        Self {
+            check: Arc::new(check),
+        }
+    }
+
+    /// Validator for the response text. Responses requesting tool calls pass.
+    pub fn text<F>(check: F) -> Self
+    where
+        F: Fn(&str) -> Result<(), String> + Send + Sync + 'static,
+    {
+        Self::new(move |input| {
+            if input.tool_calls.is_empty() {
+                check(input.text)
+            } else {
+                Ok(())
+            }
+        })
+    }
+
+    /// Requires the text to be JSON matching the schema of `format`; without a schema
+    /// any JSON value is accepted. A surrounding Markdown code fence is ignored.
+    pub fn json_schema(format: impl Into<StructuredOutputFormat>) -> Self {
+        let format: StructuredOutputFormat = format.into();
+        let schema = format.schema.map(json_schema::Schema::new);
+        let name = format.name;
+        Self::text(move |text| {
+            let value: Value = serde_json::from_str(strip_code_fence(text))
+                .map_err(|e| format!("output is not valid JSON: {e}"))?;
+            match &schema {
+                Some(schema) => schema.validate(&value).map_err(|violations| {
+                    format!(
+                        "output does not match schema '{name}': {}",
+                        json_schema::join_violations(&violations)
+                    )
+                }),
+                None => Ok(()),
+            }
+        })
+    }
+
+    /// Requires the text to match `pattern`.
+    pub fn regex(pattern: &str) -> Result<Self, LLMError> {
+        let re = Regex::new(pattern)
+            .map_err(|e| LLMError::InvalidRequest(format!("invalid validator regex: {e}")))?;
+        Ok(Self::text(move |text| {
+            if re.is_match(text) {
+                Ok(())
+            } else {
+                Err(format!("output must match pattern '{}'", re.as_str()))
+            }
+        }))
+    }
+
+    /// Limits the text to `max_chars` characters.
+    pub fn max_length(max_chars: usize) -> Self {
+        Self::text(move |text| {
+            let len = text.chars().count();
+            if len <= max_chars {
+                Ok(())
+            } else {
+                Err(format!(
+                    "output is {len} characters long, the maximum is {max_chars}"
+                ))
+            }
+        })
+    }
+
+    /// Requires both validators to pass.
+    pub fn and(self, other: Validator) -> Self {
+        Self::new(move |input| {
+            (self.check)(input)?;
+            (other.check)(input)
+        })
+    }
+
+    /// Requires at least one of the validators to pass.
+    pub fn or(self, other: Validator) -> Self {
+        Self::new(move |input| match (self.check)(input) {
+            Ok(()) => Ok(()),
+            Err(first) => (other.check)(input)
+                .map_err(|second| format!("{first}, and alternatively {second}")),
+        })
+    }
+
+    /// Runs the validator.
+    pub fn validate(&self, input: &ValidationInput<'_>) -> Result<(), String> {
+        (self.check)(input)
+    }
+
+    /// Runs the validator on a plain text response.
+    pub fn validate_text(&self, text: &str) -> Result<(), String> {
+        self.validate(&ValidationInput {
+            text,
+            tool_calls: &[],
+        })
+    }
+}
+
+/// A rejected attempt, reported to the failure hook and in
+/// [`LLMError::ValidationFailed`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationFailure {
+    /// 1-based attempt number.
+    pub attempt: usize,
+    /// Why the output was rejected.
+    pub reason: String,
+    /// The rejected text.
+    pub output: String,
+}
+
+pub(crate) fn strip_code_fence(text: &str) -> &str {
+    let trimmed = text.trim();
+    let Some(rest) = trimmed.strip_prefix("```") else {
+        return trimmed;
+    };
+    let body = rest.split_once('\n').map_or("", |(_, body)| body);
+    body.trim_end().strip_suffix("```").unwrap_or(body).trim()
+}
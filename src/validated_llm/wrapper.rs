@@ -1,31 +1,124 @@
-use crate::{builder::ValidatorFn, LLMProvider};
+use std::sync::Arc;
+
+use crate::{
+    builder::ValidatorFn, chat::Tool, tool_validation::ToolSchemas, LLMProvider, ToolCall,
+};
+
+use super::validator::{ValidationFailure, ValidationInput, Validator};
+
+/// Callback invoked for every rejected attempt.
+pub type ValidationFailureHook = dyn Fn(&ValidationFailure) + Send + Sync;
 
 /// A wrapper around an LLM provider that validates responses before returning them.
+///
+/// Chat and completion responses are re-requested with the validation error as
+/// feedback until they pass or the attempts run out, in which case
+/// [`LLMError::ValidationFailed`](crate::error::LLMError::ValidationFailed) lists
+/// every rejected attempt. Tool calls are checked against the request's tool
+/// schemas; text validators skip the text of responses requesting tool calls (see
+/// [`Validator`]). Streams are validated once fully assembled; an invalid stream
+/// ends with an error item instead of being retried.
 pub struct ValidatedLLM {
     pub(super) inner: Box<dyn LLMProvider>,
-    pub(super) validator: Box<ValidatorFn>,
+    pub(super) checks: Checks,
     pub(super) attempts: usize,
 }
 
+/// Validation state shared with stream adapters.
+#[derive(Clone)]
+pub(super) struct Checks {
+    validator: Validator,
+    /// Compiled schemas of the tools configured with [`ValidatedLLM::with_tools`]
+    tools: Option<Arc<ToolSchemas>>,
+    on_failure: Option<Arc<ValidationFailureHook>>,
+}
+
 impl ValidatedLLM {
     /// Creates a new ValidatedLLM wrapper around an existing LLM provider.
     pub fn new(inner: Box<dyn LLMProvider>, validator: Box<ValidatorFn>, attempts: usize) -> Self {
+        Self::with_validator(inner, Validator::text(validator), attempts)
+    }
+
+    /// Creates a wrapper using a composable [`Validator`].
+    pub fn with_validator(
+        inner: Box<dyn LLMProvider>,
+        validator: Validator,
+        attempts: usize,
+    ) -> Self {
         Self {
             inner,
-            validator,
+            checks: Checks {
+                validator,
+                tools: None,
+                on_failure: None,
+            },
             attempts,
         }
     }
 
-    pub(super) fn attempts(&self) -> usize {
-        self.attempts
+    /// Tools used to validate tool calls when the caller does not pass any.
+    pub fn with_tools(mut self, tools: Vec<Tool>) -> Self {
+        self.checks.tools = Some(Arc::new(ToolSchemas::new(&tools)));
+        self
+    }
+
+    /// Registers a callback receiving every rejected attempt.
+    pub fn on_failure(mut self, hook: Arc<ValidationFailureHook>) -> Self {
+        self.checks.on_failure = Some(hook);
+        self
     }
 
-    pub(super) fn validator(&self) -> &ValidatorFn {
-        &self.validator
+    pub(super) fn attempts(&self) -> usize {
+        self.attempts.max(1)
     }
 
     pub(super) fn inner(&self) -> &dyn LLMProvider {
         self.inner.as_ref()
     }
 }
+
+impl Checks {
+    /// Validates tool call arguments, then runs the validator.
+    pub(super) fn check(
+        &self,
+        text: &str,
+        tool_calls: &[ToolCall],
+        tools: Option<&[Tool]>,
+    ) -> Result<(), String> {
+        let schemas = match tools {
+            Some(tools) => Some(Arc::new(ToolSchemas::new(tools))),
+            None => self.tools.clone(),
+        };
+        if let Some(schemas) = schemas {
+            let errors: Vec<String> = tool_calls
+                .iter()
+                .filter_map(|call| schemas.validate(call).err())
+                .map(|err| err.to_string())
+                .collect();
+            if !errors.is_empty() {
+                return Err(errors.join("; "));
+            }
+        }
+        self.validator
+            .validate(&ValidationInput { text, tool_calls })
+    }
+
+    /// Builds the failure record and passes it to the hook.
+    pub(super) fn report(
+        &self,
+        attempt: usize,
+        reason: String,
+        output: String,
+    ) -> ValidationFailure {
+        let failure = ValidationFailure {
+            attempt,
+            reason,
+            output,
+        };
+        log::debug!("validation attempt {attempt} failed: {}", failure.reason);
+        if let Some(hook) = &self.on_failure {
+            hook(&failure);
+        }
+        failure
+    }
+}
@@ -1142,7 +1142,7 @@ async fn test_anthropic_chat_stream_with_tools() {
                             StreamChunk::Done { stop_reason: sr } => {
                                 stop_reason = Some(sr);
                             }
-                            StreamChunk::ToolUseInputDelta { .. } => {
+                            StreamChunk::ToolUseInputDelta { .. } | StreamChunk::Usage(_) => {
                                 // These are intermediate chunks, we don't need to collect them
                             }
                         }